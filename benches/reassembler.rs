@@ -0,0 +1,61 @@
+//! Fragment reassembly throughput for 1-, 4- and 64-fragment messages.
+//!
+//! `in_order` feeds each message's fragments back to back, so one message is pending at a time;
+//! `interleaved` sends fragment 0 of every message, then fragment 1 of every message and so on, so the
+//! reassembler holds all of them at once, as with many senders or heavy reordering. The `_hinted` variants set the
+//! fragments-per-message hint to the message's fragment count, so no message's storage grows.
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use test_shreds::reassembler::{fragment_message, FragmentReassembler};
+
+/// Messages per iteration
+const MESSAGES: u32 = 256;
+
+/// Payload bytes per fragment, about what fits a datagram on a 1500-byte MTU path
+const FRAGMENT_PAYLOAD: usize = 1200;
+
+/// Every message's fragments, by message
+fn messages(fragments: usize) -> Vec<Vec<Vec<u8>>> {
+    let payload = vec![0xab; fragments * FRAGMENT_PAYLOAD];
+    (0..MESSAGES).map(|id| fragment_message(id, &payload, FRAGMENT_PAYLOAD)).collect()
+}
+
+fn interleave(messages: Vec<Vec<Vec<u8>>>) -> Vec<Vec<u8>> {
+    let fragments = messages[0].len();
+    (0..fragments).flat_map(|i| messages.iter().map(move |m| m[i].clone())).collect()
+}
+
+fn reassemble(c: &mut Criterion) {
+    let runs = [
+        ("in_order", false, false),
+        ("in_order_hinted", false, true),
+        ("interleaved", true, false),
+        ("interleaved_hinted", true, true),
+    ];
+    for (name, interleaved, hinted) in runs {
+        let mut group = c.benchmark_group(format!("reassemble/{}", name));
+        group.throughput(Throughput::Elements(MESSAGES as u64));
+        for fragments in [1, 4, 64] {
+            let packets: Vec<Vec<u8>> = match interleaved {
+                true => interleave(messages(fragments)),
+                false => messages(fragments).concat(),
+            };
+            let hint = if hinted { fragments as u16 } else { 0 };
+            group.bench_with_input(BenchmarkId::from_parameter(fragments), &packets, |b, packets| {
+                b.iter_batched_ref(
+                    || FragmentReassembler::new().with_fragments_hint(hint),
+                    |reassembler| {
+                        for packet in packets {
+                            black_box(reassembler.process_packet(packet));
+                        }
+                    },
+                    BatchSize::SmallInput,
+                )
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, reassemble);
+criterion_main!(benches);
@@ -0,0 +1,32 @@
+//! Exposes the locked solana crate versions to the binary's startup banner, and
+//! generates the gRPC types when the `grpc` or `geyser` feature is on.
+
+use std::{env, fs, path::Path};
+
+fn main() {
+    let lock = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("Cargo.lock");
+    println!("cargo:rerun-if-changed={}", lock.display());
+    let lock = fs::read_to_string(lock).unwrap_or_default();
+
+    for (package, var) in [("solana-sdk", "SOLANA_SDK_VERSION"), ("solana-entry", "SOLANA_ENTRY_VERSION")] {
+        println!("cargo:rustc-env={}={}", var, locked_version(&lock, package).unwrap_or("unknown"));
+    }
+
+    #[cfg(any(feature = "grpc", feature = "geyser"))]
+    {
+        println!("cargo:rerun-if-changed=proto");
+        let descriptors =
+            protox::compile(["proto/detections.proto", "proto/geyser.proto"], ["proto"]).expect("invalid proto");
+        tonic_build::configure()
+            .build_client(true)
+            .compile_fds(descriptors)
+            .expect("gRPC codegen failed");
+    }
+}
+
+/// Version of `package` from a Cargo.lock `[[package]]` entry
+fn locked_version<'a>(lock: &'a str, package: &str) -> Option<&'a str> {
+    let name_line = format!("name = \"{}\"", package);
+    let mut lines = lock.lines().skip_while(|line| *line != name_line).skip(1);
+    lines.next()?.strip_prefix("version = \"")?.strip_suffix('"')
+}
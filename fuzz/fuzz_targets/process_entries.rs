@@ -0,0 +1,45 @@
+//! Feeds arbitrary bytes to the entry decoder and instruction scan.
+//!
+//! Each input runs against the default config and one with every option on,
+//! so partial decoding, buys, migrations and create accounts are all covered.
+//! Besides not panicking, a decoded message can't claim more entries than
+//! its bytes could hold.
+
+#![no_main]
+
+use std::{str::FromStr, sync::LazyLock};
+
+use libfuzzer_sys::fuzz_target;
+use solana_sdk::pubkey::Pubkey;
+use test_shreds::detector::{
+    process_entries, DetectorConfig, MigrationConfig, CREATE_POOL_DISC, PUMPFUN_PROGRAM_ID, PUMPSWAP_PROGRAM_ID,
+};
+
+/// Encoded size of an entry without transactions: num_hashes, hash, transaction count
+const MIN_ENTRY_LEN: usize = 8 + 32 + 8;
+
+static CONFIGS: LazyLock<[DetectorConfig; 2]> = LazyLock::new(|| {
+    let program_id = Pubkey::from_str(PUMPFUN_PROGRAM_ID).unwrap();
+    let migration = MigrationConfig {
+        program_id: Pubkey::from_str(PUMPSWAP_PROGRAM_ID).unwrap(),
+        discriminator: CREATE_POOL_DISC.to_vec(),
+        mint_index: 3,
+        pool_index: 0,
+    };
+    let mut everything = DetectorConfig::new(program_id, true, Some(migration));
+    everything.create_accounts = true;
+    everything.partial_decode = true;
+    [DetectorConfig::new(program_id, false, None), everything]
+});
+
+fuzz_target!(|data: &[u8]| {
+    for config in CONFIGS.iter() {
+        let Ok(processed) = process_entries(data, config, 0) else {
+            continue;
+        };
+        assert!(processed.entries * MIN_ENTRY_LEN <= data.len());
+        if let Some(index) = processed.failed_entry {
+            assert!(config.partial_decode && index == processed.entries && index > 0);
+        }
+    }
+});
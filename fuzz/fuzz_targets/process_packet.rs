@@ -0,0 +1,25 @@
+//! Feeds arbitrary datagrams through the fragment reassembler.
+//!
+//! Besides not panicking, reassembly must never produce more bytes than it was
+//! fed, or buffer more messages than it has seen fragments.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use test_shreds::reassembler::FragmentReassembler;
+
+fuzz_target!(|packets: Vec<Vec<u8>>| {
+    let mut reassembler = FragmentReassembler::new();
+    let fed: usize = packets.iter().map(Vec::len).sum();
+    let mut produced = 0;
+
+    for (count, packet) in packets.iter().enumerate() {
+        if let Some(message) = reassembler.process_packet(packet) {
+            produced += message.len();
+        }
+        assert!(reassembler.pending() <= count + 1);
+    }
+    assert!(produced <= fed);
+    reassembler.cleanup_old();
+    let _ = reassembler.snapshot();
+});
@@ -0,0 +1,115 @@
+//! Switches detection logging between per-event lines and periodic summaries
+//! depending on the recent detection rate.
+//!
+//! The rate is measured over fixed buckets. Crossing `enter_rate` switches to
+//! summaries; only dropping below the lower `exit_rate` switches back, so a rate
+//! hovering around one threshold doesn't flap between modes.
+
+use std::time::{Duration, Instant};
+
+use crate::detector::Detection;
+
+/// Rate measurement bucket used by the binary
+pub const DEFAULT_BUCKET: Duration = Duration::from_secs(1);
+
+/// Detections seen in one bucket
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DetectionCounts {
+    pub creates: u64,
+    pub buys: u64,
+    pub migrations: u64,
+    pub mint_inits: u64,
+    pub unknown: u64,
+}
+
+impl DetectionCounts {
+    pub fn total(&self) -> u64 {
+        self.creates + self.buys + self.migrations + self.mint_inits + self.unknown
+    }
+}
+
+/// How detections are currently logged
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogMode {
+    PerEvent,
+    Summary,
+}
+
+/// A finished measurement bucket
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bucket {
+    pub counts: DetectionCounts,
+    pub elapsed: Duration,
+    /// Detections per second over the bucket
+    pub rate: f64,
+    /// Mode detections in this bucket were logged in
+    pub mode: LogMode,
+    /// Set when the rate crossed a threshold and the mode changed for the next bucket
+    pub switched_to: Option<LogMode>,
+}
+
+pub struct AdaptiveVerbosity {
+    enter_rate: f64,
+    exit_rate: f64,
+    bucket: Duration,
+    started: Instant,
+    counts: DetectionCounts,
+    mode: LogMode,
+}
+
+impl AdaptiveVerbosity {
+    /// Summarize from `enter_rate` detections/s until the rate falls below `exit_rate`
+    /// (clamped to at most `enter_rate`), measuring over buckets of `bucket`
+    pub fn new(enter_rate: f64, exit_rate: f64, bucket: Duration, now: Instant) -> Self {
+        Self {
+            enter_rate,
+            exit_rate: exit_rate.min(enter_rate),
+            bucket,
+            started: now,
+            counts: DetectionCounts::default(),
+            mode: LogMode::PerEvent,
+        }
+    }
+
+    pub fn mode(&self) -> LogMode {
+        self.mode
+    }
+
+    pub fn record(&mut self, detection: &Detection) {
+        match detection {
+            Detection::Create { .. } | Detection::CreateWithDevBuy { .. } => self.counts.creates += 1,
+            Detection::Buy { .. } => self.counts.buys += 1,
+            Detection::Migration { .. } => self.counts.migrations += 1,
+            Detection::MintInit { .. } => self.counts.mint_inits += 1,
+            Detection::Unknown { .. } => self.counts.unknown += 1,
+            // Informational, so a burst of them doesn't switch logging to summaries
+            Detection::Maintenance { .. } | Detection::Heartbeat { .. } | Detection::SlotComplete { .. } => {}
+        }
+    }
+
+    /// Close the current bucket once it has run its length
+    pub fn poll(&mut self, now: Instant) -> Option<Bucket> {
+        (now.duration_since(self.started) >= self.bucket).then(|| self.close(now))
+    }
+
+    /// Close the current bucket early, e.g. at shutdown
+    pub fn close(&mut self, now: Instant) -> Bucket {
+        let elapsed = now.duration_since(self.started);
+        let counts = std::mem::take(&mut self.counts);
+        let rate = counts.total() as f64 / elapsed.as_secs_f64().max(f64::EPSILON);
+        let mode = self.mode;
+        self.mode = match mode {
+            LogMode::PerEvent if rate >= self.enter_rate => LogMode::Summary,
+            LogMode::Summary if rate < self.exit_rate => LogMode::PerEvent,
+            unchanged => unchanged,
+        };
+        self.started = now;
+        Bucket {
+            counts,
+            elapsed,
+            rate,
+            mode,
+            switched_to: (self.mode != mode).then_some(self.mode),
+        }
+    }
+}
@@ -0,0 +1,44 @@
+//! Pins threads to CPU cores to keep the scheduler from migrating them.
+//!
+//! Only Linux honors the pin; elsewhere `pin_current` logs a warning and does nothing.
+
+use tracing::warn;
+
+/// Parse a CPU list like taskset's, e.g. `2` or `0,2,4-7`
+pub fn parse_cpu_list(value: &str) -> Result<Vec<usize>, String> {
+    let mut cores = Vec::new();
+    for part in value.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let invalid = || format!("invalid CPU list entry: {}", part);
+        match part.split_once('-') {
+            Some((first, last)) => {
+                let first: usize = first.trim().parse().map_err(|_| invalid())?;
+                let last: usize = last.trim().parse().map_err(|_| invalid())?;
+                if first > last {
+                    return Err(invalid());
+                }
+                cores.extend(first..=last);
+            }
+            None => cores.push(part.parse().map_err(|_| invalid())?),
+        }
+    }
+    match cores.is_empty() {
+        true => Err("empty CPU list".to_string()),
+        false => Ok(cores),
+    }
+}
+
+/// Pin the calling thread to `core`, warning if that isn't possible
+pub fn pin_current(core: usize, what: &str) {
+    #[cfg(target_os = "linux")]
+    {
+        if core_affinity::set_for_current(core_affinity::CoreId { id: core }) {
+            tracing::debug!("📌 Pinned {} to CPU {}", what, core);
+        } else {
+            warn!("Failed to pin {} to CPU {}", what, core);
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        warn!("CPU pinning is Linux-only; {} not pinned to CPU {}", what, core);
+    }
+}
@@ -0,0 +1,108 @@
+//! Source address allow-list, so only known proxies can feed the reassembler.
+//!
+//! Entries are exact addresses or CIDR ranges, IPv4 or IPv6. IPv4-mapped IPv6 sources,
+//! as seen on a dual-stack socket, are matched as the IPv4 address they carry. Rejected
+//! sources are remembered, up to a cap, so the caller can log each one the first time only.
+
+use std::{collections::HashSet, fmt, net::IpAddr, str::FromStr};
+
+/// Rejected sources remembered as already seen; past this, new ones are no longer reported as new
+pub const MAX_REMEMBERED_REJECTED: usize = 4096;
+
+/// An address with the number of leading bits that must match; a plain address matches all of them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceRange {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl SourceRange {
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip.to_canonical()) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                prefix_matches(net.to_bits().into(), ip.to_bits().into(), 32, self.prefix)
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => prefix_matches(net.to_bits(), ip.to_bits(), 128, self.prefix),
+            _ => false,
+        }
+    }
+}
+
+fn prefix_matches(net: u128, ip: u128, bits: u8, prefix: u8) -> bool {
+    let shift = bits - prefix;
+    shift == bits || net >> shift == ip >> shift
+}
+
+impl FromStr for SourceRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix) = match s.trim().split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s.trim(), None),
+        };
+        let addr: IpAddr = addr.parse().map_err(|_| format!("invalid address: {}", addr))?;
+        let addr = addr.to_canonical();
+        let bits = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => {
+                let valid = prefix.parse::<u8>().ok().filter(|&p| p <= bits);
+                valid.ok_or_else(|| format!("invalid prefix length: /{}", prefix))?
+            }
+            None => bits,
+        };
+        Ok(Self { addr, prefix })
+    }
+}
+
+impl fmt::Display for SourceRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix)
+    }
+}
+
+/// Parse a comma-separated list of addresses and CIDR ranges
+pub fn parse_ranges(value: &str) -> Result<Vec<SourceRange>, String> {
+    let ranges = value.split(',').filter(|s| !s.trim().is_empty()).map(str::parse).collect::<Result<Vec<_>, _>>()?;
+    match ranges.is_empty() {
+        true => Err("expected at least one address or CIDR range".to_string()),
+        false => Ok(ranges),
+    }
+}
+
+/// What to do with a datagram from a source
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Admission {
+    Allowed,
+    /// Drop it; `first` for the first datagram seen from this source
+    Rejected { first: bool },
+}
+
+pub struct SourceAllowList {
+    ranges: Vec<SourceRange>,
+    rejected: HashSet<IpAddr>,
+}
+
+impl SourceAllowList {
+    pub fn new(ranges: Vec<SourceRange>) -> Self {
+        Self { ranges, rejected: HashSet::new() }
+    }
+
+    /// Whether a datagram from `source` may be processed
+    pub fn check(&mut self, source: IpAddr) -> Admission {
+        if self.ranges.iter().any(|range| range.contains(source)) {
+            return Admission::Allowed;
+        }
+        let first = self.rejected.len() < MAX_REMEMBERED_REJECTED && self.rejected.insert(source);
+        Admission::Rejected { first }
+    }
+
+    pub fn ranges(&self) -> &[SourceRange] {
+        &self.ranges
+    }
+
+    /// Distinct sources rejected so far, up to `MAX_REMEMBERED_REJECTED`
+    pub fn rejected_sources(&self) -> usize {
+        self.rejected.len()
+    }
+}
@@ -0,0 +1,157 @@
+//! Bounded pcap capture of sampled datagrams and of messages that failed to decode.
+//!
+//! Capturing everything is too much to chase a rare failure with, so this keeps a
+//! 1-in-N sample of received datagrams, every message that failed to decode, or both.
+//! A failed message is written re-fragmented with SHRD headers, since reassembled ones
+//! can outgrow a datagram, so `PCAP_REPLAY` on the file reproduces the failure. Writing
+//! stops once either bound is reached.
+
+use std::{
+    fs::File,
+    io::{self, BufWriter},
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use tracing::{info, warn};
+
+use crate::{pcap::PcapWriter, reassembler::fragment_message};
+
+/// Payload bytes per fragment of a re-fragmented failed message
+const FAILED_FRAGMENT_SIZE: usize = 1200;
+
+/// What to capture and how much
+#[derive(Debug, Clone)]
+pub struct CaptureConfig {
+    pub path: PathBuf,
+    /// Keep every Nth received datagram; 0 keeps none
+    pub sample_every: u64,
+    /// Keep every message that fails to decode
+    pub on_failure: bool,
+    /// Stop after this many datagrams
+    pub max_packets: u64,
+    /// Stop after this many bytes of file
+    pub max_bytes: u64,
+}
+
+/// Totals for the run summary
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CaptureStats {
+    pub sampled: u64,
+    pub failed_messages: u64,
+    /// Datagrams written, counting each fragment of a failed message
+    pub packets: u64,
+    pub bytes: u64,
+}
+
+pub struct PacketCapture {
+    config: CaptureConfig,
+    writer: PcapWriter<BufWriter<File>>,
+    received: u64,
+    /// Message IDs for re-fragmented failures, counting down to stay clear of the proxy's
+    next_message_id: u32,
+    stats: CaptureStats,
+    full: bool,
+}
+
+impl PacketCapture {
+    /// Create (or truncate) the capture file
+    pub fn create(config: CaptureConfig) -> io::Result<Self> {
+        let writer = PcapWriter::new(BufWriter::new(File::create(&config.path)?))?;
+        Ok(Self {
+            config,
+            writer,
+            received: 0,
+            next_message_id: u32::MAX,
+            stats: CaptureStats { bytes: 24, ..Default::default() },
+            full: false,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.config.path
+    }
+
+    /// Count a received datagram, keeping it if it's the Nth
+    pub fn packet(&mut self, packet: &[u8], src: SocketAddr) {
+        self.received += 1;
+        if self.config.sample_every == 0 || !self.received.is_multiple_of(self.config.sample_every) {
+            return;
+        }
+        if self.write(&[packet.to_vec()], src) {
+            self.stats.sampled += 1;
+        }
+    }
+
+    /// Keep a message that failed to decode, last received from `src`
+    pub fn failed(&mut self, message: &[u8], src: SocketAddr) {
+        if !self.config.on_failure {
+            return;
+        }
+        let fragments = fragment_message(self.next_message_id, message, FAILED_FRAGMENT_SIZE);
+        if self.write(&fragments, src) {
+            self.next_message_id = self.next_message_id.wrapping_sub(1);
+            self.stats.failed_messages += 1;
+            // Failures are what this is for; don't lose one to a crash
+            if let Err(e) = self.writer.flush() {
+                warn!("Capture flush to {} failed: {}", self.config.path.display(), e);
+            }
+        }
+    }
+
+    /// Write all of `datagrams` or, if that would break a bound, none of them
+    fn write(&mut self, datagrams: &[Vec<u8>], src: SocketAddr) -> bool {
+        if self.full {
+            return false;
+        }
+        // At most: record header, IPv6 and UDP headers, payload
+        let bytes: u64 = datagrams.iter().map(|d| (16 + 48 + d.len()) as u64).sum();
+        if self.stats.packets + datagrams.len() as u64 > self.config.max_packets
+            || self.stats.bytes + bytes > self.config.max_bytes
+        {
+            self.full = true;
+            warn!(
+                "⚠️  Capture {} is full ({} datagrams, {} bytes); no more will be written",
+                self.config.path.display(),
+                self.stats.packets,
+                self.stats.bytes
+            );
+            return false;
+        }
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        for datagram in datagrams {
+            match self.writer.write_datagram(timestamp, src, datagram) {
+                Ok(written) => {
+                    self.stats.packets += 1;
+                    self.stats.bytes += written as u64;
+                }
+                Err(e) => {
+                    warn!("Capture write to {} failed: {}", self.config.path.display(), e);
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    pub fn stats(&self) -> CaptureStats {
+        self.stats
+    }
+
+    /// Flush the file and log what was captured
+    pub fn finish(mut self) -> CaptureStats {
+        if let Err(e) = self.writer.flush() {
+            warn!("Capture flush to {} failed: {}", self.config.path.display(), e);
+        }
+        info!(
+            "📋 Capture: {} sampled datagrams and {} failed messages in {} ({} datagrams, {:.2} MB)",
+            self.stats.sampled,
+            self.stats.failed_messages,
+            self.config.path.display(),
+            self.stats.packets,
+            self.stats.bytes as f64 / 1_000_000.0
+        );
+        self.stats
+    }
+}
@@ -0,0 +1,85 @@
+//! Time-windowed aggregation of buy bursts for the same mint.
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use solana_sdk::pubkey::Pubkey;
+
+/// Buys for one mint aggregated over a window
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoalescedBuys {
+    pub mint: Pubkey,
+    pub count: u32,
+    /// Sum of the buys' `max_sol_cost`, in lamports
+    pub total_sol_cost: u64,
+    pub first_seen: Instant,
+}
+
+/// Collects buys per mint and releases one aggregate once the mint's window closes
+pub struct BuyCoalescer {
+    window: Duration,
+    max_pending: usize,
+    pending: HashMap<Pubkey, CoalescedBuys>,
+}
+
+impl BuyCoalescer {
+    pub fn new(window: Duration, max_pending: usize) -> Self {
+        Self {
+            window,
+            max_pending: max_pending.max(1),
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Add a buy; returns an aggregate evicted early when `max_pending` mints are already open
+    pub fn push(&mut self, mint: Pubkey, sol_cost: u64, now: Instant) -> Option<CoalescedBuys> {
+        if let Some(group) = self.pending.get_mut(&mint) {
+            group.count += 1;
+            group.total_sol_cost = group.total_sol_cost.saturating_add(sol_cost);
+            return None;
+        }
+
+        let evicted = if self.pending.len() >= self.max_pending {
+            let oldest = self
+                .pending
+                .values()
+                .min_by_key(|g| g.first_seen)
+                .map(|g| g.mint)
+                .expect("pending is non-empty");
+            self.pending.remove(&oldest)
+        } else {
+            None
+        };
+
+        self.pending.insert(mint, CoalescedBuys {
+            mint,
+            count: 1,
+            total_sol_cost: sol_cost,
+            first_seen: now,
+        });
+        evicted
+    }
+
+    /// Release every aggregate whose window has closed
+    pub fn flush_expired(&mut self, now: Instant) -> Vec<CoalescedBuys> {
+        let expired: Vec<Pubkey> = self
+            .pending
+            .values()
+            .filter(|g| now.saturating_duration_since(g.first_seen) >= self.window)
+            .map(|g| g.mint)
+            .collect();
+        expired.iter().filter_map(|mint| self.pending.remove(mint)).collect()
+    }
+
+    /// Release everything, e.g. on shutdown
+    pub fn flush_all(&mut self) -> Vec<CoalescedBuys> {
+        self.pending.drain().map(|(_, g)| g).collect()
+    }
+
+    /// Number of mints with an open window
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+}
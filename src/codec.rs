@@ -0,0 +1,306 @@
+//! Compact fixed-layout binary encoding of detection events.
+//!
+//! Every frame starts with a format version byte so consumers can reject frames
+//! they don't understand. Version 1 layout, all integers little-endian:
+//!
+//! | Offset | Size | Field |
+//! |--------|------|-------|
+//! | 0 | 1 | version (`1`) |
+//! | 1 | 1 | kind, below |
+//! | 2 | 8 | timestamp_ms |
+//! | 10 | 8 | msg_seq |
+//! | 18 | .. | kind payload |
+//! | .. | 1 | bonding curve state present (0/1), followed by 5 x u64 + 1 byte when present |
+//!
+//! Kinds: 0 = create, 1 = buy, 2 = migration, 3 = heartbeat, 4 = slot complete, 5 = mint init, 6 = unknown,
+//! 7 = maintenance, 8 = create with dev buy.
+//!
+//! Kind payloads: create = mint, bonding_curve, creator (32 bytes each; the
+//! optional associated bonding curve, metadata account, account list, token accounts and event are not carried);
+//! buy = mint, buyer, token_amount (u64), max_sol_cost (u64), without the event;
+//! migration = mint, pool, from_pumpfun (1 byte);
+//! heartbeat = packets (u64), pending (u64); slot complete = slot (u64), detections (u64);
+//! mint init = mint, authority, decimals (1 byte), token_2022 (1 byte); unknown = program, data_len (u64), then the
+//! discriminator and the hex data, each as a u16 length and its bytes; maintenance = instruction (1 byte:
+//! 0 = extend_account, 1 = close_user_volume_accumulator), account, user; create with dev buy = mint, bonding_curve,
+//! creator, dev_buy_sol (u64), dev_buy_tokens (u64), without the compute budget and event. The envelope `context`,
+//! `status`, `detection_id`, `late` and `score` aren't carried.
+
+use std::fmt;
+
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{
+    detector::{Detection, InstructionKind},
+    enrich::BondingCurveState,
+    sink::DetectionEvent,
+};
+
+/// Current binary format version
+pub const FORMAT_VERSION: u8 = 1;
+
+const KIND_CREATE: u8 = 0;
+const KIND_BUY: u8 = 1;
+const KIND_MIGRATION: u8 = 2;
+const KIND_HEARTBEAT: u8 = 3;
+const KIND_SLOT_COMPLETE: u8 = 4;
+const KIND_MINT_INIT: u8 = 5;
+const KIND_UNKNOWN: u8 = 6;
+const KIND_MAINTENANCE: u8 = 7;
+const KIND_CREATE_WITH_DEV_BUY: u8 = 8;
+
+const EXTEND_ACCOUNT: u8 = 0;
+const CLOSE_USER_VOLUME_ACCUMULATOR: u8 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CodecError {
+    UnsupportedVersion(u8),
+    UnknownKind(u8),
+    /// A maintenance detection's instruction byte names no maintenance instruction
+    UnknownInstruction(u8),
+    Truncated,
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodecError::UnsupportedVersion(v) => write!(f, "unsupported format version {}", v),
+            CodecError::UnknownKind(k) => write!(f, "unknown detection kind {}", k),
+            CodecError::UnknownInstruction(i) => write!(f, "unknown maintenance instruction {}", i),
+            CodecError::Truncated => write!(f, "frame is truncated"),
+        }
+    }
+}
+
+impl std::error::Error for CodecError {}
+
+/// Encode an event as a version 1 frame
+pub fn encode(event: &DetectionEvent) -> Vec<u8> {
+    let mut out = Vec::with_capacity(160);
+    out.push(FORMAT_VERSION);
+    out.push(match event.detection {
+        Detection::Create { .. } => KIND_CREATE,
+        Detection::Buy { .. } => KIND_BUY,
+        Detection::Migration { .. } => KIND_MIGRATION,
+        Detection::Heartbeat { .. } => KIND_HEARTBEAT,
+        Detection::SlotComplete { .. } => KIND_SLOT_COMPLETE,
+        Detection::MintInit { .. } => KIND_MINT_INIT,
+        Detection::Unknown { .. } => KIND_UNKNOWN,
+        Detection::Maintenance { .. } => KIND_MAINTENANCE,
+        Detection::CreateWithDevBuy { .. } => KIND_CREATE_WITH_DEV_BUY,
+    });
+    out.extend_from_slice(&event.timestamp_ms.to_le_bytes());
+    out.extend_from_slice(&event.msg_seq.to_le_bytes());
+
+    match &event.detection {
+        Detection::Create { mint, bonding_curve, creator, .. } => {
+            out.extend_from_slice(mint.as_ref());
+            out.extend_from_slice(bonding_curve.as_ref());
+            out.extend_from_slice(creator.as_ref());
+        }
+        Detection::CreateWithDevBuy { mint, bonding_curve, creator, dev_buy_sol, dev_buy_tokens, .. } => {
+            out.extend_from_slice(mint.as_ref());
+            out.extend_from_slice(bonding_curve.as_ref());
+            out.extend_from_slice(creator.as_ref());
+            out.extend_from_slice(&dev_buy_sol.to_le_bytes());
+            out.extend_from_slice(&dev_buy_tokens.to_le_bytes());
+        }
+        Detection::Buy { mint, buyer, token_amount, max_sol_cost, .. } => {
+            out.extend_from_slice(mint.as_ref());
+            out.extend_from_slice(buyer.as_ref());
+            out.extend_from_slice(&token_amount.to_le_bytes());
+            out.extend_from_slice(&max_sol_cost.to_le_bytes());
+        }
+        Detection::Migration { mint, pool, from_pumpfun } => {
+            out.extend_from_slice(mint.as_ref());
+            out.extend_from_slice(pool.as_ref());
+            out.push(*from_pumpfun as u8);
+        }
+        Detection::Heartbeat { packets, pending } => {
+            out.extend_from_slice(&packets.to_le_bytes());
+            out.extend_from_slice(&(*pending as u64).to_le_bytes());
+        }
+        Detection::SlotComplete { slot, detections } => {
+            out.extend_from_slice(&slot.to_le_bytes());
+            out.extend_from_slice(&(*detections as u64).to_le_bytes());
+        }
+        Detection::MintInit { mint, decimals, authority, token_2022 } => {
+            out.extend_from_slice(mint.as_ref());
+            out.extend_from_slice(authority.as_ref());
+            out.push(*decimals);
+            out.push(*token_2022 as u8);
+        }
+        Detection::Unknown { program, discriminator, data_hex, data_len } => {
+            out.extend_from_slice(program.as_ref());
+            out.extend_from_slice(&(*data_len as u64).to_le_bytes());
+            for bytes in [discriminator.as_slice(), data_hex.as_bytes()] {
+                out.extend_from_slice(&(bytes.len() as u16).to_le_bytes());
+                out.extend_from_slice(bytes);
+            }
+        }
+        Detection::Maintenance { instruction, account, user } => {
+            out.push(match instruction {
+                InstructionKind::CloseUserVolumeAccumulator => CLOSE_USER_VOLUME_ACCUMULATOR,
+                _ => EXTEND_ACCOUNT,
+            });
+            out.extend_from_slice(account.as_ref());
+            out.extend_from_slice(user.as_ref());
+        }
+    }
+
+    match &event.bonding_curve_state {
+        Some(state) => {
+            out.push(1);
+            for value in [
+                state.virtual_token_reserves,
+                state.virtual_sol_reserves,
+                state.real_token_reserves,
+                state.real_sol_reserves,
+                state.token_total_supply,
+            ] {
+                out.extend_from_slice(&value.to_le_bytes());
+            }
+            out.push(state.complete as u8);
+        }
+        None => out.push(0),
+    }
+    out
+}
+
+/// Decode a frame produced by `encode`
+pub fn decode(frame: &[u8]) -> Result<DetectionEvent, CodecError> {
+    let mut reader = Reader(frame);
+    let version = reader.u8()?;
+    if version != FORMAT_VERSION {
+        return Err(CodecError::UnsupportedVersion(version));
+    }
+    let kind = reader.u8()?;
+    let timestamp_ms = reader.u64()?;
+    let msg_seq = reader.u64()?;
+
+    let detection = match kind {
+        KIND_CREATE => Detection::Create {
+            mint: reader.pubkey()?,
+            bonding_curve: reader.pubkey()?,
+            creator: reader.pubkey()?,
+            associated_bonding_curve: None,
+            metadata: None,
+            accounts: None,
+            compute_budget: None,
+            token_accounts: Vec::new(),
+            event: None,
+        },
+        KIND_BUY => Detection::Buy {
+            mint: reader.pubkey()?,
+            buyer: reader.pubkey()?,
+            token_amount: reader.u64()?,
+            max_sol_cost: reader.u64()?,
+            event: None,
+        },
+        KIND_MIGRATION => Detection::Migration {
+            mint: reader.pubkey()?,
+            pool: reader.pubkey()?,
+            from_pumpfun: reader.u8()? != 0,
+        },
+        KIND_HEARTBEAT => Detection::Heartbeat {
+            packets: reader.u64()?,
+            pending: reader.u64()? as usize,
+        },
+        KIND_SLOT_COMPLETE => Detection::SlotComplete {
+            slot: reader.u64()?,
+            detections: reader.u64()? as usize,
+        },
+        KIND_MINT_INIT => Detection::MintInit {
+            mint: reader.pubkey()?,
+            authority: reader.pubkey()?,
+            decimals: reader.u8()?,
+            token_2022: reader.u8()? != 0,
+        },
+        KIND_UNKNOWN => Detection::Unknown {
+            program: reader.pubkey()?,
+            data_len: reader.u64()? as usize,
+            discriminator: reader.bytes()?.to_vec(),
+            data_hex: String::from_utf8_lossy(reader.bytes()?).into_owned(),
+        },
+        KIND_CREATE_WITH_DEV_BUY => Detection::CreateWithDevBuy {
+            mint: reader.pubkey()?,
+            bonding_curve: reader.pubkey()?,
+            creator: reader.pubkey()?,
+            dev_buy_sol: reader.u64()?,
+            dev_buy_tokens: reader.u64()?,
+            associated_bonding_curve: None,
+            metadata: None,
+            accounts: None,
+            compute_budget: None,
+            token_accounts: Vec::new(),
+            event: None,
+        },
+        KIND_MAINTENANCE => Detection::Maintenance {
+            instruction: match reader.u8()? {
+                EXTEND_ACCOUNT => InstructionKind::ExtendAccount,
+                CLOSE_USER_VOLUME_ACCUMULATOR => InstructionKind::CloseUserVolumeAccumulator,
+                other => return Err(CodecError::UnknownInstruction(other)),
+            },
+            account: reader.pubkey()?,
+            user: reader.pubkey()?,
+        },
+        other => return Err(CodecError::UnknownKind(other)),
+    };
+
+    let bonding_curve_state = match reader.u8()? {
+        0 => None,
+        _ => Some(BondingCurveState {
+            virtual_token_reserves: reader.u64()?,
+            virtual_sol_reserves: reader.u64()?,
+            real_token_reserves: reader.u64()?,
+            real_sol_reserves: reader.u64()?,
+            token_total_supply: reader.u64()?,
+            complete: reader.u8()? != 0,
+        }),
+    };
+
+    Ok(DetectionEvent {
+        timestamp_ms,
+        msg_seq,
+        detection,
+        bonding_curve_state,
+        context: None,
+        status: None,
+        detection_id: None,
+        late: false,
+        score: None,
+        usd_value: None,
+        emitted_at: None,
+    })
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn take(&mut self, n: usize) -> Result<&[u8], CodecError> {
+        if self.0.len() < n {
+            return Err(CodecError::Truncated);
+        }
+        let (head, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, CodecError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u64(&mut self) -> Result<u64, CodecError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn pubkey(&mut self) -> Result<Pubkey, CodecError> {
+        Ok(Pubkey::new_from_array(self.take(32)?.try_into().unwrap()))
+    }
+
+    /// A u16 length, then that many bytes
+    fn bytes(&mut self) -> Result<&[u8], CodecError> {
+        let len = u16::from_le_bytes(self.take(2)?.try_into().unwrap());
+        self.take(len.into())
+    }
+}
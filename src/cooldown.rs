@@ -0,0 +1,48 @@
+//! Per-creator cooldown on create detections.
+//!
+//! After a create from a creator is reported, further creates from the same
+//! creator are suppressed until the cooldown has passed. The cooldown runs from
+//! the reported create; suppressed ones don't extend it.
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use solana_sdk::pubkey::Pubkey;
+
+pub struct CreatorCooldown {
+    cooldown: Duration,
+    /// When each creator's last reported create was seen
+    reported: HashMap<Pubkey, Instant>,
+}
+
+impl CreatorCooldown {
+    pub fn new(cooldown: Duration) -> Self {
+        Self {
+            cooldown,
+            reported: HashMap::new(),
+        }
+    }
+
+    /// Whether a create by `creator` should be reported, starting its cooldown if so
+    pub fn allow(&mut self, creator: Pubkey, now: Instant) -> bool {
+        match self.reported.get(&creator) {
+            Some(&at) if now.saturating_duration_since(at) < self.cooldown => false,
+            _ => {
+                self.reported.insert(creator, now);
+                true
+            }
+        }
+    }
+
+    /// Forget creators whose cooldown has passed
+    pub fn prune(&mut self, now: Instant) {
+        self.reported.retain(|_, &mut at| now.saturating_duration_since(at) < self.cooldown);
+    }
+
+    /// Creators currently cooling down, or not yet pruned
+    pub fn tracked(&self) -> usize {
+        self.reported.len()
+    }
+}
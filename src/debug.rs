@@ -0,0 +1,129 @@
+//! Debug HTTP endpoint for inspecting reassembly while it runs.
+//!
+//! `GET /buffers` answers with the pipeline's oldest in-flight messages as a JSON `BuffersReport`.
+//! The pipeline owns its reassembler, so the server never touches it: each request is handed to
+//! the recv loop, which copies out at most its configured number of buffers between two datagrams
+//! and replies. Nothing is locked, and the copy stays small however many messages are pending.
+//!
+//! `GET /health` answers from the pipeline's [`Metrics`] without involving the recv loop, so it
+//! still answers while the input is down: a `Health` with the input's state, 200 while it is
+//! connected and 503 otherwise.
+//!
+//! There is no authentication; bind it to a loopback address.
+
+use std::{io, sync::Arc, time::Duration};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::{mpsc, oneshot},
+};
+use serde::Serialize;
+use tracing::{debug, info, warn};
+
+use crate::{metrics::Metrics, reassembler::BuffersReport, source::supervisor::SourceState};
+
+/// A `/buffers` request waiting for the recv loop's answer
+pub type BuffersRequest = oneshot::Sender<BuffersReport>;
+
+/// Requests queued for the recv loop; more at once are answered 503
+const REQUEST_QUEUE: usize = 4;
+
+/// How long a request waits for the recv loop, e.g. while it's stopped
+const REPLY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Largest request head read; anything after it is ignored
+const MAX_REQUEST: usize = 8 * 1024;
+
+/// The `/health` response
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Health {
+    pub source: SourceState,
+    /// Times the input was reopened after failing
+    pub source_restarts: u64,
+    /// Datagrams received since startup
+    pub packets: u64,
+}
+
+/// Serve the endpoint on `addr`, returning the `/buffers` requests for the pipeline to answer
+pub async fn serve(addr: &str, metrics: Arc<Metrics>) -> io::Result<mpsc::Receiver<BuffersRequest>> {
+    let listener = TcpListener::bind(addr).await?;
+    info!("🔍 Debug endpoint: http://{}/buffers", listener.local_addr()?);
+    let (requests, received) = mpsc::channel(REQUEST_QUEUE);
+    tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tokio::spawn(respond(stream, requests.clone(), metrics.clone()));
+                }
+                Err(e) => warn!("Debug endpoint accept failed: {}", e),
+            }
+        }
+    });
+    Ok(received)
+}
+
+async fn respond(mut stream: TcpStream, requests: mpsc::Sender<BuffersRequest>, metrics: Arc<Metrics>) {
+    let (status, body) = match read_request_line(&mut stream).await {
+        Some(line) => route(&line, &requests, &metrics).await,
+        None => ("400 Bad Request", String::new()),
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    if let Err(e) = stream.write_all(response.as_bytes()).await {
+        debug!("Debug endpoint write failed: {}", e);
+    }
+}
+
+async fn route(line: &str, requests: &mpsc::Sender<BuffersRequest>, metrics: &Metrics) -> (&'static str, String) {
+    let mut words = line.split(' ');
+    let (method, path) = (words.next().unwrap_or_default(), words.next().unwrap_or_default());
+    let path = path.split('?').next().unwrap_or_default();
+    if path != "/buffers" && path != "/health" {
+        return ("404 Not Found", String::new());
+    }
+    if method != "GET" {
+        return ("405 Method Not Allowed", String::new());
+    }
+    if path == "/health" {
+        return health(metrics);
+    }
+    let (reply, report) = oneshot::channel();
+    if requests.try_send(reply).is_err() {
+        return ("503 Service Unavailable", String::new());
+    }
+    match tokio::time::timeout(REPLY_TIMEOUT, report).await {
+        Ok(Ok(report)) => ("200 OK", serde_json::to_string(&report).expect("report serialization is infallible")),
+        _ => ("503 Service Unavailable", String::new()),
+    }
+}
+
+fn health(metrics: &Metrics) -> (&'static str, String) {
+    let snapshot = metrics.snapshot();
+    let health =
+        Health { source: snapshot.source_state, source_restarts: snapshot.source_restarts, packets: snapshot.packets };
+    let status = match health.source {
+        SourceState::Connected => "200 OK",
+        _ => "503 Service Unavailable",
+    };
+    (status, serde_json::to_string(&health).expect("health serialization is infallible"))
+}
+
+/// The request line, once the whole head has arrived
+async fn read_request_line(stream: &mut TcpStream) -> Option<String> {
+    let mut head = Vec::new();
+    let mut chunk = [0u8; 1024];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+        let read = tokio::time::timeout(REPLY_TIMEOUT, stream.read(&mut chunk)).await.ok()?.ok()?;
+        if read == 0 || head.len() >= MAX_REQUEST {
+            return None;
+        }
+        head.extend_from_slice(&chunk[..read]);
+    }
+    let head = String::from_utf8_lossy(&head);
+    head.lines().next().map(str::to_owned)
+}
@@ -0,0 +1,47 @@
+//! Counts byte-identical datagrams seen again within a time window.
+//!
+//! Packets are remembered by a 64-bit hash in two rolling generations, so memory
+//! stays bounded and a hash collision can rarely flag a distinct packet. A packet
+//! is reported as a duplicate if it was seen within the last one to two windows.
+
+use std::{
+    collections::HashSet,
+    hash::{BuildHasher, RandomState},
+    time::{Duration, Instant},
+};
+
+pub struct DuplicateFilter {
+    window: Duration,
+    capacity: usize,
+    hasher: RandomState,
+    current: HashSet<u64>,
+    previous: HashSet<u64>,
+    rotated_at: Instant,
+}
+
+impl DuplicateFilter {
+    /// Remember packets for `window`, tracking at most `capacity` hashes per generation
+    pub fn new(window: Duration, capacity: usize, now: Instant) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            window,
+            capacity,
+            hasher: RandomState::new(),
+            current: HashSet::with_capacity(capacity),
+            previous: HashSet::with_capacity(capacity),
+            rotated_at: now,
+        }
+    }
+
+    /// Record `packet`, returning whether an identical one was seen recently
+    pub fn check(&mut self, packet: &[u8], now: Instant) -> bool {
+        if now.duration_since(self.rotated_at) >= self.window || self.current.len() >= self.capacity {
+            std::mem::swap(&mut self.current, &mut self.previous);
+            self.current.clear();
+            self.rotated_at = now;
+        }
+        let hash = self.hasher.hash_one(packet);
+        let in_previous = self.previous.contains(&hash);
+        !self.current.insert(hash) || in_previous
+    }
+}
@@ -0,0 +1,1363 @@
+//! Scans deserialized entries for pumpfun instructions.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    sync::{Arc, LazyLock},
+};
+
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+use solana_entry::entry::Entry;
+use solana_sdk::{compute_budget, message::VersionedMessage, pubkey, pubkey::Pubkey, signature::Signature};
+use tracing::{debug, info, warn};
+
+use crate::{
+    envelope::{self, EnvelopeError, MessageContext},
+    events::{self, CreateEvent, PumpEvent, TradeEvent, TxStatus},
+    filter::Filter,
+    sanitize,
+};
+
+/// Pumpfun program ID
+pub const PUMPFUN_PROGRAM_ID: &str = "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P";
+
+/// CREATE instruction discriminator
+pub const CREATE_DISC: [u8; 8] = [24, 30, 200, 40, 5, 28, 7, 119];
+
+/// BUY instruction discriminator
+pub const BUY_DISC: [u8; 8] = [102, 6, 61, 18, 1, 218, 235, 234];
+
+/// SELL instruction discriminator; sells aren't decoded, but aren't unknown either
+pub const SELL_DISC: [u8; 8] = [51, 230, 133, 164, 1, 127, 131, 173];
+
+/// `withdraw` instruction discriminator: the protocol authority pulling a completed curve's reserves
+///
+/// This and `COLLECT_CREATOR_FEE_DISC` are best effort: they are `sha256("global:<name>")[..8]` for the
+/// instruction names in pump.fun's published IDL, and admin instructions can be renamed or retired in an upgrade
+/// without anything else changing.
+pub const WITHDRAW_DISC: [u8; 8] = [183, 18, 70, 156, 148, 109, 161, 34];
+
+/// `collect_creator_fee` instruction discriminator: a creator sweeping their fee vault
+pub const COLLECT_CREATOR_FEE_DISC: [u8; 8] = [20, 22, 86, 123, 198, 28, 219, 132];
+
+/// `extend_account` instruction discriminator: a program-owned account, such as a bonding curve, grown to a newer
+/// layout's size
+///
+/// This and `CLOSE_USER_VOLUME_ACCUMULATOR_DISC` are best effort in the same way as `WITHDRAW_DISC`.
+pub const EXTEND_ACCOUNT_DISC: [u8; 8] = [234, 102, 194, 203, 150, 72, 62, 229];
+
+/// `close_user_volume_accumulator` instruction discriminator: a user closing their volume tracking account and
+/// reclaiming its rent
+pub const CLOSE_USER_VOLUME_ACCUMULATOR_DISC: [u8; 8] = [249, 69, 164, 218, 150, 103, 84, 138];
+
+/// PumpSwap AMM program ID, where graduated pumpfun tokens get their pool
+pub const PUMPSWAP_PROGRAM_ID: &str = "pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA";
+
+/// PumpSwap `create_pool` instruction discriminator
+pub const CREATE_POOL_DISC: [u8; 8] = [233, 146, 209, 142, 207, 104, 64, 188];
+
+/// A create references at least this many accounts; the creator is at index 7
+pub const CREATE_MIN_ACCOUNTS: usize = 8;
+
+/// Position of the bonding curve's associated token account, which holds its token reserves,
+/// in a create's accounts: after mint (0), mint_authority (1) and bonding_curve (2)
+pub const ASSOCIATED_BONDING_CURVE_INDEX: usize = 3;
+
+/// Position of pumpfun's global config account in a create's accounts; a new global
+/// account usually means a new protocol version
+pub const GLOBAL_INDEX: usize = 4;
+
+/// Position of the token's Metaplex metadata account in a create's accounts, after the
+/// Token Metadata program itself (5)
+pub const METADATA_INDEX: usize = 6;
+
+/// Metaplex Token Metadata program, which owns every token's metadata account
+pub const TOKEN_METADATA_PROGRAM_ID: Pubkey = pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+/// Associated Token Account program, whose instructions create a wallet's token account for a mint
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey = pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+
+/// SPL Token program
+pub const TOKEN_PROGRAM_ID: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+/// SPL Token-2022 program, whose mint instructions share the classic program's layouts
+pub const TOKEN_2022_PROGRAM_ID: Pubkey = pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+/// Token instruction tags for `InitializeMint` (accounts: mint, rent sysvar) and `InitializeMint2` (accounts: mint);
+/// both take decimals (u8), the mint authority (32 bytes) and an optional freeze authority
+const INITIALIZE_MINT: u8 = 0;
+const INITIALIZE_MINT2: u8 = 20;
+
+/// ATA instruction tags; `Create` may also come as empty data
+const ATA_CREATE: u8 = 0;
+const ATA_CREATE_IDEMPOTENT: u8 = 1;
+
+/// ComputeBudget instruction tags; the tag byte is followed by the little-endian argument
+const SET_COMPUTE_UNIT_LIMIT: u8 = 2;
+const SET_COMPUTE_UNIT_PRICE: u8 = 3;
+
+/// Default cap on bytes read while decoding one message's entries
+pub const DEFAULT_DECODE_LIMIT: u64 = 16 * 1024 * 1024;
+
+/// Default cap on each string in a create's arguments; Metaplex allows up to 200 bytes for a URI
+pub const DEFAULT_ARG_STRING_LIMIT: usize = 256;
+
+/// Default cap on a create's argument bytes after the discriminator
+pub const DEFAULT_ARGS_LIMIT: usize = 1024;
+
+/// Default cap on the instruction data an unknown instruction's detection carries
+pub const DEFAULT_UNKNOWN_DATA_LIMIT: usize = 64;
+
+/// Instructions known by name, watched or not, so never reported as unknown. Anchor discriminators depend only on the
+/// name, so pump.fun's and PumpSwap's `buy` and `sell` share theirs
+const KNOWN_DISCRIMINATORS: [[u8; 8]; 8] = [
+    CREATE_DISC,
+    BUY_DISC,
+    SELL_DISC,
+    WITHDRAW_DISC,
+    COLLECT_CREATOR_FEE_DISC,
+    EXTEND_ACCOUNT_DISC,
+    CLOSE_USER_VOLUME_ACCUMULATOR_DISC,
+    CREATE_POOL_DISC,
+];
+
+/// The layout pump.fun buys are read with to find dev buys when buys themselves aren't watched
+static DEV_BUY_LAYOUT: LazyLock<InstructionLayout> = LazyLock::new(|| InstructionLayout::builtin(InstructionKind::Buy));
+
+/// Instructions the detector knows how to decode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InstructionKind {
+    Create,
+    Buy,
+    /// Admin and fee instructions; logged and counted, but never reported as detections
+    Withdraw,
+    CollectCreatorFee,
+    /// Account maintenance; reported as low-priority [`Detection::Maintenance`] detections
+    ExtendAccount,
+    CloseUserVolumeAccumulator,
+    /// An AMM pool-init, which marks a token's migration off the bonding curve
+    Migration,
+}
+
+impl InstructionKind {
+    /// The kind's name in layouts files and in maintenance detections
+    pub fn name(self) -> &'static str {
+        match self {
+            InstructionKind::Create => "create",
+            InstructionKind::Buy => "buy",
+            InstructionKind::Withdraw => "withdraw",
+            InstructionKind::CollectCreatorFee => "collect_creator_fee",
+            InstructionKind::ExtendAccount => "extend_account",
+            InstructionKind::CloseUserVolumeAccumulator => "close_user_volume_accumulator",
+            InstructionKind::Migration => "migration",
+        }
+    }
+
+    /// Discriminator of the instruction in its default program: pump.fun, or PumpSwap for migrations
+    pub fn discriminator(self) -> [u8; 8] {
+        match self {
+            InstructionKind::Create => CREATE_DISC,
+            InstructionKind::Buy => BUY_DISC,
+            InstructionKind::Withdraw => WITHDRAW_DISC,
+            InstructionKind::CollectCreatorFee => COLLECT_CREATOR_FEE_DISC,
+            InstructionKind::ExtendAccount => EXTEND_ACCOUNT_DISC,
+            InstructionKind::CloseUserVolumeAccumulator => CLOSE_USER_VOLUME_ACCUMULATOR_DISC,
+            InstructionKind::Migration => CREATE_POOL_DISC,
+        }
+    }
+
+    /// Accounts every layout of this kind has to locate
+    pub fn required_roles(self) -> &'static [Role] {
+        match self {
+            InstructionKind::Create => &[Role::Mint, Role::BondingCurve, Role::Creator],
+            InstructionKind::Buy => &[Role::Mint, Role::User],
+            InstructionKind::Withdraw => &[Role::Mint, Role::BondingCurve, Role::User],
+            InstructionKind::CollectCreatorFee => &[Role::Creator, Role::CreatorVault],
+            InstructionKind::ExtendAccount | InstructionKind::CloseUserVolumeAccumulator => &[Role::Account, Role::User],
+            InstructionKind::Migration => &[Role::Mint, Role::Pool],
+        }
+    }
+}
+
+/// An account an instruction layout can locate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Mint,
+    BondingCurve,
+    /// The bonding curve's token account; reported on creates with `create_accounts`
+    AssociatedBondingCurve,
+    /// The token's Metaplex metadata account; reported on creates with `create_accounts`
+    Metadata,
+    Creator,
+    /// The signer of a buy or a maintenance instruction, or the withdraw authority
+    User,
+    CreatorVault,
+    /// The account a maintenance instruction extends or closes
+    Account,
+    Pool,
+}
+
+/// Where one program's instruction of some kind keeps its accounts
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct InstructionLayout {
+    pub kind: InstructionKind,
+    /// Instruction data prefix; Anchor programs use 8 bytes, Raydium AMM v4 a single tag byte.
+    /// The kind's default when left out of a layouts file
+    #[serde(default)]
+    pub discriminator: Vec<u8>,
+    /// Position of each role's account in the instruction's accounts
+    pub accounts: BTreeMap<Role, usize>,
+}
+
+impl InstructionLayout {
+    /// The layout of `kind` in its default program
+    pub fn builtin(kind: InstructionKind) -> Self {
+        let accounts: &[(Role, usize)] = match kind {
+            InstructionKind::Create => &[
+                (Role::Mint, 0),
+                (Role::BondingCurve, 2),
+                (Role::AssociatedBondingCurve, ASSOCIATED_BONDING_CURVE_INDEX),
+                (Role::Metadata, METADATA_INDEX),
+                (Role::Creator, 7),
+            ],
+            InstructionKind::Buy => &[(Role::Mint, 2), (Role::User, 6)],
+            InstructionKind::Withdraw => &[(Role::Mint, 2), (Role::BondingCurve, 3), (Role::User, 6)],
+            InstructionKind::CollectCreatorFee => &[(Role::Creator, 0), (Role::CreatorVault, 1)],
+            InstructionKind::ExtendAccount => &[(Role::Account, 0), (Role::User, 1)],
+            InstructionKind::CloseUserVolumeAccumulator => &[(Role::User, 0), (Role::Account, 1)],
+            InstructionKind::Migration => &[(Role::Pool, 0), (Role::Mint, 3)],
+        };
+        Self { kind, discriminator: kind.discriminator().to_vec(), accounts: accounts.iter().copied().collect() }
+    }
+
+    pub fn index(&self, role: Role) -> Option<usize> {
+        self.accounts.get(&role).copied()
+    }
+
+    /// Accounts an instruction needs for the roles its kind requires to resolve; optional ones past the end are
+    /// left out
+    pub fn min_accounts(&self) -> usize {
+        let required = self.kind.required_roles().iter().filter_map(|&role| self.index(role));
+        required.max().map_or(0, |max| max + 1)
+    }
+
+    /// Reject a layout missing a discriminator or a role its kind needs
+    pub fn validate(&self) -> Result<(), String> {
+        if self.discriminator.is_empty() {
+            return Err(format!("{:?} layout has an empty discriminator", self.kind));
+        }
+        match self.kind.required_roles().iter().find(|role| !self.accounts.contains_key(role)) {
+            Some(role) => Err(format!("{:?} layout doesn't locate the {:?} account", self.kind, role)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Parse a layouts file: a JSON object from base58 program id to that program's instruction layouts
+///
+/// Each layout is validated on its own; [`DetectorConfig::validate`] checks them against each other.
+pub fn parse_layouts(json: &str) -> Result<Vec<(Pubkey, InstructionLayout)>, String> {
+    let programs: BTreeMap<String, Vec<InstructionLayout>> = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    let mut layouts = Vec::new();
+    for (program, program_layouts) in programs {
+        let program_id: Pubkey = program.parse().map_err(|_| format!("invalid program id: {}", program))?;
+        for mut layout in program_layouts {
+            if layout.discriminator.is_empty() {
+                layout.discriminator = layout.kind.discriminator().to_vec();
+            }
+            layout.validate().map_err(|e| format!("{}: {}", program, e))?;
+            layouts.push((program_id, layout));
+        }
+    }
+    Ok(layouts)
+}
+
+/// Decodes instructions the detector doesn't know natively
+///
+/// `data` is the whole instruction data, discriminator included. `accounts` are the
+/// instruction's accounts resolved to pubkeys, in the instruction's order; an index past
+/// the transaction's static keys (an address lookup table entry) resolves to the default
+/// pubkey. Closures of the same shape implement this.
+pub trait InstructionDecoder: Send + Sync {
+    fn decode(&self, data: &[u8], accounts: &[Pubkey]) -> Option<Detection>;
+}
+
+impl<F: Fn(&[u8], &[Pubkey]) -> Option<Detection> + Send + Sync> InstructionDecoder for F {
+    fn decode(&self, data: &[u8], accounts: &[Pubkey]) -> Option<Detection> {
+        self(data, accounts)
+    }
+}
+
+/// Custom decoders by program and 8-byte discriminator
+#[derive(Clone, Default)]
+pub struct DecoderRegistry {
+    decoders: HashMap<(Pubkey, [u8; 8]), Arc<dyn InstructionDecoder>>,
+}
+
+impl DecoderRegistry {
+    /// Decode `program_id` instructions whose data starts with `discriminator`, replacing any
+    /// decoder already registered for them
+    pub fn register(&mut self, program_id: Pubkey, discriminator: [u8; 8], decoder: impl InstructionDecoder + 'static) {
+        self.decoders.insert((program_id, discriminator), Arc::new(decoder));
+    }
+
+    /// The decoder for an instruction of `program_id` with `data`, if one is registered
+    pub fn get(&self, program_id: &Pubkey, data: &[u8]) -> Option<&dyn InstructionDecoder> {
+        if self.decoders.is_empty() {
+            return None;
+        }
+        let discriminator = *data.first_chunk::<8>()?;
+        self.decoders.get(&(*program_id, discriminator)).map(Arc::as_ref)
+    }
+
+    pub fn len(&self) -> usize {
+        self.decoders.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.decoders.is_empty()
+    }
+}
+
+impl fmt::Debug for DecoderRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.decoders.keys().map(|(program, disc)| (program, disc))).finish()
+    }
+}
+
+/// Positions in one program's layouts by discriminator, a map per discriminator length, shortest first
+type DiscriminatorIndex = Vec<(usize, HashMap<Vec<u8>, usize>)>;
+
+/// Which pumpfun instructions to report
+#[derive(Debug, Clone)]
+pub struct DetectorConfig {
+    /// pump.fun itself, whose logged events are merged and which migrations are correlated with
+    pub program_id: Pubkey,
+    /// Messages needing more than this many bytes to decode are rejected, so a
+    /// hostile length prefix can't drive a huge allocation
+    pub decode_limit: u64,
+    /// Only detections matching this are reported
+    pub filter: Option<Filter>,
+    /// Attach every account of a create instruction to its detection
+    pub create_accounts: bool,
+    /// Decode entries one by one, keeping those before a corrupt one instead of dropping the message
+    pub partial_decode: bool,
+    /// Creates declaring a longer name, symbol or URI have their arguments rejected
+    pub arg_string_limit: usize,
+    /// Creates with more argument bytes than this have them rejected unread
+    pub args_limit: usize,
+    /// Position of the global config account in a create's accounts; when set, each create's
+    /// global is reported in [`ProcessedMessage::globals`]
+    pub global_index: Option<usize>,
+    /// Check each create's metadata account against the one derived from its mint, counting mismatches
+    pub verify_metadata: bool,
+    /// Attach token accounts created for a new mint in the same message to its create
+    pub token_accounts: bool,
+    /// Report every `InitializeMint`/`InitializeMint2` of the Token and Token-2022 programs, pump.fun or not
+    pub mint_inits: bool,
+    /// Report instructions of watched programs that no layout, decoder or known discriminator claims as
+    /// [`Detection::Unknown`], with at most this many bytes of their data
+    pub unknown_data_limit: Option<usize>,
+    /// Fold a create and a later buy of its mint by its creator in the same transaction into one
+    /// [`Detection::CreateWithDevBuy`]; pump.fun buys are decoded for this even when they aren't watched
+    pub dev_buys: bool,
+    /// Consulted for instructions the built-in detection doesn't claim
+    pub decoders: DecoderRegistry,
+    /// Watched instructions by program, so an instruction of any other program costs one lookup
+    layouts: HashMap<Pubkey, Vec<InstructionLayout>>,
+    /// Each program's `layouts` by discriminator, so matching an instruction costs a lookup per
+    /// discriminator length rather than a comparison per layout
+    by_discriminator: HashMap<Pubkey, DiscriminatorIndex>,
+}
+
+impl DetectorConfig {
+    pub fn new(program_id: Pubkey, detect_buys: bool, migration: Option<MigrationConfig>) -> Self {
+        let mut layouts = HashMap::from([(program_id, vec![InstructionLayout::builtin(InstructionKind::Create)])]);
+        if detect_buys {
+            layouts.entry(program_id).or_default().push(InstructionLayout::builtin(InstructionKind::Buy));
+        }
+        if let Some(migration) = migration {
+            let layout = InstructionLayout {
+                kind: InstructionKind::Migration,
+                discriminator: migration.discriminator,
+                accounts: BTreeMap::from([(Role::Mint, migration.mint_index), (Role::Pool, migration.pool_index)]),
+            };
+            layouts.entry(migration.program_id).or_default().push(layout);
+        }
+        let mut config = Self {
+            program_id,
+            decode_limit: DEFAULT_DECODE_LIMIT,
+            filter: None,
+            create_accounts: false,
+            partial_decode: false,
+            arg_string_limit: DEFAULT_ARG_STRING_LIMIT,
+            args_limit: DEFAULT_ARGS_LIMIT,
+            global_index: None,
+            verify_metadata: false,
+            token_accounts: false,
+            mint_inits: false,
+            unknown_data_limit: None,
+            dev_buys: false,
+            decoders: DecoderRegistry::default(),
+            layouts,
+            by_discriminator: HashMap::new(),
+        };
+        config.index_layouts();
+        config
+    }
+
+    /// Also look for instructions of `kind`, with its built-in layout in its default program
+    pub fn watch(&mut self, kind: InstructionKind) {
+        let program_id = match kind {
+            InstructionKind::Migration => Pubkey::from_str_const(PUMPSWAP_PROGRAM_ID),
+            _ => self.program_id,
+        };
+        self.set_layout(program_id, InstructionLayout::builtin(kind));
+    }
+
+    /// Decode `program_id`'s instructions of the layout's kind with it, replacing the program's previous one
+    pub fn set_layout(&mut self, program_id: Pubkey, layout: InstructionLayout) {
+        let layouts = self.layouts.entry(program_id).or_default();
+        layouts.retain(|l| l.kind != layout.kind);
+        layouts.push(layout);
+        self.index_layouts();
+    }
+
+    /// Use `layouts` for the kinds they cover, in place of every program's layouts of those kinds.
+    /// Layouts of kinds not watched are left out and returned
+    pub fn use_layouts(&mut self, layouts: Vec<(Pubkey, InstructionLayout)>) -> Vec<(Pubkey, InstructionLayout)> {
+        let (used, unwatched): (Vec<_>, Vec<_>) = layouts.into_iter().partition(|(_, l)| self.watches(l.kind));
+        for (_, layout) in &used {
+            self.layouts.values_mut().for_each(|layouts| layouts.retain(|l| l.kind != layout.kind));
+        }
+        for (program_id, layout) in used {
+            self.layouts.entry(program_id).or_default().push(layout);
+        }
+        self.layouts.retain(|_, layouts| !layouts.is_empty());
+        self.index_layouts();
+        unwatched
+    }
+
+    /// Whether instructions of `kind` are reported
+    pub fn watches(&self, kind: InstructionKind) -> bool {
+        self.layouts.values().flatten().any(|l| l.kind == kind)
+    }
+
+    /// Programs with an instruction of `kind` watched
+    pub fn programs_watching(&self, kind: InstructionKind) -> impl Iterator<Item = &Pubkey> + '_ {
+        self.layouts.iter().filter(move |(_, layouts)| layouts.iter().any(|l| l.kind == kind)).map(|(id, _)| id)
+    }
+
+    /// Every program with an instruction watched, and how each is decoded
+    pub fn layouts(&self) -> &HashMap<Pubkey, Vec<InstructionLayout>> {
+        &self.layouts
+    }
+
+    /// Reject invalid layouts, and two in one program where one discriminator starts the other
+    pub fn validate(&self) -> Result<(), String> {
+        for (program_id, layouts) in &self.layouts {
+            for (i, layout) in layouts.iter().enumerate() {
+                layout.validate().map_err(|e| format!("{}: {}", program_id, e))?;
+                if let Some(other) = layouts[i + 1..].iter().find(|other| {
+                    other.discriminator.starts_with(&layout.discriminator)
+                        || layout.discriminator.starts_with(&other.discriminator)
+                }) {
+                    return Err(format!(
+                        "{}: {:?} and {:?} layouts have overlapping discriminators",
+                        program_id, layout.kind, other.kind
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The watched layout `data` of a `program_id` instruction matches
+    fn layout(&self, program_id: &Pubkey, data: &[u8]) -> Option<&InstructionLayout> {
+        let layouts = self.layouts.get(program_id)?;
+        let mut by_len = self.by_discriminator.get(program_id)?.iter();
+        by_len.find_map(|(len, positions)| positions.get(data.get(..*len)?)).map(|&i| &layouts[i])
+    }
+
+    /// Rebuild `by_discriminator` after `layouts` changed; of overlapping discriminators, which
+    /// [`validate`](Self::validate) rejects, the shortest wins
+    fn index_layouts(&mut self) {
+        self.by_discriminator = self
+            .layouts
+            .iter()
+            .map(|(&program_id, layouts)| {
+                let mut by_len: BTreeMap<usize, HashMap<Vec<u8>, usize>> = BTreeMap::new();
+                for (i, layout) in layouts.iter().enumerate() {
+                    let positions = by_len.entry(layout.discriminator.len()).or_default();
+                    positions.entry(layout.discriminator.clone()).or_insert(i);
+                }
+                (program_id, by_len.into_iter().collect())
+            })
+            .collect();
+    }
+
+    /// The layout to read a pump.fun buy with when it isn't watched but may be a dev buy
+    fn dev_buy_layout(&self, program_id: &Pubkey, data: &[u8]) -> Option<&InstructionLayout> {
+        (self.dev_buys && *program_id == self.program_id && data.starts_with(&BUY_DISC)).then(|| &*DEV_BUY_LAYOUT)
+    }
+}
+
+/// AMM pool-init instruction that marks a token's migration off the bonding curve; shorthand for a
+/// [`InstructionKind::Migration`] layout
+#[derive(Debug, Clone)]
+pub struct MigrationConfig {
+    pub program_id: Pubkey,
+    /// Instruction data prefix; Anchor programs use 8 bytes, Raydium AMM v4 a single tag byte
+    pub discriminator: Vec<u8>,
+    /// Position of the token mint in the instruction's accounts
+    pub mint_index: usize,
+    /// Position of the pool account in the instruction's accounts
+    pub pool_index: usize,
+}
+
+/// An instruction of interest
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Detection {
+    Create {
+        #[serde(with = "pubkey_str")]
+        mint: Pubkey,
+        #[serde(with = "pubkey_str")]
+        bonding_curve: Pubkey,
+        #[serde(with = "pubkey_str")]
+        creator: Pubkey,
+        /// The bonding curve's token account, when `create_accounts` is on
+        #[serde(skip_serializing_if = "Option::is_none", with = "pubkey_opt")]
+        associated_bonding_curve: Option<Pubkey>,
+        /// The token's Metaplex metadata account as the instruction references it, when `create_accounts` is on
+        #[serde(skip_serializing_if = "Option::is_none", with = "pubkey_opt")]
+        metadata: Option<Pubkey>,
+        /// The instruction's accounts in order, when `create_accounts` is on
+        #[serde(skip_serializing_if = "Option::is_none", with = "pubkey_list")]
+        accounts: Option<Vec<Pubkey>>,
+        /// The transaction's compute unit limit and price, when it sets either
+        #[serde(skip_serializing_if = "Option::is_none")]
+        compute_budget: Option<ComputeBudget>,
+        /// Associated token accounts created for the mint in the same message, when `token_accounts` is on
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        token_accounts: Vec<TokenAccount>,
+        /// pump.fun's own event for the create, when the envelope carried the transaction's logs
+        #[serde(skip_serializing_if = "Option::is_none")]
+        event: Option<Box<CreateEvent>>,
+    },
+    /// A create and the creator's own buy of the new token later in the same transaction, with `dev_buys` on
+    CreateWithDevBuy {
+        #[serde(with = "pubkey_str")]
+        mint: Pubkey,
+        #[serde(with = "pubkey_str")]
+        bonding_curve: Pubkey,
+        #[serde(with = "pubkey_str")]
+        creator: Pubkey,
+        /// Lamports the buy cost according to pump.fun's event, or without one the buy's slippage cap
+        dev_buy_sol: u64,
+        /// Tokens bought according to pump.fun's event, or without one the amount requested
+        dev_buy_tokens: u64,
+        /// The rest are the create's, as in [`Detection::Create`]
+        #[serde(skip_serializing_if = "Option::is_none", with = "pubkey_opt")]
+        associated_bonding_curve: Option<Pubkey>,
+        #[serde(skip_serializing_if = "Option::is_none", with = "pubkey_opt")]
+        metadata: Option<Pubkey>,
+        #[serde(skip_serializing_if = "Option::is_none", with = "pubkey_list")]
+        accounts: Option<Vec<Pubkey>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        compute_budget: Option<ComputeBudget>,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        token_accounts: Vec<TokenAccount>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        event: Option<Box<CreateEvent>>,
+    },
+    Buy {
+        #[serde(with = "pubkey_str")]
+        mint: Pubkey,
+        #[serde(with = "pubkey_str")]
+        buyer: Pubkey,
+        /// Token amount requested
+        token_amount: u64,
+        /// Slippage cap in lamports; the actual cost is only known after execution
+        max_sol_cost: u64,
+        /// pump.fun's own event for the buy, with the amounts actually traded, when the envelope
+        /// carried the transaction's logs
+        #[serde(skip_serializing_if = "Option::is_none")]
+        event: Option<TradeEvent>,
+    },
+    Migration {
+        #[serde(with = "pubkey_str")]
+        mint: Pubkey,
+        #[serde(with = "pubkey_str")]
+        pool: Pubkey,
+        /// The mint was also seen in a pumpfun instruction in the same transaction or message
+        from_pumpfun: bool,
+    },
+    /// Liveness signal the pipeline sends its sinks on a timer; never found in a message
+    Heartbeat {
+        /// Datagrams received since the previous heartbeat
+        packets: u64,
+        /// Messages awaiting fragments
+        pending: usize,
+    },
+    /// A new SPL token mint, from any launchpad or none, when `mint_inits` is on
+    MintInit {
+        #[serde(with = "pubkey_str")]
+        mint: Pubkey,
+        decimals: u8,
+        /// The mint authority, who can mint more of the token
+        #[serde(with = "pubkey_str")]
+        authority: Pubkey,
+        /// Initialized through Token-2022 rather than the classic Token program
+        token_2022: bool,
+    },
+    /// An instruction of a watched program that nothing decodes, when `unknown_data_limit` is set
+    Unknown {
+        #[serde(with = "pubkey_str")]
+        program: Pubkey,
+        /// The data's first 8 bytes, or all of it when shorter
+        discriminator: Vec<u8>,
+        /// The data, discriminator included, cut to `unknown_data_limit` bytes, as lowercase hex
+        data_hex: String,
+        /// The data's full length
+        data_len: usize,
+    },
+    /// A watched account maintenance instruction: informational, for following what happens around a token
+    Maintenance {
+        /// `extend_account` or `close_user_volume_accumulator`
+        instruction: InstructionKind,
+        /// The account extended or closed
+        #[serde(with = "pubkey_str")]
+        account: Pubkey,
+        /// The instruction's signer
+        #[serde(with = "pubkey_str")]
+        user: Pubkey,
+    },
+    /// Sent after a slot's detections when the pipeline batches them per slot; never found in a message
+    SlotComplete {
+        slot: u64,
+        /// Detections sent for the slot just before this marker
+        detections: usize,
+    },
+}
+
+impl Detection {
+    /// The `kind` field of the detection's JSON
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Detection::Create { .. } => "create",
+            Detection::CreateWithDevBuy { .. } => "create_with_dev_buy",
+            Detection::Buy { .. } => "buy",
+            Detection::Migration { .. } => "migration",
+            Detection::MintInit { .. } => "mint_init",
+            Detection::Unknown { .. } => "unknown",
+            Detection::Maintenance { .. } => "maintenance",
+            Detection::Heartbeat { .. } => "heartbeat",
+            Detection::SlotComplete { .. } => "slot_complete",
+        }
+    }
+
+    /// Lamports the detection spends: a dev buy's cost, or a buy's, which is its max cost without the trade event
+    pub fn sol_amount(&self) -> Option<u64> {
+        match self {
+            Detection::CreateWithDevBuy { dev_buy_sol, .. } => Some(*dev_buy_sol),
+            Detection::Buy { event: Some(event), .. } => Some(event.sol_amount),
+            Detection::Buy { max_sol_cost, .. } => Some(*max_sol_cost),
+            _ => None,
+        }
+    }
+}
+
+/// Serialize pubkeys as base58 strings rather than raw byte arrays
+pub(crate) mod pubkey_str {
+    use serde::Serializer;
+    use solana_sdk::pubkey::Pubkey;
+
+    pub fn serialize<S: Serializer>(key: &Pubkey, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(key)
+    }
+}
+
+/// Serialize an optional pubkey as a base58 string
+pub(crate) mod pubkey_opt {
+    use serde::Serializer;
+    use solana_sdk::pubkey::Pubkey;
+
+    pub fn serialize<S: Serializer>(key: &Option<Pubkey>, serializer: S) -> Result<S::Ok, S::Error> {
+        match key {
+            Some(key) => serializer.collect_str(key),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
+/// Serialize an optional pubkey list as base58 strings
+pub(crate) mod pubkey_list {
+    use serde::Serializer;
+    use solana_sdk::pubkey::Pubkey;
+
+    pub fn serialize<S: Serializer>(keys: &Option<Vec<Pubkey>>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(keys.iter().flatten().map(ToString::to_string))
+    }
+}
+
+/// An associated token account created for a mint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct TokenAccount {
+    /// The wallet the account belongs to
+    #[serde(with = "pubkey_str")]
+    pub owner: Pubkey,
+    #[serde(with = "pubkey_str")]
+    pub account: Pubkey,
+}
+
+/// What a transaction set through the ComputeBudget program
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ComputeBudget {
+    /// `SetComputeUnitLimit`, in compute units
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit_limit: Option<u32>,
+    /// `SetComputeUnitPrice`, in micro-lamports per compute unit
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit_price: Option<u64>,
+}
+
+impl ComputeBudget {
+    /// Priority fee in lamports, when both the limit and price are set
+    pub fn priority_fee(&self) -> Option<u64> {
+        let micro_lamports = u128::from(self.unit_limit?) * u128::from(self.unit_price?);
+        Some(micro_lamports.div_ceil(1_000_000) as u64)
+    }
+}
+
+/// Decode the unit limit and price from `message`'s ComputeBudget instructions
+///
+/// Each instruction is a tag byte and a little-endian argument: `2` sets the limit (u32) and `3` the price (u64
+/// micro-lamports). Other tags (heap frame, loaded data size) don't affect the fee and are skipped, as are
+/// truncated arguments. `None` when neither is set.
+pub fn compute_budget(message: &VersionedMessage) -> Option<ComputeBudget> {
+    let accounts = message.static_account_keys();
+    let mut budget = ComputeBudget::default();
+    for ix in message.instructions() {
+        if accounts.get(ix.program_id_index as usize) != Some(&compute_budget::ID) {
+            continue;
+        }
+        match ix.data.split_first() {
+            Some((&SET_COMPUTE_UNIT_LIMIT, arg)) => {
+                budget.unit_limit = arg.first_chunk().map(|b| u32::from_le_bytes(*b)).or(budget.unit_limit)
+            }
+            Some((&SET_COMPUTE_UNIT_PRICE, arg)) => {
+                budget.unit_price = arg.first_chunk().map(|b| u64::from_le_bytes(*b)).or(budget.unit_price)
+            }
+            _ => {}
+        }
+    }
+    (budget != ComputeBudget::default()).then_some(budget)
+}
+
+/// The Metaplex metadata account of `mint`: the Token Metadata program's PDA for the seeds
+/// `"metadata"`, the program id and the mint
+pub fn metadata_pda(mint: &Pubkey) -> Pubkey {
+    let seeds: [&[u8]; 3] = [b"metadata", TOKEN_METADATA_PROGRAM_ID.as_ref(), mint.as_ref()];
+    Pubkey::find_program_address(&seeds, &TOKEN_METADATA_PROGRAM_ID).0
+}
+
+/// Decode an `InitializeMint` or `InitializeMint2` instruction's data into its decimals and mint authority
+///
+/// The layout is the tag byte, decimals (u8), the mint authority (32 bytes), then the freeze authority as an
+/// option tag byte and, when that is 1, 32 more bytes. The freeze authority isn't needed and isn't read. `None`
+/// for other instructions or truncated data.
+pub fn decode_initialize_mint(data: &[u8]) -> Option<(u8, Pubkey)> {
+    let (&tag, rest) = data.split_first()?;
+    if tag != INITIALIZE_MINT && tag != INITIALIZE_MINT2 {
+        return None;
+    }
+    let (&decimals, rest) = rest.split_first()?;
+    Some((decimals, Pubkey::new_from_array(*rest.first_chunk()?)))
+}
+
+/// Metadata passed to a create: Borsh `name`, `symbol` and `uri` strings, each a u32 LE length then UTF-8
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreateArgs {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+}
+
+/// Why a create's arguments weren't decoded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArgsError {
+    /// More argument bytes than the configured limit
+    TooLarge { len: usize, limit: usize },
+    /// A string's length prefix exceeds the configured limit
+    StringTooLong { len: usize, limit: usize },
+    /// The data ends before the declared strings do
+    Truncated,
+}
+
+impl ArgsError {
+    /// Whether a configured limit was hit, rather than the data being malformed
+    pub fn is_limit(&self) -> bool {
+        matches!(self, ArgsError::TooLarge { .. } | ArgsError::StringTooLong { .. })
+    }
+}
+
+impl fmt::Display for ArgsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArgsError::TooLarge { len, limit } => write!(f, "{} argument bytes exceed the {} byte limit", len, limit),
+            ArgsError::StringTooLong { len, limit } => {
+                write!(f, "{} byte string exceeds the {} byte limit", len, limit)
+            }
+            ArgsError::Truncated => write!(f, "arguments are truncated"),
+        }
+    }
+}
+
+impl std::error::Error for ArgsError {}
+
+/// Decode a create's arguments from its instruction data, led by a `discriminator_len`-byte discriminator
+///
+/// Both limits are checked before anything is copied, so a hostile length prefix costs a comparison rather
+/// than an allocation. Bytes after the URI (newer program versions append a creator) are ignored. Strings are
+/// decoded lossily and sanitized, as in logged events.
+pub fn decode_create_args(
+    data: &[u8],
+    discriminator_len: usize,
+    config: &DetectorConfig,
+) -> Result<CreateArgs, ArgsError> {
+    let mut rest = data.get(discriminator_len..).ok_or(ArgsError::Truncated)?;
+    if rest.len() > config.args_limit {
+        return Err(ArgsError::TooLarge { len: rest.len(), limit: config.args_limit });
+    }
+    let mut string = || {
+        let (prefix, tail) = rest.split_first_chunk::<4>().ok_or(ArgsError::Truncated)?;
+        let len = u32::from_le_bytes(*prefix) as usize;
+        if len > config.arg_string_limit {
+            return Err(ArgsError::StringTooLong { len, limit: config.arg_string_limit });
+        }
+        let bytes = tail.get(..len).ok_or(ArgsError::Truncated)?;
+        rest = &tail[len..];
+        Ok(sanitize::sanitize(&sanitize::decode_lossy(bytes)).into_owned())
+    };
+    Ok(CreateArgs { name: string()?, symbol: string()?, uri: string()? })
+}
+
+/// Outcome of scanning one reassembled message
+#[derive(Debug, Default)]
+pub struct ProcessedMessage {
+    pub entries: usize,
+    pub transactions: usize,
+    /// In message order: by entry, then transaction, then instruction within it
+    pub detections: Vec<Detection>,
+    /// Outcome of each detection's transaction, in step with `detections`; known only for transactions the
+    /// envelope carried logs for
+    pub statuses: Vec<TxStatus>,
+    /// First signature of each detection's transaction, in step with `detections`
+    pub signatures: Vec<Signature>,
+    /// With `partial_decode`, the entry that failed to decode; only the ones before it were scanned
+    pub failed_entry: Option<usize>,
+    /// Detections dropped by the config's filter
+    pub filtered: usize,
+    /// Transactions skipped for having no account keys or no instructions
+    pub malformed: usize,
+    /// Creates whose arguments broke the configured size limits and were left undecoded
+    pub oversized_args: usize,
+    /// With `global_index`, the global account each create referenced, in message order
+    pub globals: Vec<Pubkey>,
+    /// With `verify_metadata`, creates whose metadata account isn't the one derived from their mint
+    pub metadata_mismatches: usize,
+    /// From the message's envelope, when the proxy sent one
+    pub context: Option<MessageContext>,
+    /// Bytes left over after the entries, which are ignored
+    pub trailing_bytes: usize,
+    /// pump.fun events read from the envelope's transaction logs
+    pub events: usize,
+    /// The entries held no transactions at all, only ticks, so there was nothing to scan
+    pub tick_only: bool,
+    /// Watched admin and fee instructions, which are logged rather than detected
+    pub admin: usize,
+}
+
+/// Why a message couldn't be scanned at all
+#[derive(Debug)]
+pub enum ProcessError {
+    /// The message's envelope was malformed
+    Envelope(EnvelopeError),
+    /// The entries are larger than the config's `decode_limit`
+    SizeLimit { limit: u64 },
+    /// The bytes aren't bincode-serialized entries, or with `partial_decode` not even the first entry decoded
+    Deserialize(bincode::Error),
+}
+
+impl fmt::Display for ProcessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProcessError::Envelope(e) => write!(f, "{}", e),
+            ProcessError::SizeLimit { limit } => write!(f, "entries exceed the {} byte decode limit", limit),
+            ProcessError::Deserialize(e) => write!(f, "failed to deserialize entries: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ProcessError {}
+
+/// Open the message's [envelope](crate::envelope), if any, and process the entries inside
+pub fn process_message(data: &[u8], config: &DetectorConfig, msg_seq: u64) -> Result<ProcessedMessage, ProcessError> {
+    let (mut context, entries) = envelope::open_with_limit(data, config.decode_limit).map_err(ProcessError::Envelope)?;
+    let logs: HashMap<Signature, Vec<String>> = context
+        .as_mut()
+        .map(|c| std::mem::take(&mut c.logs).into_iter().map(|tx| (tx.signature, tx.logs)).collect())
+        .unwrap_or_default();
+    Ok(ProcessedMessage { context, ..scan_entries(entries, config, msg_seq, &logs)? })
+}
+
+/// Process entries and detect pumpfun instructions
+///
+/// Detections come back in the order their instructions appear in the message, so replays
+/// and golden files are reproducible; any future parallel scan must keep that order. Bytes
+/// after the entries aren't an error: they are counted in `trailing_bytes` and ignored.
+pub fn process_entries(data: &[u8], config: &DetectorConfig, msg_seq: u64) -> Result<ProcessedMessage, ProcessError> {
+    scan_entries(data, config, msg_seq, &HashMap::new())
+}
+
+/// `process_entries`, merging in the events pump.fun logged in the transactions `logs` has, by first signature
+fn scan_entries(
+    data: &[u8],
+    config: &DetectorConfig,
+    msg_seq: u64,
+    logs: &HashMap<Signature, Vec<String>>,
+) -> Result<ProcessedMessage, ProcessError> {
+    // Same encoding as `bincode::deserialize`, plus the size limit. `Options::deserialize`
+    // drops the limit for slice input, so drive a deserializer directly. It reads through
+    // `rest`, which is left holding whatever follows the entries.
+    let options = bincode::DefaultOptions::new()
+        .with_limit(config.decode_limit)
+        .with_fixint_encoding()
+        .allow_trailing_bytes();
+    let mut rest = data;
+    let mut deserializer = bincode::Deserializer::with_reader(&mut rest, options);
+    let undecodable = |e: bincode::Error| match *e {
+        bincode::ErrorKind::SizeLimit => ProcessError::SizeLimit { limit: config.decode_limit },
+        _ => ProcessError::Deserialize(e),
+    };
+    let mut failed_entry = None;
+    let entries: Vec<Entry> = if config.partial_decode {
+        // Same framing as `Vec<Entry>`: a u64 count, then the entries back to back
+        let count = u64::deserialize(&mut deserializer).map_err(undecodable)?;
+        let mut entries = Vec::new();
+        for index in 0..count {
+            match Entry::deserialize(&mut deserializer) {
+                Ok(entry) => entries.push(entry),
+                Err(e) if index == 0 => return Err(undecodable(e)),
+                Err(e) => {
+                    warn!(
+                        target: "detector",
+                        "Msg #{}: entry {} of {} failed to decode ({}); scanning the {} before it",
+                        msg_seq, index, count, e, index
+                    );
+                    failed_entry = Some(index as usize);
+                    break;
+                }
+            }
+        }
+        entries
+    } else {
+        Vec::deserialize(&mut deserializer).map_err(undecodable)?
+    };
+
+    drop(deserializer);
+    // A footer from a newer proxy, or a fragment that over-delivered; the entries before it are intact
+    let trailing_bytes = if failed_entry.is_none() { rest.len() } else { 0 };
+    if trailing_bytes > 0 {
+        debug!(target: "detector", "Msg #{}: {} bytes after the entries ignored", msg_seq, trailing_bytes);
+    }
+
+    let entries_count = entries.len();
+    let total_txs: usize = entries.iter().map(|e| e.transactions.len()).sum();
+    // Common on tick-heavy streams, and nothing below could find anything in them
+    if total_txs == 0 {
+        debug!(target: "detector", "📦 Msg #{}: {} tick entries, no transactions", msg_seq, entries_count);
+        return Ok(ProcessedMessage {
+            entries: entries_count,
+            failed_entry,
+            trailing_bytes,
+            tick_only: true,
+            ..Default::default()
+        });
+    }
+
+    // Log each message's stats
+    info!(target: "detector", "📦 Msg #{}: {} entries, {} txs", msg_seq, entries_count, total_txs);
+
+    let mut detections = Vec::new();
+    let mut statuses = Vec::new();
+    let mut signatures = Vec::new();
+    let mut malformed = 0;
+    let mut oversized_args = 0;
+    let mut metadata_mismatches = 0;
+    let mut globals = Vec::new();
+    let mut event_count = 0;
+    let mut admin = 0;
+    // (mint, token account) for every ATA creation, matched against the creates once the whole message is scanned
+    let mut created_atas = Vec::new();
+
+    for entry in &entries {
+        for tx in &entry.transactions {
+            let accounts = tx.message.static_account_keys();
+            // Nothing in these could match; a real transaction always has a fee payer and an instruction
+            if accounts.is_empty() || tx.message.instructions().is_empty() {
+                malformed += 1;
+                continue;
+            }
+            let tx_start = detections.len();
+
+            for ix in tx.message.instructions() {
+                let program_idx = ix.program_id_index as usize;
+                if program_idx >= accounts.len() {
+                    continue;
+                }
+
+                let program_id = &accounts[program_idx];
+                let data = ix.data.as_slice();
+                let ix_account = |pos: usize| {
+                    ix.accounts
+                        .get(pos)
+                        .and_then(|&idx| accounts.get(idx as usize).copied())
+                        .unwrap_or_default()
+                };
+
+                // 0: funder, 1: associated token account, 2: wallet, 3: mint
+                if config.token_accounts
+                    && program_id == &ASSOCIATED_TOKEN_PROGRAM_ID
+                    && matches!(data.first(), None | Some(&ATA_CREATE) | Some(&ATA_CREATE_IDEMPOTENT))
+                {
+                    created_atas.push((ix_account(3), TokenAccount { owner: ix_account(2), account: ix_account(1) }));
+                    continue;
+                }
+
+                // 0: mint; the mint authority is in the data
+                if config.mint_inits && (program_id == &TOKEN_PROGRAM_ID || program_id == &TOKEN_2022_PROGRAM_ID) {
+                    if let Some((decimals, authority)) = decode_initialize_mint(data) {
+                        detections.push(Detection::MintInit {
+                            mint: ix_account(0),
+                            decimals,
+                            authority,
+                            token_2022: program_id == &TOKEN_2022_PROGRAM_ID,
+                        });
+                        continue;
+                    }
+                }
+
+                let layout = config.layout(program_id, data).or_else(|| config.dev_buy_layout(program_id, data));
+                let Some(layout) = layout else {
+                    if let Some(decoder) = config.decoders.get(program_id, data) {
+                        let ix_accounts: Vec<Pubkey> = (0..ix.accounts.len()).map(ix_account).collect();
+                        detections.extend(decoder.decode(data, &ix_accounts));
+                    } else if let Some(limit) = config.unknown_data_limit {
+                        detections.extend(unknown_instruction(config, program_id, data, limit));
+                    }
+                    continue;
+                };
+                // Too few accounts for every role to resolve; not the instruction its discriminator suggests
+                if ix.accounts.len() < layout.min_accounts() {
+                    debug!(
+                        target: "detector",
+                        "Msg #{}: {:?} discriminator match with unexpected account count ({})", msg_seq, layout.kind, ix.accounts.len()
+                    );
+                    continue;
+                }
+                let role = |role: Role| layout.index(role).map(ix_account).unwrap_or_default();
+
+                match layout.kind {
+                    InstructionKind::Migration => {
+                        let mint = role(Role::Mint);
+                        let created_here = detections.iter().any(|d| {
+                            matches!(d, Detection::Create { mint: m, .. } | Detection::CreateWithDevBuy { mint: m, .. }
+                                if *m == mint)
+                        });
+                        let pumpfun_in_tx = tx.message.instructions().iter().any(|other| {
+                            accounts.get(other.program_id_index as usize) == Some(&config.program_id)
+                                && other.accounts.iter().any(|&idx| accounts.get(idx as usize) == Some(&mint))
+                        });
+                        detections.push(Detection::Migration {
+                            mint,
+                            pool: role(Role::Pool),
+                            from_pumpfun: created_here || pumpfun_in_tx,
+                        });
+                    }
+                    InstructionKind::Create => {
+                        // The accounts identify the token, so a create is reported whatever its arguments hold
+                        match decode_create_args(data, layout.discriminator.len(), config) {
+                            Ok(args) => debug!(
+                                target: "detector",
+                                "Msg #{}: create {:?} ({}) {}", msg_seq, args.name, args.symbol, args.uri
+                            ),
+                            // Counted and warned about once per stats window, so a flood of these can't flood the log
+                            Err(e) => {
+                                oversized_args += e.is_limit() as usize;
+                                debug!(target: "detector", "Msg #{}: create arguments not decoded: {}", msg_seq, e);
+                            }
+                        }
+                        if let Some(index) = config.global_index {
+                            globals.extend(ix.accounts.get(index).and_then(|&idx| accounts.get(idx as usize)));
+                        }
+                        // An optional role; unlike the required ones, it may lie past the instruction's accounts
+                        let optional = |role: Role| {
+                            let pos = layout.index(role)?;
+                            ix.accounts.get(pos).and_then(|&idx| accounts.get(idx as usize).copied())
+                        };
+                        let metadata = optional(Role::Metadata);
+                        if config.verify_metadata {
+                            let mint = role(Role::Mint);
+                            let derived = metadata_pda(&mint);
+                            if metadata != Some(derived) {
+                                metadata_mismatches += 1;
+                                debug!(
+                                    target: "detector",
+                                    "Msg #{}: create of {} references metadata {:?}, expected {}", msg_seq, mint, metadata, derived
+                                );
+                            }
+                        }
+                        detections.push(Detection::Create {
+                            mint: role(Role::Mint),
+                            bonding_curve: role(Role::BondingCurve),
+                            creator: role(Role::Creator),
+                            associated_bonding_curve: config
+                                .create_accounts
+                                .then(|| optional(Role::AssociatedBondingCurve))
+                                .flatten(),
+                            metadata: metadata.filter(|_| config.create_accounts),
+                            // Out-of-range indices resolve to the default pubkey, as above
+                            accounts: config
+                                .create_accounts
+                                .then(|| (0..ix.accounts.len()).map(ix_account).collect()),
+                            compute_budget: compute_budget(&tx.message),
+                            token_accounts: Vec::new(),
+                            event: None,
+                        });
+                    }
+                    InstructionKind::Buy => {
+                        // Args after the discriminator: amount (u64), max_sol_cost (u64)
+                        let args = data[layout.discriminator.len()..].first_chunk::<16>();
+                        if let Some((amount, max_sol_cost)) = args.map(|args| args.split_at(8)) {
+                            detections.push(Detection::Buy {
+                                mint: role(Role::Mint),
+                                buyer: role(Role::User),
+                                token_amount: u64::from_le_bytes(amount.try_into().unwrap()),
+                                max_sol_cost: u64::from_le_bytes(max_sol_cost.try_into().unwrap()),
+                                event: None,
+                            });
+                        }
+                    }
+                    InstructionKind::Withdraw => {
+                        admin += 1;
+                        info!(
+                            target: "detector",
+                            "🏦 Msg #{}: withdraw of {} from bonding curve {} by {}",
+                            msg_seq, role(Role::Mint), role(Role::BondingCurve), role(Role::User)
+                        );
+                    }
+                    InstructionKind::CollectCreatorFee => {
+                        admin += 1;
+                        info!(
+                            target: "detector",
+                            "💸 Msg #{}: creator fee collected by {} from vault {}",
+                            msg_seq, role(Role::Creator), role(Role::CreatorVault)
+                        );
+                    }
+                    kind @ (InstructionKind::ExtendAccount | InstructionKind::CloseUserVolumeAccumulator) => {
+                        detections.push(Detection::Maintenance {
+                            instruction: kind,
+                            account: role(Role::Account),
+                            user: role(Role::User),
+                        });
+                    }
+                }
+            }
+
+            let tx_logs = tx.signatures.first().and_then(|signature| logs.get(signature));
+            if let Some(tx_logs) = tx_logs {
+                let events = events::parse_logs(tx_logs, &config.program_id);
+                event_count += events.len();
+                for event in events {
+                    merge_event(&mut detections, tx_start, event, config, &tx.message);
+                }
+            }
+            // After the events, so a dev buy is folded in with what it actually cost
+            if config.dev_buys {
+                fold_dev_buys(&mut detections, tx_start, config.watches(InstructionKind::Buy));
+            }
+            statuses.resize(detections.len(), tx_logs.map_or(TxStatus::Unknown, |l| events::tx_status(l)));
+            signatures.resize(detections.len(), tx.signatures.first().copied().unwrap_or_default());
+        }
+    }
+
+    for (mint, token_account) in created_atas {
+        for detection in &mut detections {
+            if let Detection::Create { mint: m, token_accounts, .. }
+            | Detection::CreateWithDevBuy { mint: m, token_accounts, .. } = detection
+            {
+                if *m == mint && !token_accounts.contains(&token_account) {
+                    token_accounts.push(token_account);
+                }
+            }
+        }
+    }
+
+    // Filter last, so migrations still correlate with creates the filter drops
+    let found = detections.len();
+    if let Some(filter) = &config.filter {
+        let kept = detections.into_iter().zip(statuses.into_iter().zip(signatures)).filter(|(d, _)| filter.matches(d));
+        (detections, (statuses, signatures)) = kept.unzip();
+    }
+
+    Ok(ProcessedMessage {
+        entries: entries_count,
+        transactions: total_txs,
+        filtered: found - detections.len(),
+        detections,
+        statuses,
+        signatures,
+        failed_entry,
+        malformed,
+        oversized_args,
+        metadata_mismatches,
+        globals,
+        context: None,
+        trailing_bytes,
+        events: event_count,
+        tick_only: false,
+        admin,
+    })
+}
+
+/// `data` of a `program_id` instruction as an unknown detection, if the program is watched and the data isn't an
+/// instruction known by name
+fn unknown_instruction(config: &DetectorConfig, program_id: &Pubkey, data: &[u8], limit: usize) -> Option<Detection> {
+    if !config.layouts.contains_key(program_id) || KNOWN_DISCRIMINATORS.iter().any(|disc| data.starts_with(disc)) {
+        return None;
+    }
+    Some(Detection::Unknown {
+        program: *program_id,
+        discriminator: data[..data.len().min(8)].to_vec(),
+        data_hex: data[..data.len().min(limit)].iter().map(|byte| format!("{:02x}", byte)).collect(),
+        data_len: data.len(),
+    })
+}
+
+/// Fold each create from `tx_start` on and the first later buy of its mint by its creator into one detection, dropping
+/// the transaction's other buys unless `keep_buys`
+fn fold_dev_buys(detections: &mut Vec<Detection>, tx_start: usize, keep_buys: bool) {
+    let mut tx: Vec<Option<Detection>> = detections.drain(tx_start..).map(Some).collect();
+    for i in 0..tx.len() {
+        let Some(Detection::Create { mint, creator, .. }) = &tx[i] else {
+            continue;
+        };
+        let (mint, creator) = (*mint, *creator);
+        let Some(j) = tx[i + 1..]
+            .iter()
+            .position(|d| matches!(d, Some(Detection::Buy { mint: m, buyer, .. }) if *m == mint && *buyer == creator))
+        else {
+            continue;
+        };
+        let Some(Detection::Buy { token_amount, max_sol_cost, event: trade, .. }) = tx[i + 1 + j].take() else {
+            unreachable!("the buy was just matched");
+        };
+        let Some(Detection::Create {
+            bonding_curve,
+            associated_bonding_curve,
+            metadata,
+            accounts,
+            compute_budget,
+            token_accounts,
+            event,
+            ..
+        }) = tx[i].take()
+        else {
+            unreachable!("the create was just matched");
+        };
+        tx[i] = Some(Detection::CreateWithDevBuy {
+            mint,
+            bonding_curve,
+            creator,
+            dev_buy_sol: trade.as_ref().map_or(max_sol_cost, |trade| trade.sol_amount),
+            dev_buy_tokens: trade.as_ref().map_or(token_amount, |trade| trade.token_amount),
+            associated_bonding_curve,
+            metadata,
+            accounts,
+            compute_budget,
+            token_accounts,
+            event,
+        });
+    }
+    detections.extend(tx.into_iter().flatten().filter(|d| keep_buys || !matches!(d, Detection::Buy { .. })));
+}
+
+/// Fold one of pump.fun's events into the detections the transaction's instructions produced, from
+/// `tx_start` on, or add the detection the instructions missed, e.g. a create through another program
+///
+/// The event is the program's own account of what happened, so its keys win over the ones resolved
+/// from the instruction.
+fn merge_event(
+    detections: &mut Vec<Detection>,
+    tx_start: usize,
+    event: PumpEvent,
+    config: &DetectorConfig,
+    message: &VersionedMessage,
+) {
+    match event {
+        PumpEvent::Create(mut event) => {
+            if !config.create_accounts {
+                event.raw = None;
+            }
+            // Match by mint, or else take a create whose mint an address lookup table hid
+            let position = detections[tx_start..]
+                .iter()
+                .position(|d| matches!(d, Detection::Create { mint, .. } if *mint == event.mint))
+                .or_else(|| {
+                    detections[tx_start..].iter().position(|d| {
+                        matches!(d, Detection::Create { mint, event: None, .. } if *mint == Pubkey::default())
+                    })
+                });
+            match position.map(|i| &mut detections[tx_start + i]) {
+                Some(Detection::Create { mint, bonding_curve, creator, event: slot, .. }) => {
+                    (*mint, *bonding_curve) = (event.mint, event.bonding_curve);
+                    *creator = event.creator.unwrap_or(*creator);
+                    *slot = Some(Box::new(event));
+                }
+                _ => detections.push(Detection::Create {
+                    mint: event.mint,
+                    bonding_curve: event.bonding_curve,
+                    creator: event.creator.unwrap_or(event.user),
+                    associated_bonding_curve: None,
+                    metadata: None,
+                    accounts: None,
+                    compute_budget: compute_budget(message),
+                    token_accounts: Vec::new(),
+                    event: Some(Box::new(event)),
+                }),
+            }
+        }
+        PumpEvent::Trade(event) if event.is_buy && (config.dev_buys || config.watches(InstructionKind::Buy)) => {
+            let matching = detections[tx_start..].iter_mut().find(|d| {
+                matches!(d, Detection::Buy { mint, buyer, event: None, .. } if *mint == event.mint && *buyer == event.user)
+            });
+            match matching {
+                Some(Detection::Buy { event: slot, .. }) => *slot = Some(event),
+                _ => detections.push(Detection::Buy {
+                    mint: event.mint,
+                    buyer: event.user,
+                    token_amount: event.token_amount,
+                    max_sol_cost: event.sol_amount,
+                    event: Some(event),
+                }),
+            }
+        }
+        // Sells aren't detected
+        PumpEvent::Trade(_) => {}
+    }
+}
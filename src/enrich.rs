@@ -0,0 +1,150 @@
+//! Optional RPC enrichment of detections before they reach the sinks.
+//!
+//! Lookups run on the tokio runtime behind a bounded queue, so the recv loop only
+//! ever does a non-blocking enqueue. A lookup that fails or exceeds its timeout
+//! forwards the detection un-enriched rather than dropping it.
+
+use std::{sync::Arc, time::Duration};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::Serialize;
+use serde_json::{json, Value};
+use solana_sdk::pubkey::Pubkey;
+use tokio::{
+    sync::{mpsc, Semaphore},
+    task::JoinHandle,
+};
+use tracing::{debug, warn};
+
+use crate::{
+    detector::Detection,
+    sink::{DetectionEvent, Sinks},
+};
+
+/// Enrichment settings
+#[derive(Debug, Clone)]
+pub struct EnrichConfig {
+    /// Solana JSON-RPC endpoint
+    pub rpc_url: String,
+    /// Per-lookup deadline
+    pub timeout: Duration,
+    /// Maximum lookups in flight at once
+    pub max_concurrency: usize,
+    /// Events waiting for a lookup slot before new ones bypass enrichment
+    pub queue_capacity: usize,
+}
+
+/// Pumpfun bonding curve account state, as read right after the create
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BondingCurveState {
+    pub virtual_token_reserves: u64,
+    pub virtual_sol_reserves: u64,
+    pub real_token_reserves: u64,
+    pub real_sol_reserves: u64,
+    pub token_total_supply: u64,
+    pub complete: bool,
+}
+
+impl BondingCurveState {
+    /// Decode the account data: 8-byte Anchor discriminator, five u64 fields, then a bool
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        let field = |i: usize| {
+            let start = 8 + i * 8;
+            data.get(start..start + 8).map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+        };
+        Some(Self {
+            virtual_token_reserves: field(0)?,
+            virtual_sol_reserves: field(1)?,
+            real_token_reserves: field(2)?,
+            real_sol_reserves: field(3)?,
+            token_total_supply: field(4)?,
+            complete: *data.get(48)? != 0,
+        })
+    }
+}
+
+/// Queue in front of the enrichment task
+pub struct Enricher {
+    tx: mpsc::Sender<DetectionEvent>,
+    sinks: Arc<Sinks>,
+    task: JoinHandle<()>,
+}
+
+impl Enricher {
+    /// Start the enrichment task, forwarding every event to `sinks` once looked up
+    pub fn spawn(config: EnrichConfig, sinks: Arc<Sinks>) -> reqwest::Result<Self> {
+        let client = reqwest::Client::builder().timeout(config.timeout).build()?;
+        let (tx, mut rx) = mpsc::channel::<DetectionEvent>(config.queue_capacity.max(1));
+        let max_concurrency = config.max_concurrency.max(1);
+        let permits = Arc::new(Semaphore::new(max_concurrency));
+        let forward = sinks.clone();
+
+        let task = tokio::spawn(async move {
+            let config = Arc::new(config);
+            while let Some(mut event) = rx.recv().await {
+                let permit = permits.clone().acquire_owned().await.expect("semaphore is never closed");
+                let (client, config, sinks) = (client.clone(), config.clone(), forward.clone());
+                tokio::spawn(async move {
+                    if let Detection::Create { bonding_curve, .. } | Detection::CreateWithDevBuy { bonding_curve, .. } =
+                        &event.detection
+                    {
+                        match tokio::time::timeout(config.timeout, fetch_bonding_curve(&client, &config.rpc_url, bonding_curve)).await {
+                            Ok(Ok(state)) => event.bonding_curve_state = state,
+                            Ok(Err(e)) => debug!(target: "sink", "Enrichment lookup failed for {}: {}", bonding_curve, e),
+                            Err(_) => debug!(target: "sink", "Enrichment lookup timed out for {}", bonding_curve),
+                        }
+                    }
+                    sinks.emit(event);
+                    // Release the sinks before the permit so shutdown sees the last reference go
+                    drop(sinks);
+                    drop(permit);
+                });
+            }
+            // Wait for in-flight lookups before reporting the queue drained
+            let _ = permits.acquire_many(max_concurrency as u32).await;
+        });
+
+        Ok(Self { tx, sinks, task })
+    }
+
+    /// Queue an event for enrichment; when the queue is full it goes straight to the sinks
+    pub fn submit(&self, event: DetectionEvent) {
+        if let Err(e) = self.tx.try_send(event) {
+            let event = match e {
+                mpsc::error::TrySendError::Full(event) | mpsc::error::TrySendError::Closed(event) => event,
+            };
+            self.sinks.emit(event);
+        }
+    }
+
+    /// Stop accepting events and wait for queued and in-flight lookups to finish
+    pub async fn shutdown(self) {
+        drop(self.tx);
+        if let Err(e) = self.task.await {
+            warn!(target: "sink", "Enrichment task failed: {}", e);
+        }
+    }
+}
+
+async fn fetch_bonding_curve(
+    client: &reqwest::Client,
+    rpc_url: &str,
+    account: &Pubkey,
+) -> Result<Option<BondingCurveState>, Box<dyn std::error::Error + Send + Sync>> {
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "getAccountInfo",
+        "params": [account.to_string(), {"encoding": "base64", "commitment": "processed"}],
+    });
+    let response: Value = client.post(rpc_url).json(&request).send().await?.error_for_status()?.json().await?;
+    if let Some(error) = response.get("error") {
+        return Err(format!("RPC error: {}", error).into());
+    }
+
+    // A null value means the account isn't visible to the RPC node yet
+    let Some(data) = response.pointer("/result/value/data/0").and_then(Value::as_str) else {
+        return Ok(None);
+    };
+    Ok(BondingCurveState::decode(&BASE64.decode(data)?))
+}
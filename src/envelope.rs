@@ -0,0 +1,151 @@
+//! Optional envelope a proxy can wrap a message's entries in, to say where they came from.
+//!
+//! A bare message is a bincode `Vec<Entry>`, which starts with its u64 entry count. An
+//! enveloped one starts with [`ENVELOPE_MAGIC`] instead, which as a count would be over a
+//! billion entries, so the two can't be confused. Layout, integers little-endian:
+//!
+//! | Bytes | Field |
+//! |-------|-------|
+//! | 4 | magic `SHEV` |
+//! | 1 | version, currently 1 |
+//! | 8 | slot |
+//! | 4 | index of the first shred the entries came from |
+//! | 4 | index of the last shred, inclusive |
+//! | 8 | capture time at the proxy, unix microseconds |
+//! | 2 | proxy hostname length `n` |
+//! | n | proxy hostname, UTF-8 |
+//! | 4 | meta length `m`; version 2 only |
+//! | m | bincode `Vec<TransactionLogs>`; version 2 only |
+//! | rest | bincode `Vec<Entry>` |
+//!
+//! Version 1 envelopes are still read; [`wrap`] writes version 2.
+
+use std::fmt;
+
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+use solana_sdk::signature::Signature;
+
+use crate::detector::DEFAULT_DECODE_LIMIT;
+
+/// First bytes of an enveloped message
+pub const ENVELOPE_MAGIC: &[u8; 4] = b"SHEV";
+
+/// Envelope version this build writes; it reads this and every earlier one
+pub const ENVELOPE_VERSION: u8 = 2;
+
+/// Where a message's entries came from, as the proxy reported it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MessageContext {
+    pub slot: u64,
+    pub first_shred_index: u32,
+    /// Inclusive
+    pub last_shred_index: u32,
+    /// Unix microseconds when the proxy captured the shreds
+    pub captured_at_us: u64,
+    /// Hostname of the proxy that sent the message
+    pub proxy: String,
+    /// Log messages of the transactions the proxy had meta for; the detector consumes these,
+    /// so they're empty by the time a detection carries the context
+    #[serde(skip)]
+    pub logs: Vec<TransactionLogs>,
+}
+
+/// A transaction's log messages, from the meta the proxy got with it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransactionLogs {
+    /// The transaction's first signature, which identifies it among the entries
+    pub signature: Signature,
+    pub logs: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnvelopeError {
+    UnsupportedVersion(u8),
+    Truncated,
+    InvalidHostname,
+    InvalidMeta,
+    /// The meta needs more than this many bytes to decode
+    MetaSizeLimit { limit: u64 },
+}
+
+impl fmt::Display for EnvelopeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedVersion(version) => write!(f, "unsupported envelope version {}", version),
+            Self::Truncated => write!(f, "envelope header truncated"),
+            Self::InvalidHostname => write!(f, "envelope proxy hostname is not UTF-8"),
+            Self::InvalidMeta => write!(f, "envelope transaction meta doesn't decode"),
+            Self::MetaSizeLimit { limit } => write!(f, "envelope transaction meta exceeds the {} byte decode limit", limit),
+        }
+    }
+}
+
+impl std::error::Error for EnvelopeError {}
+
+/// Split `data` into its envelope, if it has one, and the entry bytes
+pub fn open(data: &[u8]) -> Result<(Option<MessageContext>, &[u8]), EnvelopeError> {
+    open_with_limit(data, DEFAULT_DECODE_LIMIT)
+}
+
+/// Like `open`, reading at most `decode_limit` bytes while decoding the meta, as for a message's entries
+pub fn open_with_limit(data: &[u8], decode_limit: u64) -> Result<(Option<MessageContext>, &[u8]), EnvelopeError> {
+    let Some(mut rest) = data.strip_prefix(ENVELOPE_MAGIC) else {
+        return Ok((None, data));
+    };
+    let mut take = |n: usize| {
+        let (head, tail) = rest.split_at_checked(n).ok_or(EnvelopeError::Truncated)?;
+        rest = tail;
+        Ok(head)
+    };
+    let version = take(1)?[0];
+    if version == 0 || version > ENVELOPE_VERSION {
+        return Err(EnvelopeError::UnsupportedVersion(version));
+    }
+    let slot = u64::from_le_bytes(take(8)?.try_into().unwrap());
+    let first_shred_index = u32::from_le_bytes(take(4)?.try_into().unwrap());
+    let last_shred_index = u32::from_le_bytes(take(4)?.try_into().unwrap());
+    let captured_at_us = u64::from_le_bytes(take(8)?.try_into().unwrap());
+    let len = u16::from_le_bytes(take(2)?.try_into().unwrap());
+    let proxy = std::str::from_utf8(take(len as usize)?).map_err(|_| EnvelopeError::InvalidHostname)?.to_string();
+    let logs = match version {
+        1 => Vec::new(),
+        _ => {
+            let len = u32::from_le_bytes(take(4)?.try_into().unwrap());
+            decode_meta(take(len as usize)?, decode_limit)?
+        }
+    };
+    let context = MessageContext { slot, first_shred_index, last_shred_index, captured_at_us, proxy, logs };
+    Ok((Some(context), rest))
+}
+
+/// Decode a version 2 meta block as `bincode::deserialize` would, reading at most `limit` bytes
+fn decode_meta(mut meta: &[u8], limit: u64) -> Result<Vec<TransactionLogs>, EnvelopeError> {
+    // `Options::deserialize` drops the limit for slice input, so drive a deserializer directly, as for entries
+    let options = bincode::DefaultOptions::new().with_limit(limit).with_fixint_encoding().allow_trailing_bytes();
+    let mut deserializer = bincode::Deserializer::with_reader(&mut meta, options);
+    Vec::deserialize(&mut deserializer).map_err(|e| match *e {
+        bincode::ErrorKind::SizeLimit => EnvelopeError::MetaSizeLimit { limit },
+        _ => EnvelopeError::InvalidMeta,
+    })
+}
+
+/// Wrap serialized entries in an envelope; a hostname over 65535 bytes is truncated
+pub fn wrap(context: &MessageContext, entries: &[u8]) -> Vec<u8> {
+    let len = (0..=context.proxy.len().min(u16::MAX as usize)).rev().find(|&i| context.proxy.is_char_boundary(i));
+    let proxy = &context.proxy.as_bytes()[..len.unwrap_or(0)];
+    let meta = bincode::serialize(&context.logs).expect("log serialization is infallible");
+    let mut data = Vec::with_capacity(35 + proxy.len() + meta.len() + entries.len());
+    data.extend_from_slice(ENVELOPE_MAGIC);
+    data.push(ENVELOPE_VERSION);
+    data.extend_from_slice(&context.slot.to_le_bytes());
+    data.extend_from_slice(&context.first_shred_index.to_le_bytes());
+    data.extend_from_slice(&context.last_shred_index.to_le_bytes());
+    data.extend_from_slice(&context.captured_at_us.to_le_bytes());
+    data.extend_from_slice(&(proxy.len() as u16).to_le_bytes());
+    data.extend_from_slice(proxy);
+    data.extend_from_slice(&(meta.len() as u32).to_le_bytes());
+    data.extend_from_slice(&meta);
+    data.extend_from_slice(entries);
+    data
+}
@@ -0,0 +1,254 @@
+//! pump.fun's Anchor events, read from transaction log messages.
+//!
+//! pump.fun emits an event for every create and trade through `sol_log_data`, which the
+//! runtime logs as `Program data: <base64>`. Decoded, that is an 8-byte discriminator,
+//! `sha256("event:<Name>")[..8]`, then the event's fields Borsh-encoded: pubkeys as 32 raw
+//! bytes, integers little-endian, `bool` as one byte and strings as a u32 length then UTF-8.
+//!
+//! `CreateEvent`: name, symbol, uri (strings), mint, bonding_curve, user (pubkeys); programs
+//! since the 2025 creator-fee upgrade append creator (pubkey), timestamp (i64) and
+//! virtual_token_reserves, virtual_sol_reserves, real_token_reserves, token_total_supply (u64).
+//!
+//! `TradeEvent`: mint (pubkey), sol_amount, token_amount (u64), is_buy (bool), user (pubkey),
+//! timestamp (i64), virtual_sol_reserves, virtual_token_reserves (u64). Newer programs append
+//! reserves and fees, which aren't read.
+//!
+//! Fields an event doesn't carry are `None`, and anything after the fields read is ignored, so
+//! both layouts decode. The strings are decoded lossily and sanitized (see `sanitize`), so an event
+//! with invalid UTF-8 or control characters in its name still decodes.
+//!
+//! The same logs tell whether the transaction succeeded: the runtime logs `Program <id> failed: <reason>`
+//! for the instruction that failed, and for each program up the invoke stack, and a failed transaction is
+//! reverted as a whole, events included.
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{Serialize, Serializer};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{
+    detector::{pubkey_opt, pubkey_str},
+    sanitize,
+};
+
+/// `sha256("event:CreateEvent")[..8]`
+pub const CREATE_EVENT_DISC: [u8; 8] = [27, 114, 169, 77, 222, 235, 99, 118];
+
+/// `sha256("event:TradeEvent")[..8]`
+pub const TRADE_EVENT_DISC: [u8; 8] = [189, 219, 127, 211, 78, 230, 97, 238];
+
+const DATA_PREFIX: &str = "Program data: ";
+
+/// A token launch, as pump.fun logged it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CreateEvent {
+    /// Sanitized for output; see `raw` for the bytes
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    /// A string holds bidi controls or zero-width characters, which can make a name display as another token's
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub suspicious: bool,
+    /// The strings' bytes as logged, when sanitizing changed any of them. The detector keeps them only with
+    /// `create_accounts`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raw: Option<Box<RawStrings>>,
+    #[serde(with = "pubkey_str")]
+    pub mint: Pubkey,
+    #[serde(with = "pubkey_str")]
+    pub bonding_curve: Pubkey,
+    /// The signer who paid for the create
+    #[serde(with = "pubkey_str")]
+    pub user: Pubkey,
+    /// Who earns the creator fees; absent before the creator-fee upgrade
+    #[serde(with = "pubkey_opt", skip_serializing_if = "Option::is_none")]
+    pub creator: Option<Pubkey>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<i64>,
+    /// The curve's starting reserves
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reserves: Option<CreateReserves>,
+}
+
+/// A create event's strings before decoding, serialized as base64
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RawStrings {
+    #[serde(serialize_with = "base64_bytes")]
+    pub name: Vec<u8>,
+    #[serde(serialize_with = "base64_bytes")]
+    pub symbol: Vec<u8>,
+    #[serde(serialize_with = "base64_bytes")]
+    pub uri: Vec<u8>,
+}
+
+fn base64_bytes<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&BASE64.encode(bytes))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct CreateReserves {
+    pub virtual_token_reserves: u64,
+    pub virtual_sol_reserves: u64,
+    pub real_token_reserves: u64,
+    pub token_total_supply: u64,
+}
+
+/// A buy or sell on the bonding curve, as pump.fun logged it, with the amounts actually traded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct TradeEvent {
+    #[serde(with = "pubkey_str")]
+    pub mint: Pubkey,
+    /// Lamports paid or received
+    pub sol_amount: u64,
+    pub token_amount: u64,
+    pub is_buy: bool,
+    #[serde(with = "pubkey_str")]
+    pub user: Pubkey,
+    pub timestamp: i64,
+    /// The curve's reserves after the trade
+    pub virtual_sol_reserves: u64,
+    pub virtual_token_reserves: u64,
+}
+
+/// Whether a transaction executed successfully, as far as its log messages tell
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TxStatus {
+    /// No logs came with the transaction, or they were truncated before any failure could show
+    #[default]
+    Unknown,
+    Succeeded,
+    /// An instruction failed, so everything the transaction did was reverted
+    Failed,
+}
+
+/// Read a transaction's outcome from its log messages
+pub fn tx_status(logs: &[String]) -> TxStatus {
+    let failed = logs.iter().any(|line| {
+        line.strip_prefix("Program ").is_some_and(|rest| rest.split(' ').nth(1) == Some("failed:"))
+    });
+    match (failed, logs.is_empty() || logs.iter().any(|line| line == "Log truncated")) {
+        (true, _) => TxStatus::Failed,
+        (false, true) => TxStatus::Unknown,
+        (false, false) => TxStatus::Succeeded,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PumpEvent {
+    Create(CreateEvent),
+    Trade(TradeEvent),
+}
+
+/// The events `program_id` logged in a transaction's log messages, in order
+///
+/// Only `Program data:` lines logged while `program_id` itself is executing count, so a
+/// program that invokes it, or that it invokes, can't pass off its own data as an event.
+pub fn parse_logs(logs: &[String], program_id: &Pubkey) -> Vec<PumpEvent> {
+    let program = program_id.to_string();
+    let mut stack: Vec<&str> = Vec::new();
+    let mut events = Vec::new();
+    for line in logs {
+        if let Some(data) = line.strip_prefix(DATA_PREFIX) {
+            if stack.last() == Some(&program.as_str()) {
+                events.extend(BASE64.decode(data.trim()).ok().and_then(|bytes| decode_event(&bytes)));
+            }
+            continue;
+        }
+        let Some(rest) = line.strip_prefix("Program ") else {
+            continue;
+        };
+        let mut words = rest.split(' ');
+        match (words.next(), words.next()) {
+            (Some(id), Some("invoke")) => stack.push(id),
+            (Some(id), Some("success" | "failed:")) if stack.last() == Some(&id) => {
+                stack.pop();
+            }
+            _ => {}
+        }
+    }
+    events
+}
+
+/// Decode one event's bytes, discriminator included
+pub fn decode_event(bytes: &[u8]) -> Option<PumpEvent> {
+    let (disc, rest) = bytes.split_first_chunk::<8>()?;
+    let mut reader = Reader(rest);
+    match *disc {
+        CREATE_EVENT_DISC => {
+            let raw = RawStrings { name: reader.string()?, symbol: reader.string()?, uri: reader.string()? };
+            let decoded = [&raw.name, &raw.symbol, &raw.uri].map(|bytes| sanitize::decode_lossy(bytes));
+            let [name, symbol, uri] = decoded.each_ref().map(|text| sanitize::sanitize(text).into_owned());
+            let altered = [(&name, &raw.name), (&symbol, &raw.symbol), (&uri, &raw.uri)]
+                .iter()
+                .any(|(text, bytes)| text.as_bytes() != bytes.as_slice());
+            let mut event = CreateEvent {
+                name,
+                symbol,
+                uri,
+                suspicious: decoded.iter().any(|text| sanitize::is_suspicious(text)),
+                raw: altered.then(|| Box::new(raw)),
+                mint: reader.pubkey()?,
+                bonding_curve: reader.pubkey()?,
+                user: reader.pubkey()?,
+                creator: None,
+                timestamp: None,
+                reserves: None,
+            };
+            // The newer fields come as a block; a partial one is ignored
+            if let (Some(creator), Some(timestamp)) = (reader.pubkey(), reader.u64()) {
+                event.creator = Some(creator);
+                event.timestamp = Some(timestamp as i64);
+                if let (Some(virtual_token), Some(virtual_sol), Some(real_token), Some(supply)) =
+                    (reader.u64(), reader.u64(), reader.u64(), reader.u64())
+                {
+                    event.reserves = Some(CreateReserves {
+                        virtual_token_reserves: virtual_token,
+                        virtual_sol_reserves: virtual_sol,
+                        real_token_reserves: real_token,
+                        token_total_supply: supply,
+                    });
+                }
+            }
+            Some(PumpEvent::Create(event))
+        }
+        TRADE_EVENT_DISC => Some(PumpEvent::Trade(TradeEvent {
+            mint: reader.pubkey()?,
+            sol_amount: reader.u64()?,
+            token_amount: reader.u64()?,
+            is_buy: reader.u8()? != 0,
+            user: reader.pubkey()?,
+            timestamp: reader.u64()? as i64,
+            virtual_sol_reserves: reader.u64()?,
+            virtual_token_reserves: reader.u64()?,
+        })),
+        _ => None,
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn take(&mut self, n: usize) -> Option<&[u8]> {
+        let (head, rest) = self.0.split_at_checked(n)?;
+        self.0 = rest;
+        Some(head)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.take(1)?[0])
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn pubkey(&mut self) -> Option<Pubkey> {
+        Some(Pubkey::new_from_array(self.take(32)?.try_into().unwrap()))
+    }
+
+    /// A Borsh string's bytes, not yet decoded
+    fn string(&mut self) -> Option<Vec<u8>> {
+        let len = u32::from_le_bytes(self.take(4)?.try_into().unwrap());
+        Some(self.take(len as usize)?.to_vec())
+    }
+}
@@ -0,0 +1,371 @@
+//! A small predicate language for choosing which detections to report.
+//!
+//! ```text
+//! expr       := or
+//! or         := and ("||" and)*
+//! and        := unary ("&&" unary)*
+//! unary      := "!" unary | "(" expr ")" | comparison
+//! comparison := field op value | field "in" list | bool_field
+//! op         := "==" | "!=" | "<" | "<=" | ">" | ">="
+//! ```
+//!
+//! Fields are `kind` (`create`, `create_with_dev_buy`, `buy`, `migration`, `mint_init`, `unknown` or
+//! `maintenance`), the pubkeys `mint`, `bonding_curve`, `creator`, `buyer`, `pool` and `program`, the numbers
+//! `sol_amount` (a buy's max SOL cost, or a dev buy's cost) and `token_amount`, and the bool `from_pumpfun`.
+//! Pubkeys compare with `==`/`!=` or `in` a named list; numbers with any
+//! operator. A comparison on a field the detection doesn't have is false, so
+//! `creator != X` only matches creates but `!(creator == X)` matches buys too.
+
+use std::{collections::HashSet, fmt, str::FromStr, sync::Arc};
+
+use solana_sdk::pubkey::Pubkey;
+
+use crate::detector::Detection;
+
+/// A parsed filter expression
+#[derive(Debug, Clone)]
+pub struct Filter {
+    expr: Expr,
+    source: String,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Kind { equal: bool, kind: &'static str },
+    Key { field: Field, equal: bool, key: Pubkey },
+    KeyIn { field: Field, keys: Arc<HashSet<Pubkey>> },
+    Number { field: Field, op: Op, value: f64 },
+    Bool { field: Field, value: bool },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Kind,
+    Mint,
+    BondingCurve,
+    Creator,
+    Buyer,
+    Pool,
+    Program,
+    SolAmount,
+    TokenAmount,
+    FromPumpfun,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Op(Op),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            Token::Word(word) => return write!(f, "'{}'", word),
+            Token::Op(Op::Eq) => "==",
+            Token::Op(Op::Ne) => "!=",
+            Token::Op(Op::Lt) => "<",
+            Token::Op(Op::Le) => "<=",
+            Token::Op(Op::Gt) => ">",
+            Token::Op(Op::Ge) => ">=",
+            Token::And => "&&",
+            Token::Or => "||",
+            Token::Not => "!",
+            Token::Open => "(",
+            Token::Close => ")",
+        };
+        write!(f, "'{}'", symbol)
+    }
+}
+
+const KINDS: [&str; 7] = ["create", "create_with_dev_buy", "buy", "migration", "mint_init", "unknown", "maintenance"];
+
+impl Field {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "kind" => Field::Kind,
+            "mint" => Field::Mint,
+            "bonding_curve" => Field::BondingCurve,
+            "creator" => Field::Creator,
+            "buyer" => Field::Buyer,
+            "pool" => Field::Pool,
+            "program" => Field::Program,
+            "sol_amount" => Field::SolAmount,
+            "token_amount" => Field::TokenAmount,
+            "from_pumpfun" => Field::FromPumpfun,
+            _ => return None,
+        })
+    }
+
+    fn key(self, detection: &Detection) -> Option<Pubkey> {
+        match (self, detection) {
+            (
+                Field::Mint,
+                Detection::Create { mint, .. }
+                | Detection::CreateWithDevBuy { mint, .. }
+                | Detection::Buy { mint, .. }
+                | Detection::Migration { mint, .. }
+                | Detection::MintInit { mint, .. },
+            ) => Some(*mint),
+            (
+                Field::BondingCurve,
+                Detection::Create { bonding_curve, .. } | Detection::CreateWithDevBuy { bonding_curve, .. },
+            ) => Some(*bonding_curve),
+            (Field::Creator, Detection::Create { creator, .. } | Detection::CreateWithDevBuy { creator, .. }) => {
+                Some(*creator)
+            }
+            (Field::Buyer, Detection::Buy { buyer, .. }) => Some(*buyer),
+            (Field::Pool, Detection::Migration { pool, .. }) => Some(*pool),
+            (Field::Program, Detection::Unknown { program, .. }) => Some(*program),
+            _ => None,
+        }
+    }
+
+    fn number(self, detection: &Detection) -> Option<f64> {
+        match (self, detection) {
+            (Field::SolAmount, Detection::Buy { max_sol_cost, .. }) => Some(*max_sol_cost as f64 / 1_000_000_000.0),
+            (Field::SolAmount, Detection::CreateWithDevBuy { dev_buy_sol, .. }) => {
+                Some(*dev_buy_sol as f64 / 1_000_000_000.0)
+            }
+            (Field::TokenAmount, Detection::Buy { token_amount, .. }) => Some(*token_amount as f64),
+            (Field::TokenAmount, Detection::CreateWithDevBuy { dev_buy_tokens, .. }) => Some(*dev_buy_tokens as f64),
+            _ => None,
+        }
+    }
+
+    fn bool(self, detection: &Detection) -> Option<bool> {
+        match (self, detection) {
+            (Field::FromPumpfun, Detection::Migration { from_pumpfun, .. }) => Some(*from_pumpfun),
+            _ => None,
+        }
+    }
+}
+
+impl Op {
+    fn compare(self, a: f64, b: f64) -> bool {
+        match self {
+            Op::Eq => a == b,
+            Op::Ne => a != b,
+            Op::Lt => a < b,
+            Op::Le => a <= b,
+            Op::Gt => a > b,
+            Op::Ge => a >= b,
+        }
+    }
+}
+
+impl Expr {
+    fn matches(&self, detection: &Detection) -> bool {
+        match self {
+            Expr::And(a, b) => a.matches(detection) && b.matches(detection),
+            Expr::Or(a, b) => a.matches(detection) || b.matches(detection),
+            Expr::Not(e) => !e.matches(detection),
+            Expr::Kind { equal, kind } => (detection.kind() == *kind) == *equal,
+            Expr::Key { field, equal, key } => field.key(detection).is_some_and(|k| (k == *key) == *equal),
+            Expr::KeyIn { field, keys } => field.key(detection).is_some_and(|k| keys.contains(&k)),
+            Expr::Number { field, op, value } => field.number(detection).is_some_and(|n| op.compare(n, *value)),
+            Expr::Bool { field, value } => field.bool(detection) == Some(*value),
+        }
+    }
+}
+
+impl Filter {
+    /// Parse `source`, resolving `in <name>` lists through `lists`
+    pub fn parse(source: &str, lists: impl Fn(&str) -> Option<Vec<String>>) -> Result<Self, String> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser { tokens, pos: 0, lists: &lists };
+        let expr = parser.or()?;
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            return Err(format!("unexpected {} after the end of the expression", token));
+        }
+        Ok(Self { expr, source: source.to_string() })
+    }
+
+    /// Whether `detection` passes the filter
+    pub fn matches(&self, detection: &Detection) -> bool {
+        self.expr.matches(detection)
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+    while let Some((at, c)) = chars.next() {
+        let next = chars.peek().map(|&(_, c)| c);
+        let token = match (c, next) {
+            (c, _) if c.is_whitespace() => continue,
+            ('&', Some('&')) => Token::And,
+            ('|', Some('|')) => Token::Or,
+            ('=', Some('=')) => Token::Op(Op::Eq),
+            ('!', Some('=')) => Token::Op(Op::Ne),
+            ('<', Some('=')) => Token::Op(Op::Le),
+            ('>', Some('=')) => Token::Op(Op::Ge),
+            ('<', _) => Token::Op(Op::Lt),
+            ('>', _) => Token::Op(Op::Gt),
+            ('!', _) => Token::Not,
+            ('(', _) => Token::Open,
+            (')', _) => Token::Close,
+            (c, _) if c.is_ascii_alphanumeric() || c == '_' || c == '.' => {
+                let mut word = c.to_string();
+                while let Some(&(_, c)) = chars.peek().filter(|(_, c)| c.is_ascii_alphanumeric() || *c == '_' || *c == '.') {
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+                continue;
+            }
+            (c, _) => return Err(format!("unexpected '{}' at column {}", c, at + 1)),
+        };
+        // Two-character tokens consume their second character
+        if matches!(token, Token::And | Token::Or | Token::Op(Op::Eq | Op::Ne | Op::Le | Op::Ge)) {
+            chars.next();
+        }
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a, L> {
+    tokens: Vec<Token>,
+    pos: usize,
+    lists: &'a L,
+}
+
+impl<L: Fn(&str) -> Option<Vec<String>>> Parser<'_, L> {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        let found = self.tokens.get(self.pos) == Some(token);
+        self.pos += found as usize;
+        found
+    }
+
+    fn word(&mut self, what: &str) -> Result<String, String> {
+        match self.next() {
+            Some(Token::Word(word)) => Ok(word),
+            Some(token) => Err(format!("expected {}, found {}", what, token)),
+            None => Err(format!("expected {}, found the end of the expression", what)),
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.eat(&Token::Or) {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.unary()?;
+        while self.eat(&Token::And) {
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat(&Token::Not) {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        if self.eat(&Token::Open) {
+            let expr = self.or()?;
+            if !self.eat(&Token::Close) {
+                return Err("missing ')'".to_string());
+            }
+            return Ok(expr);
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr, String> {
+        let name = self.word("a field")?;
+        let field = Field::parse(&name).ok_or_else(|| format!("unknown field '{}'", name))?;
+
+        if self.tokens.get(self.pos) == Some(&Token::Word("in".to_string())) {
+            self.pos += 1;
+            let list = self.word("a list name")?;
+            if matches!(field, Field::Kind | Field::SolAmount | Field::TokenAmount | Field::FromPumpfun) {
+                return Err(format!("'in' needs a pubkey field, not '{}'", name));
+            }
+            let keys = (self.lists)(&list)
+                .ok_or_else(|| format!("unknown list '{}'", list))?
+                .iter()
+                .map(|key| Pubkey::from_str(key).map_err(|_| format!("list '{}' holds an invalid pubkey '{}'", list, key)))
+                .collect::<Result<HashSet<_>, _>>()?;
+            return Ok(Expr::KeyIn { field, keys: Arc::new(keys) });
+        }
+
+        let op = match self.tokens.get(self.pos) {
+            Some(Token::Op(op)) => {
+                let op = *op;
+                self.pos += 1;
+                op
+            }
+            // A bare bool field tests for true
+            _ if field == Field::FromPumpfun => return Ok(Expr::Bool { field, value: true }),
+            _ => return Err(format!("expected an operator after '{}'", name)),
+        };
+        let value = self.word("a value")?;
+        let equality = match op {
+            Op::Eq => Some(true),
+            Op::Ne => Some(false),
+            _ => None,
+        };
+
+        match field {
+            Field::SolAmount | Field::TokenAmount => {
+                let value = value.parse().map_err(|_| format!("'{}' needs a number, not '{}'", name, value))?;
+                Ok(Expr::Number { field, op, value })
+            }
+            _ => {
+                let equal = equality.ok_or_else(|| format!("'{}' only supports == and !=", name))?;
+                match field {
+                    Field::Kind => {
+                        let kind = KINDS
+                            .into_iter()
+                            .find(|k| *k == value)
+                            .ok_or_else(|| format!("unknown kind '{}', expected one of {}", value, KINDS.join(", ")))?;
+                        Ok(Expr::Kind { equal, kind })
+                    }
+                    Field::FromPumpfun => {
+                        let value: bool = value.parse().map_err(|_| format!("'{}' needs true or false, not '{}'", name, value))?;
+                        Ok(Expr::Bool { field, value: value == equal })
+                    }
+                    _ => {
+                        let key = Pubkey::from_str(&value).map_err(|_| format!("'{}' is not a valid pubkey", value))?;
+                        Ok(Expr::Key { field, equal, key })
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,115 @@
+//! Tees received datagrams verbatim to other UDP endpoints.
+//!
+//! Sends happen on their own thread behind a bounded queue, so a slow or
+//! unreachable target never holds up the recv loop: a full queue drops the
+//! datagram, and a failed send drops it for that target, both counted.
+
+use std::{
+    io,
+    net::{SocketAddr, UdpSocket},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    thread::JoinHandle,
+};
+
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tracing::{debug, warn};
+
+/// Forwarding outcomes over a stats window
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ForwardStats {
+    /// Datagrams sent, counting each target separately
+    pub sent: u64,
+    /// Sends that failed, counting each target separately
+    pub failed: u64,
+    /// Datagrams dropped at the full queue, before reaching any target
+    pub dropped: u64,
+}
+
+impl ForwardStats {
+    /// Add `other`'s counters to these
+    pub fn accumulate(&mut self, other: &ForwardStats) {
+        self.sent += other.sent;
+        self.failed += other.failed;
+        self.dropped += other.dropped;
+    }
+}
+
+#[derive(Default)]
+struct ForwardCounters {
+    sent: AtomicU64,
+    failed: AtomicU64,
+    dropped: AtomicU64,
+}
+
+pub struct Forwarder {
+    targets: Vec<SocketAddr>,
+    tx: mpsc::Sender<Vec<u8>>,
+    counters: Arc<ForwardCounters>,
+    worker: JoinHandle<()>,
+}
+
+impl Forwarder {
+    /// Start a thread sending every queued datagram to each of `targets`, buffering up to `capacity`
+    pub fn spawn(targets: Vec<SocketAddr>, capacity: usize) -> io::Result<Self> {
+        let v4 = targets.iter().any(SocketAddr::is_ipv4).then(|| UdpSocket::bind("0.0.0.0:0")).transpose()?;
+        let v6 = targets.iter().any(SocketAddr::is_ipv6).then(|| UdpSocket::bind("[::]:0")).transpose()?;
+        let (tx, mut rx) = mpsc::channel::<Vec<u8>>(capacity.max(1));
+        let counters = Arc::new(ForwardCounters::default());
+        let (shared, destinations) = (counters.clone(), targets.clone());
+
+        let worker = std::thread::Builder::new().name("forward".into()).spawn(move || {
+            while let Some(datagram) = rx.blocking_recv() {
+                for target in &destinations {
+                    let socket = match target {
+                        SocketAddr::V4(_) => v4.as_ref(),
+                        SocketAddr::V6(_) => v6.as_ref(),
+                    };
+                    match socket.expect("a socket is bound for every target family").send_to(&datagram, target) {
+                        Ok(_) => shared.sent.fetch_add(1, Ordering::Relaxed),
+                        Err(e) => {
+                            debug!("Forward to {} failed: {}", target, e);
+                            shared.failed.fetch_add(1, Ordering::Relaxed)
+                        }
+                    };
+                }
+            }
+        })?;
+        Ok(Self { targets, tx, counters, worker })
+    }
+
+    pub fn targets(&self) -> &[SocketAddr] {
+        &self.targets
+    }
+
+    /// Queue a copy of `datagram` for every target without blocking
+    pub fn forward(&self, datagram: &[u8]) {
+        if let Err(TrySendError::Full(_)) = self.tx.try_send(datagram.to_vec()) {
+            self.counters.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Counters since the last call
+    pub fn take_stats(&self) -> ForwardStats {
+        ForwardStats {
+            sent: self.counters.sent.swap(0, Ordering::Relaxed),
+            failed: self.counters.failed.swap(0, Ordering::Relaxed),
+            dropped: self.counters.dropped.swap(0, Ordering::Relaxed),
+        }
+    }
+
+    /// Send what's queued, then stop the thread, returning the counters since the last `take_stats`
+    pub fn shutdown(self) -> ForwardStats {
+        drop(self.tx);
+        if self.worker.join().is_err() {
+            warn!("Forward worker panicked");
+        }
+        ForwardStats {
+            sent: self.counters.sent.load(Ordering::Relaxed),
+            failed: self.counters.failed.load(Ordering::Relaxed),
+            dropped: self.counters.dropped.load(Ordering::Relaxed),
+        }
+    }
+}
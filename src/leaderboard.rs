@@ -0,0 +1,65 @@
+//! Creators with the most creates in the current stats window.
+//!
+//! At most `max_tracked` creators are counted per window. Once that many are tracked,
+//! a new creator replaces the one with the fewest creates, so counts stay exact until
+//! a window sees more distinct creators than that, and prolific ones still surface.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::detector::pubkey_str;
+
+/// Default cap on creators counted per window
+pub const DEFAULT_MAX_TRACKED: usize = 4096;
+
+/// One creator's creates in a window
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TopCreator {
+    #[serde(with = "pubkey_str")]
+    pub creator: Pubkey,
+    pub creates: u32,
+}
+
+pub struct CreatorLeaderboard {
+    top: usize,
+    max_tracked: usize,
+    counts: HashMap<Pubkey, u32>,
+}
+
+impl CreatorLeaderboard {
+    /// Report the `top` creators each window, counting at most `max_tracked`
+    pub fn new(top: usize, max_tracked: usize) -> Self {
+        Self { top, max_tracked: max_tracked.max(1), counts: HashMap::new() }
+    }
+
+    /// Count a create by `creator`
+    pub fn record(&mut self, creator: Pubkey) {
+        if let Some(count) = self.counts.get_mut(&creator) {
+            *count = count.saturating_add(1);
+            return;
+        }
+        if self.counts.len() >= self.max_tracked {
+            let fewest = self.counts.iter().min_by_key(|(_, &count)| count).map(|(&key, _)| key);
+            if let Some(fewest) = fewest {
+                self.counts.remove(&fewest);
+            }
+        }
+        self.counts.insert(creator, 1);
+    }
+
+    /// The top creators, most creates first (ties by pubkey), and start a new window
+    pub fn take(&mut self) -> Vec<TopCreator> {
+        let mut creators: Vec<_> =
+            self.counts.drain().map(|(creator, creates)| TopCreator { creator, creates }).collect();
+        creators.sort_unstable_by(|a, b| b.creates.cmp(&a.creates).then(a.creator.cmp(&b.creator)));
+        creators.truncate(self.top);
+        creators
+    }
+
+    /// Creators counted so far this window
+    pub fn tracked(&self) -> usize {
+        self.counts.len()
+    }
+}
@@ -0,0 +1,40 @@
+//! Tiny Shreds UDP Client library
+//!
+//! Reassembles fragmented shred messages received from shredstream_proxy and
+//! scans the resulting entries for pumpfun instructions.
+
+pub mod adaptive;
+pub mod affinity;
+pub mod allowlist;
+pub mod capture;
+pub mod clock;
+pub mod coalesce;
+pub mod codec;
+pub mod cooldown;
+pub mod debug;
+pub mod dedup;
+pub mod detector;
+pub mod enrich;
+pub mod envelope;
+pub mod events;
+pub mod filter;
+pub mod forward;
+pub mod leaderboard;
+pub mod logging;
+pub mod metrics;
+pub mod pcap;
+pub mod pipeline;
+pub mod price;
+pub mod ratelimit;
+pub mod reaction;
+pub mod reassembler;
+pub mod reload;
+pub mod replay;
+pub mod sanitize;
+pub mod score;
+pub mod sink;
+pub mod slotbatch;
+pub mod slots;
+pub mod source;
+pub mod verify;
+pub mod watchdog;
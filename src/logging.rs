@@ -0,0 +1,138 @@
+//! Tracing setup: a console layer plus an optional rolling file, each with its own format.
+
+use std::{path::PathBuf, str::FromStr};
+
+use tracing::Subscriber;
+use tracing_appender::{
+    non_blocking::WorkerGuard,
+    rolling::{InitError, RollingFileAppender, Rotation},
+};
+use tracing_subscriber::{
+    fmt::MakeWriter, layer::SubscriberExt, registry::LookupSpan, reload, util::SubscriberInitExt, EnvFilter, Layer,
+    Registry,
+};
+
+/// Line format for one log output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// The `tracing-subscriber` default single-line format
+    Full,
+    Compact,
+    /// Multi-line, for reading at a terminal
+    Pretty,
+    /// One JSON object per line
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "full" => Ok(LogFormat::Full),
+            "compact" => Ok(LogFormat::Compact),
+            "pretty" => Ok(LogFormat::Pretty),
+            "json" => Ok(LogFormat::Json),
+            other => Err(format!("unknown log format: {}", other)),
+        }
+    }
+}
+
+/// How often the log file starts a new segment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogRotation {
+    Minutely,
+    Hourly,
+    Daily,
+    Never,
+}
+
+impl FromStr for LogRotation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "minutely" => Ok(LogRotation::Minutely),
+            "hourly" => Ok(LogRotation::Hourly),
+            "daily" => Ok(LogRotation::Daily),
+            "never" => Ok(LogRotation::Never),
+            other => Err(format!("unknown log rotation: {}", other)),
+        }
+    }
+}
+
+impl From<LogRotation> for Rotation {
+    fn from(rotation: LogRotation) -> Self {
+        match rotation {
+            LogRotation::Minutely => Rotation::MINUTELY,
+            LogRotation::Hourly => Rotation::HOURLY,
+            LogRotation::Daily => Rotation::DAILY,
+            LogRotation::Never => Rotation::NEVER,
+        }
+    }
+}
+
+/// Rolling log file settings
+#[derive(Debug, Clone)]
+pub struct FileLogConfig {
+    /// Segments are written next to this path, named `<file name>.<date>`
+    pub path: PathBuf,
+    pub rotation: LogRotation,
+    /// Segments to keep; 0 keeps all
+    pub retain: usize,
+    pub format: LogFormat,
+}
+
+/// The installed subscriber; keep it alive until exit so buffered file lines are flushed
+pub struct Logging {
+    filter: reload::Handle<EnvFilter, Registry>,
+    _file_guard: Option<WorkerGuard>,
+}
+
+impl Logging {
+    /// Replace the active filter with `directives`, in `RUST_LOG` syntax
+    pub fn set_filter(&self, directives: &str) -> Result<(), String> {
+        let filter = EnvFilter::try_new(directives).map_err(|e| e.to_string())?;
+        self.filter.reload(filter).map_err(|e| e.to_string())
+    }
+}
+
+/// Install the global subscriber, filtered by `directives` in `RUST_LOG` syntax
+///
+/// File writes go through a background thread, so a slow disk never blocks the caller.
+pub fn init(directives: &str, console: LogFormat, file: Option<&FileLogConfig>) -> Result<Logging, InitError> {
+    let (filter, handle) = reload::Layer::new(EnvFilter::try_new(directives).unwrap_or_else(|_| EnvFilter::new("info")));
+    let mut layers = vec![fmt_layer(console, std::io::stdout, true)];
+
+    let guard = match file {
+        Some(config) => {
+            let dir = config.path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(".".as_ref());
+            let prefix = config.path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+            let mut builder = RollingFileAppender::builder().rotation(config.rotation.into()).filename_prefix(prefix);
+            if config.retain > 0 {
+                builder = builder.max_log_files(config.retain);
+            }
+            let (writer, guard) = tracing_appender::non_blocking(builder.build(dir)?);
+            layers.push(fmt_layer(config.format, writer, false));
+            Some(guard)
+        }
+        None => None,
+    };
+
+    tracing_subscriber::registry().with(filter).with(layers).init();
+    Ok(Logging { filter: handle, _file_guard: guard })
+}
+
+fn fmt_layer<S, W>(format: LogFormat, writer: W, ansi: bool) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let layer = tracing_subscriber::fmt::layer().with_writer(writer).with_ansi(ansi);
+    match format {
+        LogFormat::Full => layer.boxed(),
+        LogFormat::Compact => layer.compact().boxed(),
+        LogFormat::Pretty => layer.pretty().boxed(),
+        LogFormat::Json => layer.json().boxed(),
+    }
+}
@@ -4,234 +4,1660 @@
 
 use std::{
     collections::HashMap,
+    future::Future,
     str::FromStr,
-    time::{Duration, Instant},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, LazyLock, RwLock,
+    },
+    time::Duration,
 };
 
-use solana_entry::entry::Entry;
+use arc_swap::ArcSwap;
+
 use solana_sdk::pubkey::Pubkey;
-use tokio::net::UdpSocket;
-use tracing::{info, warn, error, debug};
+use test_shreds::{
+    adaptive::{AdaptiveVerbosity, DEFAULT_BUCKET},
+    affinity,
+    allowlist::{parse_ranges, SourceAllowList},
+    capture::{CaptureConfig, PacketCapture},
+    clock::{Clock, SystemClock},
+    coalesce::BuyCoalescer,
+    cooldown::CreatorCooldown,
+    dedup::DuplicateFilter,
+    detector::{
+        parse_layouts, DetectorConfig, InstructionKind, InstructionLayout, MigrationConfig, Role, CREATE_POOL_DISC,
+        DEFAULT_ARGS_LIMIT, DEFAULT_ARG_STRING_LIMIT, DEFAULT_DECODE_LIMIT, DEFAULT_UNKNOWN_DATA_LIMIT, GLOBAL_INDEX,
+        METADATA_INDEX, PUMPFUN_PROGRAM_ID, PUMPSWAP_PROGRAM_ID,
+    },
+    enrich::{EnrichConfig, Enricher},
+    filter::Filter,
+    forward::Forwarder,
+    leaderboard::{self, CreatorLeaderboard},
+    logging::{self, FileLogConfig, LogFormat, LogRotation, Logging},
+    pcap::PcapReader,
+    pipeline::{log_detection, log_process_error, Pipeline},
+    price::SolPrice,
+    ratelimit::{self, SourceRateLimiter},
+    replay::{replay, ReplaySpeed},
+    reassembler::{FragmentReassembler, DEFAULT_MAX_PENDING, DEFAULT_SIZE_TOLERANCE, MAX_FRAGMENTS_HINT},
+    reload,
+    score::{parse_name_patterns, CreateScorer, ScoreConfig, ScoreWeights},
+    sink::{
+        jsonl::JsonlSink,
+        projection::Projection,
+        rotate::RotationConfig,
+        tcp::{OutputFormat, TcpSink},
+        SinkHandle, Sinks,
+    },
+    source::{
+        bind_udp,
+        supervisor::{RestartPolicy, SupervisedSource},
+        Commitment, QueuePolicy, QueuedSource, SourceKind,
+    },
+    verify::{Verifier, VerifyConfig},
+    watchdog::{StallAction, Watchdog, DEFAULT_STALE_THRESHOLD},
+};
+use tokio::{net::UdpSocket, sync::watch};
+use tracing::{error, info, info_span, warn, Instrument, Span};
 
-/// Pumpfun program ID
-const PUMPFUN_PROGRAM_ID: &str = "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P";
+/// Reloadable settings from `RELOAD_CONFIG_PATH`, taking precedence over the environment
+static OVERRIDES: LazyLock<RwLock<HashMap<String, String>>> = LazyLock::new(Default::default);
 
-/// CREATE instruction discriminator
-const CREATE_DISC: [u8; 8] = [24, 30, 200, 40, 5, 28, 7, 119];
+/// Cleared by `validate --config`, so only the file is checked
+static READ_ENV: AtomicBool = AtomicBool::new(true);
 
-/// Fragment header size
-const HEADER_SIZE: usize = 16;
+/// A setting from the override file, or else the environment
+fn setting(key: &str) -> Option<String> {
+    let env = || READ_ENV.load(Ordering::Relaxed).then(|| std::env::var(key).ok()).flatten();
+    OVERRIDES.read().unwrap().get(key).cloned().or_else(env)
+}
 
-/// Magic bytes for fragmented messages
-const MAGIC: &[u8; 4] = b"SHRD";
+/// Read a setting, falling back to `default` when unset or unparsable
+fn env_or<T: FromStr>(key: &str, default: T) -> T {
+    setting(key).and_then(|v| v.parse().ok()).unwrap_or(default)
+}
 
-/// Fragment reassembler for handling multi-packet messages
-struct FragmentReassembler {
-    buffers: HashMap<u32, FragmentBuffer>,
+/// Where one pipeline reads its settings: `PIPELINE_<NAME>_<KEY>` when set, else `<KEY>`
+#[derive(Debug, Clone, Default)]
+struct Scope {
+    /// Unset for the single pipeline run when `PIPELINES` is
+    name: Option<String>,
 }
 
-struct FragmentBuffer {
-    total_fragments: u16,
-    total_size: u32,
-    received: HashMap<u16, Vec<u8>>,
-    created_at: Instant,
+impl Scope {
+    /// This pipeline's own name for `key`
+    fn scoped_key(&self, key: &str) -> Option<String> {
+        let name = self.name.as_ref()?;
+        Some(format!("{}{}_{}", reload::PIPELINE_PREFIX, name.to_ascii_uppercase(), key))
+    }
+
+    fn setting(&self, key: &str) -> Option<String> {
+        self.scoped_key(key).and_then(|k| setting(&k)).or_else(|| setting(key))
+    }
+
+    fn env_or<T: FromStr>(&self, key: &str, default: T) -> T {
+        self.setting(key).and_then(|v| v.parse().ok()).unwrap_or(default)
+    }
+
+    /// Span labeling this pipeline's log lines
+    fn span(&self) -> Span {
+        match &self.name {
+            Some(name) => info_span!("pipeline", name = %name),
+            None => Span::none(),
+        }
+    }
+
+    /// `message`, prefixed with the pipeline's name when it has one
+    fn label(&self, message: impl std::fmt::Display) -> String {
+        match &self.name {
+            Some(name) => format!("pipeline {}: {}", name, message),
+            None => message.to_string(),
+        }
+    }
 }
 
-impl FragmentReassembler {
-    fn new() -> Self {
-        Self { buffers: HashMap::new() }
+/// Pipelines named in `PIPELINES`, or a single unnamed one when it's unset
+fn scopes_from_env() -> Result<Vec<Scope>, String> {
+    let Some(value) = setting("PIPELINES") else {
+        return Ok(vec![Scope::default()]);
+    };
+    let mut scopes: Vec<Scope> = Vec::new();
+    for name in value.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+        if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(format!("invalid pipeline name in PIPELINES: {}", name));
+        }
+        if scopes.iter().any(|s| s.name.as_deref().is_some_and(|n| n.eq_ignore_ascii_case(name))) {
+            return Err(format!("duplicate pipeline name in PIPELINES: {}", name));
+        }
+        scopes.push(Scope { name: Some(name.to_string()) });
     }
+    match scopes.is_empty() {
+        true => Err("PIPELINES names no pipeline".to_string()),
+        false => Ok(scopes),
+    }
+}
 
-    /// Process incoming packet, returns complete message if reassembly is done
-    fn process_packet(&mut self, data: &[u8]) -> Option<Vec<u8>> {
-        // Check if this is a fragmented message (starts with SHRD magic)
-        if data.len() >= HEADER_SIZE && &data[0..4] == MAGIC {
-            let message_id = u32::from_le_bytes(data[4..8].try_into().unwrap());
-            let fragment_index = u16::from_le_bytes(data[8..10].try_into().unwrap());
-            let total_fragments = u16::from_le_bytes(data[10..12].try_into().unwrap());
-            let total_size = u32::from_le_bytes(data[12..16].try_into().unwrap());
-            let fragment_data = data[HEADER_SIZE..].to_vec();
+/// Resolve `FORWARD_TO`'s comma-separated `host:port` targets
+fn forward_targets(value: &str) -> std::io::Result<Vec<std::net::SocketAddr>> {
+    use std::net::ToSocketAddrs;
 
-            debug!(
-                "Fragment: msg_id={}, idx={}/{}, size={}",
-                message_id, fragment_index + 1, total_fragments, fragment_data.len()
-            );
+    let mut targets = Vec::new();
+    for target in value.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+        let invalid = |e: std::io::Error| std::io::Error::new(e.kind(), format!("FORWARD_TO target {}: {}", target, e));
+        let addr = target.to_socket_addrs().map_err(invalid)?.next();
+        targets.push(addr.ok_or_else(|| invalid(std::io::ErrorKind::NotFound.into()))?);
+    }
+    Ok(targets)
+}
 
-            let entry = self.buffers.entry(message_id).or_insert_with(|| FragmentBuffer {
-                total_fragments,
-                total_size,
-                received: HashMap::new(),
-                created_at: Instant::now(),
-            });
-
-            entry.received.insert(fragment_index, fragment_data);
-
-            // Check if complete
-            if entry.received.len() == total_fragments as usize {
-                let mut complete = Vec::with_capacity(total_size as usize);
-                for i in 0..total_fragments {
-                    if let Some(frag) = entry.received.get(&i) {
-                        complete.extend_from_slice(frag);
-                    }
-                }
-                self.buffers.remove(&message_id);
-                info!("Reassembled message: {} bytes from {} fragments", complete.len(), total_fragments);
-                return Some(complete);
+/// Default `UDP_BIND_ADDR`
+const DEFAULT_BIND_ADDR: &str = "0.0.0.0:9001";
+
+/// Settings pipelines can't share without fighting over a socket or file
+const EXCLUSIVE_SETTINGS: &[&str] = &[
+    "UDP_BIND_ADDR",
+    "REASSEMBLER_SNAPSHOT_PATH",
+    "JSONL_PATH",
+    "TCP_OUTPUT_ADDR",
+    "GRPC_OUTPUT_ADDR",
+    "SQLITE_PATH",
+    "FIFO_PATH",
+    "ZMQ_PUB_ADDR",
+    "CAPTURE_PATH",
+    "DEBUG_HTTP_ADDR",
+];
+
+/// Reject configs where two pipelines end up with the same exclusive setting
+fn check_exclusive(scopes: &[Scope]) -> Result<(), String> {
+    for key in EXCLUSIVE_SETTINGS {
+        let mut seen: HashMap<String, &Scope> = HashMap::new();
+        for scope in scopes {
+            // A Geyser pipeline binds nothing
+            if *key == "UDP_BIND_ADDR" && scope.env_or("SOURCE", SourceKind::Udp) == SourceKind::Geyser {
+                continue;
+            }
+            let Some(value) = scope.setting(key).or_else(|| (*key == "UDP_BIND_ADDR").then(|| DEFAULT_BIND_ADDR.to_string()))
+            else {
+                continue;
+            };
+            if let Some(other) = seen.insert(value.clone(), scope) {
+                return Err(format!(
+                    "pipelines {} and {} both use {}={}; give each its own",
+                    other.name.as_deref().unwrap_or_default(),
+                    scope.name.as_deref().unwrap_or_default(),
+                    key,
+                    value
+                ));
             }
-            None
-        } else {
-            // Non-fragmented message - return as-is
-            Some(data.to_vec())
         }
     }
+    Ok(())
+}
+
+/// Parse a comma-separated list of byte values, e.g. `233,146,209`
+fn parse_discriminator(value: &str) -> Option<Vec<u8>> {
+    value.split(',').map(|b| b.trim().parse().ok()).collect::<Option<Vec<u8>>>().filter(|d| !d.is_empty())
+}
 
-    /// Cleanup old incomplete buffers (call periodically)
-    fn cleanup_old(&mut self) {
-        let max_age = Duration::from_secs(10);
-        self.buffers.retain(|_, v| v.created_at.elapsed() < max_age);
+/// Build the migration detector from env, defaulting to PumpSwap `create_pool`
+fn migration_config_from_env(scope: &Scope) -> Result<Option<MigrationConfig>, Box<dyn std::error::Error>> {
+    if !scope.env_or("DETECT_MIGRATIONS", false) {
+        return Ok(None);
     }
+    let program_id = scope.setting("MIGRATION_PROGRAM_ID").unwrap_or_else(|| PUMPSWAP_PROGRAM_ID.to_string());
+    let discriminator = match scope.setting("MIGRATION_DISC") {
+        Some(v) => parse_discriminator(&v).ok_or_else(|| format!("invalid MIGRATION_DISC: {}", v))?,
+        None => CREATE_POOL_DISC.to_vec(),
+    };
+    Ok(Some(MigrationConfig {
+        program_id: Pubkey::from_str(&program_id)?,
+        discriminator,
+        // PumpSwap create_pool: 0: pool, 3: base_mint
+        mint_index: scope.env_or("MIGRATION_MINT_INDEX", 3),
+        pool_index: scope.env_or("MIGRATION_POOL_INDEX", 0),
+    }))
 }
 
-/// Process entries and detect pumpfun token creates
-/// Returns (creates_found, entries_count, tx_count)
-fn process_entries(data: &[u8], pumpfun_program_id: &Pubkey, msg_seq: u64) -> (usize, usize, usize) {
-    let entries: Vec<Entry> = match bincode::deserialize(data) {
-        Ok(e) => e,
+/// Build the detector settings from env and the override file
+fn detector_config_from_env(scope: &Scope) -> Result<DetectorConfig, Box<dyn std::error::Error>> {
+    let mut config = DetectorConfig::new(
+        Pubkey::from_str(PUMPFUN_PROGRAM_ID)?,
+        scope.env_or("DETECT_BUYS", false),
+        migration_config_from_env(scope)?,
+    );
+    config.create_accounts = scope.env_or("CREATE_ACCOUNTS", false);
+    config.token_accounts = scope.env_or("DETECT_TOKEN_ACCOUNTS", false);
+    config.mint_inits = scope.env_or("DETECT_MINT_INITS", false);
+    config.dev_buys = scope.env_or("DETECT_DEV_BUYS", false);
+    config.unknown_data_limit = scope
+        .env_or("DETECT_UNKNOWN", false)
+        .then(|| scope.env_or("UNKNOWN_DATA_MAX_BYTES", DEFAULT_UNKNOWN_DATA_LIMIT));
+    if scope.env_or("DETECT_ADMIN", false) {
+        config.watch(InstructionKind::Withdraw);
+        config.watch(InstructionKind::CollectCreatorFee);
+    }
+    if scope.env_or("DETECT_MAINTENANCE", false) {
+        config.watch(InstructionKind::ExtendAccount);
+        config.watch(InstructionKind::CloseUserVolumeAccumulator);
+    }
+    config.partial_decode = scope.env_or("PARTIAL_ENTRY_DECODE", false);
+    config.decode_limit = scope.env_or("ENTRY_DECODE_LIMIT_MB", DEFAULT_DECODE_LIMIT / 1024 / 1024) * 1024 * 1024;
+    config.arg_string_limit = scope.env_or("CREATE_ARG_MAX_LEN", DEFAULT_ARG_STRING_LIMIT);
+    config.args_limit = scope.env_or("CREATE_ARGS_MAX_BYTES", DEFAULT_ARGS_LIMIT);
+    config.global_index =
+        scope.env_or("LOG_GLOBAL_ACCOUNT", false).then(|| scope.env_or("CREATE_GLOBAL_INDEX", GLOBAL_INDEX));
+    config.verify_metadata = scope.env_or("VERIFY_METADATA_PDA", false);
+    let metadata_index = scope.env_or("CREATE_METADATA_INDEX", METADATA_INDEX);
+    if metadata_index != METADATA_INDEX {
+        let mut layout = InstructionLayout::builtin(InstructionKind::Create);
+        layout.accounts.insert(Role::Metadata, metadata_index);
+        config.set_layout(config.program_id, layout);
+    }
+    if let Some(path) = scope.setting("PROGRAM_LAYOUTS_PATH") {
+        let layouts = parse_layouts(&std::fs::read_to_string(&path).map_err(|e| format!("{}: {}", path, e))?)
+            .map_err(|e| format!("invalid PROGRAM_LAYOUTS_PATH {}: {}", path, e))?;
+        for (program_id, layout) in config.use_layouts(layouts) {
+            warn!("{}", scope.label(format!("{:?} layout for {} ignored: that kind isn't enabled", layout.kind, program_id)));
+        }
+    }
+    config.validate().map_err(|e| format!("invalid instruction layouts: {}", e))?;
+    if let Some(expr) = scope.setting("DETECTION_FILTER").filter(|e| !e.trim().is_empty()) {
+        let lists = |name: &str| {
+            let value = scope.setting(&format!("{}{}", reload::FILTER_LIST_PREFIX, name.to_ascii_uppercase()))?;
+            Some(value.split(',').map(str::trim).filter(|k| !k.is_empty()).map(String::from).collect())
+        };
+        config.filter = Some(Filter::parse(&expr, lists).map_err(|e| format!("invalid DETECTION_FILTER: {}", e))?);
+    }
+    Ok(config)
+}
+
+/// Replace the overrides with the contents of `path`, returning the keys that were ignored
+fn load_overrides(path: &str) -> std::io::Result<Vec<String>> {
+    let overrides = reload::parse(&std::fs::read_to_string(path)?);
+    *OVERRIDES.write().unwrap() = overrides.values;
+    Ok(overrides.ignored)
+}
+
+/// Detector settings of each running pipeline, swapped on reload
+type Detectors = Vec<(Scope, Arc<ArcSwap<DetectorConfig>>)>;
+
+/// Re-read the override file and apply it to the running process
+fn reload_config(path: &str, logging: &Logging, detectors: &Detectors) {
+    let previous = OVERRIDES.read().unwrap().clone();
+    match load_overrides(path) {
+        Ok(ignored) => {
+            for key in ignored {
+                warn!("🔄 {} can't be changed without a restart; ignored", key);
+            }
+        }
+        Err(e) => {
+            warn!("🔄 Config reload failed to read {}: {}", path, e);
+            return;
+        }
+    }
+    let configs: Result<Vec<_>, _> = detectors
+        .iter()
+        .map(|(scope, _)| detector_config_from_env(scope).map_err(|e| scope.label(e)))
+        .collect();
+    match configs {
+        Ok(configs) => {
+            for ((_, detector), config) in detectors.iter().zip(configs) {
+                detector.store(Arc::new(config));
+            }
+        }
         Err(e) => {
-            warn!("Failed to deserialize entries: {}", e);
-            return (0, 0, 0);
+            warn!("🔄 Config reload rejected, keeping previous settings: {}", e);
+            *OVERRIDES.write().unwrap() = previous;
+            return;
+        }
+    }
+    if let Err(e) = logging.set_filter(&setting("RUST_LOG").unwrap_or_else(|| "info".to_string())) {
+        warn!("🔄 Invalid RUST_LOG in {}, keeping the previous filter: {}", path, e);
+    }
+    info!("🔄 Reloaded settings from {}", path);
+}
+
+/// Start every sink enabled in env
+fn sinks_from_env(scope: &Scope) -> std::io::Result<Sinks> {
+    let mut sinks = Sinks::default();
+    let capacity = scope.env_or("SINK_QUEUE_CAPACITY", 4096);
+    let window = Duration::from_millis(scope.env_or("SINK_BATCH_WINDOW_MS", 0));
+    if !window.is_zero() {
+        info!("Sink batching: events written in batches every {}ms", window.as_millis());
+    }
+    let projection = scope.env_or("OUTPUT_FIELDS", Projection::default());
+    if let Some(fields) = projection.fields() {
+        info!("JSON outputs write only: {}", fields.join(", "));
+    }
+
+    if let Some(path) = scope.setting("JSONL_PATH") {
+        let rotation = RotationConfig {
+            max_bytes: scope.env_or("JSONL_ROTATE_MB", 0u64) * 1024 * 1024,
+            max_age: Some(Duration::from_secs(scope.env_or("JSONL_ROTATE_SECS", 0))).filter(|d| !d.is_zero()),
+            gzip: scope.env_or("JSONL_GZIP", false),
+            retain: scope.env_or("JSONL_RETAIN", 0),
+            retain_age: Some(Duration::from_secs(scope.env_or("JSONL_RETAIN_SECS", 0))).filter(|d| !d.is_zero()),
+        };
+        info!("JSON-lines sink: {}", path);
+        let sink = JsonlSink::open(path, rotation)?.with_projection(projection.clone());
+        sinks.push(SinkHandle::spawn_batched("jsonl", sink, capacity, window)?);
+    }
+
+    if let Some(addr) = scope.setting("TCP_OUTPUT_ADDR") {
+        let format = scope.env_or("TCP_OUTPUT_FORMAT", OutputFormat::Json);
+        info!("TCP output: {} ({:?})", addr, format);
+        let sink = TcpSink::bind(&addr, format)?.with_projection(projection.clone());
+        sinks.push(SinkHandle::spawn_batched("tcp", sink, capacity, window)?);
+    }
+
+    if let Some(addr) = scope.setting("GRPC_OUTPUT_ADDR") {
+        #[cfg(feature = "grpc")]
+        {
+            info!("gRPC output: {}", addr);
+            let sink = test_shreds::sink::grpc::GrpcSink::serve(&addr, capacity)?;
+            sinks.push(SinkHandle::spawn_batched("grpc", sink, capacity, window)?);
+        }
+        #[cfg(not(feature = "grpc"))]
+        warn!("GRPC_OUTPUT_ADDR={} ignored: built without the `grpc` feature", addr);
+    }
+
+    if let Some(path) = scope.setting("SQLITE_PATH") {
+        #[cfg(feature = "sqlite")]
+        {
+            info!("SQLite sink: {}", path);
+            let sink = test_shreds::sink::sqlite::SqliteSink::open(&path, scope.env_or("SQLITE_BATCH_SIZE", 500))?;
+            sinks.push(SinkHandle::spawn_batched("sqlite", sink, capacity, window)?);
+        }
+        #[cfg(not(feature = "sqlite"))]
+        warn!("SQLITE_PATH={} ignored: built without the `sqlite` feature", path);
+    }
+
+    if let Some(endpoint) = scope.setting("ZMQ_PUB_ADDR") {
+        #[cfg(feature = "zmq")]
+        {
+            let format = scope.env_or("ZMQ_PUB_FORMAT", OutputFormat::Json);
+            info!("ZeroMQ PUB output: {} ({:?})", endpoint, format);
+            let sink = test_shreds::sink::zmq::ZmqSink::bind(&zmq_address(&endpoint), format)?
+                .with_projection(projection.clone());
+            sinks.push(SinkHandle::spawn_batched("zmq", sink, capacity, window)?);
+        }
+        #[cfg(not(feature = "zmq"))]
+        warn!("ZMQ_PUB_ADDR={} ignored: built without the `zmq` feature", endpoint);
+    }
+
+    if let Some(path) = scope.setting("FIFO_PATH") {
+        #[cfg(unix)]
+        {
+            info!("FIFO sink: {}", path);
+            let sink = test_shreds::sink::fifo::FifoSink::open(path)?.with_projection(projection);
+            sinks.push(SinkHandle::spawn_batched("fifo", sink, capacity, window)?);
         }
+        #[cfg(not(unix))]
+        warn!("FIFO_PATH={} ignored: named pipes need a Unix system", path);
+    }
+
+    Ok(sinks)
+}
+
+/// Every setting the binary reads, in README order
+const SETTINGS: &[&str] = &[
+    "PIPELINES",
+    "SOURCE",
+    "UDP_BIND_ADDR",
+    "UDP_BIND_INTERFACE",
+    "BIND_RETRY_ATTEMPTS",
+    "BIND_RETRY_INITIAL_MS",
+    "BIND_RETRY_MAX_MS",
+    "GEYSER_ENDPOINT",
+    "GEYSER_X_TOKEN",
+    "GEYSER_COMMITMENT",
+    "SOURCE_RESTART_BACKOFF_MS",
+    "SOURCE_RESTART_MAX_BACKOFF_MS",
+    "SOURCE_RESTART_LIMIT",
+    "REASSEMBLER_SNAPSHOT_PATH",
+    "REASSEMBLY_MIN_COMPLETION",
+    "MAX_PENDING_MESSAGES",
+    "FRAGMENT_SIZE_TOLERANCE",
+    "FRAGMENTS_PER_MESSAGE_HINT",
+    "WORKER_QUEUE_CAPACITY",
+    "WORKER_QUEUE_POLICY",
+    "DUPLICATE_WINDOW_MS",
+    "DUPLICATE_MAX_TRACKED",
+    "ALLOWED_SOURCES",
+    "RATE_LIMIT_PPS",
+    "RATE_LIMIT_BURST",
+    "RATE_LIMIT_MAX_SOURCES",
+    "UDP_PATH_MTU",
+    "STALE_PACKET_SECS",
+    "STALL_TIMEOUT_SECS",
+    "STALL_ACTION",
+    "HEARTBEAT_SECS",
+    "SLOT_BATCH_TIMEOUT_MS",
+    "SLOT_ORDER_WINDOW_MS",
+    "MAX_PACKETS",
+    "MAX_RUNTIME_SECS",
+    "FORWARD_TO",
+    "FORWARD_QUEUE_CAPACITY",
+    "CAPTURE_PATH",
+    "CAPTURE_SAMPLE_EVERY",
+    "CAPTURE_ON_FAILURE",
+    "CAPTURE_MAX_PACKETS",
+    "CAPTURE_MAX_MB",
+    "ENTRY_DECODE_LIMIT_MB",
+    "CREATE_ACCOUNTS",
+    "DETECT_TOKEN_ACCOUNTS",
+    "PARTIAL_ENTRY_DECODE",
+    "CREATE_ARG_MAX_LEN",
+    "CREATE_ARGS_MAX_BYTES",
+    "LOG_GLOBAL_ACCOUNT",
+    "CREATE_GLOBAL_INDEX",
+    "CREATE_METADATA_INDEX",
+    "VERIFY_METADATA_PDA",
+    "DETECT_BUYS",
+    "DETECT_DEV_BUYS",
+    "DETECT_ADMIN",
+    "DETECT_MAINTENANCE",
+    "DETECT_MINT_INITS",
+    "DETECT_UNKNOWN",
+    "UNKNOWN_DATA_MAX_BYTES",
+    "BUY_COALESCE_WINDOW_MS",
+    "BUY_COALESCE_MAX_KEYS",
+    "CREATOR_COOLDOWN_SECS",
+    "SCORE_CREATES",
+    "SCORE_WEIGHTS",
+    "SCORE_FULL_BUY_LAMPORTS",
+    "SCORE_FULL_PRIORITY_FEE_LAMPORTS",
+    "SCORE_NAME_PATTERNS",
+    "SCORE_MIN",
+    "TOP_CREATORS",
+    "TOP_CREATORS_MAX_TRACKED",
+    "DETECTION_FILTER",
+    "DETECT_MIGRATIONS",
+    "MIGRATION_PROGRAM_ID",
+    "MIGRATION_DISC",
+    "MIGRATION_MINT_INDEX",
+    "MIGRATION_POOL_INDEX",
+    "PROGRAM_LAYOUTS_PATH",
+    "JSONL_PATH",
+    "JSONL_ROTATE_MB",
+    "JSONL_ROTATE_SECS",
+    "JSONL_GZIP",
+    "JSONL_RETAIN",
+    "JSONL_RETAIN_SECS",
+    "TCP_OUTPUT_ADDR",
+    "TCP_OUTPUT_FORMAT",
+    "GRPC_OUTPUT_ADDR",
+    "SQLITE_PATH",
+    "SQLITE_BATCH_SIZE",
+    "FIFO_PATH",
+    "ZMQ_PUB_ADDR",
+    "ZMQ_PUB_FORMAT",
+    "OUTPUT_FIELDS",
+    "ENRICH_RPC_URL",
+    "ENRICH_TIMEOUT_MS",
+    "ENRICH_MAX_CONCURRENCY",
+    "VERIFY_RPC_URL",
+    "VERIFY_SAMPLE_EVERY",
+    "VERIFY_DELAY_MS",
+    "VERIFY_TIMEOUT_MS",
+    "VERIFY_MAX_CONCURRENCY",
+    "VERIFY_QUEUE_CAPACITY",
+    "SOL_USD_PRICE",
+    "SOL_USD_PRICE_URL",
+    "SOL_USD_PRICE_POINTER",
+    "SOL_USD_REFRESH_SECS",
+    "SINK_QUEUE_CAPACITY",
+    "SINK_BATCH_WINDOW_MS",
+    "SINK_THROTTLE_RATIO",
+    "DEBUG_HTTP_ADDR",
+    "DEBUG_BUFFERS_LIMIT",
+    "PCAP_REPLAY",
+    "PCAP_REPLAY_SPEED",
+    "LOG_ADAPTIVE_RATE",
+    "LOG_ADAPTIVE_EXIT_RATE",
+    "RELOAD_CONFIG_PATH",
+    "RUNTIME_FLAVOR",
+    "RUNTIME_WORKER_THREADS",
+    "RUNTIME_MAX_BLOCKING_THREADS",
+    "RUNTIME_WORKER_CPUS",
+    "RECV_CPU",
+    "RUST_LOG",
+    "LOG_FORMAT",
+    "LOG_FILE",
+    "LOG_FILE_ROTATION",
+    "LOG_FILE_RETAIN",
+    "LOG_FILE_FORMAT",
+];
+
+/// Strip credentials and query strings, where RPC providers put API keys
+fn redact_url(url: &str) -> String {
+    let (scheme, rest) = url.split_once("://").unwrap_or(("", url));
+    let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    let host = authority.rsplit_once('@').map_or(authority.to_string(), |(_, host)| format!("<redacted>@{}", host));
+    let path = path.split_once('?').map_or(path.to_string(), |(path, _)| format!("{}?<redacted>", path));
+    match scheme {
+        "" => format!("{}{}", host, path),
+        scheme => format!("{}://{}{}", scheme, host, path),
+    }
+}
+
+/// Every explicitly set setting as `KEY=VALUE`, in README order, with URLs and tokens redacted
+fn explicit_settings(scopes: &[Scope]) -> Vec<String> {
+    SETTINGS
+        .iter()
+        .flat_map(|key| std::iter::once(key.to_string()).chain(scopes.iter().filter_map(|s| s.scoped_key(key))))
+        .filter_map(|key| {
+            let value = setting(&key)?;
+            Some(match key {
+                _ if key.ends_with("_TOKEN") => format!("{}=<redacted>", key),
+                _ if key.ends_with("_URL") || key.ends_with("_ENDPOINT") => format!("{}={}", key, redact_url(&value)),
+                _ => format!("{}={}", key, value),
+            })
+        })
+        .collect()
+}
+
+/// Log every explicitly set setting on one line; the rest are at their README defaults
+fn log_settings(scopes: &[Scope]) {
+    let set = explicit_settings(scopes);
+    match set.is_empty() {
+        true => info!("Settings: all defaults"),
+        false => info!("Settings: {}", set.join(" ")),
+    }
+}
+
+/// Optional cargo features, and whether this build has each
+const FEATURES: &[(&str, bool)] = &[
+    ("grpc", cfg!(feature = "grpc")),
+    ("sqlite", cfg!(feature = "sqlite")),
+    ("geyser", cfg!(feature = "geyser")),
+    ("zmq", cfg!(feature = "zmq")),
+];
+
+/// Whether a pipeline's settings turn a capability on
+type Configured = fn(&Scope) -> bool;
+
+/// Capabilities a pipeline's settings turn on, with the cargo feature each needs
+const CAPABILITIES: &[(&str, Option<&str>, Configured)] = &[
+    ("geyser_source", Some("geyser"), |s| s.env_or("SOURCE", SourceKind::Udp) == SourceKind::Geyser),
+    ("buys", None, |s| s.env_or("DETECT_BUYS", false)),
+    ("migrations", None, |s| s.env_or("DETECT_MIGRATIONS", false)),
+    ("dev_buys", None, |s| s.env_or("DETECT_DEV_BUYS", false)),
+    ("admin", None, |s| s.env_or("DETECT_ADMIN", false)),
+    ("maintenance", None, |s| s.env_or("DETECT_MAINTENANCE", false)),
+    ("mint_inits", None, |s| s.env_or("DETECT_MINT_INITS", false)),
+    ("unknown_instructions", None, |s| s.env_or("DETECT_UNKNOWN", false)),
+    ("detection_filter", None, |s| s.setting("DETECTION_FILTER").is_some_and(|f| !f.trim().is_empty())),
+    ("creator_cooldown", None, |s| s.env_or("CREATOR_COOLDOWN_SECS", 0u64) > 0),
+    ("create_scoring", None, |s| s.env_or("SCORE_CREATES", false)),
+    ("top_creators", None, |s| s.env_or("TOP_CREATORS", 0usize) > 0),
+    ("duplicate_filter", None, |s| s.env_or("DUPLICATE_WINDOW_MS", 0u64) > 0),
+    ("allow_list", None, |s| s.setting("ALLOWED_SOURCES").is_some()),
+    ("rate_limit", None, |s| s.env_or("RATE_LIMIT_PPS", 0.0) > 0.0),
+    ("forwarding", None, |s| s.setting("FORWARD_TO").is_some()),
+    ("capture", None, |s| s.setting("CAPTURE_PATH").is_some()),
+    ("snapshot", None, |s| s.setting("REASSEMBLER_SNAPSHOT_PATH").is_some()),
+    ("heartbeat", None, |s| s.env_or("HEARTBEAT_SECS", 0u64) > 0),
+    ("stall_abort", None, |s| {
+        s.env_or("STALL_TIMEOUT_SECS", 10u64) > 0 && s.env_or("STALL_ACTION", StallAction::Log) == StallAction::Abort
+    }),
+    ("slot_batching", None, |s| s.env_or("SLOT_BATCH_TIMEOUT_MS", 0u64) > 0),
+    ("slot_ordering", None, |s| s.env_or("SLOT_ORDER_WINDOW_MS", 0u64) > 0),
+    ("jsonl_sink", None, |s| s.setting("JSONL_PATH").is_some()),
+    ("tcp_sink", None, |s| s.setting("TCP_OUTPUT_ADDR").is_some()),
+    ("grpc_sink", Some("grpc"), |s| s.setting("GRPC_OUTPUT_ADDR").is_some()),
+    ("sqlite_sink", Some("sqlite"), |s| s.setting("SQLITE_PATH").is_some()),
+    ("fifo_sink", None, |s| s.setting("FIFO_PATH").is_some()),
+    ("zmq_sink", Some("zmq"), |s| s.setting("ZMQ_PUB_ADDR").is_some()),
+    ("enrichment", None, |s| s.setting("ENRICH_RPC_URL").is_some()),
+    ("verification", None, |s| s.setting("VERIFY_RPC_URL").is_some()),
+    ("output_projection", None, |s| s.setting("OUTPUT_FIELDS").is_some()),
+    ("sink_batching", None, |s| s.env_or("SINK_BATCH_WINDOW_MS", 0u64) > 0),
+    ("sink_throttle", None, |s| s.env_or("SINK_THROTTLE_RATIO", 0.0) > 0.0),
+    ("debug_endpoint", None, |s| s.setting("DEBUG_HTTP_ADDR").is_some()),
+];
+
+/// What this build has compiled in and what the settings turn on, as JSON
+///
+/// A capability configured in a build without its feature is listed under `missing_feature`, with the feature,
+/// rather than as enabled, since the pipeline ignores or rejects it.
+fn capabilities(scopes: &[Scope]) -> serde_json::Value {
+    let compiled = |feature: Option<&str>| feature.is_none_or(|f| FEATURES.iter().any(|&(name, on)| name == f && on));
+    let pipelines: Vec<_> = scopes
+        .iter()
+        .map(|scope| {
+            let configured = CAPABILITIES.iter().filter(|(_, _, configured)| configured(scope));
+            let (enabled, missing): (Vec<_>, Vec<_>) = configured.partition(|(_, feature, _)| compiled(*feature));
+            serde_json::json!({
+                "name": scope.name,
+                "enabled": enabled.iter().map(|(name, _, _)| name).collect::<Vec<_>>(),
+                "missing_feature": missing
+                    .iter()
+                    .map(|(name, feature, _)| (name.to_string(), feature.unwrap_or_default().into()))
+                    .collect::<serde_json::Map<_, _>>(),
+            })
+        })
+        .collect();
+    let global = [
+        ("reload", setting("RELOAD_CONFIG_PATH").is_some()),
+        ("usd_prices", env_or("SOL_USD_PRICE", 0.0) > 0.0 || setting("SOL_USD_PRICE_URL").is_some()),
+        ("file_log", setting("LOG_FILE").is_some()),
+    ];
+    serde_json::json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "features": FEATURES.iter().map(|&(name, on)| (name.to_string(), on.into())).collect::<serde_json::Map<_, _>>(),
+        "enabled": global.iter().filter(|(_, on)| *on).map(|(name, _)| name).collect::<Vec<_>>(),
+        "pipelines": pipelines,
+    })
+}
+
+/// Checks a setting's value the way the code reading it parses it
+type Check = fn(&str) -> Result<(), String>;
+
+fn parses<T: FromStr>(value: &str) -> Result<(), String>
+where
+    T::Err: std::fmt::Display,
+{
+    value.parse::<T>().map(drop).map_err(|e| e.to_string())
+}
+
+fn any_value(_: &str) -> Result<(), String> {
+    Ok(())
+}
+
+/// A ZeroMQ TCP endpoint, e.g. `tcp://*:5556`, as the `host:port` to bind; `tcp://` is optional and `*` means every
+/// interface
+fn zmq_address(endpoint: &str) -> String {
+    let addr = endpoint.trim().strip_prefix("tcp://").unwrap_or(endpoint.trim());
+    match addr.strip_prefix("*:") {
+        Some(port) => format!("0.0.0.0:{}", port),
+        None => addr.to_string(),
+    }
+}
+
+fn zmq_format(value: &str) -> Result<(), String> {
+    match value.parse::<OutputFormat>()? {
+        OutputFormat::JsonArray => Err("expected json or binary; each ZeroMQ message holds one event".to_string()),
+        _ => Ok(()),
+    }
+}
+
+fn host_port(value: &str) -> Result<(), String> {
+    match value.rsplit_once(':') {
+        Some((host, port)) if !host.is_empty() && port.parse::<u16>().is_ok() => Ok(()),
+        _ => Err(format!("expected host:port, got {:?}", value)),
+    }
+}
+
+/// A name Linux could give a network interface: 1 to 15 bytes, without `/` or whitespace
+fn interface_name(value: &str) -> Result<(), String> {
+    match (1..16).contains(&value.len()) && !value.contains(|c: char| c == '/' || c.is_whitespace()) {
+        true => Ok(()),
+        false => Err(format!("expected a network interface name, got {:?}", value)),
+    }
+}
+
+fn score(value: &str) -> Result<(), String> {
+    match value.trim().parse::<u8>() {
+        Ok(score) if score <= 100 => Ok(()),
+        _ => Err(format!("expected a score from 0 to 100, got {}", value)),
+    }
+}
+
+fn tolerance(value: &str) -> Result<(), String> {
+    match value.trim().parse::<f64>() {
+        Ok(t) if t == 0.0 || (t >= 1.0 && t.is_finite()) => Ok(()),
+        _ => Err(format!("expected 0 or a factor of at least 1, got {}", value)),
+    }
+}
+
+fn fragments_hint(value: &str) -> Result<(), String> {
+    match value.trim().parse::<u16>() {
+        Ok(hint) if hint <= MAX_FRAGMENTS_HINT => Ok(()),
+        _ => Err(format!("expected a fragment count from 0 to {}, got {}", MAX_FRAGMENTS_HINT, value)),
+    }
+}
+
+fn ratio(value: &str) -> Result<(), String> {
+    match value.trim().parse::<f64>() {
+        Ok(ratio) if (0.0..=1.0).contains(&ratio) => Ok(()),
+        _ => Err(format!("expected a ratio between 0 and 1, got {}", value)),
+    }
+}
+
+/// A readable file of valid instruction layouts
+fn layouts_file(value: &str) -> Result<(), String> {
+    parse_layouts(&std::fs::read_to_string(value).map_err(|e| e.to_string())?).map(drop)
+}
+
+fn http_url(value: &str) -> Result<(), String> {
+    let url = reqwest::Url::parse(value).map_err(|e| e.to_string())?;
+    match url.scheme() {
+        "http" | "https" => Ok(()),
+        scheme => Err(format!("expected an http or https URL, got {}://", scheme)),
+    }
+}
+
+fn pubkey(value: &str) -> Result<(), String> {
+    Pubkey::from_str(value).map(drop).map_err(|e| e.to_string())
+}
+
+fn discriminator(value: &str) -> Result<(), String> {
+    parse_discriminator(value).map(drop).ok_or_else(|| "expected comma-separated byte values".to_string())
+}
+
+fn json_pointer(value: &str) -> Result<(), String> {
+    match value.is_empty() || value.starts_with('/') {
+        true => Ok(()),
+        false => Err("a JSON pointer is empty or starts with /".to_string()),
+    }
+}
+
+fn log_filter(value: &str) -> Result<(), String> {
+    tracing_subscriber::EnvFilter::try_new(value).map(drop).map_err(|e| e.to_string())
+}
+
+/// Settings read once for the whole process
+const GLOBAL_CHECKS: &[(&str, Check)] = &[
+    ("PIPELINES", any_value),
+    ("SOL_USD_PRICE", parses::<f64>),
+    ("SOL_USD_PRICE_URL", http_url),
+    ("SOL_USD_PRICE_POINTER", json_pointer),
+    ("SOL_USD_REFRESH_SECS", parses::<u64>),
+    ("PCAP_REPLAY", any_value),
+    ("PCAP_REPLAY_SPEED", parses::<ReplaySpeed>),
+    ("RELOAD_CONFIG_PATH", any_value),
+    ("RUNTIME_FLAVOR", parses::<RuntimeFlavor>),
+    ("RUNTIME_WORKER_THREADS", parses::<usize>),
+    ("RUNTIME_MAX_BLOCKING_THREADS", parses::<usize>),
+    ("RUNTIME_WORKER_CPUS", |v| affinity::parse_cpu_list(v).map(drop)),
+    ("RUST_LOG", log_filter),
+    ("LOG_FORMAT", parses::<LogFormat>),
+    ("LOG_FILE", any_value),
+    ("LOG_FILE_ROTATION", parses::<LogRotation>),
+    ("LOG_FILE_RETAIN", parses::<usize>),
+    ("LOG_FILE_FORMAT", parses::<LogFormat>),
+];
+
+/// Settings each pipeline reads, as `PIPELINE_<NAME>_<KEY>` or `<KEY>`
+const PIPELINE_CHECKS: &[(&str, Check)] = &[
+    ("SOURCE", parses::<SourceKind>),
+    ("UDP_BIND_ADDR", host_port),
+    ("UDP_BIND_INTERFACE", interface_name),
+    ("BIND_RETRY_ATTEMPTS", parses::<u32>),
+    ("BIND_RETRY_INITIAL_MS", parses::<u64>),
+    ("BIND_RETRY_MAX_MS", parses::<u64>),
+    ("GEYSER_ENDPOINT", http_url),
+    ("GEYSER_X_TOKEN", any_value),
+    ("GEYSER_COMMITMENT", parses::<Commitment>),
+    ("SOURCE_RESTART_BACKOFF_MS", parses::<u64>),
+    ("SOURCE_RESTART_MAX_BACKOFF_MS", parses::<u64>),
+    ("SOURCE_RESTART_LIMIT", parses::<u32>),
+    ("REASSEMBLER_SNAPSHOT_PATH", any_value),
+    ("REASSEMBLY_MIN_COMPLETION", ratio),
+    ("MAX_PENDING_MESSAGES", parses::<usize>),
+    ("FRAGMENT_SIZE_TOLERANCE", tolerance),
+    ("FRAGMENTS_PER_MESSAGE_HINT", fragments_hint),
+    ("WORKER_QUEUE_CAPACITY", parses::<usize>),
+    ("WORKER_QUEUE_POLICY", parses::<QueuePolicy>),
+    ("DUPLICATE_WINDOW_MS", parses::<u64>),
+    ("DUPLICATE_MAX_TRACKED", parses::<usize>),
+    ("ALLOWED_SOURCES", |v| parse_ranges(v).map(drop)),
+    ("RATE_LIMIT_PPS", parses::<f64>),
+    ("RATE_LIMIT_BURST", parses::<f64>),
+    ("RATE_LIMIT_MAX_SOURCES", parses::<usize>),
+    ("UDP_PATH_MTU", parses::<usize>),
+    ("STALE_PACKET_SECS", parses::<u64>),
+    ("STALL_TIMEOUT_SECS", parses::<u64>),
+    ("STALL_ACTION", parses::<StallAction>),
+    ("HEARTBEAT_SECS", parses::<u64>),
+    ("SLOT_BATCH_TIMEOUT_MS", parses::<u64>),
+    ("SLOT_ORDER_WINDOW_MS", parses::<u64>),
+    ("MAX_PACKETS", parses::<u64>),
+    ("MAX_RUNTIME_SECS", parses::<u64>),
+    ("FORWARD_TO", |v| v.split(',').map(str::trim).try_for_each(host_port)),
+    ("FORWARD_QUEUE_CAPACITY", parses::<usize>),
+    ("CAPTURE_PATH", any_value),
+    ("CAPTURE_SAMPLE_EVERY", parses::<u64>),
+    ("CAPTURE_ON_FAILURE", parses::<bool>),
+    ("CAPTURE_MAX_PACKETS", parses::<u64>),
+    ("CAPTURE_MAX_MB", parses::<u64>),
+    ("ENTRY_DECODE_LIMIT_MB", parses::<u64>),
+    ("CREATE_ACCOUNTS", parses::<bool>),
+    ("DETECT_TOKEN_ACCOUNTS", parses::<bool>),
+    ("PARTIAL_ENTRY_DECODE", parses::<bool>),
+    ("CREATE_ARG_MAX_LEN", parses::<usize>),
+    ("CREATE_ARGS_MAX_BYTES", parses::<usize>),
+    ("LOG_GLOBAL_ACCOUNT", parses::<bool>),
+    ("CREATE_GLOBAL_INDEX", parses::<usize>),
+    ("CREATE_METADATA_INDEX", parses::<usize>),
+    ("VERIFY_METADATA_PDA", parses::<bool>),
+    ("DETECT_BUYS", parses::<bool>),
+    ("DETECT_DEV_BUYS", parses::<bool>),
+    ("DETECT_ADMIN", parses::<bool>),
+    ("DETECT_MAINTENANCE", parses::<bool>),
+    ("DETECT_MINT_INITS", parses::<bool>),
+    ("DETECT_UNKNOWN", parses::<bool>),
+    ("UNKNOWN_DATA_MAX_BYTES", parses::<usize>),
+    ("BUY_COALESCE_WINDOW_MS", parses::<u64>),
+    ("BUY_COALESCE_MAX_KEYS", parses::<usize>),
+    ("CREATOR_COOLDOWN_SECS", parses::<u64>),
+    ("SCORE_CREATES", parses::<bool>),
+    ("SCORE_WEIGHTS", parses::<ScoreWeights>),
+    ("SCORE_FULL_BUY_LAMPORTS", parses::<u64>),
+    ("SCORE_FULL_PRIORITY_FEE_LAMPORTS", parses::<u64>),
+    ("SCORE_NAME_PATTERNS", any_value),
+    ("SCORE_MIN", score),
+    ("TOP_CREATORS", parses::<usize>),
+    ("TOP_CREATORS_MAX_TRACKED", parses::<usize>),
+    // Parsed with its lists by `detector_config_from_env`
+    ("DETECTION_FILTER", any_value),
+    ("DETECT_MIGRATIONS", parses::<bool>),
+    ("MIGRATION_PROGRAM_ID", pubkey),
+    ("MIGRATION_DISC", discriminator),
+    ("MIGRATION_MINT_INDEX", parses::<usize>),
+    ("MIGRATION_POOL_INDEX", parses::<usize>),
+    ("PROGRAM_LAYOUTS_PATH", layouts_file),
+    ("JSONL_PATH", any_value),
+    ("JSONL_ROTATE_MB", parses::<u64>),
+    ("JSONL_ROTATE_SECS", parses::<u64>),
+    ("JSONL_GZIP", parses::<bool>),
+    ("JSONL_RETAIN", parses::<usize>),
+    ("JSONL_RETAIN_SECS", parses::<u64>),
+    ("TCP_OUTPUT_ADDR", host_port),
+    ("TCP_OUTPUT_FORMAT", parses::<OutputFormat>),
+    ("GRPC_OUTPUT_ADDR", host_port),
+    ("SQLITE_PATH", any_value),
+    ("SQLITE_BATCH_SIZE", parses::<usize>),
+    ("FIFO_PATH", any_value),
+    ("ZMQ_PUB_ADDR", |v| host_port(&zmq_address(v))),
+    ("ZMQ_PUB_FORMAT", zmq_format),
+    ("OUTPUT_FIELDS", parses::<Projection>),
+    ("ENRICH_RPC_URL", http_url),
+    ("ENRICH_TIMEOUT_MS", parses::<u64>),
+    ("ENRICH_MAX_CONCURRENCY", parses::<usize>),
+    ("VERIFY_RPC_URL", http_url),
+    ("VERIFY_SAMPLE_EVERY", parses::<u64>),
+    ("VERIFY_DELAY_MS", parses::<u64>),
+    ("VERIFY_TIMEOUT_MS", parses::<u64>),
+    ("VERIFY_MAX_CONCURRENCY", parses::<usize>),
+    ("VERIFY_QUEUE_CAPACITY", parses::<usize>),
+    ("SINK_QUEUE_CAPACITY", parses::<usize>),
+    ("SINK_BATCH_WINDOW_MS", parses::<u64>),
+    ("SINK_THROTTLE_RATIO", ratio),
+    ("DEBUG_HTTP_ADDR", host_port),
+    ("DEBUG_BUFFERS_LIMIT", parses::<usize>),
+    ("LOG_ADAPTIVE_RATE", parses::<f64>),
+    ("LOG_ADAPTIVE_EXIT_RATE", parses::<f64>),
+    ("RECV_CPU", |v| parses::<usize>(v.trim())),
+];
+
+/// Whether `key` is something one pipeline reads, possibly per pipeline
+fn is_pipeline_setting(key: &str) -> bool {
+    PIPELINE_CHECKS.iter().any(|(k, _)| *k == key) || key.starts_with(reload::FILTER_LIST_PREFIX)
+}
+
+/// Every problem with the settings in effect, as `KEY: problem`, using the startup path's own parsing
+///
+/// `keys` are the setting names to vet for typos and pipeline names PIPELINES doesn't list.
+fn validate_settings(keys: &[String]) -> Vec<String> {
+    let mut problems = Vec::new();
+    let scopes = match scopes_from_env() {
+        Ok(scopes) => scopes,
+        Err(e) => return vec![format!("PIPELINES: {}", e)],
     };
 
-    let entries_count = entries.len();
-    let total_txs: usize = entries.iter().map(|e| e.transactions.len()).sum();
-    
-    // Log each message's stats
-    info!("📦 Msg #{}: {} entries, {} txs", msg_seq, entries_count, total_txs);
-    
-    let mut creates_found = 0;
-
-    for entry in &entries {
-        for tx in &entry.transactions {
-            let accounts = tx.message.static_account_keys();
-
-            for ix in tx.message.instructions() {
-                let program_idx = ix.program_id_index as usize;
-                if program_idx >= accounts.len() {
-                    continue;
-                }
+    for key in keys {
+        let known = match key.strip_prefix(reload::PIPELINE_PREFIX) {
+            Some(_) => scopes
+                .iter()
+                .filter_map(|s| s.scoped_key(""))
+                .any(|prefix| key.strip_prefix(&prefix).is_some_and(is_pipeline_setting)),
+            None => GLOBAL_CHECKS.iter().any(|(k, _)| k == key) || is_pipeline_setting(key),
+        };
+        if known {
+            continue;
+        }
+        let global = scopes
+            .iter()
+            .filter_map(|s| s.scoped_key(""))
+            .find_map(|prefix| key.strip_prefix(&prefix).filter(|k| GLOBAL_CHECKS.iter().any(|(g, _)| g == k)));
+        problems.push(match global {
+            Some(global) => format!("{}: {} applies to the whole process; set it without the pipeline prefix", key, global),
+            None if key.starts_with(reload::PIPELINE_PREFIX) => {
+                format!("{}: not a per-pipeline setting of any pipeline in PIPELINES", key)
+            }
+            None => format!("{}: unknown setting", key),
+        });
+    }
 
-                let program_id = &accounts[program_idx];
-                if program_id != pumpfun_program_id {
-                    continue;
-                }
+    let global = GLOBAL_CHECKS.iter().map(|&(key, check)| (key.to_string(), check));
+    let per_pipeline = PIPELINE_CHECKS.iter().flat_map(|&(key, check)| {
+        std::iter::once(key.to_string())
+            .chain(scopes.iter().filter_map(|s| s.scoped_key(key)))
+            .map(move |key| (key, check))
+    });
+    for (key, check) in global.chain(per_pipeline) {
+        if let Some(Err(e)) = setting(&key).map(|value| check(&value)) {
+            problems.push(format!("{}: {}", key, e));
+        }
+    }
+    // What's left needs values that parse, and is only checked by building the real thing
+    if !problems.is_empty() {
+        return problems;
+    }
 
-                let data = ix.data.as_slice();
-                if data.len() < 8 {
-                    continue;
-                }
+    if let Err(e) = check_exclusive(&scopes) {
+        problems.push(format!("PIPELINES: {}", e));
+    }
+    for scope in &scopes {
+        if let Err(e) = detector_config_from_env(scope) {
+            problems.push(scope.label(e));
+        }
+    }
+    problems
+}
 
-                // Check for CREATE instruction
-                if data[0..8] == CREATE_DISC {
-                    creates_found += 1;
-                    
-                    let ix_accounts: Vec<Pubkey> = ix.accounts
-                        .iter()
-                        .filter_map(|&idx| accounts.get(idx as usize).copied())
-                        .collect();
-
-                    // 0: mint (token address), 2: bonding_curve, 7: creator
-                    let token_address = ix_accounts.get(0).map(|p| p.to_string()).unwrap_or_default();
-                    let bonding_curve = ix_accounts.get(2).map(|p| p.to_string()).unwrap_or_default();
-                    let creator = ix_accounts.get(7).map(|p| p.to_string()).unwrap_or_default();
-
-                    info!("═══════════════════════════════════════════════════════");
-                    info!("🚀 PUMPFUN TOKEN FOUND!");
-                    info!("   Token Address: {}", token_address);
-                    info!("   Bonding Curve: {}", bonding_curve);
-                    info!("   Creator: {}", creator);
-                    info!("   Message: #{}, Entries: {}, Txs: {}", msg_seq, entries_count, total_txs);
-                    info!("═══════════════════════════════════════════════════════");
-                }
+/// `validate [--config PATH]`: check the settings and exit, printing them when they're valid
+///
+/// With `--config`, only the `KEY=VALUE` file is checked, ignoring the environment; without it, the environment
+/// plus `RELOAD_CONFIG_PATH` is checked as startup would see it.
+fn validate(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let keys: Vec<String> = match args {
+        [flag, path] if flag == "--config" => {
+            let pairs = reload::parse_lines(&std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?);
+            READ_ENV.store(false, Ordering::Relaxed);
+            let keys = pairs.iter().map(|(key, _)| key.clone()).collect();
+            *OVERRIDES.write().unwrap() = pairs.into_iter().collect();
+            keys
+        }
+        [] => {
+            if let Ok(path) = std::env::var("RELOAD_CONFIG_PATH") {
+                load_overrides(&path).map_err(|e| format!("RELOAD_CONFIG_PATH: {}: {}", path, e))?;
             }
+            // Other variables in the environment aren't ours to judge
+            std::env::vars()
+                .map(|(key, _)| key)
+                .chain(OVERRIDES.read().unwrap().keys().cloned())
+                .filter(|key| key.starts_with(reload::PIPELINE_PREFIX))
+                .collect()
         }
+        _ => return Err("usage: validate [--config PATH]".into()),
+    };
+
+    let problems = validate_settings(&keys);
+    if problems.is_empty() {
+        println!("OK");
+        for line in explicit_settings(&scopes_from_env()?) {
+            println!("{}", line);
+        }
+        return Ok(());
     }
+    eprintln!("{} problem(s):", problems.len());
+    for problem in problems {
+        eprintln!("  {}", problem);
+    }
+    std::process::exit(1);
+}
 
-    (creates_found, entries_count, total_txs)
+/// SOL/USD price for annotating amounts: fixed, refreshed from an endpoint, or none
+fn price_from_env() -> reqwest::Result<Option<SolPrice>> {
+    if let Some(usd) = Some(env_or("SOL_USD_PRICE", 0.0)).filter(|&p| p > 0.0) {
+        info!("SOL/USD price: fixed at ${:.2}", usd);
+        return Ok(Some(SolPrice::fixed(usd)));
+    }
+    let Ok(url) = std::env::var("SOL_USD_PRICE_URL") else {
+        return Ok(None);
+    };
+    let pointer = std::env::var("SOL_USD_PRICE_POINTER").unwrap_or_else(|_| "/solana/usd".to_string());
+    let interval = Duration::from_secs(env_or("SOL_USD_REFRESH_SECS", 60).max(1));
+    info!("SOL/USD price: {} (every {}s)", url, interval.as_secs());
+    SolPrice::spawn_refresher(url, pointer, interval).map(Some)
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    tracing_subscriber::fmt::init();
+/// Tokio scheduler to run on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RuntimeFlavor {
+    MultiThread,
+    CurrentThread,
+}
 
-    let bind_addr = std::env::var("UDP_BIND_ADDR").unwrap_or_else(|_| "0.0.0.0:9001".to_string());
-    let pumpfun_program_id = Pubkey::from_str(PUMPFUN_PROGRAM_ID)?;
+impl FromStr for RuntimeFlavor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().replace('-', "_").as_str() {
+            "multi_thread" => Ok(RuntimeFlavor::MultiThread),
+            "current_thread" => Ok(RuntimeFlavor::CurrentThread),
+            other => Err(format!("unknown runtime flavor: {}", other)),
+        }
+    }
+}
+
+/// Bind `addr`, on `interface` alone if set, retrying with exponential backoff per BIND_RETRY_* while it isn't
+/// bindable yet
+async fn bind_with_retry(scope: &Scope, addr: &str, interface: Option<&str>) -> std::io::Result<UdpSocket> {
+    let attempts: u32 = scope.env_or("BIND_RETRY_ATTEMPTS", 1).max(1);
+    let max_backoff = Duration::from_millis(scope.env_or("BIND_RETRY_MAX_MS", 30_000));
+    let mut backoff = Duration::from_millis(scope.env_or("BIND_RETRY_INITIAL_MS", 500)).min(max_backoff);
+
+    let mut attempt = 1;
+    loop {
+        match bind_udp(addr, interface).await {
+            Ok(socket) => return Ok(socket),
+            Err(e) if attempt < attempts => {
+                warn!("⏳ Bind {} failed (attempt {}/{}): {}; retrying in {}ms", addr, attempt, attempts, e, backoff.as_millis());
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(max_backoff);
+                attempt += 1;
+            }
+            Err(e) => {
+                let message = format!("could not bind {} after {} attempt(s): {}", addr, attempt, e);
+                return Err(std::io::Error::new(e.kind(), message));
+            }
+        }
+    }
+}
+
+/// Build the tokio runtime from env, logging the effective settings
+fn runtime_from_env() -> std::io::Result<tokio::runtime::Runtime> {
+    let flavor = env_or("RUNTIME_FLAVOR", RuntimeFlavor::MultiThread);
+    let default_workers = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let workers = Some(env_or("RUNTIME_WORKER_THREADS", 0)).filter(|&n| n > 0).unwrap_or(default_workers);
+    let max_blocking = Some(env_or("RUNTIME_MAX_BLOCKING_THREADS", 0)).filter(|&n| n > 0).unwrap_or(512);
+
+    let mut builder = match flavor {
+        RuntimeFlavor::MultiThread => {
+            let mut builder = tokio::runtime::Builder::new_multi_thread();
+            builder.worker_threads(workers);
+            info!("Runtime: multi-thread, {} workers, up to {} blocking threads", workers, max_blocking);
+            builder
+        }
+        RuntimeFlavor::CurrentThread => {
+            info!("Runtime: current-thread, up to {} blocking threads", max_blocking);
+            tokio::runtime::Builder::new_current_thread()
+        }
+    };
+    if let Some(value) = setting("RUNTIME_WORKER_CPUS") {
+        let cores = affinity::parse_cpu_list(&value)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("RUNTIME_WORKER_CPUS: {}", e)))?;
+        info!("Runtime threads pinned round-robin to CPUs {:?}", cores);
+        // Also runs for blocking pool threads, which take the next core in turn
+        let next = AtomicUsize::new(0);
+        builder.on_thread_start(move || {
+            let core = cores[next.fetch_add(1, Ordering::Relaxed) % cores.len()];
+            affinity::pin_current(core, "runtime thread");
+        });
+    }
+    builder.max_blocking_threads(max_blocking).enable_all().build()
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.split_first().map(|(command, rest)| (command.as_str(), rest)) {
+        Some(("validate", rest)) => return validate(rest),
+        Some(("capabilities", [])) => {
+            if let Ok(path) = std::env::var("RELOAD_CONFIG_PATH") {
+                load_overrides(&path).map_err(|e| format!("RELOAD_CONFIG_PATH: {}: {}", path, e))?;
+            }
+            println!("{:#}", capabilities(&scopes_from_env()?));
+            return Ok(());
+        }
+        _ => {}
+    }
+    let reload_path = std::env::var("RELOAD_CONFIG_PATH").ok();
+    let ignored = match &reload_path {
+        Some(path) => load_overrides(path)?,
+        None => Vec::new(),
+    };
+    let file_log = std::env::var("LOG_FILE").ok().map(|path| FileLogConfig {
+        path: path.into(),
+        rotation: env_or("LOG_FILE_ROTATION", LogRotation::Daily),
+        retain: env_or("LOG_FILE_RETAIN", 0),
+        format: env_or("LOG_FILE_FORMAT", LogFormat::Json),
+    });
+    let logging = Arc::new(logging::init(
+        &setting("RUST_LOG").unwrap_or_else(|| "info".to_string()),
+        env_or("LOG_FORMAT", LogFormat::Full),
+        file_log.as_ref(),
+    )?);
+    for key in ignored {
+        warn!("{} in {} is only read from the environment; ignored", key, reload_path.as_deref().unwrap_or_default());
+    }
+
+    runtime_from_env()?.block_on(run(reload_path, logging))
+}
+
+async fn run(reload_path: Option<String>, logging: Arc<Logging>) -> Result<(), Box<dyn std::error::Error>> {
+    let scopes = scopes_from_env()?;
+    let price = price_from_env()?;
+    if let Ok(path) = std::env::var("PCAP_REPLAY") {
+        let detector_config = detector_config_from_env(&Scope::default())?;
+        let speed = env_or("PCAP_REPLAY_SPEED", ReplaySpeed::Fast);
+        info!("Replaying {} ({:?})", path, speed);
+        let reader = PcapReader::new(std::io::BufReader::new(std::fs::File::open(&path)?))?;
+        let report = tokio::task::spawn_blocking(move || {
+            replay(reader, speed, &detector_config, |msg_seq, processed| match processed {
+                Ok(processed) => {
+                    let usd = price.as_ref().and_then(SolPrice::usd);
+                    for (detection, &status) in processed.detections.iter().zip(&processed.statuses) {
+                        log_detection(detection, status, msg_seq, processed, usd);
+                    }
+                }
+                Err(e) => log_process_error(msg_seq, e),
+            })
+        })
+        .await??;
+        info!(
+            "📼 Replay done: {} pkts ({} non-UDP records skipped), {} msgs reassembled, {} timed out, {} size mismatches, {} undecodable, {} detections",
+            report.packets,
+            report.skipped_records,
+            report.messages,
+            report.timed_out,
+            report.size_mismatches,
+            report.undecodable,
+            report.detections
+        );
+        return Ok(());
+    }
+    check_exclusive(&scopes)?;
 
     info!("===========================================");
     info!("  Tiny Shreds UDP Client - Pumpfun Detector");
+    info!(
+        "  v{} (solana-sdk {}, solana-entry {})",
+        env!("CARGO_PKG_VERSION"),
+        env!("SOLANA_SDK_VERSION"),
+        env!("SOLANA_ENTRY_VERSION")
+    );
     info!("===========================================");
-    info!("Listening on: {}", bind_addr);
-    info!("Pumpfun Program: {}", pumpfun_program_id);
+    log_settings(&scopes);
+    info!("🧩 Capabilities: {}", capabilities(&scopes));
+    if scopes.len() > 1 {
+        info!("Pipelines: {}", scopes.iter().filter_map(|s| s.name.as_deref()).collect::<Vec<_>>().join(", "));
+    }
     info!("");
 
-    let socket = UdpSocket::bind(&bind_addr).await?;
-    info!("✅ UDP socket bound successfully!");
-    info!("Waiting for packets from shredstream_proxy...");
-    info!("");
+    let mut pipelines = Vec::with_capacity(scopes.len());
+    for scope in scopes {
+        let live = LivePipeline::start(&scope, price.clone()).instrument(scope.span()).await;
+        pipelines.push((live.map_err(|e| scope.label(e))?, scope));
+    }
 
-    let mut reassembler = FragmentReassembler::new();
-    let mut buf = vec![0u8; 65536];
-    
-    let mut packets_received = 0u64;
-    let mut bytes_received = 0u64;
-    let mut creates_total = 0usize;
-    let mut entries_total = 0usize;
-    let mut txs_total = 0usize;
-    let mut msg_seq = 0u64;
-    let mut last_stats = Instant::now();
-    let mut last_cleanup = Instant::now();
+    #[cfg(unix)]
+    if let Some(path) = reload_path {
+        use tokio::signal::unix::{signal, SignalKind};
 
-    loop {
-        let (len, src) = socket.recv_from(&mut buf).await?;
-        packets_received += 1;
-        bytes_received += len as u64;
+        let mut hangups = signal(SignalKind::hangup())?;
+        let detectors: Detectors =
+            pipelines.iter().map(|(live, scope)| (scope.clone(), live.pipeline.detector_config())).collect();
+        info!("🔄 SIGHUP reloads {}", path);
+        tokio::spawn(async move {
+            while hangups.recv().await.is_some() {
+                reload_config(&path, &logging, &detectors);
+            }
+        });
+    }
 
-        if packets_received == 1 {
-            info!("🎉 First packet from {}! ({} bytes)", src, len);
+    let (stop, stopped) = watch::channel(false);
+    let stop = Arc::new(stop);
+    let ctrl_c = stop.clone();
+    tokio::spawn(async move {
+        let _ = tokio::signal::ctrl_c().await;
+        ctrl_c.send_replace(true);
+    });
+    let shutdown = move || {
+        let mut stopped = stopped.clone();
+        async move {
+            let _ = stopped.wait_for(|&stop| stop).await;
         }
+    };
 
-        // Cleanup old fragments every 5 seconds
-        if last_cleanup.elapsed() >= Duration::from_secs(5) {
-            reassembler.cleanup_old();
-            last_cleanup = Instant::now();
+    // A lone pipeline runs right here, where RECV_CPU can pin it
+    if pipelines.len() == 1 {
+        let (live, _) = pipelines.remove(0);
+        return Ok(live.serve(shutdown()).await?);
+    }
+    let tasks: Vec<_> = pipelines
+        .into_iter()
+        .map(|(mut live, scope)| {
+            if live.recv_cpu.take().is_some() {
+                warn!("{}", scope.label("RECV_CPU ignored: pipelines share the runtime's threads"));
+            }
+            let stop = stop.clone();
+            let shutdown = shutdown();
+            let span = scope.span();
+            tokio::spawn(
+                async move {
+                    let result = live.serve(shutdown).await;
+                    if result.is_err() {
+                        warn!("Stopping the other pipelines since this one failed");
+                        stop.send_replace(true);
+                    }
+                    result.map_err(|e| scope.label(e))
+                }
+                .instrument(span),
+            )
+        })
+        .collect();
+    let mut failure = None;
+    for task in tasks {
+        if let Err(e) = task.await? {
+            failure.get_or_insert(e);
         }
+    }
+    match failure {
+        Some(e) => Err(e.into()),
+        None => Ok(()),
+    }
+}
+
+/// Where a running pipeline receives from
+enum Input {
+    /// The bound socket, and the address and interface to rebind it on when it fails
+    Udp(UdpSocket, String, Option<String>),
+    #[cfg(feature = "geyser")]
+    Geyser(Box<test_shreds::source::geyser::GeyserConnector>),
+}
+
+/// Transactions a Geyser subscription buffers ahead of the pipeline
+#[cfg(feature = "geyser")]
+const GEYSER_QUEUE: usize = 4096;
 
-        // Process packet through reassembler
-        if let Some(complete_data) = reassembler.process_packet(&buf[..len]) {
-            msg_seq += 1;
-            let (creates, entries, txs) = process_entries(&complete_data, &pumpfun_program_id, msg_seq);
-            creates_total += creates;
-            entries_total += entries;
-            txs_total += txs;
+/// Subscribe to the Yellowstone endpoint `scope` configures, for the programs `detector` watches
+#[cfg(feature = "geyser")]
+fn geyser_input(scope: &Scope, detector: &DetectorConfig) -> Result<Input, Box<dyn std::error::Error>> {
+    use test_shreds::{
+        detector::{TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID},
+        source::geyser::{GeyserConfig, GeyserConnector},
+    };
+
+    let endpoint = scope.setting("GEYSER_ENDPOINT").ok_or_else(|| scope.label("SOURCE=geyser needs GEYSER_ENDPOINT"))?;
+    let mut accounts = vec![detector.program_id];
+    accounts.extend(detector.layouts().keys().filter(|&&id| id != detector.program_id));
+    if detector.mint_inits {
+        accounts.extend([TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID]);
+    }
+    let config = GeyserConfig {
+        endpoint,
+        x_token: scope.setting("GEYSER_X_TOKEN"),
+        commitment: scope.env_or("GEYSER_COMMITMENT", Commitment::Processed),
+        accounts,
+    };
+    info!("Subscribing to transactions from {} ({:?})", redact_url(&config.endpoint), config.commitment);
+    Ok(Input::Geyser(Box::new(GeyserConnector::new(config)?)))
+}
+
+#[cfg(not(feature = "geyser"))]
+fn geyser_input(scope: &Scope, _: &DetectorConfig) -> Result<Input, Box<dyn std::error::Error>> {
+    Err(scope.label("SOURCE=geyser needs a build with the `geyser` feature").into())
+}
+
+/// A pipeline attached to its input, ready to receive
+struct LivePipeline {
+    pipeline: Pipeline,
+    input: Input,
+    snapshot_path: Option<String>,
+    /// Worker queue capacity and policy, when enabled
+    queue: Option<(usize, QueuePolicy)>,
+    restart: RestartPolicy,
+    recv_cpu: Option<usize>,
+}
+
+impl LivePipeline {
+    /// Build the pipeline `scope` configures and bind its socket or subscribe, logging its settings
+    async fn start(scope: &Scope, price: Option<SolPrice>) -> Result<Self, Box<dyn std::error::Error>> {
+        let bind_addr = scope.setting("UDP_BIND_ADDR").unwrap_or_else(|| DEFAULT_BIND_ADDR.to_string());
+        let snapshot_path = scope.setting("REASSEMBLER_SNAPSHOT_PATH");
+        let detector_config = detector_config_from_env(scope)?;
+        let coalesce_window = Duration::from_millis(scope.env_or("BUY_COALESCE_WINDOW_MS", 0));
+
+        let sinks = Arc::new(sinks_from_env(scope)?);
+        let enricher = match scope.setting("ENRICH_RPC_URL") {
+            Some(rpc_url) if !sinks.is_empty() => {
+                info!("RPC enrichment: {}", rpc_url);
+                Some(Enricher::spawn(
+                    EnrichConfig {
+                        rpc_url,
+                        timeout: Duration::from_millis(scope.env_or("ENRICH_TIMEOUT_MS", 500)),
+                        max_concurrency: scope.env_or("ENRICH_MAX_CONCURRENCY", 8),
+                        queue_capacity: scope.env_or("SINK_QUEUE_CAPACITY", 4096),
+                    },
+                    sinks.clone(),
+                )?)
+            }
+            Some(_) => {
+                warn!("ENRICH_RPC_URL is set but no sink is configured; enrichment disabled");
+                None
+            }
+            None => None,
+        };
+        let recv_cpu = match scope.setting("RECV_CPU") {
+            Some(value) => Some(value.trim().parse::<usize>().map_err(|_| format!("invalid RECV_CPU: {}", value))?),
+            None => None,
+        };
+        let coalescer = (!coalesce_window.is_zero())
+            .then(|| BuyCoalescer::new(coalesce_window, scope.env_or("BUY_COALESCE_MAX_KEYS", 1024)));
+        let adaptive_rate: f64 = scope.env_or("LOG_ADAPTIVE_RATE", 0.0);
+        let adaptive_exit_rate: f64 = scope.env_or("LOG_ADAPTIVE_EXIT_RATE", adaptive_rate / 2.0);
+
+        let source = scope.env_or("SOURCE", SourceKind::Udp);
+        let interface = scope.setting("UDP_BIND_INTERFACE").filter(|_| source == SourceKind::Udp);
+        let interface = match interface {
+            Some(name) if !cfg!(target_os = "linux") => {
+                warn!(
+                    "UDP_BIND_INTERFACE={} ignored: binding to an interface needs Linux; receiving on every interface {} covers",
+                    name, bind_addr
+                );
+                None
+            }
+            interface => interface,
+        };
+        match (source, &interface) {
+            (SourceKind::Udp, Some(name)) => info!("Listening on: {} (interface {} only)", bind_addr, name),
+            (SourceKind::Udp, None) => info!("Listening on: {}", bind_addr),
+            _ => {}
+        }
+        info!("Pumpfun Program: {}", detector_config.program_id);
+        if detector_config.watches(InstructionKind::Buy) {
+            match coalesce_window.as_millis() {
+                0 => info!("Buy detection: on"),
+                ms => info!("Buy detection: on (coalescing per mint over {}ms)", ms),
+            }
         }
+        let migrations: Vec<String> =
+            detector_config.programs_watching(InstructionKind::Migration).map(ToString::to_string).collect();
+        if !migrations.is_empty() {
+            info!("Migration detection: on ({})", migrations.join(", "));
+        }
+        if let Some(filter) = &detector_config.filter {
+            info!("Detection filter: {}", filter);
+        }
+        if adaptive_rate > 0.0 {
+            info!("Adaptive logging: summaries above {}/s until below {}/s", adaptive_rate, adaptive_exit_rate.min(adaptive_rate));
+        }
+        if let Some(core) = recv_cpu {
+            info!("Recv thread CPU: {}", core);
+        }
+        info!("");
 
-        // Log stats every 15 seconds
-        if last_stats.elapsed() >= Duration::from_secs(15) {
+        let input = match source {
+            SourceKind::Udp => {
+                let socket = bind_with_retry(scope, &bind_addr, interface.as_deref()).await?;
+                info!("✅ UDP socket bound successfully!");
+                info!("Waiting for packets from shredstream_proxy...");
+                Input::Udp(socket, bind_addr.clone(), interface)
+            }
+            SourceKind::Geyser => geyser_input(scope, &detector_config)?,
+        };
+        info!("");
+
+        let reassembler = match snapshot_path.as_deref().map(std::fs::read) {
+            Some(Ok(bytes)) => match FragmentReassembler::restore(&bytes) {
+                Ok(restored) => {
+                    info!(target: "reassembler", "♻️  Restored {} in-flight messages from snapshot", restored.pending());
+                    restored
+                }
+                Err(e) => {
+                    warn!(target: "reassembler", "Ignoring unreadable reassembler snapshot: {}", e);
+                    FragmentReassembler::new()
+                }
+            },
+            Some(Err(e)) if e.kind() != std::io::ErrorKind::NotFound => {
+                warn!(target: "reassembler", "Failed to read reassembler snapshot: {}", e);
+                FragmentReassembler::new()
+            }
+            _ => FragmentReassembler::new(),
+        };
+        let reassembler = reassembler
+            .with_max_pending(scope.env_or("MAX_PENDING_MESSAGES", DEFAULT_MAX_PENDING))
+            .with_size_tolerance(scope.env_or("FRAGMENT_SIZE_TOLERANCE", DEFAULT_SIZE_TOLERANCE))
+            .with_fragments_hint(scope.env_or("FRAGMENTS_PER_MESSAGE_HINT", 0));
+        let heartbeat = match scope.env_or("HEARTBEAT_SECS", 0) {
+            0 => None,
+            _ if sinks.is_empty() => {
+                warn!("HEARTBEAT_SECS is set but no sink is configured; heartbeats disabled");
+                None
+            }
+            secs => Some(Duration::from_secs(secs)),
+        };
+        let slot_batch = match scope.env_or("SLOT_BATCH_TIMEOUT_MS", 0) {
+            0 => None,
+            _ if sinks.is_empty() => {
+                warn!("SLOT_BATCH_TIMEOUT_MS is set but no sink is configured; slot batching disabled");
+                None
+            }
+            ms => Some(Duration::from_millis(ms)),
+        };
+        let slot_order = match scope.env_or("SLOT_ORDER_WINDOW_MS", 0) {
+            0 => None,
+            _ if slot_batch.is_some() => {
+                info!("SLOT_ORDER_WINDOW_MS is ignored with SLOT_BATCH_TIMEOUT_MS set, which orders slots too");
+                None
+            }
+            _ if sinks.is_empty() => {
+                warn!("SLOT_ORDER_WINDOW_MS is set but no sink is configured; slot ordering disabled");
+                None
+            }
+            ms => Some(Duration::from_millis(ms)),
+        };
+        let program_id = detector_config.program_id;
+        let mut pipeline = Pipeline::new(detector_config, reassembler, SystemClock).with_outputs(sinks, enricher);
+        if let Some(coalescer) = coalescer {
+            pipeline = pipeline.with_coalescer(coalescer);
+        }
+        if let Some(price) = price {
+            pipeline = pipeline.with_price(price);
+        }
+        let cooldown = Duration::from_secs(scope.env_or("CREATOR_COOLDOWN_SECS", 0));
+        if !cooldown.is_zero() {
+            info!("Creator cooldown: one create per creator every {}s", cooldown.as_secs());
+            pipeline = pipeline.with_creator_cooldown(CreatorCooldown::new(cooldown));
+        }
+        if scope.env_or("SCORE_CREATES", false) {
+            let defaults = ScoreConfig::default();
+            let name_patterns = scope.setting("SCORE_NAME_PATTERNS").map(|v| parse_name_patterns(&v));
+            let config = ScoreConfig {
+                weights: scope.env_or("SCORE_WEIGHTS", defaults.weights),
+                full_buy: scope.env_or("SCORE_FULL_BUY_LAMPORTS", defaults.full_buy).max(1),
+                full_priority_fee: scope.env_or("SCORE_FULL_PRIORITY_FEE_LAMPORTS", defaults.full_priority_fee).max(1),
+                name_patterns: name_patterns.unwrap_or_default(),
+                min_score: scope.env_or("SCORE_MIN", 0u8).min(100),
+            };
+            info!("Create scoring: on ({:?}, suppressing scores under {})", config.weights, config.min_score);
+            pipeline = pipeline.with_create_scoring(CreateScorer::new(config));
+        }
+        let top_creators = scope.env_or("TOP_CREATORS", 0);
+        if top_creators > 0 {
+            let max_tracked = scope.env_or("TOP_CREATORS_MAX_TRACKED", leaderboard::DEFAULT_MAX_TRACKED);
+            pipeline = pipeline.with_leaderboard(CreatorLeaderboard::new(top_creators, max_tracked));
+        }
+        let duplicate_window = Duration::from_millis(scope.env_or("DUPLICATE_WINDOW_MS", 0));
+        if !duplicate_window.is_zero() {
+            info!("Duplicate datagram detection: on ({}ms window)", duplicate_window.as_millis());
+            pipeline = pipeline.with_duplicate_filter(DuplicateFilter::new(
+                duplicate_window,
+                scope.env_or("DUPLICATE_MAX_TRACKED", 65536),
+                SystemClock.now(),
+            ));
+        }
+        if let Some(sources) = scope.setting("ALLOWED_SOURCES") {
+            let ranges = parse_ranges(&sources).map_err(|e| scope.label(format!("ALLOWED_SOURCES: {}", e)))?;
+            info!("Source allow-list: {}", ranges.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "));
+            pipeline = pipeline.with_source_allow_list(SourceAllowList::new(ranges));
+        }
+        let rate: f64 = scope.env_or("RATE_LIMIT_PPS", 0.0);
+        if rate > 0.0 {
+            let burst = scope.env_or("RATE_LIMIT_BURST", rate * 2.0);
+            info!("Per-source rate limit: {} packets/s, bursts of {}", rate, burst);
+            pipeline = pipeline.with_rate_limit(SourceRateLimiter::new(
+                rate,
+                burst,
+                scope.env_or("RATE_LIMIT_MAX_SOURCES", ratelimit::DEFAULT_MAX_SOURCES),
+                SystemClock.now(),
+            ));
+        }
+        let min_completion: f64 = scope.env_or("REASSEMBLY_MIN_COMPLETION", 0.0);
+        if min_completion > 0.0 {
+            info!("Reassembly alert: below {:.1}% of multi-fragment messages completed", min_completion * 100.0);
+            pipeline = pipeline.with_completion_alert(min_completion);
+        }
+        let throttle: f64 = scope.env_or("SINK_THROTTLE_RATIO", 0.0);
+        if throttle > 0.0 {
+            info!("Sink throttle: enrichment skipped while every sink queue is {:.0}% full", throttle * 100.0);
+            pipeline = pipeline.with_sink_throttle(throttle);
+        }
+        let stale_secs = scope.env_or("STALE_PACKET_SECS", DEFAULT_STALE_THRESHOLD.as_secs());
+        if stale_secs > 0 {
+            pipeline = pipeline.with_watchdog(Watchdog::new(Duration::from_secs(stale_secs), SystemClock.now()));
+        }
+        let stall_secs = scope.env_or("STALL_TIMEOUT_SECS", 10);
+        if stall_secs > 0 {
+            let action = scope.env_or("STALL_ACTION", StallAction::Log);
+            let then = if action == StallAction::Abort { ", then abort" } else { "" };
+            info!("Stall watchdog: an error{} once the recv loop hasn't turned for {}s", then, stall_secs);
+            pipeline = pipeline.with_stall_watchdog(Duration::from_secs(stall_secs), action);
+        }
+        if let Some(interval) = heartbeat {
+            info!("Sink heartbeats: every {}s", interval.as_secs());
+            pipeline = pipeline.with_heartbeat(interval);
+        }
+        if let Some(timeout) = slot_batch {
+            info!("Slot batching: detections held per slot, for at most {}ms", timeout.as_millis());
+            pipeline = pipeline.with_slot_batching(timeout);
+        }
+        if let Some(window) = slot_order {
+            info!("Slot ordering: detections held up to {}ms so slots reach the sinks in order", window.as_millis());
+            pipeline = pipeline.with_slot_ordering(window);
+        }
+        let max_packets = scope.env_or("MAX_PACKETS", 0);
+        if max_packets > 0 {
+            info!("Stopping after {} packets", max_packets);
+            pipeline = pipeline.with_max_packets(max_packets);
+        }
+        let max_runtime = scope.env_or("MAX_RUNTIME_SECS", 0);
+        if max_runtime > 0 {
+            info!("Stopping after {}s", max_runtime);
+            pipeline = pipeline.with_max_runtime(Duration::from_secs(max_runtime));
+        }
+        if let Some(value) = scope.setting("FORWARD_TO") {
+            let targets = forward_targets(&value)?;
+            if !targets.is_empty() {
+                info!("Forwarding raw datagrams to: {:?}", targets);
+                pipeline = pipeline.with_forwarder(Forwarder::spawn(targets, scope.env_or("FORWARD_QUEUE_CAPACITY", 4096))?);
+            }
+        }
+        if let Some(rpc_url) = scope.setting("VERIFY_RPC_URL") {
+            let config = VerifyConfig {
+                rpc_url,
+                program_id,
+                sample_every: scope.env_or("VERIFY_SAMPLE_EVERY", 100),
+                delay: Duration::from_millis(scope.env_or("VERIFY_DELAY_MS", 30_000)),
+                timeout: Duration::from_millis(scope.env_or("VERIFY_TIMEOUT_MS", 5_000)),
+                max_concurrency: scope.env_or("VERIFY_MAX_CONCURRENCY", 4),
+                queue_capacity: scope.env_or("VERIFY_QUEUE_CAPACITY", 1024),
+            };
             info!(
-                "📊 Stats: {} pkts, {:.2} MB, {} msgs, {} entries, {} txs, {} creates",
-                packets_received,
-                bytes_received as f64 / 1_000_000.0,
-                msg_seq,
-                entries_total,
-                txs_total,
-                creates_total
+                "Verifying 1 in {} creates against {} after {}ms",
+                config.sample_every.max(1), config.rpc_url, config.delay.as_millis()
             );
-            packets_received = 0;
-            bytes_received = 0;
-            creates_total = 0;
-            entries_total = 0;
-            txs_total = 0;
-            last_stats = Instant::now();
+            pipeline = pipeline.with_verifier(Verifier::spawn(config)?);
+        }
+        if let Some(path) = scope.setting("CAPTURE_PATH") {
+            let config = CaptureConfig {
+                path: path.into(),
+                sample_every: scope.env_or("CAPTURE_SAMPLE_EVERY", 0),
+                on_failure: scope.env_or("CAPTURE_ON_FAILURE", true),
+                max_packets: scope.env_or("CAPTURE_MAX_PACKETS", 100_000),
+                max_bytes: scope.env_or("CAPTURE_MAX_MB", 100) * 1024 * 1024,
+            };
+            if config.sample_every == 0 && !config.on_failure {
+                warn!("CAPTURE_PATH is set but CAPTURE_SAMPLE_EVERY is 0 and CAPTURE_ON_FAILURE is off; nothing to capture");
+            } else {
+                let capture = PacketCapture::create(config)?;
+                info!("Capturing to {}", capture.path().display());
+                pipeline = pipeline.with_capture(capture);
+            }
         }
+        if let Some(addr) = scope.setting("DEBUG_HTTP_ADDR") {
+            let requests = test_shreds::debug::serve(&addr, pipeline.metrics()).await?;
+            pipeline = pipeline.with_buffer_inspection(requests, scope.env_or("DEBUG_BUFFERS_LIMIT", 100));
+        }
+        let path_mtu: usize = scope.env_or("UDP_PATH_MTU", 0);
+        if path_mtu > 0 {
+            pipeline = pipeline.with_path_mtu(path_mtu);
+        }
+        if env_or("LOG_FORMAT", LogFormat::Full) == LogFormat::Json {
+            pipeline = pipeline.with_structured_stats();
+        }
+        if adaptive_rate > 0.0 {
+            pipeline = pipeline.with_adaptive_logging(AdaptiveVerbosity::new(
+                adaptive_rate,
+                adaptive_exit_rate,
+                DEFAULT_BUCKET,
+                SystemClock.now(),
+            ));
+        }
+
+        let queue = match scope.env_or("WORKER_QUEUE_CAPACITY", 0) {
+            0 => None,
+            capacity => Some((capacity, scope.env_or("WORKER_QUEUE_POLICY", QueuePolicy::Drop))),
+        };
+        // Never zero, so a source failing at once can't spin
+        let max_backoff = Duration::from_millis(scope.env_or("SOURCE_RESTART_MAX_BACKOFF_MS", 30_000u64).max(1));
+        let restart = RestartPolicy {
+            backoff: Duration::from_millis(scope.env_or("SOURCE_RESTART_BACKOFF_MS", 1000u64).max(1)).min(max_backoff),
+            max_backoff,
+            limit: scope.env_or("SOURCE_RESTART_LIMIT", 0),
+        };
+        if restart.limit > 0 {
+            info!("Source restarts: giving up after {} in a row", restart.limit);
+        }
+        Ok(Self { pipeline, input, snapshot_path, queue, restart, recv_cpu })
+    }
+
+    /// Receive until `shutdown` resolves or the socket fails, then drain the outputs and save the snapshot
+    /// either way
+    async fn serve(self, shutdown: impl Future<Output = ()>) -> std::io::Result<()> {
+        let Self { mut pipeline, input, snapshot_path, queue, restart, recv_cpu } = self;
+        // Last, so the threads started above don't inherit the pin
+        if let Some(core) = recv_cpu {
+            affinity::pin_current(core, "recv thread");
+        }
+        let metrics = pipeline.metrics();
+        let result = match (input, queue) {
+            (Input::Udp(socket, addr, interface), queue) => {
+                let rebind = move || {
+                    let (addr, interface) = (addr.clone(), interface.clone());
+                    async move { bind_udp(&addr, interface.as_deref()).await }
+                };
+                let mut source = SupervisedSource::new("UDP", rebind, restart, metrics).with_source(socket);
+                match queue {
+                    None => pipeline.run(&mut source, shutdown).await,
+                    Some((capacity, policy)) => {
+                        info!("Worker queue: {} datagrams, {:?} when full", capacity, policy);
+                        pipeline.run(&mut QueuedSource::spawn(source, capacity, policy), shutdown).await
+                    }
+                }
+            }
+            // Always read ahead on its own task, so the worker queue settings don't apply
+            #[cfg(feature = "geyser")]
+            (Input::Geyser(connector), _) => {
+                let source = SupervisedSource::new("Geyser", move || connector.connect(), restart, metrics);
+                pipeline.run(&mut QueuedSource::spawn(source, GEYSER_QUEUE, QueuePolicy::Block), shutdown).await
+            }
+        };
+        if let Err(e) = &result {
+            error!("Recv loop failed: {}", e);
+        }
+        let reassembler = pipeline.finish().await;
+
+        if let Some(path) = snapshot_path {
+            std::fs::write(&path, reassembler.snapshot())?;
+            info!(target: "reassembler", "💾 Saved {} in-flight messages to {}", reassembler.pending(), path);
+        }
+
+        result
     }
 }
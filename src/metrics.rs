@@ -0,0 +1,106 @@
+//! Lifetime counters that can be read while a pipeline runs.
+//!
+//! The pipeline bumps these as it goes, so a [`Metrics::snapshot`] from any
+//! thread shows everything since startup. Per-window counts are in
+//! [`WindowStats`](crate::pipeline::WindowStats), which reset every stats interval.
+
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+
+use crate::source::supervisor::SourceState;
+
+/// Counters shared between a pipeline and whoever reads them
+#[derive(Debug, Default)]
+pub struct Metrics {
+    pub(crate) packets: AtomicU64,
+    pub(crate) messages: AtomicU64,
+    pub(crate) creates: AtomicU64,
+    pub(crate) buys: AtomicU64,
+    pub(crate) migrations: AtomicU64,
+    pub(crate) undecodable: AtomicU64,
+    pub(crate) filtered: AtomicU64,
+    pub(crate) suppressed: AtomicU64,
+    pub(crate) dropped: AtomicU64,
+    pub(crate) rate_limited: AtomicU64,
+    pub(crate) rejected: AtomicU64,
+    pub(crate) reassembled: AtomicU64,
+    pub(crate) expired: AtomicU64,
+    pub(crate) source_state: AtomicU8,
+    pub(crate) source_restarts: AtomicU64,
+}
+
+/// Counter values at one moment; every field counts since startup
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MetricsSnapshot {
+    pub packets: u64,
+    /// Reassembled messages
+    pub messages: u64,
+    /// Includes creates held back by the creator cooldown
+    pub creates: u64,
+    pub buys: u64,
+    pub migrations: u64,
+    /// Reassembled messages that couldn't be decoded as entries
+    pub undecodable: u64,
+    /// Detections dropped by the detection filter
+    pub filtered: u64,
+    /// Creates held back by the creator cooldown
+    pub suppressed: u64,
+    /// Events dropped at full sink queues, summed over sinks. Refreshed whenever the
+    /// pipeline emits or reports stats, so drops after enrichment may show up late.
+    pub dropped: u64,
+    /// Datagrams dropped by the per-source rate limit
+    pub rate_limited: u64,
+    /// Datagrams from sources outside the allow-list
+    pub rejected: u64,
+    /// Multi-fragment messages reassembled; updated once per stats window
+    pub reassembled: u64,
+    /// Multi-fragment messages that expired incomplete; updated once per stats window
+    pub expired: u64,
+    /// The input's state, as its `SupervisedSource` last reported it
+    pub source_state: SourceState,
+    /// Times the input was reopened after failing
+    pub source_restarts: u64,
+}
+
+impl MetricsSnapshot {
+    /// Share of resolved multi-fragment messages that completed rather than expired
+    pub fn completion_ratio(&self) -> Option<f64> {
+        completion_ratio(self.reassembled, self.expired)
+    }
+}
+
+/// `reassembled / (reassembled + expired)`, or `None` before any message has resolved either way
+///
+/// Both are counted when a message resolves, so messages still in flight count toward neither.
+pub fn completion_ratio(reassembled: u64, expired: u64) -> Option<f64> {
+    let resolved = reassembled + expired;
+    (resolved > 0).then(|| reassembled as f64 / resolved as f64)
+}
+
+impl Metrics {
+    /// Read every counter; each load is relaxed, so counters may be a packet apart
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        MetricsSnapshot {
+            packets: load(&self.packets),
+            messages: load(&self.messages),
+            creates: load(&self.creates),
+            buys: load(&self.buys),
+            migrations: load(&self.migrations),
+            undecodable: load(&self.undecodable),
+            filtered: load(&self.filtered),
+            suppressed: load(&self.suppressed),
+            dropped: load(&self.dropped),
+            rate_limited: load(&self.rate_limited),
+            rejected: load(&self.rejected),
+            reassembled: load(&self.reassembled),
+            expired: load(&self.expired),
+            source_state: SourceState::from_u8(self.source_state.load(Ordering::Relaxed)),
+            source_restarts: load(&self.source_restarts),
+        }
+    }
+}
+
+/// Add `n` to `counter`
+pub(crate) fn add(counter: &AtomicU64, n: u64) {
+    counter.fetch_add(n, Ordering::Relaxed);
+}
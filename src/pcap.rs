@@ -0,0 +1,236 @@
+//! Minimal reader and writer for classic libpcap capture files of UDP datagrams.
+
+use std::{
+    fmt,
+    io::{self, Read, Write},
+    net::{IpAddr, SocketAddr},
+    time::Duration,
+};
+
+/// A UDP datagram extracted from a capture
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedPacket {
+    /// Capture timestamp, relative to the unix epoch
+    pub timestamp: Duration,
+    pub payload: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub enum PcapError {
+    Io(io::Error),
+    BadMagic(u32),
+    UnsupportedLinkType(u32),
+}
+
+impl fmt::Display for PcapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PcapError::Io(e) => write!(f, "{}", e),
+            PcapError::BadMagic(m) => write!(f, "not a pcap file (magic {:#010x})", m),
+            PcapError::UnsupportedLinkType(t) => write!(f, "unsupported link type {}", t),
+        }
+    }
+}
+
+impl std::error::Error for PcapError {}
+
+impl From<io::Error> for PcapError {
+    fn from(e: io::Error) -> Self {
+        PcapError::Io(e)
+    }
+}
+
+const LINKTYPE_ETHERNET: u32 = 1;
+const LINKTYPE_RAW: u32 = 101;
+const LINKTYPE_LINUX_SLL: u32 = 113;
+const LINKTYPE_LINUX_SLL2: u32 = 276;
+
+/// Streams UDP payloads out of a pcap file in capture order
+pub struct PcapReader<R> {
+    reader: R,
+    big_endian: bool,
+    nanos: bool,
+    link_type: u32,
+    /// Records skipped because they weren't unfragmented IPv4/IPv6 UDP
+    pub skipped: u64,
+}
+
+impl<R: Read> PcapReader<R> {
+    pub fn new(mut reader: R) -> Result<Self, PcapError> {
+        let mut header = [0u8; 24];
+        reader.read_exact(&mut header)?;
+        let magic = u32::from_le_bytes(header[0..4].try_into().unwrap());
+        let (big_endian, nanos) = match magic {
+            0xa1b2c3d4 => (false, false),
+            0xa1b23c4d => (false, true),
+            0xd4c3b2a1 => (true, false),
+            0x4d3cb2a1 => (true, true),
+            other => return Err(PcapError::BadMagic(other)),
+        };
+
+        let mut pcap = Self { reader, big_endian, nanos, link_type: 0, skipped: 0 };
+        pcap.link_type = pcap.u32_at(&header, 20) & 0x0fff_ffff;
+        if ![LINKTYPE_ETHERNET, LINKTYPE_RAW, LINKTYPE_LINUX_SLL, LINKTYPE_LINUX_SLL2].contains(&pcap.link_type) {
+            return Err(PcapError::UnsupportedLinkType(pcap.link_type));
+        }
+        Ok(pcap)
+    }
+
+    fn u32_at(&self, buf: &[u8], at: usize) -> u32 {
+        let bytes: [u8; 4] = buf[at..at + 4].try_into().unwrap();
+        if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        }
+    }
+
+    /// Next UDP payload, or `None` at end of file
+    pub fn next_packet(&mut self) -> Result<Option<CapturedPacket>, PcapError> {
+        loop {
+            let mut record = [0u8; 16];
+            match self.reader.read_exact(&mut record) {
+                Ok(()) => {}
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+                Err(e) => return Err(e.into()),
+            }
+            let secs = self.u32_at(&record, 0) as u64;
+            let frac = self.u32_at(&record, 4);
+            let incl_len = self.u32_at(&record, 8) as usize;
+
+            let mut frame = vec![0u8; incl_len];
+            self.reader.read_exact(&mut frame)?;
+
+            let timestamp = Duration::from_secs(secs)
+                + if self.nanos { Duration::from_nanos(frac as u64) } else { Duration::from_micros(frac as u64) };
+            match udp_payload(self.link_type, &frame) {
+                Some(payload) => return Ok(Some(CapturedPacket { timestamp, payload: payload.to_vec() })),
+                None => self.skipped += 1,
+            }
+        }
+    }
+}
+
+/// Strip link, IP and UDP headers
+fn udp_payload(link_type: u32, frame: &[u8]) -> Option<&[u8]> {
+    let (ethertype, ip) = match link_type {
+        LINKTYPE_ETHERNET => {
+            let mut ethertype = u16::from_be_bytes(frame.get(12..14)?.try_into().ok()?);
+            let mut offset = 14;
+            // 802.1Q VLAN tag
+            if ethertype == 0x8100 {
+                ethertype = u16::from_be_bytes(frame.get(16..18)?.try_into().ok()?);
+                offset = 18;
+            }
+            (ethertype, frame.get(offset..)?)
+        }
+        LINKTYPE_LINUX_SLL => (u16::from_be_bytes(frame.get(14..16)?.try_into().ok()?), frame.get(16..)?),
+        LINKTYPE_LINUX_SLL2 => (u16::from_be_bytes(frame.get(0..2)?.try_into().ok()?), frame.get(20..)?),
+        _ => match frame.first()? >> 4 {
+            4 => (0x0800, frame),
+            6 => (0x86dd, frame),
+            _ => return None,
+        },
+    };
+
+    let udp = match ethertype {
+        0x0800 => {
+            let ihl = ((ip.first()? & 0x0f) as usize) * 4;
+            let flags_fragment = u16::from_be_bytes(ip.get(6..8)?.try_into().ok()?);
+            // Skip IP fragments: more-fragments set or a non-zero offset
+            if *ip.get(9)? != 17 || flags_fragment & 0x3fff != 0 {
+                return None;
+            }
+            ip.get(ihl..)?
+        }
+        0x86dd => {
+            if *ip.get(6)? != 17 {
+                return None;
+            }
+            ip.get(40..)?
+        }
+        _ => return None,
+    };
+
+    let udp_len = u16::from_be_bytes(udp.get(4..6)?.try_into().ok()?) as usize;
+    udp.get(8..udp_len.max(8).min(udp.len()))
+}
+
+/// Largest payload of a UDP datagram over IPv4
+pub const MAX_UDP_PAYLOAD: usize = 65_507;
+
+/// Writes UDP datagrams to a pcap file as raw IP frames, with synthesized IP and UDP headers
+///
+/// The destination is the unspecified address of the source's family, port 0. Files it
+/// writes can be read back with [`PcapReader`] and `PCAP_REPLAY`.
+pub struct PcapWriter<W> {
+    writer: W,
+}
+
+impl<W: Write> PcapWriter<W> {
+    /// Write the file header
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        let mut header = Vec::with_capacity(24);
+        header.extend_from_slice(&0xa1b2c3d4u32.to_le_bytes());
+        header.extend_from_slice(&2u16.to_le_bytes());
+        header.extend_from_slice(&4u16.to_le_bytes());
+        // Timezone offset and timestamp accuracy, both always zero
+        header.extend_from_slice(&[0; 8]);
+        header.extend_from_slice(&(u16::MAX as u32).to_le_bytes());
+        header.extend_from_slice(&LINKTYPE_RAW.to_le_bytes());
+        writer.write_all(&header)?;
+        Ok(Self { writer })
+    }
+
+    /// Append one datagram from `src`, returning the bytes written; payloads must fit in a UDP datagram
+    pub fn write_datagram(&mut self, timestamp: Duration, src: SocketAddr, payload: &[u8]) -> io::Result<usize> {
+        if payload.len() > MAX_UDP_PAYLOAD {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "payload too large for a UDP datagram"));
+        }
+        let udp_len = 8 + payload.len() as u16;
+        let mut frame = match src.ip() {
+            IpAddr::V4(ip) => {
+                let mut header = [0u8; 20];
+                header[0] = 0x45;
+                header[2..4].copy_from_slice(&(20 + udp_len).to_be_bytes());
+                header[8] = 64;
+                header[9] = 17;
+                header[12..16].copy_from_slice(&ip.octets());
+                let checksum = !header.chunks(2).fold(0u32, |sum, word| {
+                    let sum = sum + u16::from_be_bytes([word[0], word[1]]) as u32;
+                    (sum & 0xffff) + (sum >> 16)
+                }) as u16;
+                header[10..12].copy_from_slice(&checksum.to_be_bytes());
+                header.to_vec()
+            }
+            IpAddr::V6(ip) => {
+                let mut header = [0u8; 40];
+                header[0] = 0x60;
+                header[4..6].copy_from_slice(&udp_len.to_be_bytes());
+                header[6] = 17;
+                header[7] = 64;
+                header[8..24].copy_from_slice(&ip.octets());
+                header.to_vec()
+            }
+        };
+        // UDP header with no checksum; the destination port is left at 0
+        frame.extend_from_slice(&src.port().to_be_bytes());
+        frame.extend_from_slice(&[0, 0]);
+        frame.extend_from_slice(&udp_len.to_be_bytes());
+        frame.extend_from_slice(&[0, 0]);
+        frame.extend_from_slice(payload);
+
+        let mut record = Vec::with_capacity(16);
+        record.extend_from_slice(&(timestamp.as_secs() as u32).to_le_bytes());
+        record.extend_from_slice(&timestamp.subsec_micros().to_le_bytes());
+        record.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        record.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        self.writer.write_all(&record)?;
+        self.writer.write_all(&frame)?;
+        Ok(record.len() + frame.len())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
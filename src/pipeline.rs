@@ -0,0 +1,1870 @@
+//! The live recv loop: reassembly, detection, output and periodic stats.
+
+use std::{
+    collections::HashSet,
+    future::Future,
+    io,
+    net::SocketAddr,
+    sync::{atomic::Ordering, Arc},
+    time::{Duration, Instant},
+};
+
+use arc_swap::ArcSwap;
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
+
+use crate::{
+    adaptive::{AdaptiveVerbosity, Bucket, LogMode},
+    allowlist::{Admission, SourceAllowList},
+    capture::PacketCapture,
+    clock::{Clock, SystemClock},
+    coalesce::{BuyCoalescer, CoalescedBuys},
+    cooldown::CreatorCooldown,
+    debug::BuffersRequest,
+    dedup::DuplicateFilter,
+    detector::{
+        process_message, ComputeBudget, Detection, DetectorConfig, InstructionKind, ProcessError, ProcessedMessage,
+    },
+    enrich::Enricher,
+    events::{CreateEvent, TxStatus},
+    forward::{ForwardStats, Forwarder},
+    leaderboard::{CreatorLeaderboard, TopCreator},
+    metrics::{self, Metrics},
+    price::{format_sol, usd_value, SolPrice},
+    ratelimit::{SourceDrops, SourceRateLimiter},
+    reaction::{ReactionHistogram, ReactionTracker},
+    reassembler::{FragmentReassembler, FragmentSizes, ReassemblerStats, CLEANUP_INTERVAL, HEADER_SIZE},
+    score::{self, CreateScorer, SCORE_BUCKETS},
+    sink::{DetectionEvent, SinkHandle, Sinks},
+    slotbatch::{SlotBatch, SlotBatcher},
+    source::{PacketSource, QueueStats},
+    verify::{Verifier, VerifyStats},
+    watchdog::{Alert, LoopProgress, Severity, StallAction, StallWatchdog, Watchdog},
+};
+
+/// How often the stats line is logged
+pub const STATS_INTERVAL: Duration = Duration::from_secs(15);
+
+/// How often coalesced buys, the adaptive logging bucket and the watchdog are checked
+const HOUSEKEEPING_INTERVAL: Duration = Duration::from_millis(100);
+
+/// New rejected sources logged one by one per stats window; the rest are only counted
+const LOGGED_REJECTED_SOURCES: usize = 10;
+
+/// Distinct unknown discriminators logged per stats window; the rest are only counted
+const LOGGED_UNKNOWN_DISCRIMINATORS: usize = 10;
+
+/// Distinct unknown discriminators remembered per stats window, bounding the set against garbage data
+const MAX_REMEMBERED_UNKNOWN: usize = 1024;
+
+/// IPv4 and UDP header bytes around each datagram
+const IP_UDP_OVERHEAD: usize = 28;
+
+/// Counters for one stats window
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct WindowStats {
+    pub packets: u64,
+    pub bytes: u64,
+    /// Smallest and largest datagram, in bytes; 0 without packets
+    pub min_packet: usize,
+    pub max_packet: usize,
+    /// Datagrams making up the reassembled messages, an unfragmented message counting as one
+    pub message_fragments: u64,
+    /// Most datagrams one reassembled message was made of
+    pub max_message_fragments: u16,
+    /// Datagrams byte-identical to one seen shortly before
+    pub duplicates: u64,
+    /// Datagrams dropped by the per-source rate limit, before reassembly
+    pub rate_limited: u64,
+    /// Datagrams from sources outside the allow-list, dropped before reassembly
+    pub rejected: u64,
+    /// Sources rejected for the first time
+    pub rejected_sources: usize,
+    /// Reassembled messages
+    pub messages: usize,
+    pub entries: usize,
+    pub transactions: usize,
+    /// Transactions in the densest message of the window
+    pub max_message_transactions: usize,
+    pub creates: usize,
+    /// Creates folded with their creator's buy, with dev buy detection on; counted in `creates` too
+    pub dev_buys: usize,
+    pub buys: usize,
+    /// Sum of the buys' max SOL cost, in lamports
+    pub buy_volume: u64,
+    pub migrations: usize,
+    /// New SPL token mints, with mint init detection on
+    pub mint_inits: usize,
+    /// Instructions of watched programs that nothing decodes, with unknown detection on
+    pub unknown: usize,
+    /// Distinct program and discriminator pairs among them, up to `MAX_REMEMBERED_UNKNOWN`
+    pub unknown_discriminators: usize,
+    /// pump.fun `extend_account` instructions, with maintenance detection on
+    pub account_extends: usize,
+    /// pump.fun `close_user_volume_accumulator` instructions, with maintenance detection on
+    pub accumulator_closes: usize,
+    /// Reassembled messages that couldn't be decoded as entries
+    pub undecodable: usize,
+    /// Messages only scanned up to a corrupt entry
+    pub partially_decoded: usize,
+    /// Messages holding only tick entries, skipped without a scan
+    pub tick_only: usize,
+    /// Detections dropped by the detection filter
+    pub filtered: usize,
+    /// Creates held back by the creator cooldown; also counted in `creates`
+    pub suppressed: usize,
+    /// With create scoring, creates per score bucket (see `score::bucket`), suppressed ones included
+    pub scores: [usize; SCORE_BUCKETS],
+    /// Creates held back for scoring under the minimum; also counted in `creates`
+    pub low_scores: usize,
+    /// Transactions skipped for having no account keys or no instructions
+    pub malformed: usize,
+    /// Creates whose arguments broke the size limits
+    pub oversized_args: usize,
+    /// With metadata verification, creates whose metadata account isn't the one derived from their mint
+    pub metadata_mismatches: usize,
+    /// Admin and fee instructions seen, kept apart from the trading counters
+    pub admin: usize,
+    /// Detections sent past the enricher, straight to the sinks, while every sink was backed up
+    pub unenriched: usize,
+    /// Detections emitted by transaction outcome, which is only known when the envelope carried the logs
+    pub succeeded: usize,
+    pub failed: usize,
+    pub status_unknown: usize,
+    /// Multi-fragment messages reassembled
+    pub reassembled: u64,
+    /// Multi-fragment messages that expired incomplete
+    pub expired: u64,
+    /// With a leaderboard, the creators with the most creates, most first
+    pub top_creators: Vec<TopCreator>,
+    /// With a rate limit, the sources behind `rate_limited`, most drops first
+    pub rate_limited_sources: Vec<SourceDrops>,
+}
+
+/// One stats window as a single record: the window's counters with the rates and reassembly state derived
+/// from them. With structured stats on, each window is logged as one event carrying these fields.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StatsSnapshot {
+    pub window_secs: f64,
+    pub packets: u64,
+    pub bytes: u64,
+    pub min_packet: usize,
+    pub max_packet: usize,
+    pub duplicates: u64,
+    pub rate_limited: u64,
+    pub rejected: u64,
+    pub messages: usize,
+    pub tick_only: usize,
+    pub entries: usize,
+    pub transactions: usize,
+    pub max_message_transactions: usize,
+    pub creates: usize,
+    pub dev_buys: usize,
+    pub buys: usize,
+    /// Lamports
+    pub buy_volume: u64,
+    pub migrations: usize,
+    pub mint_inits: usize,
+    pub unknown: usize,
+    pub account_extends: usize,
+    pub accumulator_closes: usize,
+    pub undecodable: usize,
+    pub partially_decoded: usize,
+    pub filtered: usize,
+    pub suppressed: usize,
+    pub malformed: usize,
+    pub oversized_args: usize,
+    pub metadata_mismatches: usize,
+    pub admin: usize,
+    pub unenriched: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub status_unknown: usize,
+    /// Messages awaiting fragments when the window closed
+    pub pending: usize,
+    /// Datagrams per reassembled message, on average; absent without messages
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fragments_per_message: Option<f64>,
+    pub max_message_fragments: u16,
+    pub reassembled: u64,
+    pub expired: u64,
+    /// Share of the multi-fragment messages resolved this window that expired; absent if none resolved
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub loss_rate: Option<f64>,
+    pub size_mismatches: u64,
+    pub invalid_fragments: u64,
+    pub implausible_sizes: u64,
+    pub over_capacity: u64,
+    /// Events dropped by full sink queues since startup, over all sinks
+    pub sink_dropped: u64,
+    /// With reaction tracking, detection-to-action percentiles of the window in microseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reaction_p50_us: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reaction_p90_us: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reaction_p99_us: Option<u64>,
+}
+
+impl WindowStats {
+    /// Share of the multi-fragment messages resolved this window that completed rather than expired
+    pub fn completion_ratio(&self) -> Option<f64> {
+        metrics::completion_ratio(self.reassembled, self.expired)
+    }
+
+    /// Add `other`'s counters to these; `top_creators` and `rate_limited_sources` only mean something per
+    /// window and are left alone
+    pub fn accumulate(&mut self, other: &WindowStats) {
+        if other.packets > 0 {
+            self.min_packet = if self.packets == 0 { other.min_packet } else { self.min_packet.min(other.min_packet) };
+        }
+        self.max_packet = self.max_packet.max(other.max_packet);
+        self.message_fragments += other.message_fragments;
+        self.max_message_fragments = self.max_message_fragments.max(other.max_message_fragments);
+        self.packets += other.packets;
+        self.bytes += other.bytes;
+        self.duplicates += other.duplicates;
+        self.rate_limited += other.rate_limited;
+        self.rejected += other.rejected;
+        self.rejected_sources += other.rejected_sources;
+        self.messages += other.messages;
+        self.entries += other.entries;
+        self.transactions += other.transactions;
+        self.max_message_transactions = self.max_message_transactions.max(other.max_message_transactions);
+        self.creates += other.creates;
+        self.dev_buys += other.dev_buys;
+        self.buys += other.buys;
+        self.buy_volume += other.buy_volume;
+        self.migrations += other.migrations;
+        self.mint_inits += other.mint_inits;
+        self.unknown += other.unknown;
+        self.unknown_discriminators += other.unknown_discriminators;
+        self.account_extends += other.account_extends;
+        self.accumulator_closes += other.accumulator_closes;
+        self.undecodable += other.undecodable;
+        self.partially_decoded += other.partially_decoded;
+        self.tick_only += other.tick_only;
+        self.filtered += other.filtered;
+        self.suppressed += other.suppressed;
+        for (total, count) in self.scores.iter_mut().zip(other.scores) {
+            *total += count;
+        }
+        self.low_scores += other.low_scores;
+        self.malformed += other.malformed;
+        self.oversized_args += other.oversized_args;
+        self.metadata_mismatches += other.metadata_mismatches;
+        self.admin += other.admin;
+        self.unenriched += other.unenriched;
+        self.succeeded += other.succeeded;
+        self.failed += other.failed;
+        self.status_unknown += other.status_unknown;
+        self.reassembled += other.reassembled;
+        self.expired += other.expired;
+    }
+}
+
+/// Counters over the whole run, for the summary logged at the end
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RunStats {
+    /// Every closed window's counters added up
+    pub totals: WindowStats,
+    /// Most messages awaiting fragments at once
+    pub peak_pending: usize,
+    /// Reassembled messages dropped for a total_size mismatch
+    pub size_mismatches: u64,
+    /// Fragments dropped with the reassembler at its pending-message cap
+    pub over_capacity: u64,
+    /// Raw datagram forwarding, when enabled
+    pub forwarded: ForwardStats,
+    /// RPC verification of sampled creates, when enabled
+    pub verified: VerifyStats,
+}
+
+/// When the next heartbeat is due, and the packet count it reports from
+struct Heartbeat {
+    interval: Duration,
+    last: Instant,
+    /// Lifetime packet count at the last heartbeat
+    packets: u64,
+}
+
+/// Whether the sinks are saturated, by the pressure of the least loaded one
+struct Throttle {
+    /// Saturated from this pressure on, until it falls below half of it
+    threshold: f64,
+    /// When the sinks became saturated, while they are
+    since: Option<Instant>,
+}
+
+/// Everything between the packet source and the outputs
+pub struct Pipeline<C: Clock = SystemClock> {
+    clock: C,
+    reassembler: FragmentReassembler<C>,
+    detector: Arc<ArcSwap<DetectorConfig>>,
+    coalescer: Option<BuyCoalescer>,
+    cooldown: Option<CreatorCooldown>,
+    scorer: Option<CreateScorer>,
+    leaderboard: Option<CreatorLeaderboard>,
+    duplicates: Option<DuplicateFilter>,
+    rate_limit: Option<SourceRateLimiter>,
+    allow_list: Option<SourceAllowList>,
+    /// Unknown program and discriminator pairs seen this window, so each is logged once
+    unknown_seen: HashSet<(Pubkey, Vec<u8>)>,
+    verbosity: Option<AdaptiveVerbosity>,
+    watchdog: Option<Watchdog>,
+    /// Timeout and action of the stall watchdog `run` starts
+    stall: Option<(Duration, StallAction)>,
+    progress: Arc<LoopProgress>,
+    /// Warn when a window's reassembly completion ratio falls below this
+    completion_alert: Option<f64>,
+    heartbeat: Option<Heartbeat>,
+    throttle: Option<Throttle>,
+    forwarder: Option<Forwarder>,
+    verifier: Option<Verifier>,
+    capture: Option<PacketCapture>,
+    reactions: Option<Arc<ReactionTracker>>,
+    slots: Option<SlotBatcher>,
+    /// Follow each flushed slot with a `SlotComplete` marker
+    slot_markers: bool,
+    /// Stop `run` once this many datagrams have been received
+    max_packets: Option<u64>,
+    /// Stop `run` once this long has passed since the pipeline was created
+    max_runtime: Option<Duration>,
+    /// Debug endpoint requests for in-flight buffers, answered between datagrams
+    buffer_requests: Option<mpsc::Receiver<BuffersRequest>>,
+    /// Most buffers one answer lists
+    buffer_limit: usize,
+    /// Global account the last create referenced, with the detector's `global_index` set
+    global: Option<Pubkey>,
+    sinks: Arc<Sinks>,
+    enricher: Option<Enricher>,
+    price: Option<SolPrice>,
+    stats_interval: Duration,
+    path_mtu: Option<usize>,
+    /// Typical fragment payload size of the last window that had one
+    fragment_size: Option<usize>,
+    /// Log each stats window as one event of `StatsSnapshot` fields instead of readable lines
+    structured_stats: bool,
+    window: WindowStats,
+    last_window: Option<WindowStats>,
+    last_snapshot: Option<StatsSnapshot>,
+    run: RunStats,
+    metrics: Arc<Metrics>,
+    msg_seq: u64,
+    started: Instant,
+    last_stats: Instant,
+    last_cleanup: Instant,
+}
+
+impl<C: Clock> Pipeline<C> {
+    /// `clock` should be the one `reassembler` runs on
+    pub fn new(detector: DetectorConfig, reassembler: FragmentReassembler<C>, clock: C) -> Self {
+        let now = clock.now();
+        Self {
+            clock,
+            reassembler,
+            detector: Arc::new(ArcSwap::from_pointee(detector)),
+            coalescer: None,
+            cooldown: None,
+            scorer: None,
+            leaderboard: None,
+            duplicates: None,
+            rate_limit: None,
+            allow_list: None,
+            unknown_seen: HashSet::new(),
+            verbosity: None,
+            watchdog: None,
+            stall: None,
+            progress: Arc::new(LoopProgress::default()),
+            completion_alert: None,
+            heartbeat: None,
+            throttle: None,
+            forwarder: None,
+            verifier: None,
+            capture: None,
+            reactions: None,
+            slots: None,
+            slot_markers: false,
+            max_packets: None,
+            max_runtime: None,
+            buffer_requests: None,
+            buffer_limit: 0,
+            global: None,
+            sinks: Arc::new(Sinks::default()),
+            enricher: None,
+            price: None,
+            stats_interval: STATS_INTERVAL,
+            path_mtu: None,
+            fragment_size: None,
+            structured_stats: false,
+            window: WindowStats::default(),
+            last_window: None,
+            last_snapshot: None,
+            run: RunStats::default(),
+            metrics: Arc::default(),
+            msg_seq: 0,
+            started: now,
+            last_stats: now,
+            last_cleanup: now,
+        }
+    }
+
+    /// Aggregate buys per mint instead of logging each one
+    pub fn with_coalescer(mut self, coalescer: BuyCoalescer) -> Self {
+        self.coalescer = Some(coalescer);
+        self
+    }
+
+    /// Report at most one create per creator per cooldown, counting the rest
+    pub fn with_creator_cooldown(mut self, cooldown: CreatorCooldown) -> Self {
+        self.cooldown = Some(cooldown);
+        self
+    }
+
+    /// Give each create a priority score, suppressing those under the configured minimum
+    pub fn with_create_scoring(mut self, scorer: CreateScorer) -> Self {
+        self.scorer = Some(scorer);
+        self
+    }
+
+    /// Report the creators with the most creates in each stats window
+    pub fn with_leaderboard(mut self, leaderboard: CreatorLeaderboard) -> Self {
+        self.leaderboard = Some(leaderboard);
+        self
+    }
+
+    /// Count datagrams that repeat byte for byte within the filter's window
+    pub fn with_duplicate_filter(mut self, filter: DuplicateFilter) -> Self {
+        self.duplicates = Some(filter);
+        self
+    }
+
+    /// Drop datagrams from sources over their rate limit before they reach the reassembler
+    pub fn with_rate_limit(mut self, limiter: SourceRateLimiter) -> Self {
+        self.rate_limit = Some(limiter);
+        self
+    }
+
+    /// Drop datagrams from sources outside `allow_list` before they reach the reassembler
+    pub fn with_source_allow_list(mut self, allow_list: SourceAllowList) -> Self {
+        self.allow_list = Some(allow_list);
+        self
+    }
+
+    /// Switch detection logs to periodic summaries while the detection rate is high
+    pub fn with_adaptive_logging(mut self, verbosity: AdaptiveVerbosity) -> Self {
+        self.verbosity = Some(verbosity);
+        self
+    }
+
+    /// Alert while no packets arrive, and log when they resume
+    pub fn with_watchdog(mut self, watchdog: Watchdog) -> Self {
+        self.watchdog = Some(watchdog);
+        self
+    }
+
+    /// While `run` is receiving, watch from another thread that its loop keeps turning, and act on it not having
+    /// turned for `timeout`, e.g. with a decoder stuck on a message
+    pub fn with_stall_watchdog(mut self, timeout: Duration, action: StallAction) -> Self {
+        self.stall = Some((timeout, action));
+        self
+    }
+
+    /// Warn for each window in which less than `threshold` (0 to 1) of the multi-fragment
+    /// messages resolved were completed rather than expired
+    pub fn with_completion_alert(mut self, threshold: f64) -> Self {
+        self.completion_alert = Some(threshold);
+        self
+    }
+
+    /// Send the sinks a heartbeat every `interval`, detections or not
+    pub fn with_heartbeat(mut self, interval: Duration) -> Self {
+        let last = self.clock.now();
+        self.heartbeat = Some(Heartbeat { interval, last, packets: 0 });
+        self
+    }
+
+    /// Treat the sinks as saturated once even the least loaded one has `threshold` of its queue in use, warning
+    /// and sending detections straight to the sinks rather than through the enricher until it falls below half
+    pub fn with_sink_throttle(mut self, threshold: f64) -> Self {
+        self.throttle = Some(Throttle { threshold, since: None });
+        self
+    }
+
+    /// Copy every received datagram to the forwarder's targets before processing it
+    pub fn with_forwarder(mut self, forwarder: Forwarder) -> Self {
+        self.forwarder = Some(forwarder);
+        self
+    }
+
+    /// Check a sample of the creates emitted against the verifier's RPC node, reporting the false positives
+    pub fn with_verifier(mut self, verifier: Verifier) -> Self {
+        self.verifier = Some(verifier);
+        self
+    }
+
+    /// Write sampled datagrams and messages that fail to decode to `capture`
+    pub fn with_capture(mut self, capture: PacketCapture) -> Self {
+        self.capture = Some(capture);
+        self
+    }
+
+    /// Stamp each detection event with an id from `tracker`, whose `mark_acted` times the round trip to acting on it
+    pub fn with_reaction_tracking(mut self, tracker: Arc<ReactionTracker>) -> Self {
+        self.reactions = Some(tracker);
+        self
+    }
+
+    /// Hold detections from messages with a slot until the slot closes, then send them followed by a
+    /// `SlotComplete` marker; a slot closes when a higher one arrives or `timeout` after it opened
+    pub fn with_slot_batching(mut self, timeout: Duration) -> Self {
+        self.slots = Some(SlotBatcher::new(timeout));
+        self.slot_markers = true;
+        self
+    }
+
+    /// Hold detections from messages with a slot as slot batching does, so the sinks see slots in ascending
+    /// order, but without the markers. Detections from a slot that was already flushed go out flagged `late`
+    pub fn with_slot_ordering(mut self, window: Duration) -> Self {
+        self.slots = Some(SlotBatcher::new(window));
+        self.slot_markers = false;
+        self
+    }
+
+    /// Return from `run` once `packets` datagrams have been received, as on shutdown
+    pub fn with_max_packets(mut self, packets: u64) -> Self {
+        self.max_packets = Some(packets);
+        self
+    }
+
+    /// Return from `run` once `runtime` has passed since the pipeline was created, as on shutdown
+    pub fn with_max_runtime(mut self, runtime: Duration) -> Self {
+        self.max_runtime = Some(runtime);
+        self
+    }
+
+    /// Answer the debug endpoint's `requests` with the `limit` oldest in-flight buffers
+    pub fn with_buffer_inspection(mut self, requests: mpsc::Receiver<BuffersRequest>, limit: usize) -> Self {
+        self.buffer_requests = Some(requests);
+        self.buffer_limit = limit;
+        self
+    }
+
+    /// Send detections to `sinks`, through `enricher` when given
+    pub fn with_outputs(mut self, sinks: Arc<Sinks>, enricher: Option<Enricher>) -> Self {
+        self.sinks = sinks;
+        self.enricher = enricher;
+        self
+    }
+
+    /// Annotate logged SOL amounts with an approximate USD value
+    pub fn with_price(mut self, price: SolPrice) -> Self {
+        self.price = Some(price);
+        self
+    }
+
+    pub fn with_stats_interval(mut self, interval: Duration) -> Self {
+        self.stats_interval = interval;
+        self
+    }
+
+    /// Log each stats window as one structured event, for JSON logs, instead of the readable stats lines
+    pub fn with_structured_stats(mut self) -> Self {
+        self.structured_stats = true;
+        self
+    }
+
+    /// Warn when fragments arrive too large to cross a link of `mtu` bytes unfragmented
+    pub fn with_path_mtu(mut self, mtu: usize) -> Self {
+        self.path_mtu = Some(mtu);
+        self
+    }
+
+    /// Shared detector settings; storing a new config applies from the next message
+    pub fn detector_config(&self) -> Arc<ArcSwap<DetectorConfig>> {
+        self.detector.clone()
+    }
+
+    /// Messages reassembled so far
+    pub fn msg_seq(&self) -> u64 {
+        self.msg_seq
+    }
+
+    /// Counters of the most recently logged stats window
+    pub fn last_window(&self) -> Option<&WindowStats> {
+        self.last_window.as_ref()
+    }
+
+    /// Snapshot of the most recently logged stats window
+    pub fn last_snapshot(&self) -> Option<&StatsSnapshot> {
+        self.last_snapshot.as_ref()
+    }
+
+    /// Counters of every stats window logged so far
+    pub fn run_stats(&self) -> &RunStats {
+        &self.run
+    }
+
+    /// Lifetime counters, readable from any thread while the pipeline runs
+    pub fn metrics(&self) -> Arc<Metrics> {
+        self.metrics.clone()
+    }
+
+    /// The tracker given to `with_reaction_tracking`
+    pub fn reactions(&self) -> Option<Arc<ReactionTracker>> {
+        self.reactions.clone()
+    }
+
+    /// Global account the most recent create referenced, with the detector's `global_index` set
+    pub fn global_account(&self) -> Option<Pubkey> {
+        self.global
+    }
+
+    pub fn reassembler(&self) -> &FragmentReassembler<C> {
+        &self.reassembler
+    }
+
+    /// Receive from `source` until `shutdown` resolves or the source fails
+    pub async fn run<S: PacketSource>(&mut self, source: &mut S, shutdown: impl Future<Output = ()>) -> io::Result<()> {
+        let mut buf = vec![0u8; 65536];
+        tokio::pin!(shutdown);
+        let mut housekeeping = tokio::time::interval(HOUSEKEEPING_INTERVAL);
+        // Covers a quiet source; with packets arriving, the clock check after each one ends the run first
+        let runtime_left = self.max_runtime.map(|max| max.saturating_sub(self.clock.now().duration_since(self.started)));
+        let deadline = tokio::time::sleep(runtime_left.unwrap_or_default());
+        tokio::pin!(deadline);
+        // Housekeeping ticks keep the loop turning while the source is quiet
+        let _stall = match self.stall {
+            Some((timeout, action)) => Some(StallWatchdog::spawn(self.progress.clone(), timeout, action)?),
+            None => None,
+        };
+
+        loop {
+            if self.stall.is_some() {
+                self.progress.turn();
+            }
+            // A pending shutdown wins over a busy socket
+            let (len, src) = tokio::select! {
+                biased;
+                _ = &mut shutdown => {
+                    info!("🛑 Shutdown signal received");
+                    return Ok(());
+                }
+                _ = &mut deadline, if runtime_left.is_some() => {
+                    info!("🏁 Run time limit of {}s reached; shutting down", self.max_runtime.unwrap_or_default().as_secs());
+                    return Ok(());
+                }
+                Some(reply) = next_request(&mut self.buffer_requests) => {
+                    // The endpoint may have given up waiting
+                    let _ = reply.send(self.reassembler.inspect(self.buffer_limit));
+                    continue;
+                }
+                res = source.recv_from(&mut buf) => res?,
+                _ = housekeeping.tick(), if self.needs_housekeeping() => {
+                    let now = self.clock.now();
+                    let usd = self.usd_per_sol();
+                    if let Some(coalescer) = self.coalescer.as_mut() {
+                        coalescer.flush_expired(now).iter().for_each(|group| log_coalesced(group, usd));
+                    }
+                    self.poll_verbosity(now);
+                    self.poll_heartbeat(now);
+                    self.poll_throttle(now);
+                    self.poll_slots(now);
+                    if let Some(alert) = self.watchdog.as_mut().and_then(|w| w.poll(now)) {
+                        log_stale(&alert);
+                    }
+                    continue;
+                }
+            };
+            if self.handle_packet(&buf[..len], src) {
+                if let Some(queue) = source.take_queue_stats() {
+                    log_queue_stats(&queue);
+                }
+            }
+            if let Some(reason) = self.limit_reached() {
+                info!("🏁 {}; shutting down", reason);
+                return Ok(());
+            }
+        }
+    }
+
+    /// Whether anything is enabled that the housekeeping ticks must poll while the source is quiet
+    fn needs_housekeeping(&self) -> bool {
+        self.coalescer.is_some()
+            || self.verbosity.is_some()
+            || self.watchdog.is_some()
+            || self.stall.is_some()
+            || self.heartbeat.is_some()
+            || self.throttle.is_some()
+            || self.slots.is_some()
+    }
+
+    /// Which of `max_packets` and `max_runtime` the run has reached, if either
+    fn limit_reached(&self) -> Option<String> {
+        let packets = self.run.totals.packets + self.window.packets;
+        if let Some(max) = self.max_packets.filter(|&max| packets >= max) {
+            return Some(format!("Packet limit of {} reached", max));
+        }
+        let elapsed = self.clock.now().duration_since(self.started);
+        self.max_runtime
+            .filter(|&max| elapsed >= max)
+            .map(|max| format!("Run time limit of {}s reached", max.as_secs()))
+    }
+
+    /// Process one datagram, returning whether a stats window was just reported
+    fn handle_packet(&mut self, packet: &[u8], src: SocketAddr) -> bool {
+        self.window.packets += 1;
+        self.window.bytes += packet.len() as u64;
+        self.window.min_packet = if self.window.packets == 1 { packet.len() } else { self.window.min_packet.min(packet.len()) };
+        self.window.max_packet = self.window.max_packet.max(packet.len());
+        metrics::add(&self.metrics.packets, 1);
+        if let Some(forwarder) = &self.forwarder {
+            forwarder.forward(packet);
+        }
+        if let Some(capture) = self.capture.as_mut() {
+            capture.packet(packet, src);
+        }
+
+        if self.window.packets == 1 {
+            info!("🎉 First packet from {}! ({} bytes)", src, packet.len());
+        }
+
+        let now = self.clock.now();
+        if let Some(silence) = self.watchdog.as_mut().and_then(|w| w.packet(now)) {
+            info!("✅ Packets resumed after {:.0}s without any", silence.as_secs_f64());
+        }
+        if let Some(Admission::Rejected { first }) = self.allow_list.as_mut().map(|list| list.check(src.ip())) {
+            self.window.rejected += 1;
+            metrics::add(&self.metrics.rejected, 1);
+            if first {
+                self.window.rejected_sources += 1;
+                if self.window.rejected_sources <= LOGGED_REJECTED_SOURCES {
+                    warn!("🚫 Rejecting datagrams from {}, which isn't in ALLOWED_SOURCES", src.ip());
+                }
+            }
+            return self.poll_stats(now);
+        }
+        if let Some(filter) = self.duplicates.as_mut() {
+            self.window.duplicates += filter.check(packet, now) as u64;
+        }
+
+        // Cleanup old fragments every 5 seconds
+        if now.duration_since(self.last_cleanup) >= CLEANUP_INTERVAL {
+            self.reassembler.cleanup_old();
+            if let Some(cooldown) = self.cooldown.as_mut() {
+                cooldown.prune(now);
+            }
+            if let Some(limiter) = self.rate_limit.as_mut() {
+                limiter.prune(now);
+            }
+            self.last_cleanup = now;
+        }
+
+        if self.rate_limit.as_mut().is_some_and(|limiter| !limiter.allow(src.ip(), now)) {
+            self.window.rate_limited += 1;
+            metrics::add(&self.metrics.rate_limited, 1);
+            return self.poll_stats(now);
+        }
+
+        // Process packet through reassembler
+        let complete = self.reassembler.process_packet_from(packet, src);
+        self.run.peak_pending = self.run.peak_pending.max(self.reassembler.pending());
+        if let Some(complete_data) = complete {
+            self.msg_seq += 1;
+            self.progress.scanning(self.msg_seq);
+            let (processed, undecodable) = match process_message(&complete_data, &self.detector.load(), self.msg_seq) {
+                Ok(processed) => (processed, false),
+                Err(e) => {
+                    log_process_error(self.msg_seq, &e);
+                    (ProcessedMessage::default(), true)
+                }
+            };
+            self.progress.scanning(0);
+            self.window.messages += 1;
+            self.window.entries += processed.entries;
+            self.window.transactions += processed.transactions;
+            self.window.max_message_transactions = self.window.max_message_transactions.max(processed.transactions);
+            self.window.undecodable += undecodable as usize;
+            self.window.partially_decoded += processed.failed_entry.is_some() as usize;
+            self.window.tick_only += processed.tick_only as usize;
+            self.window.filtered += processed.filtered;
+            self.window.malformed += processed.malformed;
+            self.window.oversized_args += processed.oversized_args;
+            self.window.metadata_mismatches += processed.metadata_mismatches;
+            self.window.admin += processed.admin;
+            metrics::add(&self.metrics.messages, 1);
+            metrics::add(&self.metrics.undecodable, undecodable as u64);
+            metrics::add(&self.metrics.filtered, processed.filtered as u64);
+            self.track_global(&processed.globals);
+            if let (Some(batcher), Some(context)) = (self.slots.as_mut(), &processed.context) {
+                let closed = batcher.observe(context.slot, now);
+                self.send_slots(closed);
+            }
+            if undecodable || processed.failed_entry.is_some() {
+                if let Some(capture) = self.capture.as_mut() {
+                    capture.failed(&complete_data, src);
+                }
+            }
+            let usd = self.usd_per_sol();
+            let per_event = self.verbosity.as_ref().is_none_or(|v| v.mode() == LogMode::PerEvent);
+            if !processed.detections.is_empty() {
+                self.poll_throttle(now);
+            }
+            let saturated = self.sinks_saturated();
+
+            for (index, (detection, &status)) in processed.detections.iter().zip(&processed.statuses).enumerate() {
+                // Before the cooldown, so a creator it holds back still climbs the leaderboard
+                let creator = match detection {
+                    Detection::Create { creator, .. } | Detection::CreateWithDevBuy { creator, .. } => Some(creator),
+                    _ => None,
+                };
+                if let (Some(creator), Some(leaderboard)) = (creator, self.leaderboard.as_mut()) {
+                    leaderboard.record(*creator);
+                }
+                // Likewise, so it still counts toward its creator's history
+                let score = self.scorer.as_mut().and_then(|scorer| scorer.score(&processed.detections, index));
+                if let (Some(score), Detection::Create { mint, .. } | Detection::CreateWithDevBuy { mint, .. }) =
+                    (score, detection)
+                {
+                    self.window.scores[score::bucket(score)] += 1;
+                    debug!(target: "detector", "🏅 Create of {} scored {}", mint, score);
+                }
+                if let (Some(creator), Some(cooldown)) = (creator, self.cooldown.as_mut()) {
+                    if !cooldown.allow(*creator, now) {
+                        self.window.creates += 1;
+                        self.window.suppressed += 1;
+                        metrics::add(&self.metrics.creates, 1);
+                        metrics::add(&self.metrics.suppressed, 1);
+                        continue;
+                    }
+                }
+                if let (Some(score), Some(scorer)) = (score, &self.scorer) {
+                    if score < scorer.config().min_score {
+                        self.window.creates += 1;
+                        self.window.low_scores += 1;
+                        metrics::add(&self.metrics.creates, 1);
+                        continue;
+                    }
+                }
+                if let Some(verbosity) = self.verbosity.as_mut() {
+                    verbosity.record(detection);
+                }
+                match status {
+                    TxStatus::Succeeded => self.window.succeeded += 1,
+                    TxStatus::Failed => self.window.failed += 1,
+                    TxStatus::Unknown => self.window.status_unknown += 1,
+                }
+                let mut event = DetectionEvent::new(detection.clone(), self.msg_seq);
+                event.context = processed.context.clone();
+                event.status = Some(status);
+                event.score = score;
+                event.usd_value = detection.sol_amount().and_then(|lamports| usd_value(lamports, self.usd_per_sol()));
+                if let (Detection::Create { mint, .. } | Detection::CreateWithDevBuy { mint, .. }, Some(verifier)) =
+                    (detection, self.verifier.as_mut())
+                {
+                    if let Some(&signature) = processed.signatures.get(index) {
+                        verifier.submit(signature, *mint);
+                    }
+                }
+                if let Some(tracker) = &self.reactions {
+                    event.detection_id = Some(tracker.emit(now));
+                    event.emitted_at = Some(now);
+                }
+                if let (Some(batcher), Some(context)) = (self.slots.as_mut(), &processed.context) {
+                    batcher.push(context.slot, event, now);
+                } else {
+                    self.window.unenriched += send(self.enricher.as_ref(), &self.sinks, event, saturated) as usize;
+                }
+                match (detection, self.coalescer.as_mut()) {
+                    (Detection::Buy { mint, max_sol_cost, .. }, Some(coalescer)) => {
+                        self.window.buys += 1;
+                        metrics::add(&self.metrics.buys, 1);
+                        self.window.buy_volume += max_sol_cost;
+                        if let Some(evicted) = coalescer.push(*mint, *max_sol_cost, now) {
+                            log_coalesced(&evicted, usd);
+                        }
+                    }
+                    (Detection::Buy { max_sol_cost, .. }, None) => {
+                        self.window.buys += 1;
+                        metrics::add(&self.metrics.buys, 1);
+                        self.window.buy_volume += max_sol_cost;
+                    }
+                    (Detection::Create { .. }, _) => {
+                        self.window.creates += 1;
+                        metrics::add(&self.metrics.creates, 1);
+                    }
+                    (Detection::CreateWithDevBuy { .. }, _) => {
+                        self.window.creates += 1;
+                        self.window.dev_buys += 1;
+                        metrics::add(&self.metrics.creates, 1);
+                    }
+                    (Detection::Migration { .. }, _) => {
+                        self.window.migrations += 1;
+                        metrics::add(&self.metrics.migrations, 1);
+                    }
+                    (Detection::MintInit { .. }, _) => self.window.mint_inits += 1,
+                    (Detection::Unknown { .. }, _) => self.window.unknown += 1,
+                    (Detection::Maintenance { instruction: InstructionKind::ExtendAccount, .. }, _) => {
+                        self.window.account_extends += 1
+                    }
+                    (Detection::Maintenance { .. }, _) => self.window.accumulator_closes += 1,
+                    (Detection::Heartbeat { .. } | Detection::SlotComplete { .. }, _) => {}
+                }
+                let coalesced = matches!(detection, Detection::Buy { .. }) && self.coalescer.is_some();
+                if per_event && !coalesced && self.first_of_kind(detection) {
+                    log_detection(detection, status, self.msg_seq, &processed, usd);
+                }
+            }
+            if !processed.detections.is_empty() {
+                self.refresh_dropped();
+            }
+        }
+        self.poll_verbosity(now);
+        self.poll_heartbeat(now);
+        self.poll_slots(now);
+        self.poll_stats(now)
+    }
+
+    /// Whether to log `detection`: unknown instructions only for the first `LOGGED_UNKNOWN_DISCRIMINATORS`
+    /// program and discriminator pairs of the window, so one busy instruction can't flood the log
+    fn first_of_kind(&mut self, detection: &Detection) -> bool {
+        let Detection::Unknown { program, discriminator, .. } = detection else {
+            return true;
+        };
+        let pair = (*program, discriminator.clone());
+        if self.unknown_seen.len() >= MAX_REMEMBERED_UNKNOWN || !self.unknown_seen.insert(pair) {
+            return false;
+        }
+        self.window.unknown_discriminators += 1;
+        self.window.unknown_discriminators <= LOGGED_UNKNOWN_DISCRIMINATORS
+    }
+
+    /// Report the stats window if it's due, returning whether it was
+    fn poll_stats(&mut self, now: Instant) -> bool {
+        if now.duration_since(self.last_stats) >= self.stats_interval {
+            self.report_stats(now.duration_since(self.last_stats));
+            self.last_stats = now;
+            return true;
+        }
+        false
+    }
+
+    /// Log when creates start referencing a different global account, usually a protocol upgrade
+    fn track_global(&mut self, globals: &[Pubkey]) {
+        for &global in globals {
+            match self.global.replace(global) {
+                None => info!(target: "detector", "pump.fun global account: {}", global),
+                Some(previous) if previous != global => {
+                    info!(target: "detector", "pump.fun global account changed: {} -> {}", previous, global)
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    /// Send a heartbeat if one is due
+    fn poll_heartbeat(&mut self, now: Instant) {
+        let Some(heartbeat) = self.heartbeat.as_mut().filter(|h| now.duration_since(h.last) >= h.interval) else {
+            return;
+        };
+        let packets = self.metrics.packets.load(Ordering::Relaxed);
+        let detection = Detection::Heartbeat { packets: packets - heartbeat.packets, pending: self.reassembler.pending() };
+        heartbeat.last = now;
+        heartbeat.packets = packets;
+        self.sinks.emit(DetectionEvent::new(detection, self.msg_seq));
+    }
+
+    /// Send the batches of slots open for the batching timeout
+    fn poll_slots(&mut self, now: Instant) {
+        if let Some(closed) = self.slots.as_mut().map(|batcher| batcher.flush_expired(now)) {
+            self.send_slots(closed);
+        }
+    }
+
+    /// Send each closed slot's detections, then its marker if markers are on
+    fn send_slots(&mut self, closed: Vec<SlotBatch>) {
+        let saturated = self.sinks_saturated();
+        for batch in closed {
+            let detections = batch.events.len();
+            if batch.late {
+                debug!(target: "sink", "Slot {} flushed late, with {} detections", batch.slot, detections);
+            }
+            for mut event in batch.events {
+                event.late = batch.late;
+                self.window.unenriched += send(self.enricher.as_ref(), &self.sinks, event, saturated) as usize;
+            }
+            if self.slot_markers {
+                let mut marker =
+                    DetectionEvent::new(Detection::SlotComplete { slot: batch.slot, detections }, self.msg_seq);
+                marker.late = batch.late;
+                send(self.enricher.as_ref(), &self.sinks, marker, saturated);
+            }
+        }
+    }
+
+    /// Re-check the sinks' pressure, logging when they become saturated and when they recover
+    fn poll_throttle(&mut self, now: Instant) {
+        let (Some(throttle), Some(pressure)) = (self.throttle.as_mut(), self.sinks.pressure()) else {
+            return;
+        };
+        match throttle.since {
+            None if pressure >= throttle.threshold => {
+                throttle.since = Some(now);
+                let shed = match self.enricher {
+                    Some(_) => "sending detections without enrichment",
+                    None => "nothing to shed, so detections may be dropped",
+                };
+                warn!(target: "sink", "🐢 Every sink's queue is at least {:.0}% full; {} until they drain", pressure * 100.0, shed);
+            }
+            Some(since) if pressure < throttle.threshold / 2.0 => {
+                throttle.since = None;
+                info!(target: "sink", "✅ Sink queues draining after {:.1}s saturated", now.duration_since(since).as_secs_f64());
+            }
+            _ => {}
+        }
+    }
+
+    fn sinks_saturated(&self) -> bool {
+        self.throttle.as_ref().is_some_and(|throttle| throttle.since.is_some())
+    }
+
+    /// Copy the sinks' drop counts into the metrics
+    fn refresh_dropped(&self) {
+        let dropped = self.sinks.handles().iter().map(SinkHandle::dropped).sum();
+        self.metrics.dropped.store(dropped, Ordering::Relaxed);
+    }
+
+    fn usd_per_sol(&self) -> Option<f64> {
+        self.price.as_ref().and_then(SolPrice::usd)
+    }
+
+    /// Close the adaptive logging bucket if it's due, logging mode changes and summaries
+    fn poll_verbosity(&mut self, now: Instant) {
+        if let Some(bucket) = self.verbosity.as_mut().and_then(|v| v.poll(now)) {
+            log_bucket(&bucket);
+        }
+    }
+
+    /// Log the counters of the window that ran for `elapsed` and start a new window
+    fn report_stats(&mut self, elapsed: Duration) {
+        let mut window = std::mem::take(&mut self.window);
+        self.unknown_seen.clear();
+        let secs = elapsed.as_secs_f64().max(f64::EPSILON);
+        if !self.structured_stats {
+            info!(
+                "📊 Stats: {} pkts, {:.2} MB, {} msgs, {} entries ({:.0}/s), {} txs ({:.0}/s, densest msg {}), {} creates, {} buys ({} max), {} migrations",
+                window.packets,
+                window.bytes as f64 / 1_000_000.0,
+                self.msg_seq,
+                window.entries,
+                window.entries as f64 / secs,
+                window.transactions,
+                window.transactions as f64 / secs,
+                window.max_message_transactions,
+                window.creates,
+                window.buys,
+                format_sol(window.buy_volume, self.usd_per_sol()),
+                window.migrations
+            );
+        }
+        self.refresh_dropped();
+        for sink in self.sinks.handles().iter().filter(|s| s.dropped() > 0) {
+            warn!(target: "sink", "⚠️  {} sink has dropped {} events (queue full)", sink.name(), sink.dropped());
+        }
+        if window.duplicates > 0 {
+            warn!(
+                "⚠️  {} duplicate datagrams ({:.1}% of packets); something upstream may be double-sending",
+                window.duplicates,
+                window.duplicates as f64 * 100.0 / window.packets as f64
+            );
+        }
+        if window.rejected > 0 {
+            let unlogged = window.rejected_sources.saturating_sub(LOGGED_REJECTED_SOURCES);
+            let unlogged = if unlogged > 0 { format!(" ({} new sources not logged)", unlogged) } else { String::new() };
+            warn!("⚠️  {} datagrams rejected from sources outside ALLOWED_SOURCES{}", window.rejected, unlogged);
+        }
+        if let Some(limiter) = self.rate_limit.as_mut() {
+            window.rate_limited_sources = limiter.take_dropped();
+            log_rate_limited(&window.rate_limited_sources);
+        }
+        if window.undecodable > 0 {
+            warn!(target: "detector", "⚠️  {} reassembled messages could not be decoded", window.undecodable);
+        }
+        if window.partially_decoded > 0 {
+            warn!(
+                target: "detector",
+                "⚠️  {} reassembled messages had a corrupt entry; only the entries before it were scanned",
+                window.partially_decoded
+            );
+        }
+        if window.suppressed > 0 && !self.structured_stats {
+            info!(target: "detector", "⏸️  {} creates suppressed by the creator cooldown", window.suppressed);
+        }
+        if window.scores.iter().any(|&count| count > 0) {
+            let [a, b, c, d, e] = window.scores;
+            debug!(
+                target: "detector",
+                "🏅 Create scores: {} at 0-19, {} at 20-39, {} at 40-59, {} at 60-79, {} at 80-100; {} suppressed under SCORE_MIN",
+                a, b, c, d, e, window.low_scores
+            );
+        }
+        if window.succeeded + window.failed > 0 {
+            info!(
+                target: "detector",
+                "🧾 Transaction status: {} detections succeeded, {} failed (reverted), {} unknown",
+                window.succeeded, window.failed, window.status_unknown
+            );
+        }
+        if window.dev_buys > 0 && !self.structured_stats {
+            info!(target: "detector", "🧑‍💻 {} of the creates came with a dev buy", window.dev_buys);
+        }
+        if window.mint_inits > 0 && !self.structured_stats {
+            info!(target: "detector", "🪙 {} new token mints", window.mint_inits);
+        }
+        if window.unknown > 0 {
+            let unlogged = window.unknown_discriminators.saturating_sub(LOGGED_UNKNOWN_DISCRIMINATORS);
+            let unlogged = if unlogged > 0 { format!(", {} not logged", unlogged) } else { String::new() };
+            info!(
+                target: "detector",
+                "🔎 {} unknown instructions ({} distinct discriminators{})",
+                window.unknown, window.unknown_discriminators, unlogged
+            );
+        }
+        if window.account_extends + window.accumulator_closes > 0 {
+            debug!(
+                target: "detector",
+                "🧰 {} account maintenance instructions: {} extend_account, {} close_user_volume_accumulator",
+                window.account_extends + window.accumulator_closes, window.account_extends, window.accumulator_closes
+            );
+        }
+        if window.tick_only > 0 {
+            debug!(
+                target: "detector",
+                "{} of {} messages held only tick entries ({:.1}%)",
+                window.tick_only, window.messages, window.tick_only as f64 * 100.0 / window.messages as f64
+            );
+        }
+        if window.malformed > 0 {
+            debug!(target: "detector", "{} transactions skipped with no account keys or no instructions", window.malformed);
+        }
+        if window.oversized_args > 0 {
+            warn!(target: "detector", "{} creates had arguments over the size limits", window.oversized_args);
+        }
+        if window.metadata_mismatches > 0 {
+            warn!(
+                target: "detector",
+                "⚠️  {} creates referenced a metadata account other than their mint's PDA; the create layout may have changed",
+                window.metadata_mismatches
+            );
+        }
+        if window.filtered > 0 {
+            debug!(target: "detector", "{} detections didn't match the detection filter", window.filtered);
+        }
+        if window.admin > 0 {
+            debug!(target: "detector", "{} admin and fee instructions", window.admin);
+        }
+        if window.unenriched > 0 {
+            warn!(target: "sink", "{} detections sent without enrichment while the sinks were saturated", window.unenriched);
+        }
+        if let Some(leaderboard) = self.leaderboard.as_mut() {
+            window.top_creators = leaderboard.take();
+            log_top_creators(&window.top_creators);
+        }
+        let reactions = self.reactions.as_ref().map(|tracker| tracker.take_window());
+        if let Some(histogram) = reactions.as_ref().filter(|_| !self.structured_stats) {
+            log_reactions("⏱️  Reaction", histogram);
+        }
+        let reassembly = self.reassembler.take_stats();
+        self.record_resolved(&mut window, &reassembly);
+        self.run.totals.accumulate(&window);
+        self.run.size_mismatches += reassembly.size_mismatches;
+        self.run.over_capacity += reassembly.over_capacity;
+        if reassembly.size_mismatches > 0 {
+            warn!(target: "reassembler", "⚠️  {} reassembled messages dropped for total_size mismatch", reassembly.size_mismatches);
+        }
+        if reassembly.expired > 0 {
+            warn!(target: "reassembler", "⚠️  {} incomplete messages expired before all fragments arrived", reassembly.expired);
+        }
+        if reassembly.invalid_fragments > 0 {
+            warn!(target: "reassembler", "⚠️  {} fragments dropped for an out-of-range index", reassembly.invalid_fragments);
+        }
+        if reassembly.implausible_sizes > 0 {
+            let dropped = reassembly.implausible_sizes;
+            warn!(target: "reassembler", "⚠️  {} fragments dropped for a total_size their fragments can't add up to", dropped);
+        }
+        // Once per window, however many fragments were dropped
+        if reassembly.over_capacity > 0 {
+            warn!(
+                target: "reassembler",
+                "⚠️  {} fragments of new messages dropped: {} messages already pending, the most allowed",
+                reassembly.over_capacity, self.reassembler.pending()
+            );
+        }
+        if let Some(ratio) = window.completion_ratio() {
+            let resolved = window.reassembled + window.expired;
+            match self.completion_alert {
+                Some(threshold) if ratio < threshold => warn!(
+                    target: "reassembler",
+                    "⚠️  Only {:.1}% of {} multi-fragment messages completed (alert below {:.1}%); packets are being lost",
+                    ratio * 100.0, resolved, threshold * 100.0
+                ),
+                _ => debug!(target: "reassembler", "{:.1}% of {} multi-fragment messages completed", ratio * 100.0, resolved),
+            }
+        }
+        self.report_fragment_sizes(reassembly.fragment_sizes);
+        if window.packets > 0 {
+            debug!(
+                "Traffic shape: {:.0} bytes/datagram (min {} / max {}), {} fragments/message (max {})",
+                window.bytes as f64 / window.packets as f64,
+                window.min_packet,
+                window.max_packet,
+                match window.messages {
+                    0 => "-".to_string(),
+                    n => format!("{:.1}", window.message_fragments as f64 / n as f64),
+                },
+                window.max_message_fragments
+            );
+        }
+        if let Some(forwarder) = &self.forwarder {
+            let forwarded = forwarder.take_stats();
+            self.run.forwarded.accumulate(&forwarded);
+            log_forwarded(&forwarded);
+        }
+        if let Some(verifier) = &self.verifier {
+            let verified = verifier.take_stats();
+            self.run.verified.accumulate(&verified);
+            log_verified(&verified);
+        }
+        let snapshot = self.snapshot(&window, elapsed, &reassembly, reactions.as_ref());
+        if self.structured_stats {
+            log_snapshot(&snapshot);
+        }
+        self.last_snapshot = Some(snapshot);
+        self.last_window = Some(window);
+    }
+
+    fn snapshot(
+        &self,
+        window: &WindowStats,
+        elapsed: Duration,
+        reassembly: &ReassemblerStats,
+        reactions: Option<&ReactionHistogram>,
+    ) -> StatsSnapshot {
+        let reaction_us = |quantile| reactions.and_then(|h| h.percentile(quantile)).map(|d| d.as_micros() as u64);
+        StatsSnapshot {
+            window_secs: elapsed.as_secs_f64(),
+            packets: window.packets,
+            bytes: window.bytes,
+            min_packet: window.min_packet,
+            max_packet: window.max_packet,
+            duplicates: window.duplicates,
+            rate_limited: window.rate_limited,
+            rejected: window.rejected,
+            messages: window.messages,
+            tick_only: window.tick_only,
+            entries: window.entries,
+            transactions: window.transactions,
+            max_message_transactions: window.max_message_transactions,
+            creates: window.creates,
+            dev_buys: window.dev_buys,
+            buys: window.buys,
+            buy_volume: window.buy_volume,
+            migrations: window.migrations,
+            mint_inits: window.mint_inits,
+            unknown: window.unknown,
+            account_extends: window.account_extends,
+            accumulator_closes: window.accumulator_closes,
+            undecodable: window.undecodable,
+            partially_decoded: window.partially_decoded,
+            filtered: window.filtered,
+            suppressed: window.suppressed,
+            malformed: window.malformed,
+            oversized_args: window.oversized_args,
+            metadata_mismatches: window.metadata_mismatches,
+            admin: window.admin,
+            unenriched: window.unenriched,
+            succeeded: window.succeeded,
+            failed: window.failed,
+            status_unknown: window.status_unknown,
+            pending: self.reassembler.pending(),
+            fragments_per_message: (window.messages > 0)
+                .then(|| window.message_fragments as f64 / window.messages as f64),
+            max_message_fragments: window.max_message_fragments,
+            reassembled: window.reassembled,
+            expired: window.expired,
+            loss_rate: window.completion_ratio().map(|ratio| 1.0 - ratio),
+            size_mismatches: reassembly.size_mismatches,
+            invalid_fragments: reassembly.invalid_fragments,
+            implausible_sizes: reassembly.implausible_sizes,
+            over_capacity: reassembly.over_capacity,
+            sink_dropped: self.sinks.handles().iter().map(SinkHandle::dropped).sum(),
+            reaction_p50_us: reaction_us(0.5),
+            reaction_p90_us: reaction_us(0.9),
+            reaction_p99_us: reaction_us(0.99),
+        }
+    }
+
+    /// Count the multi-fragment messages that completed or expired since the last window
+    fn record_resolved(&self, window: &mut WindowStats, reassembly: &ReassemblerStats) {
+        window.reassembled = reassembly.reassembled;
+        window.expired = reassembly.expired;
+        window.message_fragments = reassembly.message_fragments;
+        window.max_message_fragments = reassembly.max_message_fragments;
+        metrics::add(&self.metrics.reassembled, reassembly.reassembled);
+        metrics::add(&self.metrics.expired, reassembly.expired);
+    }
+
+    fn report_fragment_sizes(&mut self, sizes: FragmentSizes) {
+        if sizes.fragments == 0 {
+            return;
+        }
+        debug!(
+            target: "reassembler",
+            "Fragment payloads: {} fragments, min {} / typical {} / max {} bytes",
+            sizes.fragments, sizes.min, sizes.typical, sizes.max
+        );
+        if let Some(mtu) = self.path_mtu {
+            let on_wire = sizes.max + HEADER_SIZE + IP_UDP_OVERHEAD;
+            if on_wire > mtu {
+                warn!(
+                    target: "reassembler",
+                    "⚠️  Largest fragment is {} bytes on the wire, over the {} byte path MTU; it is likely IP-fragmented",
+                    on_wire, mtu
+                );
+            }
+        }
+        if sizes.typical == 0 {
+            return;
+        }
+        if let Some(previous) = self.fragment_size.replace(sizes.typical).filter(|&p| p != sizes.typical) {
+            info!(
+                target: "reassembler",
+                "📏 Fragment payload size changed from {} to {} bytes; the sender may have been reconfigured",
+                previous, sizes.typical
+            );
+        }
+    }
+
+    /// Flush pending buys, drain the outputs and log the run summary, handing back the reassembler for snapshotting
+    pub async fn finish(mut self) -> FragmentReassembler<C> {
+        let usd = self.usd_per_sol();
+        if let Some(coalescer) = self.coalescer.as_mut() {
+            coalescer.flush_all().iter().for_each(|group| log_coalesced(group, usd));
+        }
+        if let Some(verbosity) = self.verbosity.as_mut() {
+            log_bucket(&verbosity.close(self.clock.now()));
+        }
+        if let Some(closed) = self.slots.as_mut().map(SlotBatcher::flush_all) {
+            self.send_slots(closed);
+        }
+
+        if let Some(enricher) = self.enricher.take() {
+            enricher.shutdown().await;
+        }
+        if let Some(forwarder) = self.forwarder.take() {
+            self.run.forwarded.accumulate(&forwarder.shutdown());
+        }
+        if let Some(verifier) = self.verifier.take() {
+            self.run.verified.accumulate(&verifier.shutdown().await);
+        }
+        if let Some(capture) = self.capture.take() {
+            capture.finish();
+        }
+        let mut window = std::mem::take(&mut self.window);
+        let reassembly = self.reassembler.take_stats();
+        self.record_resolved(&mut window, &reassembly);
+        self.run.totals.accumulate(&window);
+        self.run.size_mismatches += reassembly.size_mismatches;
+        self.run.over_capacity += reassembly.over_capacity;
+        self.refresh_dropped();
+        let elapsed = self.clock.now().duration_since(self.started);
+        log_run_summary(&self.run, &self.metrics, elapsed, &self.sinks, self.reactions.as_deref());
+
+        match Arc::try_unwrap(self.sinks) {
+            Ok(sinks) => sinks.shutdown(),
+            Err(_) => warn!(target: "sink", "Sinks still in use at shutdown; skipping final flush"),
+        }
+
+        self.reassembler
+    }
+}
+
+/// The next debug endpoint request, or never without an endpoint
+async fn next_request(requests: &mut Option<mpsc::Receiver<BuffersRequest>>) -> Option<BuffersRequest> {
+    match requests {
+        Some(requests) => requests.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// One event per window; the JSON formatter writes each field as a key of the line's `fields` object
+fn log_snapshot(s: &StatsSnapshot) {
+    info!(
+        window_secs = s.window_secs,
+        packets = s.packets,
+        bytes = s.bytes,
+        min_packet = s.min_packet,
+        max_packet = s.max_packet,
+        duplicates = s.duplicates,
+        rate_limited = s.rate_limited,
+        rejected = s.rejected,
+        messages = s.messages,
+        tick_only = s.tick_only,
+        entries = s.entries,
+        transactions = s.transactions,
+        max_message_transactions = s.max_message_transactions,
+        creates = s.creates,
+        dev_buys = s.dev_buys,
+        buys = s.buys,
+        buy_volume = s.buy_volume,
+        migrations = s.migrations,
+        mint_inits = s.mint_inits,
+        unknown = s.unknown,
+        account_extends = s.account_extends,
+        accumulator_closes = s.accumulator_closes,
+        undecodable = s.undecodable,
+        partially_decoded = s.partially_decoded,
+        filtered = s.filtered,
+        suppressed = s.suppressed,
+        malformed = s.malformed,
+        oversized_args = s.oversized_args,
+        metadata_mismatches = s.metadata_mismatches,
+        admin = s.admin,
+        unenriched = s.unenriched,
+        succeeded = s.succeeded,
+        failed = s.failed,
+        status_unknown = s.status_unknown,
+        pending = s.pending,
+        fragments_per_message = s.fragments_per_message,
+        max_message_fragments = s.max_message_fragments,
+        reassembled = s.reassembled,
+        expired = s.expired,
+        loss_rate = s.loss_rate,
+        size_mismatches = s.size_mismatches,
+        invalid_fragments = s.invalid_fragments,
+        implausible_sizes = s.implausible_sizes,
+        over_capacity = s.over_capacity,
+        sink_dropped = s.sink_dropped,
+        reaction_p50_us = s.reaction_p50_us,
+        reaction_p90_us = s.reaction_p90_us,
+        reaction_p99_us = s.reaction_p99_us,
+        "📊 Stats"
+    );
+}
+
+/// Log the detection-to-action round trips in `histogram`, if there were any
+fn log_reactions(label: &str, histogram: &ReactionHistogram) {
+    let (Some(p50), Some(p90), Some(p99), Some(max)) =
+        (histogram.percentile(0.5), histogram.percentile(0.9), histogram.percentile(0.99), histogram.max())
+    else {
+        return;
+    };
+    info!("{}: {} detections acted on, p50 ≤ {:?}, p90 ≤ {:?}, p99 ≤ {:?}, max {:?}", label, histogram.count(), p50, p90, p99, max);
+    for (under, count) in histogram.buckets() {
+        debug!("{}:   < {:?}: {}", label, under, count);
+    }
+}
+
+fn log_top_creators(creators: &[TopCreator]) {
+    if creators.is_empty() {
+        return;
+    }
+    let ranked: Vec<_> = creators.iter().map(|c| format!("{} ({})", c.creator, c.creates)).collect();
+    debug!(target: "detector", "🏆 Top creators: {}", ranked.join(", "));
+}
+
+/// Log a single detection, with USD amounts when `usd_per_sol` is known
+pub fn log_detection(
+    detection: &Detection,
+    status: TxStatus,
+    msg_seq: u64,
+    processed: &ProcessedMessage,
+    usd_per_sol: Option<f64>,
+) {
+    // A failed transaction's effects were all reverted
+    let reverted = if status == TxStatus::Failed { " ⚠️  FAILED, reverted" } else { "" };
+    match detection {
+        Detection::Create {
+            mint,
+            bonding_curve,
+            creator,
+            associated_bonding_curve,
+            metadata,
+            accounts,
+            compute_budget,
+            token_accounts,
+            event,
+        } => {
+            info!(target: "detector", "═══════════════════════════════════════════════════════");
+            info!(target: "detector", "🚀 PUMPFUN TOKEN FOUND!{}", reverted);
+            info!(target: "detector", "   Token Address: {}", mint);
+            info!(target: "detector", "   Bonding Curve: {}", bonding_curve);
+            info!(target: "detector", "   Creator: {}", creator);
+            if let Some(account) = associated_bonding_curve {
+                info!(target: "detector", "   Bonding Curve Token Account: {}", account);
+            }
+            if let Some(account) = metadata {
+                info!(target: "detector", "   Metadata: {}", account);
+            }
+            if let Some(budget) = compute_budget {
+                log_compute_budget(budget, usd_per_sol);
+            }
+            for token_account in token_accounts {
+                info!(target: "detector", "   Token Account: {} (owner {})", token_account.account, token_account.owner);
+            }
+            if let Some(event) = event {
+                log_create_event(event, usd_per_sol);
+            }
+            log_origin(msg_seq, processed);
+            for (index, account) in accounts.iter().flatten().enumerate() {
+                debug!(target: "detector", "   Account {}: {}", index, account);
+            }
+            info!(target: "detector", "═══════════════════════════════════════════════════════");
+        }
+        Detection::CreateWithDevBuy {
+            mint,
+            bonding_curve,
+            creator,
+            dev_buy_sol,
+            dev_buy_tokens,
+            associated_bonding_curve,
+            metadata,
+            accounts,
+            compute_budget,
+            token_accounts,
+            event,
+        } => {
+            info!(target: "detector", "═══════════════════════════════════════════════════════");
+            info!(target: "detector", "🚀 PUMPFUN TOKEN FOUND, WITH DEV BUY!{}", reverted);
+            info!(target: "detector", "   Token Address: {}", mint);
+            info!(target: "detector", "   Bonding Curve: {}", bonding_curve);
+            info!(target: "detector", "   Creator: {}", creator);
+            let cost = format_sol(*dev_buy_sol, usd_per_sol);
+            info!(target: "detector", "   Dev Buy: {} tokens for {}", dev_buy_tokens, cost);
+            if let Some(account) = associated_bonding_curve {
+                info!(target: "detector", "   Bonding Curve Token Account: {}", account);
+            }
+            if let Some(account) = metadata {
+                info!(target: "detector", "   Metadata: {}", account);
+            }
+            if let Some(budget) = compute_budget {
+                log_compute_budget(budget, usd_per_sol);
+            }
+            for token_account in token_accounts {
+                info!(target: "detector", "   Token Account: {} (owner {})", token_account.account, token_account.owner);
+            }
+            if let Some(event) = event {
+                log_create_event(event, usd_per_sol);
+            }
+            log_origin(msg_seq, processed);
+            for (index, account) in accounts.iter().flatten().enumerate() {
+                debug!(target: "detector", "   Account {}: {}", index, account);
+            }
+            info!(target: "detector", "═══════════════════════════════════════════════════════");
+        }
+        Detection::Buy { mint, buyer, token_amount: _, max_sol_cost: _, event: Some(event) } => {
+            info!(
+                target: "detector",
+                "💰 Buy: {} bought {} of {} for {}{}",
+                buyer, event.token_amount, mint, format_sol(event.sol_amount, usd_per_sol), reverted
+            );
+        }
+        Detection::Buy { mint, buyer, token_amount, max_sol_cost, event: None } => {
+            info!(
+                target: "detector",
+                "💰 Buy: {} bought {} of {} (max {}){}",
+                buyer, token_amount, mint, format_sol(*max_sol_cost, usd_per_sol), reverted
+            );
+        }
+        Detection::Migration { mint, pool, from_pumpfun } => {
+            let origin = if *from_pumpfun { "pumpfun" } else { "unconfirmed origin" };
+            info!(target: "detector", "🎓 Migration: {} → pool {} ({}){}", mint, pool, origin, reverted);
+        }
+        Detection::MintInit { mint, decimals, authority, token_2022 } => {
+            let program = if *token_2022 { "Token-2022" } else { "Token" };
+            info!(
+                target: "detector",
+                "🪙 Mint initialized: {} ({} decimals, authority {}, {}){}", mint, decimals, authority, program, reverted
+            );
+        }
+        Detection::Unknown { program, discriminator, data_hex, data_len } => {
+            let cut = if data_hex.len() / 2 < *data_len { "…" } else { "" };
+            info!(
+                target: "detector",
+                "🔎 Unknown instruction of {}: discriminator {:?}, {} bytes: {}{}{}",
+                program, discriminator, data_len, data_hex, cut, reverted
+            );
+        }
+        // Low priority, so kept out of the info log
+        Detection::Maintenance { instruction, account, user } => {
+            debug!(
+                target: "detector",
+                "🧰 {} of {} by {}{}", instruction.name(), account, user, reverted
+            );
+        }
+        Detection::Heartbeat { packets, pending } => {
+            debug!(target: "sink", "💓 Heartbeat: {} pkts, {} pending", packets, pending);
+        }
+        Detection::SlotComplete { slot, detections } => {
+            debug!(target: "sink", "🧱 Slot {} complete: {} detections", slot, detections);
+        }
+    }
+}
+
+/// Log why message `msg_seq` couldn't be scanned
+pub fn log_process_error(msg_seq: u64, error: &ProcessError) {
+    match error {
+        ProcessError::Envelope(e) => debug!(target: "detector", "Msg #{}: {}", msg_seq, e),
+        ProcessError::SizeLimit { limit } => {
+            warn!(target: "detector", "Msg #{} rejected: entries exceed the {} byte decode limit", msg_seq, limit)
+        }
+        ProcessError::Deserialize(e) => warn!(target: "detector", "Failed to deserialize entries: {}", e),
+    }
+}
+
+/// Hand `event` to the enricher when there is one, else straight to the sinks; straight to them too when the
+/// sinks are `saturated`, returning whether that skipped the enricher
+fn send(enricher: Option<&Enricher>, sinks: &Sinks, event: DetectionEvent, saturated: bool) -> bool {
+    match enricher {
+        Some(enricher) if !saturated => {
+            enricher.submit(event);
+            false
+        }
+        Some(_) => {
+            sinks.emit(event);
+            true
+        }
+        None => {
+            sinks.emit(event);
+            false
+        }
+    }
+}
+
+/// The name and reserves pump.fun logged for a create
+fn log_create_event(event: &CreateEvent, usd_per_sol: Option<f64>) {
+    info!(target: "detector", "   Name: {} ({}) {}", event.name, event.symbol, event.uri);
+    if event.suspicious {
+        warn!(target: "detector", "   ⚠️  Name holds bidi or zero-width characters; it may impersonate another token");
+    }
+    if let Some(reserves) = event.reserves {
+        info!(
+            target: "detector",
+            "   Reserves: {} virtual, {} virtual tokens",
+            format_sol(reserves.virtual_sol_reserves, usd_per_sol), reserves.virtual_token_reserves
+        );
+    }
+}
+
+/// The message a create was found in, and its slot when the proxy sent one
+fn log_origin(msg_seq: u64, processed: &ProcessedMessage) {
+    info!(target: "detector", "   Message: #{}, Entries: {}, Txs: {}", msg_seq, processed.entries, processed.transactions);
+    if let Some(context) = &processed.context {
+        info!(
+            target: "detector",
+            "   Slot: {} (shreds {}-{} via {})",
+            context.slot, context.first_shred_index, context.last_shred_index, context.proxy
+        );
+    }
+}
+
+/// Log a create's priority fee, or as much of it as the transaction set
+fn log_compute_budget(budget: &ComputeBudget, usd_per_sol: Option<f64>) {
+    match (budget.priority_fee(), budget.unit_limit, budget.unit_price) {
+        (Some(fee), Some(limit), Some(price)) => info!(
+            target: "detector",
+            "   Priority Fee: {} ({} µlamports/CU, {} CU limit)", format_sol(fee, usd_per_sol), price, limit
+        ),
+        (_, Some(limit), _) => info!(target: "detector", "   Compute Limit: {} CU, no priority fee", limit),
+        (_, _, Some(price)) => info!(target: "detector", "   Priority Fee: {} µlamports/CU, default CU limit", price),
+        _ => {}
+    }
+}
+
+/// Log an upstream silence, louder the longer it lasts
+fn log_stale(alert: &Alert) {
+    let since = if alert.ever_received { "" } else { " since startup" };
+    match alert.severity {
+        Severity::Warn => warn!("📭 No packets received for {:.0}s{}", alert.silent_for.as_secs_f64(), since),
+        Severity::Error => error!("📭 No packets received for {:.0}s{}", alert.silent_for.as_secs_f64(), since),
+    }
+}
+
+/// Log the whole run's counters, for capacity planning and postmortems
+fn log_run_summary(
+    run: &RunStats,
+    metrics: &Metrics,
+    elapsed: Duration,
+    sinks: &Sinks,
+    reactions: Option<&ReactionTracker>,
+) {
+    let totals = &run.totals;
+    let counts = metrics.snapshot();
+    info!(
+        "📋 Run summary over {:.0}s: {} pkts, {:.2} MB, {} msgs, {} entries, {} txs, {} creates, {} buys, {} migrations",
+        elapsed.as_secs_f64(),
+        counts.packets,
+        totals.bytes as f64 / 1_000_000.0,
+        counts.messages,
+        totals.entries,
+        totals.transactions,
+        counts.creates,
+        counts.buys,
+        counts.migrations
+    );
+    info!(
+        target: "reassembler",
+        "📋 Reassembly: peak {} messages pending, {} expired incomplete, {} size mismatches, {} undecodable",
+        run.peak_pending, counts.expired, run.size_mismatches, counts.undecodable
+    );
+    if run.over_capacity > 0 {
+        info!(target: "reassembler", "📋 Reassembly: {} fragments dropped at the pending-message cap", run.over_capacity);
+    }
+    if totals.succeeded + totals.failed > 0 {
+        info!(
+            target: "detector",
+            "📋 Transaction status: {} detections succeeded, {} failed (reverted), {} unknown",
+            totals.succeeded, totals.failed, totals.status_unknown
+        );
+    }
+    if let Some(ratio) = counts.completion_ratio() {
+        info!(
+            target: "reassembler",
+            "📋 Reassembly completion: {:.1}% of {} multi-fragment messages",
+            ratio * 100.0,
+            counts.reassembled + counts.expired
+        );
+    }
+    for sink in sinks.handles() {
+        info!(target: "sink", "📋 {} sink: {} events dropped (queue full)", sink.name(), sink.dropped());
+    }
+    if totals.unenriched > 0 {
+        info!(target: "sink", "📋 Sink throttle: {} detections sent without enrichment", totals.unenriched);
+    }
+    if counts.rate_limited > 0 {
+        info!("📋 Rate limit: {} datagrams dropped", counts.rate_limited);
+    }
+    if counts.rejected > 0 {
+        info!("📋 Allow-list: {} datagrams rejected from {} sources", counts.rejected, totals.rejected_sources);
+    }
+    if counts.source_restarts > 0 {
+        info!(target: "source", "📋 Source: restarted {} times", counts.source_restarts);
+    }
+    if let Some(stats) = reactions.map(|tracker| tracker.stats()) {
+        log_reactions("📋 Reaction", &stats.histogram);
+        info!("📋 Reaction: {} detections emitted, {} acted on, {} unknown ids", stats.emitted, stats.histogram.count(), stats.unknown);
+    }
+    let forwarded = &run.forwarded;
+    if *forwarded != ForwardStats::default() {
+        info!(
+            "📋 Forwarding: {} sent, {} failed sends, {} dropped (queue full)",
+            forwarded.sent, forwarded.failed, forwarded.dropped
+        );
+    }
+    let verified = &run.verified;
+    if *verified != VerifyStats::default() {
+        info!(
+            target: "detector",
+            "📋 Verification: {} confirmed, {} false positives ({}), {} unverified, {} dropped (queue full)",
+            verified.confirmed, verified.false_positives, format_rate(verified), verified.unverified, verified.dropped
+        );
+    }
+}
+
+/// Warn about the sources that went over their rate limit, the noisiest first
+fn log_rate_limited(drops: &[SourceDrops]) {
+    const LOGGED_SOURCES: usize = 10;
+    for drop in drops.iter().take(LOGGED_SOURCES) {
+        match drop.source {
+            Some(source) => warn!("⚠️  Rate limit dropped {} datagrams from {}", drop.dropped, source),
+            None => warn!("⚠️  Rate limit dropped {} datagrams from sources past RATE_LIMIT_MAX_SOURCES", drop.dropped),
+        }
+    }
+    if let Some(rest) = drops.get(LOGGED_SOURCES..).filter(|rest| !rest.is_empty()) {
+        let dropped: u64 = rest.iter().map(|d| d.dropped).sum();
+        warn!("⚠️  Rate limit dropped {} datagrams from {} other sources", dropped, rest.len());
+    }
+}
+
+/// Log a window's forwarding counts, warning when copies were lost
+fn log_forwarded(forwarded: &ForwardStats) {
+    match forwarded.failed + forwarded.dropped {
+        0 => info!("📤 Forwarded {} datagram copies", forwarded.sent),
+        _ => warn!(
+            "⚠️  Forwarded {} datagram copies; {} sends failed, {} datagrams dropped (queue full)",
+            forwarded.sent, forwarded.failed, forwarded.dropped
+        ),
+    }
+}
+
+/// Log a window's verification outcomes, warning when there were false positives
+fn log_verified(verified: &VerifyStats) {
+    if *verified == VerifyStats::default() {
+        return;
+    }
+    let (confirmed, unverified, dropped) = (verified.confirmed, verified.unverified, verified.dropped);
+    match verified.false_positives {
+        0 => info!(
+            target: "detector",
+            "🔬 Verified {} creates, none false; {} unverified, {} dropped (queue full)",
+            confirmed, unverified, dropped
+        ),
+        false_positives => warn!(
+            target: "detector",
+            "⚠️  Verification found {} false positives against {} confirmed creates ({}); {} unverified, {} dropped (queue full)",
+            false_positives, confirmed, format_rate(verified), unverified, dropped
+        ),
+    }
+}
+
+/// A false-positive rate as a percentage, or `-` before any lookup settled
+fn format_rate(verified: &VerifyStats) -> String {
+    verified.false_positive_rate().map_or("-".to_string(), |rate| format!("{:.1}%", rate * 100.0))
+}
+
+/// Log queue pressure between the receiver and the pipeline
+fn log_queue_stats(queue: &QueueStats) {
+    if queue.dropped > 0 {
+        warn!("⚠️  {} datagrams dropped at the full worker queue", queue.dropped);
+    }
+    if !queue.blocked.is_zero() {
+        warn!("⏳ Receiver blocked {:.1}ms on the full worker queue", queue.blocked.as_secs_f64() * 1000.0);
+    }
+}
+
+/// Log a summary for a bucket logged in summary mode, and any mode change
+fn log_bucket(bucket: &Bucket) {
+    if bucket.mode == LogMode::Summary {
+        info!(
+            target: "detector",
+            "📈 Last {:.1}s: {} creates, {} buys, {} migrations, {} mint inits, {} unknown ({:.1}/s)",
+            bucket.elapsed.as_secs_f64(),
+            bucket.counts.creates,
+            bucket.counts.buys,
+            bucket.counts.migrations,
+            bucket.counts.mint_inits,
+            bucket.counts.unknown,
+            bucket.rate
+        );
+    }
+    match bucket.switched_to {
+        Some(LogMode::Summary) => {
+            info!(target: "detector", "🔀 Detection rate {:.1}/s: switching to summary logging", bucket.rate)
+        }
+        Some(LogMode::PerEvent) => {
+            info!(target: "detector", "🔀 Detection rate {:.1}/s: back to per-event logging", bucket.rate)
+        }
+        None => {}
+    }
+}
+
+/// Log an aggregated burst of buys
+fn log_coalesced(group: &CoalescedBuys, usd_per_sol: Option<f64>) {
+    info!(
+        target: "detector",
+        "💰 {} buys for {}: {} total (max cost)",
+        group.count, group.mint, format_sol(group.total_sol_cost, usd_per_sol)
+    );
+}
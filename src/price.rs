@@ -0,0 +1,103 @@
+//! Approximate SOL/USD price for annotating logged amounts.
+//!
+//! The price is either fixed from config or refreshed periodically from an HTTP
+//! endpoint. It is advisory only: when no price is known, amounts are shown in
+//! SOL alone and events carry no USD value.
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use serde_json::Value;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use tracing::{debug, info, warn};
+
+/// Shared handle to the current price
+#[derive(Debug, Clone, Default)]
+pub struct SolPrice {
+    /// `f64` bits of the USD price; 0 while unknown
+    usd_bits: Arc<AtomicU64>,
+}
+
+impl SolPrice {
+    pub fn fixed(usd: f64) -> Self {
+        let price = Self::default();
+        price.set(Some(usd));
+        price
+    }
+
+    /// Fetch `url` every `interval`, reading the price at JSON `pointer` (e.g. `/solana/usd`)
+    pub fn spawn_refresher(url: String, pointer: String, interval: Duration) -> reqwest::Result<Self> {
+        let client = reqwest::Client::builder().timeout(Duration::from_secs(10)).build()?;
+        let price = Self::default();
+        let shared = price.clone();
+
+        tokio::spawn(async move {
+            let mut tick = tokio::time::interval(interval);
+            let mut available = true;
+            loop {
+                tick.tick().await;
+                match fetch_price(&client, &url, &pointer).await {
+                    Ok(usd) => {
+                        if !available {
+                            info!("SOL/USD price available again: ${:.2}", usd);
+                        }
+                        available = true;
+                        shared.set(Some(usd));
+                    }
+                    Err(e) => {
+                        // One warning per outage; amounts fall back to SOL only meanwhile
+                        if available {
+                            warn!("SOL/USD price unavailable, showing SOL only: {}", e);
+                        } else {
+                            debug!("SOL/USD price refresh failed: {}", e);
+                        }
+                        available = false;
+                        shared.set(None);
+                    }
+                }
+            }
+        });
+        Ok(price)
+    }
+
+    /// USD per SOL, if known
+    pub fn usd(&self) -> Option<f64> {
+        Some(f64::from_bits(self.usd_bits.load(Ordering::Relaxed))).filter(|&p| p > 0.0)
+    }
+
+    fn set(&self, usd: Option<f64>) {
+        self.usd_bits.store(usd.filter(|p| p.is_finite()).unwrap_or(0.0).to_bits(), Ordering::Relaxed);
+    }
+}
+
+/// `1.2345 SOL`, or `1.2345 SOL ≈ $185.18` when a price is known
+pub fn format_sol(lamports: u64, usd_per_sol: Option<f64>) -> String {
+    let sol = lamports as f64 / LAMPORTS_PER_SOL as f64;
+    match usd_value(lamports, usd_per_sol) {
+        Some(usd) => format!("{:.4} SOL ≈ ${:.2}", sol, usd),
+        None => format!("{:.4} SOL", sol),
+    }
+}
+
+/// `lamports` in USD, when a price is known
+pub fn usd_value(lamports: u64, usd_per_sol: Option<f64>) -> Option<f64> {
+    usd_per_sol.map(|price| lamports as f64 / LAMPORTS_PER_SOL as f64 * price)
+}
+
+async fn fetch_price(
+    client: &reqwest::Client,
+    url: &str,
+    pointer: &str,
+) -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
+    let response: Value = client.get(url).send().await?.error_for_status()?.json().await?;
+    response
+        .pointer(pointer)
+        .and_then(|v| v.as_f64().or_else(|| v.as_str().and_then(|s| s.parse().ok())))
+        .filter(|p| *p > 0.0)
+        .ok_or_else(|| format!("no positive number at {}", pointer).into())
+}
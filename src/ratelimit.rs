@@ -0,0 +1,105 @@
+//! Per-source token buckets, so one noisy or spoofed sender can't starve the rest.
+//!
+//! Sources are keyed by IP address, not port, so a flood can't dodge its bucket by
+//! rotating ports. At most `max_sources` get a bucket of their own; past that, new
+//! sources share one overflow bucket, so a flood from many spoofed addresses is still
+//! bounded in both memory and packets. Buckets that have refilled are pruned
+//! periodically, making room for new sources.
+
+use std::{collections::HashMap, net::IpAddr, time::Instant};
+
+/// Default cap on sources with a bucket of their own
+pub const DEFAULT_MAX_SOURCES: usize = 1024;
+
+/// Packets a source had dropped over a stats window
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceDrops {
+    /// `None` for the sources sharing the overflow bucket
+    pub source: Option<IpAddr>,
+    pub dropped: u64,
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+    dropped: u64,
+}
+
+impl Bucket {
+    fn new(burst: f64, now: Instant) -> Self {
+        Self { tokens: burst, refilled_at: now, dropped: 0 }
+    }
+
+    fn refill(&mut self, rate: f64, burst: f64, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate).min(burst);
+        self.refilled_at = now;
+    }
+
+    fn take(&mut self, rate: f64, burst: f64, now: Instant) -> bool {
+        self.refill(rate, burst, now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            self.dropped += 1;
+            false
+        }
+    }
+}
+
+pub struct SourceRateLimiter {
+    rate: f64,
+    burst: f64,
+    max_sources: usize,
+    buckets: HashMap<IpAddr, Bucket>,
+    overflow: Bucket,
+}
+
+impl SourceRateLimiter {
+    /// Allow each source `rate` packets per second on average and bursts of up to `burst`
+    pub fn new(rate: f64, burst: f64, max_sources: usize, now: Instant) -> Self {
+        let burst = burst.max(1.0);
+        Self { rate, burst, max_sources, buckets: HashMap::new(), overflow: Bucket::new(burst, now) }
+    }
+
+    /// Whether a packet from `source` is within its limit, counting it as dropped if not
+    pub fn allow(&mut self, source: IpAddr, now: Instant) -> bool {
+        let (rate, burst) = (self.rate, self.burst);
+        if let Some(bucket) = self.buckets.get_mut(&source) {
+            return bucket.take(rate, burst, now);
+        }
+        if self.buckets.len() >= self.max_sources {
+            return self.overflow.take(rate, burst, now);
+        }
+        self.buckets.entry(source).or_insert_with(|| Bucket::new(burst, now)).take(rate, burst, now)
+    }
+
+    /// Forget sources whose bucket has refilled and who have no drops left to report
+    pub fn prune(&mut self, now: Instant) {
+        let (rate, burst) = (self.rate, self.burst);
+        self.buckets.retain(|_, bucket| {
+            bucket.refill(rate, burst, now);
+            bucket.tokens < burst || bucket.dropped > 0
+        });
+    }
+
+    /// Sources that had packets dropped since the last call, most drops first
+    pub fn take_dropped(&mut self) -> Vec<SourceDrops> {
+        let mut drops: Vec<_> = self
+            .buckets
+            .iter_mut()
+            .map(|(&source, bucket)| (Some(source), bucket))
+            .chain([(None, &mut self.overflow)])
+            .filter(|(_, bucket)| bucket.dropped > 0)
+            .map(|(source, bucket)| SourceDrops { source, dropped: std::mem::take(&mut bucket.dropped) })
+            .collect();
+        drops.sort_unstable_by(|a, b| b.dropped.cmp(&a.dropped).then(a.source.cmp(&b.source)));
+        drops
+    }
+
+    /// Sources with a bucket of their own
+    pub fn tracked(&self) -> usize {
+        self.buckets.len()
+    }
+}
@@ -0,0 +1,166 @@
+//! Detection-to-action latency, for embedders whose trading logic acts on detections.
+//!
+//! With tracking on, the pipeline stamps each detection event it emits with a monotonic
+//! `detection_id` and remembers when. Calling [`ReactionTracker::mark_acted`] with that id
+//! once the bot has acted records the round trip in a histogram of power-of-two microsecond
+//! buckets, which the pipeline logs each stats window. Only the most recent `max_outstanding`
+//! emissions are remembered, so acting on an older one, or on one a second time, is counted
+//! as unknown rather than timed.
+
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Default number of emitted detections remembered for `mark_acted`
+pub const DEFAULT_MAX_OUTSTANDING: usize = 65_536;
+
+/// Bucket `i` holds round trips under 2^i microseconds; the last also holds everything slower (~36 min)
+const BUCKETS: usize = 32;
+
+/// Round-trip times bucketed by powers of two of microseconds
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ReactionHistogram {
+    buckets: [u64; BUCKETS],
+    count: u64,
+    sum_us: u64,
+    min_us: u64,
+    max_us: u64,
+}
+
+impl ReactionHistogram {
+    fn record(&mut self, elapsed: Duration) {
+        let us = elapsed.as_micros().min(u64::MAX as u128) as u64;
+        let bucket = (u64::BITS - us.leading_zeros()) as usize;
+        self.buckets[bucket.min(BUCKETS - 1)] += 1;
+        self.min_us = if self.count == 0 { us } else { self.min_us.min(us) };
+        self.max_us = self.max_us.max(us);
+        self.sum_us = self.sum_us.saturating_add(us);
+        self.count += 1;
+    }
+
+    /// Round trips recorded
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn min(&self) -> Option<Duration> {
+        (self.count > 0).then(|| Duration::from_micros(self.min_us))
+    }
+
+    pub fn max(&self) -> Option<Duration> {
+        (self.count > 0).then(|| Duration::from_micros(self.max_us))
+    }
+
+    pub fn mean(&self) -> Option<Duration> {
+        (self.count > 0).then(|| Duration::from_micros(self.sum_us / self.count))
+    }
+
+    /// Upper bound of the bucket holding the `quantile` (0 to 1) round trip, capped at the slowest one
+    pub fn percentile(&self, quantile: f64) -> Option<Duration> {
+        if self.count == 0 {
+            return None;
+        }
+        let rank = ((quantile.clamp(0.0, 1.0) * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        let bucket = self.buckets.iter().position(|&n| {
+            seen += n;
+            seen >= rank
+        })?;
+        Some(Duration::from_micros((1u64 << bucket).min(self.max_us)))
+    }
+
+    /// Each non-empty bucket as (exclusive upper bound, round trips in it), fastest first
+    pub fn buckets(&self) -> impl Iterator<Item = (Duration, u64)> + '_ {
+        self.buckets
+            .iter()
+            .enumerate()
+            .filter(|(_, &n)| n > 0)
+            .map(|(i, &n)| (Duration::from_micros(1 << i), n))
+    }
+}
+
+/// Lifetime totals of a tracker
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ReactionStats {
+    /// Detections stamped with an id
+    pub emitted: u64,
+    /// `mark_acted` calls for an id that was never emitted, already acted on or forgotten
+    pub unknown: u64,
+    pub histogram: ReactionHistogram,
+}
+
+pub struct ReactionTracker {
+    max_outstanding: usize,
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    next_id: u64,
+    /// Emission time by id, oldest first; taken when acted on
+    outstanding: VecDeque<(u64, Option<Instant>)>,
+    window: ReactionHistogram,
+    totals: ReactionStats,
+}
+
+impl Default for ReactionTracker {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_OUTSTANDING)
+    }
+}
+
+impl ReactionTracker {
+    /// Remember the last `max_outstanding` emitted detections for `mark_acted`
+    pub fn new(max_outstanding: usize) -> Self {
+        Self { max_outstanding: max_outstanding.max(1), state: Mutex::default() }
+    }
+
+    /// Assign the next detection id, emitted at `now`
+    pub fn emit(&self, now: Instant) -> u64 {
+        let mut state = self.state.lock().unwrap();
+        let id = state.next_id;
+        state.next_id += 1;
+        state.totals.emitted += 1;
+        if state.outstanding.len() >= self.max_outstanding {
+            state.outstanding.pop_front();
+        }
+        state.outstanding.push_back((id, Some(now)));
+        id
+    }
+
+    /// Record that the bot acted on detection `id` just now, returning the round trip if `id` was outstanding
+    pub fn mark_acted(&self, id: u64) -> Option<Duration> {
+        self.mark_acted_at(id, Instant::now())
+    }
+
+    /// Like `mark_acted`, but acted on at `now`
+    pub fn mark_acted_at(&self, id: u64, now: Instant) -> Option<Duration> {
+        let mut state = self.state.lock().unwrap();
+        // Ids are pushed in order, so the queue is sorted by them
+        let emitted_at = state
+            .outstanding
+            .binary_search_by_key(&id, |&(id, _)| id)
+            .ok()
+            .and_then(|i| state.outstanding[i].1.take());
+        let Some(emitted_at) = emitted_at else {
+            state.totals.unknown += 1;
+            return None;
+        };
+        let elapsed = now.saturating_duration_since(emitted_at);
+        state.window.record(elapsed);
+        state.totals.histogram.record(elapsed);
+        Some(elapsed)
+    }
+
+    /// Round trips recorded since the previous call
+    pub fn take_window(&self) -> ReactionHistogram {
+        std::mem::take(&mut self.state.lock().unwrap().window)
+    }
+
+    /// Everything recorded since the tracker was created
+    pub fn stats(&self) -> ReactionStats {
+        self.state.lock().unwrap().totals.clone()
+    }
+}
@@ -0,0 +1,552 @@
+//! Fragment reassembly for messages split across multiple UDP packets.
+
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use ahash::RandomState;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+
+use crate::clock::{Clock, SystemClock};
+
+/// Fragment header size
+pub const HEADER_SIZE: usize = 16;
+
+/// Magic bytes for fragmented messages
+pub const MAGIC: &[u8; 4] = b"SHRD";
+
+/// Incomplete buffers older than this are discarded by default
+pub const DEFAULT_MAX_FRAGMENT_AGE: Duration = Duration::from_secs(10);
+
+/// Default cap on messages awaiting fragments at once
+pub const DEFAULT_MAX_PENDING: usize = 16_384;
+
+/// Largest payload a fragment can carry: the most a UDP datagram over IPv4 holds, less the header
+const MAX_FRAGMENT_PAYLOAD: usize = 65_507 - HEADER_SIZE;
+
+/// How far a message's declared `total_size` may stray from what its fragment sizes imply, by default
+pub const DEFAULT_SIZE_TOLERANCE: f64 = 4.0;
+
+/// Largest fragments-per-message hint; each pending message reserves room for this many fragments, whatever its
+/// own count
+pub const MAX_FRAGMENTS_HINT: u16 = 1024;
+
+/// How often callers are expected to run `cleanup_old`
+pub const CLEANUP_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Most missing indices `inspect` lists per buffer
+pub const MAX_LISTED_MISSING: usize = 64;
+
+/// Counters collected by the reassembler since the last `take_stats` call
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ReassemblerStats {
+    /// Completed messages dropped because their length disagreed with `total_size`
+    pub size_mismatches: u64,
+    /// Incomplete messages discarded by `cleanup_old`
+    pub expired: u64,
+    /// Multi-fragment messages reassembled to their declared size
+    pub reassembled: u64,
+    /// Fragments dropped for an index outside their message's fragment count
+    pub invalid_fragments: u64,
+    /// Fragments dropped for a `total_size` their message's fragment count and sizes make implausible
+    pub implausible_sizes: u64,
+    /// Fragments dropped because they would start a new message with `max_pending` already in flight
+    pub over_capacity: u64,
+    pub fragment_sizes: FragmentSizes,
+    /// Datagrams making up the messages handed back, an unfragmented message counting as one
+    pub message_fragments: u64,
+    /// Most datagrams any one message handed back was made of
+    pub max_message_fragments: u16,
+}
+
+impl ReassemblerStats {
+    fn completed(&mut self, fragments: u16) {
+        self.message_fragments += fragments as u64;
+        self.max_message_fragments = self.max_message_fragments.max(fragments);
+    }
+}
+
+/// Payload sizes of SHRD fragments, excluding the header
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FragmentSizes {
+    pub fragments: u64,
+    pub min: usize,
+    pub max: usize,
+    /// Most common size of non-final fragments, i.e. the sender's chunk size; 0 if none were seen
+    pub typical: usize,
+    /// Majority-vote counter backing `typical`
+    votes: u64,
+}
+
+impl FragmentSizes {
+    fn record(&mut self, len: usize, is_final: bool) {
+        self.min = if self.fragments == 0 { len } else { self.min.min(len) };
+        self.max = self.max.max(len);
+        self.fragments += 1;
+        // Final fragments carry the remainder, so only full chunks say what the sender splits at
+        if is_final {
+            return;
+        }
+        if self.votes == 0 {
+            self.typical = len;
+        }
+        self.votes = if self.typical == len { self.votes + 1 } else { self.votes - 1 };
+    }
+}
+
+/// Fragment reassembler for handling multi-packet messages
+pub struct FragmentReassembler<C: Clock = SystemClock> {
+    /// Keyed with ahash, which is randomly seeded like the std hasher but several times faster on a u32
+    buffers: HashMap<u32, FragmentBuffer, RandomState>,
+    stats: ReassemblerStats,
+    max_age: Duration,
+    max_pending: usize,
+    /// 0 when `total_size` isn't checked against the fragments
+    size_tolerance: f64,
+    /// Fragments each new message's storage is sized for upfront; 0 grows it as they arrive
+    fragments_hint: u16,
+    clock: C,
+}
+
+struct FragmentBuffer {
+    total_fragments: u16,
+    total_size: u32,
+    /// Payloads by fragment index; grown up to the highest index seen rather than allocated
+    /// for `total_fragments` upfront, since the count comes straight off the wire
+    fragments: Vec<Option<Vec<u8>>>,
+    /// Bit `i % 64` of word `i / 64` is set once fragment `i` has arrived; grown like `fragments`
+    received: Vec<u64>,
+    /// Bits set in `received`
+    count: u16,
+    created_at: Instant,
+    /// Sender of the first fragment seen, when the caller said
+    source: Option<SocketAddr>,
+}
+
+impl FragmentBuffer {
+    /// An empty buffer with room for `capacity` fragments, at most `total_fragments`, before it grows
+    fn new(total_fragments: u16, total_size: u32, created_at: Instant, capacity: u16) -> Self {
+        let capacity = capacity.min(total_fragments) as usize;
+        Self {
+            total_fragments,
+            total_size,
+            fragments: Vec::with_capacity(capacity),
+            received: Vec::with_capacity(capacity.div_ceil(64)),
+            count: 0,
+            created_at,
+            source: None,
+        }
+    }
+
+    /// Store fragment `index`, replacing an earlier copy; false if it's beyond the fragment count
+    fn insert(&mut self, index: u16, data: Vec<u8>) -> bool {
+        if index >= self.total_fragments {
+            return false;
+        }
+        let (i, word, bit) = (index as usize, index as usize / 64, 1u64 << (index % 64));
+        if self.fragments.len() <= i {
+            self.fragments.resize_with(i + 1, || None);
+        }
+        if self.received.len() <= word {
+            self.received.resize(word + 1, 0);
+        }
+        if self.received[word] & bit == 0 {
+            self.received[word] |= bit;
+            self.count += 1;
+        }
+        self.fragments[i] = Some(data);
+        true
+    }
+
+    fn is_complete(&self) -> bool {
+        self.count == self.total_fragments
+    }
+
+    /// Indices still missing, lowest first
+    fn missing(&self) -> impl Iterator<Item = u16> + '_ {
+        (0..self.total_fragments).filter(|&i| {
+            self.received.get(i as usize / 64).is_none_or(|word| word & (1 << (i % 64)) == 0)
+        })
+    }
+}
+
+/// An in-flight message, as `inspect` reports it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BufferInfo {
+    pub message_id: u32,
+    /// Sender of its first fragment; unknown for buffers restored from a snapshot
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<SocketAddr>,
+    pub age_ms: u64,
+    pub received: u16,
+    pub total_fragments: u16,
+    /// Missing fragment indices, lowest first; only the first `MAX_LISTED_MISSING`
+    pub missing: Vec<u16>,
+}
+
+/// The oldest in-flight messages, oldest first
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BuffersReport {
+    /// Messages awaiting fragments, including those left out of `buffers`
+    pub pending: usize,
+    pub buffers: Vec<BufferInfo>,
+}
+
+/// On-disk form of the reassembler's in-flight buffers
+#[derive(Serialize, Deserialize)]
+struct ReassemblerSnapshot {
+    /// Wall-clock time of the snapshot, so downtime counts towards buffer age
+    taken_at_ms: u64,
+    buffers: Vec<BufferSnapshot>,
+}
+
+/// Serializable form of an in-flight buffer; `Instant` has no portable encoding,
+/// so the buffer's age at snapshot time is stored instead
+#[derive(Serialize, Deserialize)]
+struct BufferSnapshot {
+    message_id: u32,
+    total_fragments: u16,
+    total_size: u32,
+    received: Vec<(u16, Vec<u8>)>,
+    age_ms: u64,
+}
+
+impl Default for FragmentReassembler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FragmentReassembler {
+    pub fn new() -> Self {
+        Self::with_max_age(DEFAULT_MAX_FRAGMENT_AGE)
+    }
+
+    /// Create a reassembler that discards incomplete messages older than `max_age`
+    pub fn with_max_age(max_age: Duration) -> Self {
+        Self::with_clock(SystemClock, max_age)
+    }
+
+    /// Rebuild a reassembler from `snapshot` output, pruning buffers that have since expired
+    pub fn restore(bytes: &[u8]) -> bincode::Result<Self> {
+        Self::restore_with_clock(bytes, SystemClock)
+    }
+}
+
+impl<C: Clock> FragmentReassembler<C> {
+    /// Create a reassembler driven by `clock`
+    pub fn with_clock(clock: C, max_age: Duration) -> Self {
+        Self {
+            buffers: HashMap::default(),
+            stats: ReassemblerStats::default(),
+            max_age,
+            max_pending: DEFAULT_MAX_PENDING,
+            size_tolerance: DEFAULT_SIZE_TOLERANCE,
+            fragments_hint: 0,
+            clock,
+        }
+    }
+
+    /// Cap the messages awaiting fragments at once; fragments of new messages are dropped while at the cap,
+    /// until a pending one completes or expires. Buffers already held, e.g. restored ones, are kept
+    pub fn with_max_pending(mut self, max_pending: usize) -> Self {
+        self.max_pending = max_pending;
+        self
+    }
+
+    /// Drop fragments whose message's `total_size` is off by more than a factor of `tolerance` (at least 1) from
+    /// what the fragment count and sizes imply, before anything is buffered for them; 0 turns the check off,
+    /// leaving only the check of the assembled length
+    pub fn with_size_tolerance(mut self, tolerance: f64) -> Self {
+        self.size_tolerance = tolerance;
+        self
+    }
+
+    /// Size each new message's fragment storage for `fragments` fragments upfront (capped at its own count and
+    /// `MAX_FRAGMENTS_HINT`), so messages of up to that many fragments never reallocate it; 0 grows it as they arrive
+    pub fn with_fragments_hint(mut self, fragments: u16) -> Self {
+        self.fragments_hint = fragments.min(MAX_FRAGMENTS_HINT);
+        self
+    }
+
+    /// Process incoming packet, returns complete message if reassembly is done
+    pub fn process_packet(&mut self, data: &[u8]) -> Option<Vec<u8>> {
+        self.ingest(data, None)
+    }
+
+    /// Like `process_packet`, remembering `source` as the sender of a message it starts for `inspect`
+    pub fn process_packet_from(&mut self, data: &[u8], source: SocketAddr) -> Option<Vec<u8>> {
+        self.ingest(data, Some(source))
+    }
+
+    fn ingest(&mut self, data: &[u8], source: Option<SocketAddr>) -> Option<Vec<u8>> {
+        // Check if this is a fragmented message (starts with SHRD magic)
+        if data.len() >= HEADER_SIZE && &data[0..4] == MAGIC {
+            let message_id = u32::from_le_bytes(data[4..8].try_into().unwrap());
+            let fragment_index = u16::from_le_bytes(data[8..10].try_into().unwrap());
+            let total_fragments = u16::from_le_bytes(data[10..12].try_into().unwrap());
+            let total_size = u32::from_le_bytes(data[12..16].try_into().unwrap());
+            let payload = &data[HEADER_SIZE..];
+            self.stats.fragment_sizes.record(payload.len(), fragment_index as u32 + 1 >= total_fragments as u32);
+
+            // Single-fragment message: hand it straight back without buffering
+            if total_fragments == 1 {
+                if fragment_index != 0 || payload.len() != total_size as usize {
+                    warn!(
+                        target: "reassembler",
+                        "Dropping msg_id={}: single fragment idx={} carries {} bytes but header declared total_size={}",
+                        message_id, fragment_index, payload.len(), total_size
+                    );
+                    self.stats.size_mismatches += 1;
+                    return None;
+                }
+                self.stats.completed(1);
+                return Some(payload.to_vec());
+            }
+            if fragment_index >= total_fragments {
+                debug!(
+                    target: "reassembler",
+                    "Dropping fragment of msg_id={}: idx={} with total_fragments={}", message_id, fragment_index, total_fragments
+                );
+                self.stats.invalid_fragments += 1;
+                return None;
+            }
+            if self.size_tolerance > 0.0
+                && !plausible_size(total_size, total_fragments, fragment_index, payload.len(), self.size_tolerance)
+            {
+                debug!(
+                    target: "reassembler",
+                    "Dropping fragment of msg_id={}: idx={}/{} of {} bytes with total_size={}",
+                    message_id, fragment_index, total_fragments, payload.len(), total_size
+                );
+                self.stats.implausible_sizes += 1;
+                return None;
+            }
+            let fragment_data = payload.to_vec();
+
+            debug!(
+                target: "reassembler",
+                "Fragment: msg_id={}, idx={}/{}, size={}",
+                message_id, fragment_index as u32 + 1, total_fragments, fragment_data.len()
+            );
+
+            // Many small messages that never complete would otherwise grow the map without bound
+            if self.buffers.len() >= self.max_pending && !self.buffers.contains_key(&message_id) {
+                debug!(target: "reassembler", "Dropping fragment of msg_id={}: {} messages already pending", message_id, self.buffers.len());
+                self.stats.over_capacity += 1;
+                return None;
+            }
+
+            let (now, hint) = (self.clock.now(), self.fragments_hint);
+            let entry = self.buffers.entry(message_id).or_insert_with(|| FragmentBuffer {
+                source,
+                ..FragmentBuffer::new(total_fragments, total_size, now, hint)
+            });
+
+            // Fragments of one message may disagree on the count; the first one seen wins
+            if !entry.insert(fragment_index, fragment_data) {
+                self.stats.invalid_fragments += 1;
+                return None;
+            }
+
+            if entry.is_complete() {
+                let (total_fragments, total_size) = (entry.total_fragments, entry.total_size);
+                let buffer = self.buffers.remove(&message_id).expect("buffer was just updated");
+
+                // A sender bug or truncated fragment yields the wrong length; drop it here
+                // rather than handing bincode bytes it can only fail on. Checked before
+                // allocating, since total_size comes straight off the wire.
+                let assembled: usize = buffer.fragments.iter().flatten().map(Vec::len).sum();
+                if assembled != total_size as usize {
+                    warn!(
+                        target: "reassembler",
+                        "Dropping msg_id={}: assembled {} bytes but header declared total_size={}",
+                        message_id, assembled, total_size
+                    );
+                    self.stats.size_mismatches += 1;
+                    return None;
+                }
+                let mut complete = Vec::with_capacity(assembled);
+                for frag in buffer.fragments.iter().flatten() {
+                    complete.extend_from_slice(frag);
+                }
+
+                info!(target: "reassembler", "Reassembled message: {} bytes from {} fragments", complete.len(), total_fragments);
+                self.stats.reassembled += 1;
+                self.stats.completed(total_fragments);
+                return Some(complete);
+            }
+            None
+        } else {
+            // Non-fragmented message - return as-is
+            self.stats.completed(1);
+            Some(data.to_vec())
+        }
+    }
+
+    /// Cleanup old incomplete buffers (call periodically)
+    pub fn cleanup_old(&mut self) {
+        let (now, max_age) = (self.clock.now(), self.max_age);
+        let before = self.buffers.len();
+        self.buffers.retain(|message_id, buffer| {
+            let keep = now.saturating_duration_since(buffer.created_at) < max_age;
+            if !keep {
+                debug!(
+                    target: "reassembler",
+                    "Expired msg_id={}: {}/{} fragments, missing idx {}",
+                    message_id, buffer.count, buffer.total_fragments, describe_missing(buffer)
+                );
+            }
+            keep
+        });
+        self.stats.expired += (before - self.buffers.len()) as u64;
+    }
+
+    /// Serialize in-flight buffers so they can survive a restart
+    pub fn snapshot(&self) -> Vec<u8> {
+        let now = self.clock.now();
+        let buffers: Vec<BufferSnapshot> = self
+            .buffers
+            .iter()
+            .map(|(&message_id, buf)| BufferSnapshot {
+                message_id,
+                total_fragments: buf.total_fragments,
+                total_size: buf.total_size,
+                received: (0..)
+                    .zip(&buf.fragments)
+                    .filter_map(|(i, frag)| frag.as_ref().map(|d| (i, d.clone())))
+                    .collect(),
+                age_ms: now.saturating_duration_since(buf.created_at).as_millis() as u64,
+            })
+            .collect();
+        let snapshot = ReassemblerSnapshot { taken_at_ms: unix_millis(), buffers };
+        bincode::serialize(&snapshot).expect("snapshot serialization is infallible")
+    }
+
+    /// Like `restore`, but with buffer ages measured against `clock`
+    pub fn restore_with_clock(bytes: &[u8], clock: C) -> bincode::Result<Self> {
+        let snapshot: ReassemblerSnapshot = bincode::deserialize(bytes)?;
+        let downtime_ms = unix_millis().saturating_sub(snapshot.taken_at_ms);
+        let now = clock.now();
+        let mut reassembler = Self::with_clock(clock, DEFAULT_MAX_FRAGMENT_AGE);
+
+        for snap in snapshot.buffers {
+            let age = Duration::from_millis(snap.age_ms.saturating_add(downtime_ms));
+            if age >= reassembler.max_age {
+                continue;
+            }
+            let Some(created_at) = now.checked_sub(age) else {
+                continue;
+            };
+            let mut buffer = FragmentBuffer::new(snap.total_fragments, snap.total_size, created_at, 0);
+            for (index, data) in snap.received {
+                buffer.insert(index, data);
+            }
+            reassembler.buffers.insert(snap.message_id, buffer);
+        }
+
+        Ok(reassembler)
+    }
+
+    /// Fragment indices `message_id` is still waiting for, lowest first, or `None` if it isn't pending
+    pub fn missing_fragments(&self, message_id: u32) -> Option<Vec<u16>> {
+        self.buffers.get(&message_id).map(|buffer| buffer.missing().collect())
+    }
+
+    /// The `limit` oldest messages awaiting fragments, with what each has and lacks
+    pub fn inspect(&self, limit: usize) -> BuffersReport {
+        let now = self.clock.now();
+        let key = |(message_id, buffer): &(&u32, &FragmentBuffer)| (buffer.created_at, **message_id);
+        let mut oldest: Vec<(&u32, &FragmentBuffer)> = self.buffers.iter().collect();
+        // Partition first, so a full map costs a linear pass rather than a full sort
+        if limit < oldest.len() {
+            oldest.select_nth_unstable_by_key(limit, key);
+            oldest.truncate(limit);
+        }
+        oldest.sort_unstable_by_key(key);
+        let buffers = oldest
+            .into_iter()
+            .map(|(&message_id, buffer)| BufferInfo {
+                message_id,
+                source: buffer.source,
+                age_ms: now.saturating_duration_since(buffer.created_at).as_millis() as u64,
+                received: buffer.count,
+                total_fragments: buffer.total_fragments,
+                missing: buffer.missing().take(MAX_LISTED_MISSING).collect(),
+            })
+            .collect();
+        BuffersReport { pending: self.buffers.len(), buffers }
+    }
+
+    /// Number of messages currently awaiting more fragments
+    pub fn pending(&self) -> usize {
+        self.buffers.len()
+    }
+
+    /// Return the counters accumulated since the previous call and reset them
+    pub fn take_stats(&mut self) -> ReassemblerStats {
+        std::mem::take(&mut self.stats)
+    }
+}
+
+/// Split `payload` into SHRD-framed fragments of at most `max_payload` bytes each,
+/// as the sender does
+pub fn fragment_message(message_id: u32, payload: &[u8], max_payload: usize) -> Vec<Vec<u8>> {
+    let chunks: Vec<&[u8]> = if payload.is_empty() {
+        vec![&[]]
+    } else {
+        payload.chunks(max_payload.max(1)).collect()
+    };
+    let total_fragments = chunks.len() as u16;
+
+    chunks
+        .iter()
+        .enumerate()
+        .map(|(index, chunk)| {
+            let mut packet = Vec::with_capacity(HEADER_SIZE + chunk.len());
+            packet.extend_from_slice(MAGIC);
+            packet.extend_from_slice(&message_id.to_le_bytes());
+            packet.extend_from_slice(&(index as u16).to_le_bytes());
+            packet.extend_from_slice(&total_fragments.to_le_bytes());
+            packet.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+            packet.extend_from_slice(chunk);
+            packet
+        })
+        .collect()
+}
+
+/// Whether a message of `total_fragments` fragments, fragment `index` of which carries `len` bytes, could be
+/// `total_size` bytes long, give or take a factor of `tolerance`. Senders cut a message into equal chunks with the
+/// remainder last, so a non-final fragment puts the message between `total_fragments - 1` and `total_fragments`
+/// times its size. The final one only says the other chunks fit in a datagram each
+fn plausible_size(total_size: u32, total_fragments: u16, index: u16, len: usize, tolerance: f64) -> bool {
+    let (size, len, chunks) = (total_size as f64, len as f64, total_fragments as f64);
+    if total_size == 0 || len > size {
+        return false;
+    }
+    match index + 1 == total_fragments {
+        true => size <= (chunks - 1.0) * MAX_FRAGMENT_PAYLOAD as f64 + len,
+        false => size <= chunks * len * tolerance && size * tolerance >= (chunks - 1.0) * len,
+    }
+}
+
+/// The first few missing indices, for logging
+fn describe_missing(buffer: &FragmentBuffer) -> String {
+    const SHOWN: usize = 16;
+    let missing = buffer.total_fragments - buffer.count;
+    let mut shown: Vec<String> = buffer.missing().take(SHOWN).map(|i| i.to_string()).collect();
+    if missing as usize > SHOWN {
+        shown.push(format!("... ({} more)", missing as usize - SHOWN));
+    }
+    shown.join(",")
+}
+
+fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
@@ -0,0 +1,91 @@
+//! Settings that can be changed at runtime from an env-style override file.
+//!
+//! The file holds `KEY=VALUE` lines in the same form as the environment
+//! variables. It is read at startup and again on SIGHUP; only keys in
+//! [`RELOADABLE`] are applied, everything else needs a restart.
+
+use std::collections::HashMap;
+
+/// Settings picked up without a restart
+pub const RELOADABLE: &[&str] = &[
+    "RUST_LOG",
+    "DETECT_BUYS",
+    "DETECT_DEV_BUYS",
+    "DETECT_ADMIN",
+    "DETECT_MAINTENANCE",
+    "DETECT_MINT_INITS",
+    "DETECT_UNKNOWN",
+    "UNKNOWN_DATA_MAX_BYTES",
+    "DETECT_MIGRATIONS",
+    "MIGRATION_PROGRAM_ID",
+    "MIGRATION_DISC",
+    "MIGRATION_MINT_INDEX",
+    "MIGRATION_POOL_INDEX",
+    "PROGRAM_LAYOUTS_PATH",
+    "ENTRY_DECODE_LIMIT_MB",
+    "CREATE_ACCOUNTS",
+    "DETECT_TOKEN_ACCOUNTS",
+    "PARTIAL_ENTRY_DECODE",
+    "CREATE_ARG_MAX_LEN",
+    "CREATE_ARGS_MAX_BYTES",
+    "LOG_GLOBAL_ACCOUNT",
+    "CREATE_GLOBAL_INDEX",
+    "CREATE_METADATA_INDEX",
+    "VERIFY_METADATA_PDA",
+    "DETECTION_FILTER",
+];
+
+/// Named lists for `DETECTION_FILTER`, also reloadable
+pub const FILTER_LIST_PREFIX: &str = "FILTER_LIST_";
+
+/// Prefix of one pipeline's settings, as in `PIPELINE_<NAME>_<KEY>`
+pub const PIPELINE_PREFIX: &str = "PIPELINE_";
+
+/// Whether `key` is picked up without a restart, for every pipeline or one in particular
+pub fn is_reloadable(key: &str) -> bool {
+    let shared = |key: &str| RELOADABLE.contains(&key) || key.starts_with(FILTER_LIST_PREFIX);
+    shared(key)
+        || key
+            .strip_prefix(PIPELINE_PREFIX)
+            .is_some_and(|rest| rest.match_indices('_').any(|(i, _)| i > 0 && shared(&rest[i + 1..])))
+}
+
+/// Parsed override file
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Overrides {
+    /// Reloadable settings from the file
+    pub values: HashMap<String, String>,
+    /// Keys in the file that can't be changed at runtime
+    pub ignored: Vec<String>,
+}
+
+/// Parse `KEY=VALUE` lines, keeping only reloadable keys
+pub fn parse(text: &str) -> Overrides {
+    let mut overrides = Overrides::default();
+    for (key, value) in parse_lines(text) {
+        if is_reloadable(&key) {
+            overrides.values.insert(key, value);
+        } else {
+            overrides.ignored.push(key);
+        }
+    }
+    overrides
+}
+
+/// Every `KEY=VALUE` pair in file order; blank lines, `#` comments and an `export ` prefix are allowed
+pub fn parse_lines(text: &str) -> Vec<(String, String)> {
+    let mut pairs = Vec::new();
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim().trim_start_matches("export ").trim();
+        let value = value.trim();
+        let value = value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .unwrap_or(value);
+        pairs.push((key.to_string(), value.to_string()));
+    }
+    pairs
+}
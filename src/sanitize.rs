@@ -0,0 +1,51 @@
+//! Making on-chain token strings safe to log and serialize.
+//!
+//! A token's name, symbol and URI are whatever bytes its creator chose. Decoding is lossy, so
+//! invalid UTF-8 becomes U+FFFD instead of failing, and [`sanitize`] escapes everything that could
+//! rewrite a terminal or a log line: control characters (newlines included, so a name can't forge
+//! a log line), bidirectional controls such as U+202E RIGHT-TO-LEFT OVERRIDE, and zero-width
+//! characters. Escapes take the form `\u{202e}`, so the result is plain printable text.
+//!
+//! Bidi controls and zero-width characters have no business in a token name, but are a known way
+//! to make one token's name display like another's; [`is_suspicious`] flags them.
+
+use std::borrow::Cow;
+
+/// Decode `bytes` as UTF-8, replacing invalid sequences with U+FFFD
+pub fn decode_lossy(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+/// `text` with control, bidi and zero-width characters escaped; borrowed when there are none
+pub fn sanitize(text: &str) -> Cow<'_, str> {
+    if !text.chars().any(needs_escape) {
+        return Cow::Borrowed(text);
+    }
+    let mut out = String::with_capacity(text.len() + 8);
+    for c in text.chars() {
+        match needs_escape(c) {
+            true => out.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            false => out.push(c),
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// Whether `text` holds bidi controls or zero-width characters, which can disguise what it displays as
+pub fn is_suspicious(text: &str) -> bool {
+    text.chars().any(|c| is_bidi_control(c) || is_zero_width(c))
+}
+
+fn needs_escape(c: char) -> bool {
+    c.is_control() || is_bidi_control(c) || is_zero_width(c)
+}
+
+/// Explicit directional marks, embeddings, overrides and isolates
+fn is_bidi_control(c: char) -> bool {
+    matches!(c, '\u{061c}' | '\u{200e}' | '\u{200f}' | '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}')
+}
+
+/// Zero-width space, non-joiner and joiner, word joiner, and the byte order mark
+fn is_zero_width(c: char) -> bool {
+    matches!(c, '\u{200b}'..='\u{200d}' | '\u{2060}' | '\u{feff}')
+}
@@ -0,0 +1,199 @@
+//! Priority scores for creates, so consumers can triage during a launch storm.
+//!
+//! A create's score is a weighted average of four factors, each scaled to 0..=1, as a whole number from 0 to 100:
+//!
+//! - `initial_buy`: the creator's own buy of the mint in the same message, or a folded dev buy, over `full_buy`
+//!   lamports; 0 without one
+//! - `creator`: `1 / (1 + n)` for a creator seen creating `n` tokens before, so serial launchers rank lower
+//! - `name`: 1 when the name or symbol contains one of `name_patterns`, ignoring case; 0 otherwise
+//! - `priority_fee`: the transaction's priority fee over `full_priority_fee` lamports; 0 when it sets none
+//!
+//! [`score`] is pure; [`CreateScorer`] gathers its inputs and remembers creators.
+
+use std::{collections::HashMap, str::FromStr};
+
+use solana_sdk::pubkey::Pubkey;
+
+use crate::detector::Detection;
+
+/// Creators whose past creates are counted; past this, a new one replaces the one with the fewest
+pub const MAX_TRACKED_CREATORS: usize = 4096;
+
+/// Score buckets reported per stats window: 0-19, 20-39, 40-59, 60-79 and 80-100
+pub const SCORE_BUCKETS: usize = 5;
+
+/// How much each factor counts; only their ratios matter
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScoreWeights {
+    pub initial_buy: f64,
+    pub creator: f64,
+    pub name: f64,
+    pub priority_fee: f64,
+}
+
+impl Default for ScoreWeights {
+    fn default() -> Self {
+        Self { initial_buy: 40.0, creator: 20.0, name: 10.0, priority_fee: 30.0 }
+    }
+}
+
+impl FromStr for ScoreWeights {
+    type Err = String;
+
+    /// `factor=weight` pairs separated by commas, e.g. `initial_buy=50,priority_fee=50`; factors left out weigh 0
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut weights = Self { initial_buy: 0.0, creator: 0.0, name: 0.0, priority_fee: 0.0 };
+        for pair in s.split(',').filter(|pair| !pair.trim().is_empty()) {
+            let (factor, weight) = pair.split_once('=').ok_or_else(|| format!("expected factor=weight: {}", pair))?;
+            let weight: f64 = weight.trim().parse().map_err(|_| format!("invalid weight: {}", weight.trim()))?;
+            if !weight.is_finite() || weight < 0.0 {
+                return Err(format!("weight must be a non-negative number: {}", weight));
+            }
+            match factor.trim() {
+                "initial_buy" => weights.initial_buy = weight,
+                "creator" => weights.creator = weight,
+                "name" => weights.name = weight,
+                "priority_fee" => weights.priority_fee = weight,
+                other => {
+                    return Err(format!("unknown factor '{}', expected initial_buy, creator, name or priority_fee", other))
+                }
+            }
+        }
+        match weights.total() > 0.0 {
+            true => Ok(weights),
+            false => Err("expected at least one positive weight".to_string()),
+        }
+    }
+}
+
+impl ScoreWeights {
+    fn total(&self) -> f64 {
+        self.initial_buy + self.creator + self.name + self.priority_fee
+    }
+}
+
+/// How scores are computed, and which creates are kept
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoreConfig {
+    pub weights: ScoreWeights,
+    /// Initial buy, in lamports, that scores the factor's full weight
+    pub full_buy: u64,
+    /// Priority fee, in lamports, that scores the factor's full weight
+    pub full_priority_fee: u64,
+    /// Substrings that make a name or symbol match, lowercase
+    pub name_patterns: Vec<String>,
+    /// Creates scoring below this are suppressed; 0 keeps them all
+    pub min_score: u8,
+}
+
+impl Default for ScoreConfig {
+    fn default() -> Self {
+        Self {
+            weights: ScoreWeights::default(),
+            full_buy: 5_000_000_000,
+            full_priority_fee: 1_000_000,
+            name_patterns: Vec::new(),
+            min_score: 0,
+        }
+    }
+}
+
+/// What a create's score is computed from
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ScoreInputs {
+    /// The creator's buy of the mint in the same message, in lamports
+    pub initial_buy: Option<u64>,
+    /// Creates by the same creator seen before this one
+    pub creator_creates: u32,
+    pub name_match: bool,
+    /// Lamports
+    pub priority_fee: Option<u64>,
+}
+
+/// `inputs` scored from 0 to 100 under `config`
+pub fn score(inputs: &ScoreInputs, config: &ScoreConfig) -> u8 {
+    let ratio = |value: Option<u64>, full: u64| value.map_or(0.0, |value| (value as f64 / full.max(1) as f64).min(1.0));
+    let weights = &config.weights;
+    let weighted = weights.initial_buy * ratio(inputs.initial_buy, config.full_buy)
+        + weights.creator / (1.0 + f64::from(inputs.creator_creates))
+        + weights.name * f64::from(u8::from(inputs.name_match))
+        + weights.priority_fee * ratio(inputs.priority_fee, config.full_priority_fee);
+    match weights.total() > 0.0 {
+        true => (weighted * 100.0 / weights.total()).round().clamp(0.0, 100.0) as u8,
+        false => 0,
+    }
+}
+
+/// The bucket of `SCORE_BUCKETS` a score falls in
+pub fn bucket(score: u8) -> usize {
+    (usize::from(score) / 20).min(SCORE_BUCKETS - 1)
+}
+
+/// Scores the creates of each message, counting creates per creator as it goes
+pub struct CreateScorer {
+    config: ScoreConfig,
+    creators: HashMap<Pubkey, u32>,
+}
+
+impl CreateScorer {
+    pub fn new(config: ScoreConfig) -> Self {
+        Self { config, creators: HashMap::new() }
+    }
+
+    pub fn config(&self) -> &ScoreConfig {
+        &self.config
+    }
+
+    /// Score the create at `index` of `detections`, a message's detections, and count it against its creator;
+    /// `None` for anything but a create
+    pub fn score(&mut self, detections: &[Detection], index: usize) -> Option<u8> {
+        let (mint, creator, compute_budget, event, dev_buy) = match detections.get(index)? {
+            Detection::Create { mint, creator, compute_budget, event, .. } => {
+                (mint, creator, compute_budget, event, None)
+            }
+            Detection::CreateWithDevBuy { mint, creator, compute_budget, event, dev_buy_sol, .. } => {
+                (mint, creator, compute_budget, event, Some(*dev_buy_sol))
+            }
+            _ => return None,
+        };
+        let initial_buy = dev_buy.or_else(|| {
+            detections.iter().find_map(|detection| match detection {
+                Detection::Buy { mint: m, buyer, max_sol_cost, event, .. } if m == mint && buyer == creator => {
+                    Some(event.as_ref().map_or(*max_sol_cost, |trade| trade.sol_amount))
+                }
+                _ => None,
+            })
+        });
+        let name_match = event.as_ref().is_some_and(|event| {
+            let (name, symbol) = (event.name.to_lowercase(), event.symbol.to_lowercase());
+            self.config.name_patterns.iter().any(|pattern| name.contains(pattern) || symbol.contains(pattern))
+        });
+        let inputs = ScoreInputs {
+            initial_buy,
+            creator_creates: self.creators.get(creator).copied().unwrap_or(0),
+            name_match,
+            priority_fee: compute_budget.as_ref().and_then(|budget| budget.priority_fee()),
+        };
+        self.record(*creator);
+        Some(score(&inputs, &self.config))
+    }
+
+    fn record(&mut self, creator: Pubkey) {
+        if let Some(count) = self.creators.get_mut(&creator) {
+            *count = count.saturating_add(1);
+            return;
+        }
+        if self.creators.len() >= MAX_TRACKED_CREATORS {
+            let fewest = self.creators.iter().min_by_key(|(_, &count)| count).map(|(&key, _)| key);
+            if let Some(fewest) = fewest {
+                self.creators.remove(&fewest);
+            }
+        }
+        self.creators.insert(creator, 1);
+    }
+}
+
+/// Comma-separated name patterns, trimmed and lowercased
+pub fn parse_name_patterns(value: &str) -> Vec<String> {
+    value.split(',').map(|pattern| pattern.trim().to_lowercase()).filter(|pattern| !pattern.is_empty()).collect()
+}
@@ -0,0 +1,241 @@
+//! Structured detection outputs, each drained on its own thread off the recv hot path.
+
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod breaker;
+#[cfg(unix)]
+pub mod fifo;
+pub mod jsonl;
+pub mod projection;
+pub mod rotate;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod tcp;
+#[cfg(feature = "zmq")]
+pub mod zmq;
+
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tracing::warn;
+
+use crate::{detector::Detection, enrich::BondingCurveState, envelope::MessageContext, events::TxStatus};
+
+/// A detection plus the context it was found in
+#[derive(Debug, Clone, Serialize)]
+pub struct DetectionEvent {
+    /// Wall-clock time the detection was emitted, in unix milliseconds
+    pub timestamp_ms: u64,
+    /// Sequence number of the reassembled message it came from
+    pub msg_seq: u64,
+    #[serde(flatten)]
+    pub detection: Detection,
+    /// Filled in by the RPC enrichment stage, when enabled and the lookup succeeds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bonding_curve_state: Option<BondingCurveState>,
+    /// Slot and proxy from the message's envelope, when it had one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<MessageContext>,
+    /// For a detection found in a message, whether its transaction succeeded; `unknown` unless the envelope
+    /// carried the transaction's logs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<TxStatus>,
+    /// With reaction tracking, the id to pass to `ReactionTracker::mark_acted`; increases with each detection
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detection_id: Option<u64>,
+    /// With slot ordering, its slot had already been flushed, so it arrives out of slot order
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub late: bool,
+    /// With create scoring, a create's priority from 0 to 100
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<u8>,
+    /// With a SOL/USD price, the USD value of a buy's or dev buy's SOL amount when it was emitted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usd_value: Option<f64>,
+    /// With reaction tracking, when the pipeline emitted the event
+    #[serde(skip)]
+    pub emitted_at: Option<Instant>,
+}
+
+impl DetectionEvent {
+    pub fn new(detection: Detection, msg_seq: u64) -> Self {
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        Self {
+            timestamp_ms,
+            msg_seq,
+            detection,
+            bonding_curve_state: None,
+            context: None,
+            status: None,
+            detection_id: None,
+            late: false,
+            score: None,
+            usd_value: None,
+            emitted_at: None,
+        }
+    }
+}
+
+/// A destination for detection events
+pub trait Sink: Send + 'static {
+    fn write(&mut self, event: &DetectionEvent) -> io::Result<()>;
+
+    /// Write the events gathered over one batch window, in order; by default one `write` each, carrying on past
+    /// a failed one and returning its error
+    fn write_batch(&mut self, events: &[Arc<DetectionEvent>]) -> io::Result<()> {
+        let mut result = Ok(());
+        for event in events {
+            if let Err(e) = self.write(event) {
+                result = result.and(Err(e));
+            }
+        }
+        result
+    }
+
+    /// Called once the queue is drained, and on shutdown
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Queue feeding one sink's worker thread
+pub struct SinkHandle {
+    name: &'static str,
+    tx: mpsc::Sender<Arc<DetectionEvent>>,
+    dropped: Arc<AtomicU64>,
+    worker: JoinHandle<()>,
+}
+
+impl SinkHandle {
+    /// Start a worker thread draining a bounded queue of `capacity` events into `sink`
+    pub fn spawn<S: Sink>(name: &'static str, sink: S, capacity: usize) -> io::Result<Self> {
+        Self::spawn_batched(name, sink, capacity, Duration::ZERO)
+    }
+
+    /// Like `spawn`, but once an event arrives the worker waits out `window` and hands `sink` everything queued
+    /// by then as one batch. A zero window writes each event as it arrives
+    pub fn spawn_batched<S: Sink>(name: &'static str, sink: S, capacity: usize, window: Duration) -> io::Result<Self> {
+        Self::spawn_batched_with(name, sink, capacity, window, std::thread::sleep)
+    }
+
+    /// Like `spawn_batched`, with `wait` called to wait out each window instead of sleeping through it, so a test
+    /// can decide when a window ends
+    pub fn spawn_batched_with<S: Sink>(
+        name: &'static str,
+        mut sink: S,
+        capacity: usize,
+        window: Duration,
+        mut wait: impl FnMut(Duration) + Send + 'static,
+    ) -> io::Result<Self> {
+        let (tx, mut rx) = mpsc::channel::<Arc<DetectionEvent>>(capacity.max(1));
+        let worker = std::thread::Builder::new()
+            .name(format!("sink-{}", name))
+            .spawn(move || {
+                while let Some(event) = rx.blocking_recv() {
+                    let written = match window.is_zero() {
+                        true => sink.write(&event),
+                        false => {
+                            // A closed queue is still drained, so the batch open at shutdown is written too
+                            wait(window);
+                            let mut batch = vec![event];
+                            while let Ok(event) = rx.try_recv() {
+                                batch.push(event);
+                            }
+                            sink.write_batch(&batch)
+                        }
+                    };
+                    if let Err(e) = written {
+                        warn!(target: "sink", "{} sink write failed: {}", name, e);
+                    }
+                    if rx.is_empty() {
+                        if let Err(e) = sink.flush() {
+                            warn!(target: "sink", "{} sink flush failed: {}", name, e);
+                        }
+                    }
+                }
+                if let Err(e) = sink.flush() {
+                    warn!(target: "sink", "{} sink flush failed: {}", name, e);
+                }
+            })?;
+        Ok(Self {
+            name,
+            tx,
+            dropped: Arc::new(AtomicU64::new(0)),
+            worker,
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Events dropped because the queue was full
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Share of the queue in use, from 0 (empty) to 1 (full, so new events are dropped)
+    pub fn pressure(&self) -> f64 {
+        1.0 - self.tx.capacity() as f64 / self.tx.max_capacity() as f64
+    }
+}
+
+/// All configured sinks
+#[derive(Default)]
+pub struct Sinks {
+    handles: Vec<SinkHandle>,
+}
+
+impl Sinks {
+    pub fn push(&mut self, handle: SinkHandle) {
+        self.handles.push(handle);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.handles.is_empty()
+    }
+
+    pub fn handles(&self) -> &[SinkHandle] {
+        &self.handles
+    }
+
+    /// Pressure of the least loaded sink, i.e. how backed up the sinks are at the very least; `None` without sinks
+    pub fn pressure(&self) -> Option<f64> {
+        self.handles.iter().map(SinkHandle::pressure).min_by(f64::total_cmp)
+    }
+
+    /// Queue an event for every sink without blocking; full queues drop and count
+    pub fn emit(&self, event: DetectionEvent) {
+        if self.handles.is_empty() {
+            return;
+        }
+        let event = Arc::new(event);
+        for handle in &self.handles {
+            if let Err(TrySendError::Full(_)) = handle.tx.try_send(event.clone()) {
+                handle.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Close every queue and wait for the workers to drain it
+    pub fn shutdown(self) {
+        for handle in self.handles {
+            drop(handle.tx);
+            if handle.worker.join().is_err() {
+                warn!(target: "sink", "{} sink worker panicked", handle.name);
+            }
+        }
+    }
+}
@@ -0,0 +1,191 @@
+//! Circuit breaker for sinks whose destination can go away.
+//!
+//! While closed, failed writes are retried with jittered exponential backoff.
+//! After `failure_threshold` consecutive failed events the breaker opens and
+//! events are dropped and counted instead of attempted. Once `open_for` has
+//! passed it half-opens: the next event is a single probe that closes the
+//! breaker on success or reopens it on failure.
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    io,
+    time::{Duration, Instant},
+};
+
+use tracing::{debug, info, warn};
+
+use super::{DetectionEvent, Sink};
+
+/// When to give up on a destination, and how hard to retry before that
+#[derive(Debug, Clone)]
+pub struct BreakerConfig {
+    /// Consecutive failed events that open the breaker
+    pub failure_threshold: u32,
+    /// How long the breaker stays open before probing
+    pub open_for: Duration,
+    /// Retries per event while closed, after the first attempt
+    pub max_retries: u32,
+    /// Backoff before the first retry; doubles per retry up to `max_backoff`
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for BreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            open_for: Duration::from_secs(30),
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerState {
+    Closed,
+    Open { until: Instant },
+    HalfOpen,
+}
+
+/// The breaker's state machine, driven by the caller's clock
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    open_for: Duration,
+    state: BreakerState,
+    consecutive_failures: u32,
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: u32, open_for: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            open_for,
+            state: BreakerState::Closed,
+            consecutive_failures: 0,
+        }
+    }
+
+    pub fn state(&self) -> BreakerState {
+        self.state
+    }
+
+    /// Whether an attempt may be made now, half-opening an open breaker whose time is up
+    pub fn allow(&mut self, now: Instant) -> bool {
+        match self.state {
+            BreakerState::Open { until } if now < until => false,
+            BreakerState::Open { .. } => {
+                self.state = BreakerState::HalfOpen;
+                true
+            }
+            BreakerState::Closed | BreakerState::HalfOpen => true,
+        }
+    }
+
+    pub fn success(&mut self) {
+        self.state = BreakerState::Closed;
+        self.consecutive_failures = 0;
+    }
+
+    /// Record a failed event; a failed probe reopens at once
+    pub fn failure(&mut self, now: Instant) {
+        self.consecutive_failures += 1;
+        if self.state == BreakerState::HalfOpen || self.consecutive_failures >= self.failure_threshold {
+            self.state = BreakerState::Open { until: now + self.open_for };
+        }
+    }
+}
+
+/// Wraps a sink with retries and a circuit breaker
+pub struct BreakerSink<S> {
+    name: &'static str,
+    inner: S,
+    config: BreakerConfig,
+    breaker: CircuitBreaker,
+    /// Events dropped while open, reset when the breaker closes
+    dropped: u64,
+}
+
+impl<S: Sink> BreakerSink<S> {
+    pub fn new(name: &'static str, inner: S, config: BreakerConfig) -> Self {
+        let breaker = CircuitBreaker::new(config.failure_threshold, config.open_for);
+        Self { name, inner, config, breaker, dropped: 0 }
+    }
+
+    pub fn state(&self) -> BreakerState {
+        self.breaker.state()
+    }
+
+    /// Try `event` once, then retry with backoff unless probing
+    fn attempt(&mut self, event: &DetectionEvent) -> io::Result<()> {
+        let retries = match self.breaker.state() {
+            BreakerState::HalfOpen => 0,
+            _ => self.config.max_retries,
+        };
+        let mut backoff = self.config.initial_backoff.min(self.config.max_backoff);
+        let mut retry = 0;
+        loop {
+            match self.inner.write(event) {
+                Ok(()) => return Ok(()),
+                Err(e) if retry < retries => {
+                    retry += 1;
+                    std::thread::sleep(jitter(backoff));
+                    backoff = (backoff * 2).min(self.config.max_backoff);
+                    debug!(target: "sink", "{} sink write failed ({}); retry {}/{}", self.name, e, retry, retries);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl<S: Sink> Sink for BreakerSink<S> {
+    fn write(&mut self, event: &DetectionEvent) -> io::Result<()> {
+        let was = self.breaker.state();
+        if !self.breaker.allow(Instant::now()) {
+            self.dropped += 1;
+            return Ok(());
+        }
+        if was != self.breaker.state() {
+            info!(target: "sink", "🔌 {} sink circuit half-open; probing", self.name);
+        }
+
+        let result = self.attempt(event);
+        match &result {
+            Ok(()) => self.breaker.success(),
+            Err(_) => self.breaker.failure(Instant::now()),
+        }
+        match (was, self.breaker.state()) {
+            (BreakerState::Closed, BreakerState::Open { .. }) => warn!(
+                target: "sink",
+                "🔌 {} sink circuit open after {} failed events; dropping events for {:.0}s",
+                self.name, self.config.failure_threshold, self.config.open_for.as_secs_f64()
+            ),
+            (BreakerState::Open { .. }, BreakerState::Open { .. }) => {
+                warn!(target: "sink", "🔌 {} sink probe failed; circuit open again", self.name)
+            }
+            (BreakerState::Open { .. }, BreakerState::Closed) => {
+                info!(target: "sink", "🔌 {} sink circuit closed; {} events dropped while open", self.name, self.dropped);
+                self.dropped = 0;
+            }
+            _ => {}
+        }
+        result
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.breaker.state() {
+            BreakerState::Closed => self.inner.flush(),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// A random duration between half of `backoff` and all of it, so retrying writers spread out
+fn jitter(backoff: Duration) -> Duration {
+    let random = RandomState::new().build_hasher().finish();
+    backoff / 2 + backoff.mul_f64((random % 1024) as f64 / 2048.0)
+}
@@ -0,0 +1,145 @@
+//! Named pipe (FIFO) sink: JSON lines for whichever process has the pipe open for reading.
+//!
+//! The pipe is opened non-blocking, which fails at once while no reader has it open. Events are then dropped
+//! and counted rather than queued, and every write retries the open, so a reader that comes back, or a new one,
+//! gets events from then on. A reader that goes away shows up as a broken pipe and is handled the same way. A
+//! reader too slow to keep the pipe from filling loses events too; a line cut short by a full pipe is finished
+//! before anything else is written, so readers only ever see whole lines.
+
+use std::{
+    ffi::CString,
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    os::unix::{
+        ffi::OsStrExt,
+        fs::{FileTypeExt, OpenOptionsExt},
+    },
+    path::PathBuf,
+};
+
+use tracing::{info, warn};
+
+use super::{projection::Projection, DetectionEvent, Sink};
+
+pub struct FifoSink {
+    path: PathBuf,
+    /// Open while a reader is attached
+    pipe: Option<File>,
+    /// The rest of a line the pipe had no room for
+    partial: Vec<u8>,
+    /// Events dropped over the sink's life
+    dropped: u64,
+    /// Events dropped since the last one written
+    unreported: u64,
+    projection: Projection,
+}
+
+impl FifoSink {
+    /// Use the FIFO at `path`, creating it if nothing is there; anything else at `path` is an error
+    pub fn open(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        match std::fs::metadata(&path) {
+            Ok(metadata) if metadata.file_type().is_fifo() => {}
+            Ok(_) => {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("{} is not a FIFO", path.display())))
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                let c_path = CString::new(path.as_os_str().as_bytes())?;
+                // SAFETY: `c_path` is a valid NUL-terminated string for the duration of the call
+                if unsafe { libc::mkfifo(c_path.as_ptr(), 0o600) } != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            Err(e) => return Err(e),
+        }
+        Ok(Self { path, pipe: None, partial: Vec::new(), dropped: 0, unreported: 0, projection: Projection::default() })
+    }
+
+    /// Write only `projection`'s fields of each event
+    pub fn with_projection(mut self, projection: Projection) -> Self {
+        self.projection = projection;
+        self
+    }
+
+    /// Events dropped so far, for want of a reader or room in the pipe
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Open the pipe if a reader has it open, returning whether one has
+    fn attach(&mut self) -> io::Result<bool> {
+        match OpenOptions::new().write(true).custom_flags(libc::O_NONBLOCK).open(&self.path) {
+            Ok(pipe) => {
+                info!(target: "sink", "📮 FIFO {} reader attached", self.path.display());
+                self.pipe = Some(pipe);
+                Ok(true)
+            }
+            Err(e) if e.raw_os_error() == Some(libc::ENXIO) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Write any unfinished line, then `line`, returning whether `line` went in; the pipe must be open
+    fn try_send(&mut self, line: &[u8]) -> io::Result<bool> {
+        let Some(pipe) = self.pipe.as_mut() else {
+            return Ok(false);
+        };
+        let finished = write_some(pipe, &self.partial)?;
+        self.partial.drain(..finished);
+        if !self.partial.is_empty() {
+            return Ok(false);
+        }
+        match write_some(pipe, line)? {
+            0 => Ok(false),
+            written => {
+                self.partial.extend_from_slice(&line[written..]);
+                Ok(true)
+            }
+        }
+    }
+}
+
+/// Write as much of `bytes` as `pipe` takes without blocking, returning how much that was
+fn write_some(pipe: &mut File, bytes: &[u8]) -> io::Result<usize> {
+    let mut written = 0;
+    while written < bytes.len() {
+        match pipe.write(&bytes[written..]) {
+            Ok(0) => break,
+            Ok(n) => written += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(written)
+}
+
+impl Sink for FifoSink {
+    fn write(&mut self, event: &DetectionEvent) -> io::Result<()> {
+        let mut line = serde_json::to_vec(&self.projection.apply(event))?;
+        line.push(b'\n');
+        let sent = match self.pipe.is_some() || self.attach()? {
+            true => match self.try_send(&line) {
+                Ok(sent) => sent,
+                Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {
+                    let path = self.path.display();
+                    warn!(target: "sink", "📮 FIFO {} reader went away; dropping events until one attaches", path);
+                    self.pipe = None;
+                    self.partial.clear();
+                    false
+                }
+                Err(e) => return Err(e),
+            },
+            false => false,
+        };
+        if !sent {
+            self.dropped += 1;
+            self.unreported += 1;
+        } else if self.unreported > 0 {
+            let path = self.path.display();
+            info!(target: "sink", "📮 FIFO {}: {} events dropped (no reader, or pipe full)", path, self.unreported);
+            self.unreported = 0;
+        }
+        Ok(())
+    }
+}
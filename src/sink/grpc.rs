@@ -0,0 +1,269 @@
+//! gRPC server-streaming sink, built with the `grpc` feature.
+//!
+//! Events fan out through a broadcast channel: every subscriber reads the same
+//! stream, and one that falls more than the channel's capacity behind skips the
+//! events it missed instead of slowing the others down.
+
+use std::{collections::HashSet, io, net::SocketAddr, pin::Pin};
+
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio_stream::{
+    wrappers::{ReceiverStream, TcpListenerStream},
+    Stream,
+};
+use tonic::{Request, Response, Status};
+use tracing::{info, warn};
+
+use super::{DetectionEvent, Sink};
+use crate::{detector::Detection, enrich::BondingCurveState, events::TxStatus};
+
+/// Types generated from `proto/detections.proto`
+pub mod proto {
+    tonic::include_proto!("shreds.v1");
+}
+
+use proto::{
+    detection_event,
+    detection_stream_server::{DetectionStream, DetectionStreamServer},
+    Kind, SubscribeRequest,
+};
+
+pub struct GrpcSink {
+    tx: broadcast::Sender<proto::DetectionEvent>,
+}
+
+impl GrpcSink {
+    /// Serve subscriptions on `addr`, buffering up to `capacity` events per subscriber
+    ///
+    /// Must be called from within a tokio runtime, which the server runs on.
+    pub fn serve(addr: &str, capacity: usize) -> io::Result<Self> {
+        let listener = std::net::TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        let listener = tokio::net::TcpListener::from_std(listener)?;
+        let (tx, _) = broadcast::channel(capacity.max(1));
+        let service = DetectionStreamServer::new(Service { tx: tx.clone() });
+
+        tokio::spawn(async move {
+            let result = tonic::transport::Server::builder()
+                .add_service(service)
+                .serve_with_incoming(TcpListenerStream::new(listener))
+                .await;
+            if let Err(e) = result {
+                warn!(target: "sink", "gRPC output server stopped: {}", e);
+            }
+        });
+        Ok(Self { tx })
+    }
+}
+
+impl Sink for GrpcSink {
+    fn write(&mut self, event: &DetectionEvent) -> io::Result<()> {
+        // Fails only when nobody is subscribed
+        let _ = self.tx.send(event.into());
+        Ok(())
+    }
+}
+
+struct Service {
+    tx: broadcast::Sender<proto::DetectionEvent>,
+}
+
+/// What one subscriber asked for
+struct Subscription {
+    kinds: HashSet<i32>,
+    creators: HashSet<String>,
+}
+
+impl Subscription {
+    fn matches(&self, event: &proto::DetectionEvent) -> bool {
+        let (kind, creator) = match &event.detection {
+            Some(detection_event::Detection::Create(create)) => (Kind::Create, Some(&create.creator)),
+            Some(detection_event::Detection::CreateWithDevBuy(create)) => {
+                (Kind::CreateWithDevBuy, Some(&create.creator))
+            }
+            Some(detection_event::Detection::Buy(_)) => (Kind::Buy, None),
+            Some(detection_event::Detection::Migration(_)) => (Kind::Migration, None),
+            Some(detection_event::Detection::MintInit(_)) => (Kind::MintInit, None),
+            Some(detection_event::Detection::Unknown(_)) => (Kind::Unknown, None),
+            Some(detection_event::Detection::Maintenance(_)) => (Kind::Maintenance, None),
+            // Liveness is for everyone
+            Some(detection_event::Detection::Heartbeat(_) | detection_event::Detection::SlotComplete(_)) => return true,
+            None => return false,
+        };
+        (self.kinds.is_empty() || self.kinds.contains(&(kind as i32)))
+            && (self.creators.is_empty() || creator.is_some_and(|c| self.creators.contains(c)))
+    }
+}
+
+type EventStream = Pin<Box<dyn Stream<Item = Result<proto::DetectionEvent, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl DetectionStream for Service {
+    type SubscribeStream = EventStream;
+
+    async fn subscribe(&self, request: Request<SubscribeRequest>) -> Result<Response<Self::SubscribeStream>, Status> {
+        let peer: Option<SocketAddr> = request.remote_addr();
+        let request = request.into_inner();
+        let subscription = Subscription {
+            kinds: request.kinds.into_iter().filter(|&k| k != Kind::Unspecified as i32).collect(),
+            creators: request.creators.into_iter().collect(),
+        };
+        info!(target: "sink", "gRPC output client subscribed: {:?}", peer);
+
+        let mut rx = self.tx.subscribe();
+        let (out, stream) = tokio::sync::mpsc::channel(16);
+        tokio::spawn(async move {
+            loop {
+                let event = match rx.recv().await {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(missed)) => {
+                        warn!(target: "sink", "gRPC output client {:?} fell behind; skipped {} events", peer, missed);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => return,
+                };
+                if subscription.matches(&event) && out.send(Ok(event)).await.is_err() {
+                    info!(target: "sink", "gRPC output client {:?} disconnected", peer);
+                    return;
+                }
+            }
+        });
+        Ok(Response::new(Box::pin(ReceiverStream::new(stream))))
+    }
+}
+
+impl From<&DetectionEvent> for proto::DetectionEvent {
+    fn from(event: &DetectionEvent) -> Self {
+        let detection = match &event.detection {
+            Detection::Create {
+                mint,
+                bonding_curve,
+                creator,
+                associated_bonding_curve,
+                metadata,
+                accounts,
+                compute_budget,
+                token_accounts,
+                ..
+            } => {
+                detection_event::Detection::Create(proto::Create {
+                    mint: mint.to_string(),
+                    bonding_curve: bonding_curve.to_string(),
+                    creator: creator.to_string(),
+                    associated_bonding_curve: associated_bonding_curve.as_ref().map(ToString::to_string),
+                    metadata: metadata.as_ref().map(ToString::to_string),
+                    accounts: accounts.iter().flatten().map(ToString::to_string).collect(),
+                    compute_unit_limit: compute_budget.and_then(|b| b.unit_limit),
+                    compute_unit_price: compute_budget.and_then(|b| b.unit_price),
+                    token_accounts: token_accounts
+                        .iter()
+                        .map(|t| proto::TokenAccount { owner: t.owner.to_string(), account: t.account.to_string() })
+                        .collect(),
+                })
+            }
+            Detection::CreateWithDevBuy {
+                mint,
+                bonding_curve,
+                creator,
+                dev_buy_sol,
+                dev_buy_tokens,
+                associated_bonding_curve,
+                metadata,
+                accounts,
+                compute_budget,
+                token_accounts,
+                ..
+            } => detection_event::Detection::CreateWithDevBuy(proto::CreateWithDevBuy {
+                mint: mint.to_string(),
+                bonding_curve: bonding_curve.to_string(),
+                creator: creator.to_string(),
+                dev_buy_sol: *dev_buy_sol,
+                dev_buy_tokens: *dev_buy_tokens,
+                compute_unit_limit: compute_budget.and_then(|b| b.unit_limit),
+                compute_unit_price: compute_budget.and_then(|b| b.unit_price),
+                accounts: accounts.iter().flatten().map(ToString::to_string).collect(),
+                associated_bonding_curve: associated_bonding_curve.as_ref().map(ToString::to_string),
+                token_accounts: token_accounts
+                    .iter()
+                    .map(|t| proto::TokenAccount { owner: t.owner.to_string(), account: t.account.to_string() })
+                    .collect(),
+                metadata: metadata.as_ref().map(ToString::to_string),
+            }),
+            Detection::Buy { mint, buyer, token_amount, max_sol_cost, .. } => detection_event::Detection::Buy(proto::Buy {
+                mint: mint.to_string(),
+                buyer: buyer.to_string(),
+                token_amount: *token_amount,
+                max_sol_cost: *max_sol_cost,
+            }),
+            Detection::Migration { mint, pool, from_pumpfun } => detection_event::Detection::Migration(proto::Migration {
+                mint: mint.to_string(),
+                pool: pool.to_string(),
+                from_pumpfun: *from_pumpfun,
+            }),
+            Detection::MintInit { mint, decimals, authority, token_2022 } => {
+                detection_event::Detection::MintInit(proto::MintInit {
+                    mint: mint.to_string(),
+                    decimals: *decimals as u32,
+                    authority: authority.to_string(),
+                    token_2022: *token_2022,
+                })
+            }
+            Detection::Unknown { program, discriminator, data_hex, data_len } => {
+                detection_event::Detection::Unknown(proto::Unknown {
+                    program: program.to_string(),
+                    discriminator: discriminator.clone(),
+                    data_hex: data_hex.clone(),
+                    data_len: *data_len as u64,
+                })
+            }
+            Detection::Maintenance { instruction, account, user } => {
+                detection_event::Detection::Maintenance(proto::Maintenance {
+                    instruction: instruction.name().to_string(),
+                    account: account.to_string(),
+                    user: user.to_string(),
+                })
+            }
+            Detection::Heartbeat { packets, pending } => detection_event::Detection::Heartbeat(proto::Heartbeat {
+                packets: *packets,
+                pending: *pending as u64,
+            }),
+            Detection::SlotComplete { slot, detections } => {
+                detection_event::Detection::SlotComplete(proto::SlotComplete { slot: *slot, detections: *detections as u64 })
+            }
+        };
+        Self {
+            timestamp_ms: event.timestamp_ms,
+            msg_seq: event.msg_seq,
+            detection: Some(detection),
+            bonding_curve_state: event.bonding_curve_state.as_ref().map(Into::into),
+            detection_id: event.detection_id,
+            status: event.status.map(|status| proto::TxStatus::from(status) as i32),
+            late: event.late,
+            score: event.score.map(u32::from),
+            usd_value: event.usd_value,
+        }
+    }
+}
+
+impl From<&BondingCurveState> for proto::BondingCurveState {
+    fn from(state: &BondingCurveState) -> Self {
+        Self {
+            virtual_token_reserves: state.virtual_token_reserves,
+            virtual_sol_reserves: state.virtual_sol_reserves,
+            real_token_reserves: state.real_token_reserves,
+            real_sol_reserves: state.real_sol_reserves,
+            token_total_supply: state.token_total_supply,
+            complete: state.complete,
+        }
+    }
+}
+
+impl From<TxStatus> for proto::TxStatus {
+    fn from(status: TxStatus) -> Self {
+        match status {
+            TxStatus::Unknown => proto::TxStatus::Unknown,
+            TxStatus::Succeeded => proto::TxStatus::Succeeded,
+            TxStatus::Failed => proto::TxStatus::Failed,
+        }
+    }
+}
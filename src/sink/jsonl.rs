@@ -0,0 +1,38 @@
+//! JSON-lines file sink: one detection event per line.
+
+use std::{io, path::PathBuf};
+
+use super::{
+    projection::Projection,
+    rotate::{RotatingFile, RotationConfig},
+    DetectionEvent, Sink,
+};
+
+pub struct JsonlSink {
+    file: RotatingFile,
+    projection: Projection,
+}
+
+impl JsonlSink {
+    pub fn open(path: impl Into<PathBuf>, rotation: RotationConfig) -> io::Result<Self> {
+        Ok(Self { file: RotatingFile::open(path, rotation)?, projection: Projection::default() })
+    }
+
+    /// Write only `projection`'s fields of each event
+    pub fn with_projection(mut self, projection: Projection) -> Self {
+        self.projection = projection;
+        self
+    }
+}
+
+impl Sink for JsonlSink {
+    fn write(&mut self, event: &DetectionEvent) -> io::Result<()> {
+        let mut line = serde_json::to_vec(&self.projection.apply(event))?;
+        line.push(b'\n');
+        self.file.write_all(&line)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
@@ -0,0 +1,116 @@
+//! Field projection for the JSON outputs: with `OUTPUT_FIELDS` set, each event is cut down to the fields listed.
+//!
+//! Only top-level fields can be picked; a nested object such as `bonding_curve_state` is kept or dropped whole.
+//! The fields come out in the order they're listed, and one an event doesn't carry is left out of it, so
+//! `OUTPUT_FIELDS=timestamp_ms,kind,mint` turns a create into `{"timestamp_ms":…,"kind":"create","mint":"…"}` and a
+//! heartbeat into `{"timestamp_ms":…,"kind":"heartbeat"}`.
+
+use std::str::FromStr;
+
+use serde::{ser::Error as _, Serialize, Serializer};
+use serde_json::Value;
+
+use super::DetectionEvent;
+
+/// Every top-level field of a JSON event, whatever its kind
+pub const FIELDS: &[&str] = &[
+    "timestamp_ms",
+    "msg_seq",
+    "kind",
+    "mint",
+    "bonding_curve",
+    "creator",
+    "associated_bonding_curve",
+    "metadata",
+    "accounts",
+    "compute_budget",
+    "token_accounts",
+    "event",
+    "dev_buy_sol",
+    "dev_buy_tokens",
+    "buyer",
+    "token_amount",
+    "max_sol_cost",
+    "pool",
+    "from_pumpfun",
+    "decimals",
+    "authority",
+    "token_2022",
+    "program",
+    "discriminator",
+    "data_hex",
+    "data_len",
+    "instruction",
+    "account",
+    "user",
+    "packets",
+    "pending",
+    "slot",
+    "detections",
+    "bonding_curve_state",
+    "context",
+    "status",
+    "detection_id",
+    "late",
+    "score",
+    "usd_value",
+];
+
+/// Which fields of an event a JSON output writes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Projection {
+    /// In output order; `None` writes every field
+    fields: Option<Vec<&'static str>>,
+}
+
+impl Projection {
+    /// The projected fields in order, or `None` when every field is written
+    pub fn fields(&self) -> Option<&[&'static str]> {
+        self.fields.as_deref()
+    }
+
+    /// `event` as this projection writes it
+    pub fn apply<'a>(&'a self, event: &'a DetectionEvent) -> Projected<'a> {
+        Projected { event, fields: self.fields.as_deref() }
+    }
+}
+
+/// A comma-separated list of field names from [`FIELDS`], each at most once
+impl FromStr for Projection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields = Vec::new();
+        for name in s.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+            let Some(&field) = FIELDS.iter().find(|&&field| field == name) else {
+                return Err(format!("unknown output field {:?}; expected some of {}", name, FIELDS.join(",")));
+            };
+            if fields.contains(&field) {
+                return Err(format!("output field {} is listed twice", field));
+            }
+            fields.push(field);
+        }
+        match fields.is_empty() {
+            true => Err("expected at least one output field".to_string()),
+            false => Ok(Self { fields: Some(fields) }),
+        }
+    }
+}
+
+/// An event cut down to a projection's fields
+pub struct Projected<'a> {
+    event: &'a DetectionEvent,
+    fields: Option<&'a [&'static str]>,
+}
+
+impl Serialize for Projected<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let Some(fields) = self.fields else {
+            return self.event.serialize(serializer);
+        };
+        let Value::Object(mut all) = serde_json::to_value(self.event).map_err(S::Error::custom)? else {
+            return Err(S::Error::custom("an event serializes as an object"));
+        };
+        serializer.collect_map(fields.iter().filter_map(|&field| all.remove(field).map(|value| (field, value))))
+    }
+}
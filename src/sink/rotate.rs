@@ -0,0 +1,187 @@
+//! Size- and time-based rotation for file sinks, with optional gzip of rotated segments.
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    thread::JoinHandle,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use flate2::{write::GzEncoder, Compression};
+use tracing::{info, warn};
+
+/// When to rotate and what to keep
+#[derive(Debug, Clone, Default)]
+pub struct RotationConfig {
+    /// Rotate once the active file reaches this many bytes (0 = never)
+    pub max_bytes: u64,
+    /// Rotate once the active file has been open this long, checked on write
+    pub max_age: Option<Duration>,
+    /// Compress rotated segments to `.gz`
+    pub gzip: bool,
+    /// Keep at most this many rotated segments (0 = unlimited)
+    pub retain: usize,
+    /// Delete rotated segments older than this
+    pub retain_age: Option<Duration>,
+}
+
+/// An append-only file that rotates to `<path>.<unix_ms>[.gz]` segments
+pub struct RotatingFile {
+    path: PathBuf,
+    config: RotationConfig,
+    writer: BufWriter<File>,
+    written: u64,
+    opened_at: Instant,
+    maintenance: Option<JoinHandle<()>>,
+}
+
+impl RotatingFile {
+    pub fn open(path: impl Into<PathBuf>, config: RotationConfig) -> io::Result<Self> {
+        let path = path.into();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let written = file.metadata()?.len();
+        Ok(Self {
+            path,
+            config,
+            writer: BufWriter::new(file),
+            written,
+            opened_at: Instant::now(),
+            maintenance: None,
+        })
+    }
+
+    pub fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        if self.rotation_due() {
+            self.rotate()?;
+        }
+        self.writer.write_all(buf)?;
+        self.written += buf.len() as u64;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    fn rotation_due(&self) -> bool {
+        if self.written == 0 {
+            return false;
+        }
+        let too_big = self.config.max_bytes > 0 && self.written >= self.config.max_bytes;
+        let too_old = self.config.max_age.is_some_and(|age| self.opened_at.elapsed() >= age);
+        too_big || too_old
+    }
+
+    /// Move the active file aside and start a fresh one; compression and pruning
+    /// run on a background thread so the sink keeps writing
+    pub fn rotate(&mut self) -> io::Result<()> {
+        self.writer.flush()?;
+        let mut ts = unix_millis();
+        while segment_path(&self.path, ts).exists() || gz_path(&segment_path(&self.path, ts)).exists() {
+            ts += 1;
+        }
+        let segment = segment_path(&self.path, ts);
+        fs::rename(&self.path, &segment)?;
+
+        let file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        self.writer = BufWriter::new(file);
+        self.written = 0;
+        self.opened_at = Instant::now();
+        info!(target: "sink", "Rotated {} to {}", self.path.display(), segment.display());
+
+        // One maintenance pass at a time keeps pruning from racing compression
+        if let Some(previous) = self.maintenance.take() {
+            let _ = previous.join();
+        }
+        let (path, config) = (self.path.clone(), self.config.clone());
+        self.maintenance = Some(std::thread::spawn(move || {
+            if config.gzip {
+                if let Err(e) = compress(&segment) {
+                    warn!(target: "sink", "Failed to compress {}: {}", segment.display(), e);
+                }
+            }
+            if let Err(e) = prune(&path, &config) {
+                warn!(target: "sink", "Failed to prune rotated segments of {}: {}", path.display(), e);
+            }
+        }));
+        Ok(())
+    }
+}
+
+impl Drop for RotatingFile {
+    fn drop(&mut self) {
+        let _ = self.writer.flush();
+        if let Some(maintenance) = self.maintenance.take() {
+            let _ = maintenance.join();
+        }
+    }
+}
+
+fn segment_path(path: &Path, unix_ms: u64) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", unix_ms));
+    PathBuf::from(name)
+}
+
+fn gz_path(segment: &Path) -> PathBuf {
+    let mut name = segment.as_os_str().to_owned();
+    name.push(".gz");
+    PathBuf::from(name)
+}
+
+fn compress(segment: &Path) -> io::Result<()> {
+    let mut encoder = GzEncoder::new(File::create(gz_path(segment))?, Compression::default());
+    io::copy(&mut File::open(segment)?, &mut encoder)?;
+    encoder.finish()?;
+    fs::remove_file(segment)
+}
+
+/// Rotated segments of `path` as (timestamp, file), newest first
+pub fn rotated_segments(path: &Path) -> io::Result<Vec<(u64, PathBuf)>> {
+    let dir = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+    let prefix = match path.file_name().and_then(|n| n.to_str()) {
+        Some(n) => format!("{}.", n),
+        None => return Ok(Vec::new()),
+    };
+
+    let mut segments: Vec<(u64, PathBuf)> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let suffix = name.strip_prefix(&prefix)?;
+            let ts = suffix.strip_suffix(".gz").unwrap_or(suffix).parse().ok()?;
+            Some((ts, entry.path()))
+        })
+        .collect();
+    segments.sort_by_key(|(ts, _)| std::cmp::Reverse(*ts));
+    Ok(segments)
+}
+
+fn prune(path: &Path, config: &RotationConfig) -> io::Result<()> {
+    let now = unix_millis();
+    for (i, (ts, segment)) in rotated_segments(path)?.into_iter().enumerate() {
+        let over_count = config.retain > 0 && i >= config.retain;
+        let over_age = config
+            .retain_age
+            .is_some_and(|age| now.saturating_sub(ts) > age.as_millis() as u64);
+        if over_count || over_age {
+            match fs::remove_file(&segment) {
+                Ok(()) => info!(target: "sink", "Deleted old segment {}", segment.display()),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
@@ -0,0 +1,205 @@
+//! SQLite sink: one row per detection, for ad hoc SQL over a single box's history.
+//!
+//! Rows are inserted inside an open transaction that is committed once `batch_size`
+//! rows are pending, whenever the sink's queue drains, and on shutdown. A busy feed
+//! therefore commits in large batches while a quiet one commits almost immediately.
+//! Heartbeats and slot markers aren't detections and are skipped, and so are unknown and maintenance
+//! instructions, which have no mint and are meant for discovery rather than history.
+
+use std::{io, path::Path};
+
+use rusqlite::{params, Connection};
+use tracing::info;
+
+use super::{DetectionEvent, Sink};
+use crate::detector::Detection;
+
+/// Schema version stored in `PRAGMA user_version`
+pub const SCHEMA_VERSION: i64 = 1;
+
+/// Statements taking the schema from version `i` to `i + 1`; append, never edit
+const MIGRATIONS: &[&str] = &["CREATE TABLE detections (
+        id INTEGER PRIMARY KEY,
+        timestamp_ms INTEGER NOT NULL,
+        msg_seq INTEGER NOT NULL,
+        kind TEXT NOT NULL,
+        mint TEXT NOT NULL,
+        creator TEXT,
+        bonding_curve TEXT,
+        buyer TEXT,
+        token_amount INTEGER,
+        max_sol_cost INTEGER,
+        pool TEXT,
+        from_pumpfun INTEGER,
+        compute_unit_limit INTEGER,
+        compute_unit_price INTEGER,
+        event_json TEXT NOT NULL
+    );
+    CREATE INDEX detections_mint ON detections (mint);
+    CREATE INDEX detections_creator ON detections (creator) WHERE creator IS NOT NULL;"];
+
+const INSERT: &str = "INSERT INTO detections (
+        timestamp_ms, msg_seq, kind, mint, creator, bonding_curve, buyer, token_amount, max_sol_cost,
+        pool, from_pumpfun, compute_unit_limit, compute_unit_price, event_json
+    ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)";
+
+pub struct SqliteSink {
+    conn: Connection,
+    batch_size: usize,
+    pending: usize,
+}
+
+impl SqliteSink {
+    /// Open or create the database at `path`, bringing its schema up to date
+    pub fn open(path: impl AsRef<Path>, batch_size: usize) -> io::Result<Self> {
+        let conn = Connection::open(path).map_err(io::Error::other)?;
+        // WAL lets readers query while rows are being written
+        conn.execute_batch("PRAGMA journal_mode = WAL; PRAGMA synchronous = NORMAL;")
+            .map_err(io::Error::other)?;
+        migrate(&conn)?;
+        Ok(Self { conn, batch_size: batch_size.max(1), pending: 0 })
+    }
+
+    fn commit(&mut self) -> io::Result<()> {
+        if self.pending > 0 {
+            self.conn.execute_batch("COMMIT").map_err(io::Error::other)?;
+            self.pending = 0;
+        }
+        Ok(())
+    }
+}
+
+/// Apply the migrations the database hasn't had yet, each in its own transaction
+fn migrate(conn: &Connection) -> io::Result<()> {
+    let version: i64 = conn
+        .pragma_query_value(None, "user_version", |row| row.get(0))
+        .map_err(io::Error::other)?;
+    if version > SCHEMA_VERSION {
+        return Err(io::Error::other(format!(
+            "database schema version {} is newer than this build's {}",
+            version, SCHEMA_VERSION
+        )));
+    }
+    for (from, sql) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        let to = from + 1;
+        conn.execute_batch(&format!("BEGIN; {}; PRAGMA user_version = {}; COMMIT;", sql, to))
+            .map_err(io::Error::other)?;
+        info!(target: "sink", "SQLite schema migrated to version {}", to);
+    }
+    Ok(())
+}
+
+/// SQLite integers are signed; larger values (an uncapped `max_sol_cost`, say) are stored as NULL
+fn integer(value: u64) -> Option<i64> {
+    i64::try_from(value).ok()
+}
+
+/// Columns that only some kinds fill in
+#[derive(Default)]
+struct Row {
+    creator: Option<String>,
+    bonding_curve: Option<String>,
+    buyer: Option<String>,
+    token_amount: Option<i64>,
+    max_sol_cost: Option<i64>,
+    pool: Option<String>,
+    from_pumpfun: Option<bool>,
+    compute_unit_limit: Option<u32>,
+    compute_unit_price: Option<i64>,
+}
+
+impl Sink for SqliteSink {
+    fn write(&mut self, event: &DetectionEvent) -> io::Result<()> {
+        let (kind, mint, row) = match &event.detection {
+            Detection::Create { mint, bonding_curve, creator, compute_budget, .. } => (
+                "create",
+                mint,
+                Row {
+                    creator: Some(creator.to_string()),
+                    bonding_curve: Some(bonding_curve.to_string()),
+                    compute_unit_limit: compute_budget.and_then(|b| b.unit_limit),
+                    compute_unit_price: compute_budget.and_then(|b| b.unit_price).and_then(integer),
+                    ..Default::default()
+                },
+            ),
+            // The dev buy fills in the buy columns, its cost standing in for the cap
+            Detection::CreateWithDevBuy {
+                mint,
+                bonding_curve,
+                creator,
+                dev_buy_sol,
+                dev_buy_tokens,
+                compute_budget,
+                ..
+            } => (
+                "create_with_dev_buy",
+                mint,
+                Row {
+                    creator: Some(creator.to_string()),
+                    bonding_curve: Some(bonding_curve.to_string()),
+                    buyer: Some(creator.to_string()),
+                    token_amount: integer(*dev_buy_tokens),
+                    max_sol_cost: integer(*dev_buy_sol),
+                    compute_unit_limit: compute_budget.and_then(|b| b.unit_limit),
+                    compute_unit_price: compute_budget.and_then(|b| b.unit_price).and_then(integer),
+                    ..Default::default()
+                },
+            ),
+            Detection::Buy { mint, buyer, token_amount, max_sol_cost, .. } => (
+                "buy",
+                mint,
+                Row {
+                    buyer: Some(buyer.to_string()),
+                    token_amount: integer(*token_amount),
+                    max_sol_cost: integer(*max_sol_cost),
+                    ..Default::default()
+                },
+            ),
+            Detection::Migration { mint, pool, from_pumpfun } => (
+                "migration",
+                mint,
+                Row { pool: Some(pool.to_string()), from_pumpfun: Some(*from_pumpfun), ..Default::default() },
+            ),
+            // The decimals and authority are only in event_json
+            Detection::MintInit { mint, .. } => ("mint_init", mint, Row::default()),
+            Detection::Heartbeat { .. }
+            | Detection::SlotComplete { .. }
+            | Detection::Unknown { .. }
+            | Detection::Maintenance { .. } => return Ok(()),
+        };
+        let json = serde_json::to_string(event)?;
+
+        if self.pending == 0 {
+            self.conn.execute_batch("BEGIN").map_err(io::Error::other)?;
+        }
+        let inserted = self.conn.prepare_cached(INSERT).and_then(|mut insert| {
+            insert.execute(params![
+                event.timestamp_ms as i64,
+                event.msg_seq as i64,
+                kind,
+                mint.to_string(),
+                row.creator,
+                row.bonding_curve,
+                row.buyer,
+                row.token_amount,
+                row.max_sol_cost,
+                row.pool,
+                row.from_pumpfun,
+                row.compute_unit_limit,
+                row.compute_unit_price,
+                json,
+            ])
+        });
+        // A failed insert leaves the rest of the batch intact
+        self.pending += 1;
+        inserted.map_err(io::Error::other)?;
+        if self.pending >= self.batch_size {
+            self.commit()?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.commit()
+    }
+}
@@ -0,0 +1,128 @@
+//! TCP stream sink: every connected client receives every event.
+//!
+//! JSON frames are newline-terminated; binary frames (see `codec`) are prefixed
+//! with their length as a u32 LE. In the JSON array format each write is one line
+//! holding an array of events: a whole batch with sink batching on, else just one.
+
+use std::{
+    io::{self, Write},
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tracing::{info, warn};
+
+use super::{projection::Projection, DetectionEvent, Sink};
+use crate::codec;
+
+/// Write timeout per client; a client slower than this is disconnected
+const CLIENT_WRITE_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Wire format for streamed events
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Json,
+    JsonArray,
+    Binary,
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "json" => Ok(OutputFormat::Json),
+            "json_array" => Ok(OutputFormat::JsonArray),
+            "binary" => Ok(OutputFormat::Binary),
+            other => Err(format!("unknown output format: {}", other)),
+        }
+    }
+}
+
+pub struct TcpSink {
+    format: OutputFormat,
+    /// Applies to the JSON formats only
+    projection: Projection,
+    clients: Arc<Mutex<Vec<TcpStream>>>,
+}
+
+impl TcpSink {
+    /// Listen on `addr`, accepting clients on a background thread
+    pub fn bind(addr: &str, format: OutputFormat) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let clients = Arc::new(Mutex::new(Vec::new()));
+        let accepted = clients.clone();
+
+        std::thread::Builder::new().name("tcp-sink-accept".into()).spawn(move || {
+            for stream in listener.incoming() {
+                match stream.and_then(|s| s.set_write_timeout(Some(CLIENT_WRITE_TIMEOUT)).map(|_| s)) {
+                    Ok(stream) => {
+                        info!(target: "sink", "TCP output client connected: {:?}", stream.peer_addr());
+                        accepted.lock().unwrap().push(stream);
+                    }
+                    Err(e) => warn!(target: "sink", "TCP output accept failed: {}", e),
+                }
+            }
+        })?;
+
+        Ok(Self { format, projection: Projection::default(), clients })
+    }
+
+    /// Write only `projection`'s fields of each event in the JSON formats; binary frames are unaffected
+    pub fn with_projection(mut self, projection: Projection) -> Self {
+        self.projection = projection;
+        self
+    }
+
+    /// Every event's frame, back to back
+    fn frames(&self, events: &[&DetectionEvent]) -> io::Result<Vec<u8>> {
+        let mut out = Vec::new();
+        match self.format {
+            OutputFormat::JsonArray => {
+                let events: Vec<_> = events.iter().map(|event| self.projection.apply(event)).collect();
+                serde_json::to_writer(&mut out, &events)?;
+                out.push(b'\n');
+            }
+            OutputFormat::Json => {
+                for event in events {
+                    serde_json::to_writer(&mut out, &self.projection.apply(event))?;
+                    out.push(b'\n');
+                }
+            }
+            OutputFormat::Binary => {
+                for event in events {
+                    let body = codec::encode(event);
+                    out.extend_from_slice(&(body.len() as u32).to_le_bytes());
+                    out.extend_from_slice(&body);
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    /// Send `bytes` to every client, dropping those that can't keep up
+    fn send(&self, bytes: &[u8]) {
+        self.clients.lock().unwrap().retain_mut(|client| match client.write_all(bytes) {
+            Ok(()) => true,
+            Err(e) => {
+                info!(target: "sink", "TCP output client {:?} dropped: {}", client.peer_addr(), e);
+                false
+            }
+        });
+    }
+}
+
+impl Sink for TcpSink {
+    fn write(&mut self, event: &DetectionEvent) -> io::Result<()> {
+        self.send(&self.frames(&[event])?);
+        Ok(())
+    }
+
+    /// One write per client for the whole batch
+    fn write_batch(&mut self, events: &[Arc<DetectionEvent>]) -> io::Result<()> {
+        let events: Vec<&DetectionEvent> = events.iter().map(Arc::as_ref).collect();
+        self.send(&self.frames(&events)?);
+        Ok(())
+    }
+}
@@ -0,0 +1,315 @@
+//! ZeroMQ PUB sink: every detection is published with its kind as the topic, so SUB sockets filter by kind.
+//!
+//! The socket speaks ZMTP 3 (the wire protocol of libzmq 4 and its bindings) with the NULL mechanism directly over
+//! TCP, so there's no libzmq to link. Each event is a two-frame message: the topic (`create`, `create_with_dev_buy`,
+//! `buy`, `migration`, `mint_init`, `unknown`, `maintenance`, `heartbeat` or `slot_complete`), then the event as JSON
+//! or in the binary format (see `codec`, without its length prefix, since frames carry their own). As with any PUB
+//! socket, a subscriber gets the messages whose topic starts with one of its subscriptions, nothing before it
+//! subscribes, and a subscriber too slow to take a message within the write timeout is disconnected.
+//!
+//! It's a small subset of libzmq's PUB socket, enough for SUB and XSUB peers:
+//! - ZMTP 3.0 and 3.1 over TCP with the NULL mechanism only; PLAIN, CURVE and GSSAPI peers are turned away, so there's
+//!   no authentication or encryption
+//! - No high-water mark: libzmq queues messages for a slow subscriber and drops them past its HWM, while this sink
+//!   writes straight to the socket and disconnects a subscriber whose socket buffer stays full for 100ms
+//! - A thread per subscriber reading its subscriptions and heartbeats, on top of the one accepting them, while every
+//!   message is written to each subscriber in turn on the sink's thread; fine for tens of subscribers, not thousands
+
+use std::{
+    io::{self, Read, Write},
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tracing::{debug, info, warn};
+
+use super::{projection::Projection, tcp::OutputFormat, DetectionEvent, Sink};
+use crate::codec;
+
+/// Write timeout per subscriber; a subscriber slower than this is disconnected
+const SUBSCRIBER_WRITE_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// How long a connecting peer has to complete the greeting and handshake
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Largest frame accepted from a subscriber; they only send subscriptions and heartbeats
+const MAX_INCOMING_FRAME: u64 = 64 * 1024;
+
+/// Frame flags
+const MORE: u8 = 0x01;
+const LONG: u8 = 0x02;
+const COMMAND: u8 = 0x04;
+
+/// A connected SUB socket
+struct Subscriber {
+    addr: SocketAddr,
+    /// Shared with its reader thread, which answers heartbeats
+    stream: Mutex<TcpStream>,
+    /// Topic prefixes, one per subscription, so a prefix subscribed twice takes two cancels
+    topics: Mutex<Vec<Vec<u8>>>,
+}
+
+impl Subscriber {
+    fn wants(&self, topic: &str) -> bool {
+        self.topics.lock().unwrap().iter().any(|prefix| topic.as_bytes().starts_with(prefix))
+    }
+}
+
+pub struct ZmqSink {
+    format: OutputFormat,
+    /// Applies to JSON bodies only
+    projection: Projection,
+    subscribers: Arc<Mutex<Vec<Arc<Subscriber>>>>,
+}
+
+impl ZmqSink {
+    /// Listen on `addr`, a `host:port`, accepting subscribers on a background thread; `format` is JSON or binary
+    pub fn bind(addr: &str, format: OutputFormat) -> io::Result<Self> {
+        if format == OutputFormat::JsonArray {
+            let reason = "ZeroMQ messages hold one event: use json or binary";
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, reason));
+        }
+        let listener = TcpListener::bind(addr)?;
+        let subscribers = Arc::new(Mutex::new(Vec::new()));
+        let accepted = subscribers.clone();
+
+        std::thread::Builder::new().name("zmq-sink-accept".into()).spawn(move || {
+            for stream in listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        warn!(target: "sink", "ZeroMQ output accept failed: {}", e);
+                        continue;
+                    }
+                };
+                let subscribers = accepted.clone();
+                let peer = std::thread::Builder::new().name("zmq-sink-peer".into());
+                if let Err(e) = peer.spawn(move || serve(stream, subscribers)) {
+                    warn!(target: "sink", "ZeroMQ output couldn't start a subscriber thread: {}", e);
+                }
+            }
+        })?;
+
+        Ok(Self { format, projection: Projection::default(), subscribers })
+    }
+
+    /// Write only `projection`'s fields of each event in JSON bodies; binary ones are unaffected
+    pub fn with_projection(mut self, projection: Projection) -> Self {
+        self.projection = projection;
+        self
+    }
+
+    /// Subscribers that have completed the handshake
+    pub fn subscribers(&self) -> usize {
+        self.subscribers.lock().unwrap().len()
+    }
+}
+
+impl Sink for ZmqSink {
+    fn write(&mut self, event: &DetectionEvent) -> io::Result<()> {
+        let topic = event.detection.kind();
+        let mut subscribers = self.subscribers.lock().unwrap();
+        if !subscribers.iter().any(|subscriber| subscriber.wants(topic)) {
+            return Ok(());
+        }
+        let body = match self.format {
+            OutputFormat::Binary => codec::encode(event),
+            OutputFormat::Json | OutputFormat::JsonArray => serde_json::to_vec(&self.projection.apply(event))?,
+        };
+        let mut message = Vec::with_capacity(body.len() + topic.len() + 11);
+        frame(&mut message, MORE, topic.as_bytes());
+        frame(&mut message, 0, &body);
+
+        subscribers.retain(|subscriber| {
+            if !subscriber.wants(topic) {
+                return true;
+            }
+            let mut stream = subscriber.stream.lock().unwrap();
+            match stream.write_all(&message) {
+                Ok(()) => true,
+                Err(e) => {
+                    info!(target: "sink", "ZeroMQ subscriber {} dropped: {}", subscriber.addr, e);
+                    // Ends its reader thread too
+                    let _ = stream.shutdown(Shutdown::Both);
+                    false
+                }
+            }
+        });
+        Ok(())
+    }
+}
+
+/// Greet and handshake with a new peer, then apply its subscriptions until it disconnects
+fn serve(mut stream: TcpStream, subscribers: Arc<Mutex<Vec<Arc<Subscriber>>>>) {
+    let addr = match stream.peer_addr() {
+        Ok(addr) => addr,
+        Err(_) => return,
+    };
+    if let Err(e) = handshake(&mut stream) {
+        debug!(target: "sink", "ZeroMQ peer {} rejected: {}", addr, e);
+        return;
+    }
+    let writer = stream.try_clone().and_then(|s| s.set_write_timeout(Some(SUBSCRIBER_WRITE_TIMEOUT)).map(|_| s));
+    let writer = match writer {
+        Ok(writer) => writer,
+        Err(e) => {
+            warn!(target: "sink", "ZeroMQ subscriber {} setup failed: {}", addr, e);
+            return;
+        }
+    };
+    info!(target: "sink", "ZeroMQ subscriber connected: {}", addr);
+    let subscriber = Arc::new(Subscriber { addr, stream: Mutex::new(writer), topics: Mutex::new(Vec::new()) });
+    subscribers.lock().unwrap().push(subscriber.clone());
+
+    let disconnected = loop {
+        match read_frame(&mut stream) {
+            Ok((flags, body)) => apply(&subscriber, flags, &body),
+            Err(e) => break e,
+        }
+    };
+    debug!(target: "sink", "ZeroMQ subscriber {} disconnected: {}", addr, disconnected);
+    subscribers.lock().unwrap().retain(|other| !Arc::ptr_eq(other, &subscriber));
+}
+
+/// Exchange greetings and READY commands, accepting only SUB and XSUB peers with the NULL mechanism
+fn handshake(stream: &mut TcpStream) -> io::Result<()> {
+    let invalid = |reason: String| io::Error::new(io::ErrorKind::InvalidData, reason);
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+    stream.set_write_timeout(Some(HANDSHAKE_TIMEOUT))?;
+
+    // Signature, version 3.1, mechanism, as-server, filler
+    let mut greeting = [0u8; 64];
+    greeting[0] = 0xff;
+    greeting[9] = 0x7f;
+    greeting[10..12].copy_from_slice(&[3, 1]);
+    greeting[12..16].copy_from_slice(b"NULL");
+    stream.write_all(&greeting)?;
+    let mut peer = [0u8; 64];
+    stream.read_exact(&mut peer)?;
+    if peer[0] != 0xff || peer[9] & 1 != 1 || peer[10] < 3 {
+        return Err(invalid("not a ZMTP 3 peer".to_string()));
+    }
+    if peer[12..32].iter().take_while(|&&b| b != 0).ne(b"NULL") {
+        return Err(invalid("only the NULL mechanism is supported".to_string()));
+    }
+
+    let mut ready = vec![5];
+    ready.extend_from_slice(b"READY");
+    property(&mut ready, "Socket-Type", b"PUB");
+    let mut command = Vec::new();
+    frame(&mut command, COMMAND, &ready);
+    stream.write_all(&command)?;
+
+    let (flags, body) = read_frame(stream)?;
+    if flags & COMMAND == 0 || command_name(&body) != Some(&b"READY"[..]) {
+        return Err(invalid("expected a READY command".to_string()));
+    }
+    match properties(&body[6..]).find(|(name, _)| name.eq_ignore_ascii_case(b"Socket-Type")) {
+        Some((_, b"SUB" | b"XSUB")) => {}
+        Some((_, other)) => return Err(invalid(format!("{} sockets can't subscribe", String::from_utf8_lossy(other)))),
+        None => return Err(invalid("READY without a Socket-Type".to_string())),
+    }
+    stream.set_read_timeout(None)?;
+    Ok(())
+}
+
+/// Apply a frame from a subscriber: a subscription change, or a heartbeat to answer
+fn apply(subscriber: &Subscriber, flags: u8, body: &[u8]) {
+    let change = match (flags & COMMAND != 0, command_name(body)) {
+        // ZMTP 3.1
+        (true, Some(b"SUBSCRIBE")) => Some((true, &body[10..])),
+        (true, Some(b"CANCEL")) => Some((false, &body[7..])),
+        (true, Some(b"PING")) => {
+            // TTL, then up to 16 bytes of context to send back
+            let context = body.get(7..).unwrap_or_default();
+            let mut pong = vec![4];
+            pong.extend_from_slice(b"PONG");
+            pong.extend_from_slice(&context[..context.len().min(16)]);
+            let mut command = Vec::new();
+            frame(&mut command, COMMAND, &pong);
+            let _ = subscriber.stream.lock().unwrap().write_all(&command);
+            None
+        }
+        (true, _) => None,
+        // ZMTP 3.0 sends subscriptions as messages
+        (false, _) => match body.split_first() {
+            Some((1, topic)) => Some((true, topic)),
+            Some((0, topic)) => Some((false, topic)),
+            _ => None,
+        },
+    };
+    let mut topics = subscriber.topics.lock().unwrap();
+    match change {
+        Some((true, topic)) => topics.push(topic.to_vec()),
+        Some((false, topic)) => {
+            if let Some(i) = topics.iter().position(|t| t == topic) {
+                topics.swap_remove(i);
+            }
+        }
+        None => {}
+    }
+}
+
+/// Append a frame holding `body`
+fn frame(out: &mut Vec<u8>, flags: u8, body: &[u8]) {
+    match u8::try_from(body.len()) {
+        Ok(len) => out.extend_from_slice(&[flags, len]),
+        Err(_) => {
+            out.push(flags | LONG);
+            out.extend_from_slice(&(body.len() as u64).to_be_bytes());
+        }
+    }
+    out.extend_from_slice(body);
+}
+
+/// Read one frame, returning its flags and body
+fn read_frame(stream: &mut TcpStream) -> io::Result<(u8, Vec<u8>)> {
+    let mut flags = [0u8; 1];
+    stream.read_exact(&mut flags)?;
+    let len = match flags[0] & LONG {
+        0 => {
+            let mut len = [0u8; 1];
+            stream.read_exact(&mut len)?;
+            len[0] as u64
+        }
+        _ => {
+            let mut len = [0u8; 8];
+            stream.read_exact(&mut len)?;
+            u64::from_be_bytes(len)
+        }
+    };
+    if len > MAX_INCOMING_FRAME {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("{}-byte frame from a subscriber", len)));
+    }
+    let mut body = vec![0u8; len as usize];
+    stream.read_exact(&mut body)?;
+    Ok((flags[0], body))
+}
+
+/// The name of the command whose body is `body`
+fn command_name(body: &[u8]) -> Option<&[u8]> {
+    let (&len, rest) = body.split_first()?;
+    rest.get(..len as usize)
+}
+
+/// Append a metadata property
+fn property(out: &mut Vec<u8>, name: &str, value: &[u8]) {
+    out.push(name.len() as u8);
+    out.extend_from_slice(name.as_bytes());
+    out.extend_from_slice(&(value.len() as u32).to_be_bytes());
+    out.extend_from_slice(value);
+}
+
+/// The metadata properties in `bytes`, stopping at the first malformed one
+fn properties(mut bytes: &[u8]) -> impl Iterator<Item = (&[u8], &[u8])> {
+    std::iter::from_fn(move || {
+        let (&name_len, rest) = bytes.split_first()?;
+        let name = rest.get(..name_len as usize)?;
+        let rest = &rest[name_len as usize..];
+        let value_len = u32::from_be_bytes(rest.get(..4)?.try_into().unwrap()) as usize;
+        let value = rest.get(4..4 + value_len)?;
+        bytes = &rest[4 + value_len..];
+        Some((name, value))
+    })
+}
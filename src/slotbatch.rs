@@ -0,0 +1,94 @@
+//! Per-slot batching of detection events, for consumers that process a slot at a time.
+//!
+//! Detections from messages whose envelope names their slot are held per slot. A slot's
+//! batch closes when a message from a higher slot arrives, or once the slot has been open
+//! for the timeout, whichever comes first; the pipeline then sends its detections followed
+//! by a `slot_complete` marker. Slots whose messages held no detections still get a marker.
+//!
+//! Batches close in ascending slot order, and a slot's detections keep their arrival order: a
+//! slot timing out closes every open slot below it too. So output only goes backwards for a
+//! message from a slot at or below one already closed, which arrived too late for the window.
+//! It opens that slot again and closes with the next message from a higher slot or the timeout,
+//! so a slot can be completed more than once; such batches are flagged `late` rather than dropped.
+
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+
+use crate::sink::DetectionEvent;
+
+/// A closed slot's detections, in arrival order
+#[derive(Debug, Clone)]
+pub struct SlotBatch {
+    pub slot: u64,
+    /// A slot at or below it had already closed, so this batch breaks the ascending order
+    pub late: bool,
+    pub events: Vec<DetectionEvent>,
+}
+
+struct OpenSlot {
+    opened: Instant,
+    events: Vec<DetectionEvent>,
+}
+
+/// Holds detections per slot until the slot closes
+pub struct SlotBatcher {
+    timeout: Duration,
+    open: BTreeMap<u64, OpenSlot>,
+    /// Highest slot closed so far
+    closed: Option<u64>,
+}
+
+impl SlotBatcher {
+    /// Close a slot `timeout` after it opened if no higher slot has closed it
+    pub fn new(timeout: Duration) -> Self {
+        Self { timeout, open: BTreeMap::new(), closed: None }
+    }
+
+    /// Note a message from `slot`, opening it if needed, and close every open slot below it
+    pub fn observe(&mut self, slot: u64, now: Instant) -> Vec<SlotBatch> {
+        self.open.entry(slot).or_insert_with(|| OpenSlot { opened: now, events: Vec::new() });
+        slot.checked_sub(1).map(|below| self.close_through(below)).unwrap_or_default()
+    }
+
+    /// Hold `event` until `slot` closes
+    pub fn push(&mut self, slot: u64, event: DetectionEvent, now: Instant) {
+        self.open.entry(slot).or_insert_with(|| OpenSlot { opened: now, events: Vec::new() }).events.push(event);
+    }
+
+    /// Close every slot that has been open for the timeout, and every slot below one that has, lowest first
+    pub fn flush_expired(&mut self, now: Instant) -> Vec<SlotBatch> {
+        let expired = self
+            .open
+            .iter()
+            .filter(|(_, open)| now.saturating_duration_since(open.opened) >= self.timeout)
+            .map(|(&slot, _)| slot)
+            .max();
+        expired.map(|slot| self.close_through(slot)).unwrap_or_default()
+    }
+
+    /// Close every open slot, e.g. on shutdown
+    pub fn flush_all(&mut self) -> Vec<SlotBatch> {
+        self.close_through(u64::MAX)
+    }
+
+    /// Slots currently open
+    pub fn open_slots(&self) -> usize {
+        self.open.len()
+    }
+
+    /// Close the open slots up to and including `slot`, lowest first
+    fn close_through(&mut self, slot: u64) -> Vec<SlotBatch> {
+        let rest = slot.checked_add(1).map(|above| self.open.split_off(&above)).unwrap_or_default();
+        let closing = std::mem::replace(&mut self.open, rest);
+        closing
+            .into_iter()
+            .map(|(slot, open)| {
+                let late = self.closed.is_some_and(|closed| slot <= closed);
+                self.closed = self.closed.max(Some(slot));
+                SlotBatch { slot, late, events: open.events }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,66 @@
+//! Flags slots seen with two different terminal PoH hashes.
+//!
+//! A slot's entries form one PoH chain, so every copy of a complete slot ends in the
+//! same hash. Two different ones mean the leader produced more than one version of the
+//! slot (equivocation, i.e. duplicate shreds) or the feed is relaying blocks from two
+//! forks. Neither copy is known to be the one the cluster confirms.
+//!
+//! The fragment header carries no slot number, so nothing in this crate feeds the
+//! tracker yet; it is for embedders whose source knows which slot a message belongs to.
+
+use std::collections::BTreeMap;
+
+use solana_sdk::hash::Hash;
+
+/// A slot reported with a different terminal hash than it was first seen with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotConflict {
+    pub slot: u64,
+    /// The hash the slot was first seen with
+    pub first: Hash,
+    /// The hash that disagreed with it
+    pub conflicting: Hash,
+}
+
+/// Terminal PoH hash of the most recent slots
+pub struct SlotHashes {
+    capacity: usize,
+    hashes: BTreeMap<u64, Hash>,
+    conflicts: u64,
+}
+
+impl SlotHashes {
+    /// Remember the `capacity` highest slots; older ones are forgotten and no longer checked
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity: capacity.max(1), hashes: BTreeMap::new(), conflicts: 0 }
+    }
+
+    /// Record `hash` as the hash of `slot`'s final entry, returning a conflict if the slot
+    /// was seen ending in a different one
+    ///
+    /// Only pass hashes of complete slots: a message holding part of a slot ends partway
+    /// along its chain and would look like a conflict.
+    pub fn observe(&mut self, slot: u64, hash: Hash) -> Option<SlotConflict> {
+        if let Some(&first) = self.hashes.get(&slot) {
+            if first == hash {
+                return None;
+            }
+            self.conflicts += 1;
+            return Some(SlotConflict { slot, first, conflicting: hash });
+        }
+        if self.hashes.len() >= self.capacity {
+            let &oldest = self.hashes.keys().next().unwrap();
+            if slot < oldest {
+                return None;
+            }
+            self.hashes.remove(&oldest);
+        }
+        self.hashes.insert(slot, hash);
+        None
+    }
+
+    /// Conflicts found so far
+    pub fn conflicts(&self) -> u64 {
+        self.conflicts
+    }
+}
@@ -0,0 +1,284 @@
+//! Where the recv loop gets its datagrams from.
+
+#[cfg(feature = "geyser")]
+pub mod geyser;
+pub mod supervisor;
+
+use std::{
+    collections::VecDeque,
+    future::Future,
+    io,
+    net::SocketAddr,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use tokio::{
+    net::UdpSocket,
+    sync::mpsc::{self, error::TrySendError},
+    task::JoinHandle,
+};
+
+use crate::clock::MockClock;
+
+/// A stream of datagrams, like a bound UDP socket
+pub trait PacketSource {
+    /// Receive one datagram into `buf`, truncating it if it doesn't fit
+    fn recv_from(&mut self, buf: &mut [u8]) -> impl Future<Output = io::Result<(usize, SocketAddr)>> + Send;
+
+    /// Queue counters since the last call, for sources that buffer ahead of the consumer
+    fn take_queue_stats(&mut self) -> Option<QueueStats> {
+        None
+    }
+}
+
+/// Bind a UDP socket to `addr`, and with `interface` set, to that network interface alone
+///
+/// The interface is applied with `SO_BINDTODEVICE` before binding, so only datagrams arriving on it are received,
+/// even when `addr` is a wildcard. That needs Linux, where kernels before 5.7 also need `CAP_NET_RAW`; elsewhere
+/// an interface is an `Unsupported` error, and callers should bind by address alone.
+pub async fn bind_udp(addr: &str, interface: Option<&str>) -> io::Result<UdpSocket> {
+    let Some(interface) = interface else {
+        return UdpSocket::bind(addr).await;
+    };
+    let mut last_err = None;
+    for addr in tokio::net::lookup_host(addr).await? {
+        match bind_to_device(addr, interface) {
+            Ok(socket) => return Ok(socket),
+            Err(e) => last_err = Some(e),
+        }
+    }
+    Err(last_err.unwrap_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "address resolved to nothing")))
+}
+
+#[cfg(target_os = "linux")]
+fn bind_to_device(addr: SocketAddr, interface: &str) -> io::Result<UdpSocket> {
+    use socket2::{Domain, Protocol, Socket, Type};
+
+    let socket = Socket::new(Domain::for_address(addr), Type::DGRAM, Some(Protocol::UDP))?;
+    socket.bind_device(Some(interface.as_bytes())).map_err(|e| match e.raw_os_error() {
+        Some(libc::EPERM) => {
+            io::Error::new(e.kind(), format!("binding to interface {} needs CAP_NET_RAW: {}", interface, e))
+        }
+        Some(libc::ENODEV) => io::Error::new(io::ErrorKind::NotFound, format!("no network interface {}", interface)),
+        _ => e,
+    })?;
+    socket.set_nonblocking(true)?;
+    socket.bind(&addr.into())?;
+    UdpSocket::from_std(socket.into())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn bind_to_device(_: SocketAddr, _: &str) -> io::Result<UdpSocket> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "binding to a network interface needs Linux"))
+}
+
+impl PacketSource for UdpSocket {
+    async fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        UdpSocket::recv_from(self, buf).await
+    }
+}
+
+/// In-memory source replaying a scripted sequence of datagrams
+///
+/// Each datagram advances the shared `MockClock` by its delay before it is
+/// delivered. Once the script is exhausted `recv_from` fails with `UnexpectedEof`,
+/// or with `stay_open` set, waits forever like a quiet socket.
+pub struct MockSource {
+    clock: MockClock,
+    script: VecDeque<(Duration, Vec<u8>, SocketAddr)>,
+    stay_open: bool,
+}
+
+impl MockSource {
+    pub fn new(clock: MockClock) -> Self {
+        Self { clock, script: VecDeque::new(), stay_open: false }
+    }
+
+    /// Go quiet rather than fail once the script is exhausted
+    pub fn stay_open(&mut self) {
+        self.stay_open = true;
+    }
+
+    /// Queue `datagram` from `from`, delivered `delay` after the previous one
+    pub fn push(&mut self, delay: Duration, datagram: Vec<u8>, from: SocketAddr) {
+        self.script.push_back((delay, datagram, from));
+    }
+
+    /// Datagrams not yet delivered
+    pub fn remaining(&self) -> usize {
+        self.script.len()
+    }
+}
+
+impl PacketSource for MockSource {
+    async fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        let Some((delay, datagram, from)) = self.script.pop_front() else {
+            if self.stay_open {
+                std::future::pending::<()>().await;
+            }
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "scripted source exhausted"));
+        };
+        self.clock.advance(delay);
+        let len = datagram.len().min(buf.len());
+        buf[..len].copy_from_slice(&datagram[..len]);
+        Ok((len, from))
+    }
+}
+
+/// Where a pipeline's datagrams come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceKind {
+    /// Datagrams from shredstream_proxy on a UDP socket
+    Udp,
+    /// Transactions from a Yellowstone gRPC subscription (`geyser` feature)
+    Geyser,
+}
+
+impl FromStr for SourceKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "udp" => Ok(SourceKind::Udp),
+            "geyser" => Ok(SourceKind::Geyser),
+            other => Err(format!("unknown source: {}", other)),
+        }
+    }
+}
+
+/// Commitment level a `Geyser` source subscribes at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Commitment {
+    Processed,
+    Confirmed,
+    Finalized,
+}
+
+impl FromStr for Commitment {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "processed" => Ok(Commitment::Processed),
+            "confirmed" => Ok(Commitment::Confirmed),
+            "finalized" => Ok(Commitment::Finalized),
+            other => Err(format!("unknown commitment: {}", other)),
+        }
+    }
+}
+
+/// What the receiver does when the queue to the pipeline is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueuePolicy {
+    /// Drop the newest datagram and keep receiving
+    Drop,
+    /// Wait for room; the socket buffer fills meanwhile and the kernel drops instead
+    Block,
+}
+
+impl FromStr for QueuePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "drop" => Ok(QueuePolicy::Drop),
+            "block" => Ok(QueuePolicy::Block),
+            other => Err(format!("unknown queue policy: {}", other)),
+        }
+    }
+}
+
+/// Queue pressure over a stats window
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct QueueStats {
+    /// Datagrams dropped because the queue was full (`Drop` policy)
+    pub dropped: u64,
+    /// Time the receiver spent waiting for room (`Block` policy)
+    pub blocked: Duration,
+}
+
+#[derive(Default)]
+struct QueueCounters {
+    dropped: AtomicU64,
+    blocked_ns: AtomicU64,
+}
+
+type Datagram = io::Result<(Vec<u8>, SocketAddr)>;
+
+/// Receives from another source on its own task, through a bounded queue
+///
+/// This keeps the socket drained while the pipeline is busy with a large message.
+pub struct QueuedSource {
+    rx: mpsc::Receiver<Datagram>,
+    counters: Arc<QueueCounters>,
+    task: JoinHandle<()>,
+}
+
+impl QueuedSource {
+    pub fn spawn<S: PacketSource + Send + 'static>(mut source: S, capacity: usize, policy: QueuePolicy) -> Self {
+        let (tx, rx) = mpsc::channel::<Datagram>(capacity.max(1));
+        let counters = Arc::new(QueueCounters::default());
+        let shared = counters.clone();
+
+        let task = tokio::spawn(async move {
+            let mut buf = vec![0u8; 65536];
+            loop {
+                let datagram = match source.recv_from(&mut buf).await {
+                    Ok((len, from)) => (buf[..len].to_vec(), from),
+                    Err(e) => {
+                        // Errors always wait for room so the consumer sees them
+                        let _ = tx.send(Err(e)).await;
+                        return;
+                    }
+                };
+                match tx.try_send(Ok(datagram)) {
+                    Ok(()) => {}
+                    Err(TrySendError::Full(_)) if policy == QueuePolicy::Drop => {
+                        shared.dropped.fetch_add(1, Ordering::Relaxed);
+                    }
+                    Err(TrySendError::Full(item)) => {
+                        let started = Instant::now();
+                        if tx.send(item).await.is_err() {
+                            return;
+                        }
+                        shared.blocked_ns.fetch_add(started.elapsed().as_nanos() as u64, Ordering::Relaxed);
+                    }
+                    Err(TrySendError::Closed(_)) => return,
+                }
+            }
+        });
+        Self { rx, counters, task }
+    }
+}
+
+impl PacketSource for QueuedSource {
+    async fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        match self.rx.recv().await {
+            Some(Ok((datagram, from))) => {
+                let len = datagram.len().min(buf.len());
+                buf[..len].copy_from_slice(&datagram[..len]);
+                Ok((len, from))
+            }
+            Some(Err(e)) => Err(e),
+            None => Err(io::Error::other("receiver task stopped")),
+        }
+    }
+
+    fn take_queue_stats(&mut self) -> Option<QueueStats> {
+        Some(QueueStats {
+            dropped: self.counters.dropped.swap(0, Ordering::Relaxed),
+            blocked: Duration::from_nanos(self.counters.blocked_ns.swap(0, Ordering::Relaxed)),
+        })
+    }
+}
+
+impl Drop for QueuedSource {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
@@ -0,0 +1,222 @@
+//! Yellowstone gRPC (Geyser) input, built with the `geyser` feature.
+//!
+//! Subscribes to transactions touching the watched programs and hands each one to the
+//! pipeline as a datagram holding a one-entry `Vec<Entry>`, the same bytes an
+//! unfragmented shred message carries, so detection and outputs don't know the
+//! difference. A [`GeyserSource`] is one subscription and fails once it drops; the
+//! binary reopens it through a [`GeyserConnector`] under a
+//! [`SupervisedSource`](super::supervisor::SupervisedSource).
+
+use std::{
+    collections::HashMap,
+    future::Future,
+    io,
+    net::{SocketAddr, ToSocketAddrs},
+};
+
+use solana_entry::entry::Entry;
+use solana_sdk::{
+    hash::Hash,
+    instruction::CompiledInstruction,
+    message::{legacy, v0, MessageHeader, VersionedMessage},
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::VersionedTransaction,
+};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{
+    metadata::AsciiMetadataValue,
+    transport::{ClientTlsConfig, Endpoint},
+    Request, Streaming,
+};
+use tracing::{info, warn};
+
+use super::{Commitment, PacketSource};
+
+/// Types generated from `proto/geyser.proto`
+pub mod proto {
+    tonic::include_proto!("geyser");
+}
+
+use proto::{
+    geyser_client::GeyserClient, subscribe_update::UpdateOneof, CommitmentLevel, SubscribeRequest,
+    SubscribeRequestFilterTransactions, SubscribeRequestPing, SubscribeUpdate,
+};
+
+impl From<Commitment> for CommitmentLevel {
+    fn from(commitment: Commitment) -> Self {
+        match commitment {
+            Commitment::Processed => CommitmentLevel::Processed,
+            Commitment::Confirmed => CommitmentLevel::Confirmed,
+            Commitment::Finalized => CommitmentLevel::Finalized,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GeyserConfig {
+    /// `http://` or `https://` URL of the Yellowstone endpoint
+    pub endpoint: String,
+    /// Sent as the `x-token` header, for providers that require one
+    pub x_token: Option<String>,
+    pub commitment: Commitment,
+    /// Only transactions referencing one of these accounts are streamed
+    pub accounts: Vec<Pubkey>,
+}
+
+/// Opens subscriptions to one endpoint
+#[derive(Clone)]
+pub struct GeyserConnector {
+    endpoint: Endpoint,
+    token: Option<AsciiMetadataValue>,
+    config: GeyserConfig,
+    from: SocketAddr,
+}
+
+impl GeyserConnector {
+    /// Check the endpoint URL and token; nothing is connected until `connect`
+    pub fn new(config: GeyserConfig) -> io::Result<Self> {
+        let endpoint = Endpoint::from_shared(config.endpoint.clone()).map_err(io::Error::other)?;
+        let endpoint = match config.endpoint.starts_with("https://") {
+            true => endpoint.tls_config(ClientTlsConfig::new().with_webpki_roots()).map_err(io::Error::other)?,
+            false => endpoint,
+        };
+        let token = match &config.x_token {
+            Some(token) => Some(AsciiMetadataValue::try_from(token.as_str()).map_err(io::Error::other)?),
+            None => None,
+        };
+        // Only for the "first packet from" log line; the pipeline doesn't otherwise care
+        let from = endpoint_addr(&config.endpoint).unwrap_or(SocketAddr::from(([0, 0, 0, 0], 0)));
+        Ok(Self { endpoint, token, config, from })
+    }
+
+    /// Subscribe afresh
+    pub fn connect(&self) -> impl Future<Output = io::Result<GeyserSource>> + Send + 'static {
+        let connector = self.clone();
+        async move { subscribe(&connector).await.map_err(io::Error::other) }
+    }
+}
+
+/// Transactions from one Yellowstone subscription, as entry datagrams
+pub struct GeyserSource {
+    updates: Streaming<SubscribeUpdate>,
+    /// Kept open for the life of the subscription so pings can be answered on it
+    requests: mpsc::Sender<SubscribeRequest>,
+    from: SocketAddr,
+}
+
+/// Resolve the endpoint's host and port, defaulting the port from the scheme
+fn endpoint_addr(endpoint: &str) -> Option<SocketAddr> {
+    let url = reqwest::Url::parse(endpoint).ok()?;
+    (url.host_str()?, url.port_or_known_default()?).to_socket_addrs().ok()?.next()
+}
+
+async fn subscribe(connector: &GeyserConnector) -> Result<GeyserSource, Box<dyn std::error::Error + Send + Sync>> {
+    let config = &connector.config;
+    let mut client = GeyserClient::new(connector.endpoint.connect().await?);
+    let filter = SubscribeRequestFilterTransactions {
+        vote: Some(false),
+        failed: Some(false),
+        account_include: config.accounts.iter().map(Pubkey::to_string).collect(),
+        account_exclude: Vec::new(),
+    };
+    let request = SubscribeRequest {
+        transactions: HashMap::from([("watched".to_string(), filter)]),
+        commitment: Some(CommitmentLevel::from(config.commitment) as i32),
+        ping: None,
+    };
+    let (requests, request_rx) = mpsc::channel(4);
+    requests.send(request).await.ok();
+    let mut request = Request::new(ReceiverStream::new(request_rx));
+    if let Some(token) = connector.token.clone() {
+        request.metadata_mut().insert("x-token", token);
+    }
+    let updates = client.subscribe(request).await?.into_inner();
+    info!(target: "source", "✅ Subscribed to {}", config.endpoint);
+    Ok(GeyserSource { updates, requests, from: connector.from })
+}
+
+/// Convert a Yellowstone transaction, or `None` if a key, hash, signature or index is out of range
+pub fn versioned_transaction(transaction: proto::Transaction) -> Option<VersionedTransaction> {
+    let message = transaction.message?;
+    let header = message.header?;
+    let header = MessageHeader {
+        num_required_signatures: header.num_required_signatures.try_into().ok()?,
+        num_readonly_signed_accounts: header.num_readonly_signed_accounts.try_into().ok()?,
+        num_readonly_unsigned_accounts: header.num_readonly_unsigned_accounts.try_into().ok()?,
+    };
+    let pubkey = |bytes: &[u8]| Pubkey::try_from(bytes).ok();
+    let account_keys = message.account_keys.iter().map(|key| pubkey(key)).collect::<Option<Vec<_>>>()?;
+    let recent_blockhash = Hash::new_from_array(message.recent_blockhash.try_into().ok()?);
+    let instructions = message
+        .instructions
+        .into_iter()
+        .map(|ix| {
+            Some(CompiledInstruction {
+                program_id_index: ix.program_id_index.try_into().ok()?,
+                accounts: ix.accounts,
+                data: ix.data,
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+    let message = if message.versioned {
+        let address_table_lookups = message
+            .address_table_lookups
+            .into_iter()
+            .map(|lookup| {
+                Some(v0::MessageAddressTableLookup {
+                    account_key: pubkey(&lookup.account_key)?,
+                    writable_indexes: lookup.writable_indexes,
+                    readonly_indexes: lookup.readonly_indexes,
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        VersionedMessage::V0(v0::Message { header, account_keys, recent_blockhash, instructions, address_table_lookups })
+    } else {
+        VersionedMessage::Legacy(legacy::Message { header, account_keys, recent_blockhash, instructions })
+    };
+    let signatures = transaction
+        .signatures
+        .iter()
+        .map(|signature| Signature::try_from(signature.as_slice()).ok())
+        .collect::<Option<Vec<_>>>()?;
+    Some(VersionedTransaction { signatures, message })
+}
+
+/// `transaction` as a serialized single-entry `Vec<Entry>`; the entry's PoH fields are zeroed
+pub fn entry_datagram(transaction: VersionedTransaction) -> Vec<u8> {
+    let entries = vec![Entry { num_hashes: 0, hash: Hash::default(), transactions: vec![transaction] }];
+    bincode::serialize(&entries).expect("entries always serialize")
+}
+
+impl PacketSource for GeyserSource {
+    /// The next watched transaction; fails once the stream ends or errors
+    async fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        loop {
+            let update = self.updates.message().await.map_err(io::Error::other)?;
+            let update = update.ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "Geyser stream ended"))?;
+            match update.update_oneof {
+                Some(UpdateOneof::Transaction(update)) => {
+                    let Some(transaction) = update.transaction.and_then(|info| info.transaction) else {
+                        continue;
+                    };
+                    let Some(transaction) = versioned_transaction(transaction) else {
+                        warn!(target: "source", "Skipping malformed transaction in slot {}", update.slot);
+                        continue;
+                    };
+                    let datagram = entry_datagram(transaction);
+                    let len = datagram.len().min(buf.len());
+                    buf[..len].copy_from_slice(&datagram[..len]);
+                    return Ok((len, self.from));
+                }
+                // Some load balancers close streams that only ever carry server traffic
+                Some(UpdateOneof::Ping(_)) => {
+                    let ping = SubscribeRequest { ping: Some(SubscribeRequestPing { id: 1 }), ..Default::default() };
+                    self.requests.send(ping).await.ok();
+                }
+                Some(UpdateOneof::Pong(_)) | None => {}
+            }
+        }
+    }
+}
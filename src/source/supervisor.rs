@@ -0,0 +1,176 @@
+//! Restarting a failed input while the rest of the pipeline keeps running.
+//!
+//! A [`SupervisedSource`] wraps whatever opens an input, a UDP bind or a Geyser subscription, and reopens it
+//! with exponential backoff whenever receiving from it fails. The pipeline just sees a source that is slow
+//! for a while: nothing upstream of the recv loop is torn down, so reassembly state, sinks and windows carry
+//! on. Each failure and restart is logged under the `source` target, and the connection state and restart
+//! count are kept in the pipeline's [`Metrics`].
+//!
+//! Every stage is resumable, so the wrapper can be polled from a `select!` that drops `recv_from` whenever
+//! another branch wins: a backoff keeps its deadline and a reopen in progress keeps its future. Dropping the
+//! source, as the pipeline does on shutdown, abandons any pending restart.
+
+use std::{
+    future::Future,
+    io,
+    net::SocketAddr,
+    pin::Pin,
+    sync::{atomic::Ordering, Arc},
+    time::Duration,
+};
+
+use serde::Serialize;
+use tokio::time::Instant;
+use tracing::{error, info, warn};
+
+use super::{PacketSource, QueueStats};
+use crate::metrics::Metrics;
+
+/// Where a supervised source is in its lifecycle
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[repr(u8)]
+pub enum SourceState {
+    /// Receiving; also what an unsupervised source reports
+    #[default]
+    Connected = 0,
+    /// Opening, for the first time or after a backoff
+    Connecting = 1,
+    /// Failed, waiting out the backoff before reopening
+    Backoff = 2,
+    /// Gave up after `RestartPolicy::limit` failures in a row
+    Failed = 3,
+}
+
+impl SourceState {
+    pub(crate) fn from_u8(value: u8) -> Self {
+        match value {
+            1 => SourceState::Connecting,
+            2 => SourceState::Backoff,
+            3 => SourceState::Failed,
+            _ => SourceState::Connected,
+        }
+    }
+}
+
+/// How a supervised source is restarted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RestartPolicy {
+    /// Wait before the first restart; doubles after each failure in a row
+    pub backoff: Duration,
+    /// Cap on the wait between restarts
+    pub max_backoff: Duration,
+    /// Restarts tried without a datagram in between before the next failure is returned; 0 never gives up
+    pub limit: u32,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self { backoff: Duration::from_secs(1), max_backoff: Duration::from_secs(30), limit: 0 }
+    }
+}
+
+enum Stage<S, Fut> {
+    Up(S),
+    Backoff(Instant),
+    Opening(Pin<Box<Fut>>),
+}
+
+/// A source reopened by `open` whenever it fails
+pub struct SupervisedSource<S, F, Fut> {
+    name: String,
+    open: F,
+    stage: Stage<S, Fut>,
+    policy: RestartPolicy,
+    backoff: Duration,
+    /// Failures since the last datagram
+    failures: u32,
+    metrics: Arc<Metrics>,
+}
+
+impl<S, F, Fut> SupervisedSource<S, F, Fut>
+where
+    S: PacketSource,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = io::Result<S>>,
+{
+    /// Supervise the source `open` returns, opening it on the first receive; `name` labels its log lines
+    pub fn new(name: impl Into<String>, mut open: F, policy: RestartPolicy, metrics: Arc<Metrics>) -> Self {
+        metrics.source_state.store(SourceState::Connecting as u8, Ordering::Relaxed);
+        let stage = Stage::Opening(Box::pin(open()));
+        Self { name: name.into(), open, stage, policy, backoff: policy.backoff, failures: 0, metrics }
+    }
+
+    /// Start from `source`, already open, and only call `open` to restart it
+    pub fn with_source(mut self, source: S) -> Self {
+        self.stage = Stage::Up(source);
+        self.set_state(SourceState::Connected);
+        self
+    }
+
+    fn set_state(&self, state: SourceState) {
+        self.metrics.source_state.store(state as u8, Ordering::Relaxed);
+    }
+
+    /// Schedule a restart after `e`, or hand `e` back once the policy's limit is reached
+    fn fail(&mut self, e: io::Error) -> io::Result<()> {
+        self.failures += 1;
+        self.stage = Stage::Backoff(Instant::now() + self.backoff);
+        if self.policy.limit > 0 && self.failures > self.policy.limit {
+            error!(target: "source", "❌ {} source failed {} times in a row; giving up: {}", self.name, self.failures, e);
+            self.set_state(SourceState::Failed);
+            return Err(e);
+        }
+        warn!(target: "source", "⚠️  {} source failed: {}; restarting in {:?}", self.name, e, self.backoff);
+        self.set_state(SourceState::Backoff);
+        self.backoff = (self.backoff * 2).min(self.policy.max_backoff);
+        Ok(())
+    }
+}
+
+impl<S, F, Fut> PacketSource for SupervisedSource<S, F, Fut>
+where
+    S: PacketSource + Send,
+    F: FnMut() -> Fut + Send,
+    Fut: Future<Output = io::Result<S>> + Send,
+{
+    async fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        loop {
+            match &mut self.stage {
+                Stage::Up(source) => match source.recv_from(buf).await {
+                    Ok(received) => {
+                        if self.failures > 0 {
+                            self.failures = 0;
+                            self.backoff = self.policy.backoff;
+                        }
+                        return Ok(received);
+                    }
+                    Err(e) => self.fail(e)?,
+                },
+                Stage::Backoff(until) => {
+                    tokio::time::sleep_until(*until).await;
+                    self.stage = Stage::Opening(Box::pin((self.open)()));
+                    self.set_state(SourceState::Connecting);
+                    self.metrics.source_restarts.fetch_add(1, Ordering::Relaxed);
+                }
+                Stage::Opening(opening) => match opening.await {
+                    Ok(source) => {
+                        if self.failures > 0 {
+                            info!(target: "source", "✅ {} source restarted", self.name);
+                        }
+                        self.stage = Stage::Up(source);
+                        self.set_state(SourceState::Connected);
+                    }
+                    Err(e) => self.fail(e)?,
+                },
+            }
+        }
+    }
+
+    fn take_queue_stats(&mut self) -> Option<QueueStats> {
+        match &mut self.stage {
+            Stage::Up(source) => source.take_queue_stats(),
+            _ => None,
+        }
+    }
+}
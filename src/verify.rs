@@ -0,0 +1,265 @@
+//! Optional verification of sampled creates against an RPC node, to catch the detector drifting from pump.fun.
+//!
+//! One create in `sample_every` is queued with its transaction's first signature. Shreds arrive before the
+//! transaction is confirmed, so each waits out `delay` before a `getTransaction` fetches it. The create is confirmed
+//! when pump.fun's own `CreateEvent` for the mint is in the transaction's logs, and a false positive when the
+//! transaction succeeded with complete logs and no such event. The logs are the program's account of what it did,
+//! independent of the instruction decoding the detector relies on. Anything else settles nothing and is counted as
+//! unverified.
+//!
+//! Lookups run on the tokio runtime behind a bounded queue, so the recv loop only ever does a non-blocking enqueue;
+//! a sample that finds the queue full is dropped and counted.
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use serde_json::{json, Value};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use tokio::{
+    sync::{mpsc, Semaphore},
+    task::{JoinHandle, JoinSet},
+    time::Instant,
+};
+use tracing::{debug, warn};
+
+use crate::events::{self, PumpEvent};
+
+/// Verification settings
+#[derive(Debug, Clone)]
+pub struct VerifyConfig {
+    /// Solana JSON-RPC endpoint
+    pub rpc_url: String,
+    /// The program whose logs must show the create
+    pub program_id: Pubkey,
+    /// Verify one create in this many
+    pub sample_every: u64,
+    /// Wait after the detection before looking the transaction up
+    pub delay: Duration,
+    /// Per-lookup deadline
+    pub timeout: Duration,
+    /// Maximum lookups in flight at once
+    pub max_concurrency: usize,
+    /// Samples waiting out their delay before new ones are dropped
+    pub queue_capacity: usize,
+}
+
+/// Verification outcomes over a stats window
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct VerifyStats {
+    /// pump.fun logged a create of the mint
+    pub confirmed: u64,
+    /// The transaction succeeded with complete logs and pump.fun logged no create of the mint
+    pub false_positives: u64,
+    /// Lookups that settled nothing: the transaction wasn't found or failed, its logs were cut short, or the
+    /// lookup itself failed
+    pub unverified: u64,
+    /// Samples dropped at the full queue
+    pub dropped: u64,
+}
+
+impl VerifyStats {
+    /// Add `other`'s counters to these
+    pub fn accumulate(&mut self, other: &VerifyStats) {
+        self.confirmed += other.confirmed;
+        self.false_positives += other.false_positives;
+        self.unverified += other.unverified;
+        self.dropped += other.dropped;
+    }
+
+    /// Share of the settled lookups that were false positives; `None` before any settled
+    pub fn false_positive_rate(&self) -> Option<f64> {
+        match self.confirmed + self.false_positives {
+            0 => None,
+            settled => Some(self.false_positives as f64 / settled as f64),
+        }
+    }
+}
+
+/// What a looked-up transaction says about a create
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Confirmed,
+    FalsePositive,
+    /// The node doesn't have the transaction: its slot was skipped, or it isn't confirmed yet
+    NotFound,
+    /// The transaction failed, and was reverted along with its events
+    Failed,
+    /// The logs are missing or were truncated, so the event may just not be in them
+    Incomplete,
+}
+
+/// The verdict on a create of `mint` from `transaction`, a `getTransaction` result in the `json` encoding
+pub fn verdict(transaction: &Value, mint: &Pubkey, program_id: &Pubkey) -> Verdict {
+    if transaction.is_null() {
+        return Verdict::NotFound;
+    }
+    if transaction.pointer("/meta/err").is_some_and(|err| !err.is_null()) {
+        return Verdict::Failed;
+    }
+    let Some(logs) = transaction.pointer("/meta/logMessages").and_then(Value::as_array) else {
+        return Verdict::Incomplete;
+    };
+    let logs: Vec<String> = logs.iter().filter_map(Value::as_str).map(str::to_string).collect();
+    let created = events::parse_logs(&logs, program_id)
+        .iter()
+        .any(|event| matches!(event, PumpEvent::Create(create) if create.mint == *mint));
+    match created {
+        true => Verdict::Confirmed,
+        false if logs.iter().any(|line| line == "Log truncated") => Verdict::Incomplete,
+        false => Verdict::FalsePositive,
+    }
+}
+
+#[derive(Default)]
+struct VerifyCounters {
+    confirmed: AtomicU64,
+    false_positives: AtomicU64,
+    unverified: AtomicU64,
+    dropped: AtomicU64,
+}
+
+impl VerifyCounters {
+    fn load(&self, take: bool) -> VerifyStats {
+        let read = |counter: &AtomicU64| match take {
+            true => counter.swap(0, Ordering::Relaxed),
+            false => counter.load(Ordering::Relaxed),
+        };
+        VerifyStats {
+            confirmed: read(&self.confirmed),
+            false_positives: read(&self.false_positives),
+            unverified: read(&self.unverified),
+            dropped: read(&self.dropped),
+        }
+    }
+}
+
+/// A sampled create awaiting its lookup
+struct Sample {
+    signature: Signature,
+    mint: Pubkey,
+    due: Instant,
+}
+
+/// Queue in front of the verification task
+pub struct Verifier {
+    tx: mpsc::Sender<Sample>,
+    sample_every: u64,
+    delay: Duration,
+    /// Creates offered so far, sampled or not
+    creates: u64,
+    counters: Arc<VerifyCounters>,
+    task: JoinHandle<()>,
+}
+
+impl Verifier {
+    /// Start the verification task
+    pub fn spawn(config: VerifyConfig) -> reqwest::Result<Self> {
+        let client = reqwest::Client::builder().timeout(config.timeout).build()?;
+        let (tx, mut rx) = mpsc::channel::<Sample>(config.queue_capacity.max(1));
+        let permits = Arc::new(Semaphore::new(config.max_concurrency.max(1)));
+        let counters = Arc::new(VerifyCounters::default());
+        let (sample_every, delay, shared) = (config.sample_every.max(1), config.delay, counters.clone());
+
+        let task = tokio::spawn(async move {
+            let config = Arc::new(config);
+            let mut lookups = JoinSet::new();
+            while let Some(sample) = rx.recv().await {
+                tokio::time::sleep_until(sample.due).await;
+                let permit = permits.clone().acquire_owned().await.expect("semaphore is never closed");
+                let (client, config, counters) = (client.clone(), config.clone(), shared.clone());
+                lookups.spawn(async move {
+                    look_up(&client, &config, &counters, sample).await;
+                    drop(permit);
+                });
+                while lookups.try_join_next().is_some() {}
+            }
+        });
+
+        Ok(Self { tx, sample_every, delay, creates: 0, counters, task })
+    }
+
+    /// Offer the create of `mint` in transaction `signature`, queuing it for a lookup if it is sampled
+    pub fn submit(&mut self, signature: Signature, mint: Pubkey) {
+        self.creates += 1;
+        if !self.creates.is_multiple_of(self.sample_every) {
+            return;
+        }
+        let sample = Sample { signature, mint, due: Instant::now() + self.delay };
+        if self.tx.try_send(sample).is_err() {
+            self.counters.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Counters since the last call
+    pub fn take_stats(&self) -> VerifyStats {
+        self.counters.load(true)
+    }
+
+    /// Stop the task, abandoning samples still waiting out their delay and lookups in flight, and return the
+    /// counters since the last `take_stats`
+    pub async fn shutdown(self) -> VerifyStats {
+        drop(self.tx);
+        self.task.abort();
+        if let Err(e) = self.task.await {
+            if !e.is_cancelled() {
+                warn!(target: "detector", "Verification task failed: {}", e);
+            }
+        }
+        self.counters.load(false)
+    }
+}
+
+/// Look `sample` up and count the outcome
+async fn look_up(client: &reqwest::Client, config: &VerifyConfig, counters: &VerifyCounters, sample: Sample) {
+    let Sample { signature, mint, .. } = sample;
+    let lookup = tokio::time::timeout(config.timeout, fetch_transaction(client, &config.rpc_url, &signature));
+    let counter = match lookup.await {
+        Ok(Ok(transaction)) => match verdict(&transaction, &mint, &config.program_id) {
+            Verdict::Confirmed => &counters.confirmed,
+            Verdict::FalsePositive => {
+                warn!(target: "detector", "❌ Create of {} unconfirmed: transaction {} logged no create", mint, signature);
+                &counters.false_positives
+            }
+            other => {
+                debug!(target: "detector", "Create of {} unverified ({:?}): transaction {}", mint, other, signature);
+                &counters.unverified
+            }
+        },
+        Ok(Err(e)) => {
+            debug!(target: "detector", "Verification lookup failed for {}: {}", signature, e);
+            &counters.unverified
+        }
+        Err(_) => {
+            debug!(target: "detector", "Verification lookup timed out for {}", signature);
+            &counters.unverified
+        }
+    };
+    counter.fetch_add(1, Ordering::Relaxed);
+}
+
+async fn fetch_transaction(
+    client: &reqwest::Client,
+    rpc_url: &str,
+    signature: &Signature,
+) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "getTransaction",
+        "params": [
+            signature.to_string(),
+            {"encoding": "json", "commitment": "confirmed", "maxSupportedTransactionVersion": 0},
+        ],
+    });
+    let response: Value = client.post(rpc_url).json(&request).send().await?.error_for_status()?.json().await?;
+    if let Some(error) = response.get("error") {
+        return Err(format!("RPC error: {}", error).into());
+    }
+    // A null result means the node doesn't have the transaction
+    Ok(response.get("result").cloned().unwrap_or(Value::Null))
+}
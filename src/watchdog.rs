@@ -0,0 +1,261 @@
+//! Notices when packets stop arriving, and when the recv loop itself stops turning.
+//!
+//! Once nothing has been received for `threshold`, an alert is raised every
+//! `threshold` until traffic resumes. Alerts escalate from warnings to errors
+//! after [`ERROR_AFTER`] thresholds of silence.
+//!
+//! That check runs inside the recv loop, so it can't notice the loop itself
+//! wedged, e.g. by a decoder spinning on a pathological message. For that the
+//! loop records its [`LoopProgress`] on every turn, and a [`StallWatchdog`] on
+//! its own thread raises an error once the loop hasn't turned for its timeout.
+//! The thread runs a [`StallCheck`], which can be driven by hand with any clock.
+
+use std::{
+    io,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use tracing::{error, info, Span};
+
+use crate::clock::{Clock, SystemClock};
+
+/// Default silence before the first alert
+pub const DEFAULT_STALE_THRESHOLD: Duration = Duration::from_secs(30);
+
+/// Alerts become errors once the silence lasts this many thresholds
+pub const ERROR_AFTER: u32 = 4;
+
+/// How loud an alert is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Warn,
+    Error,
+}
+
+/// Packets haven't arrived for a while
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Alert {
+    /// Time since the last packet, or since startup if none arrived yet
+    pub silent_for: Duration,
+    /// Whether any packet has been received
+    pub ever_received: bool,
+    pub severity: Severity,
+}
+
+pub struct Watchdog {
+    threshold: Duration,
+    last_packet: Instant,
+    ever_received: bool,
+    /// Alerts raised in the current silence
+    alerts: u32,
+}
+
+impl Watchdog {
+    /// Alert after `threshold` without packets, counting from `now`
+    pub fn new(threshold: Duration, now: Instant) -> Self {
+        Self {
+            threshold: threshold.max(Duration::from_millis(1)),
+            last_packet: now,
+            ever_received: false,
+            alerts: 0,
+        }
+    }
+
+    /// Record a packet, returning how long the silence lasted if it had been alerted on
+    pub fn packet(&mut self, now: Instant) -> Option<Duration> {
+        let silence = now.saturating_duration_since(self.last_packet);
+        let recovered = (self.alerts > 0).then_some(silence);
+        self.last_packet = now;
+        self.ever_received = true;
+        self.alerts = 0;
+        recovered
+    }
+
+    /// Raise the next alert if one is due
+    pub fn poll(&mut self, now: Instant) -> Option<Alert> {
+        let silent_for = now.saturating_duration_since(self.last_packet);
+        if silent_for < self.threshold * (self.alerts + 1) {
+            return None;
+        }
+        self.alerts = (silent_for.as_nanos() / self.threshold.as_nanos()) as u32;
+        Some(Alert {
+            silent_for,
+            ever_received: self.ever_received,
+            severity: match self.alerts >= ERROR_AFTER {
+                true => Severity::Error,
+                false => Severity::Warn,
+            },
+        })
+    }
+}
+
+/// What the stall watchdog does about a recv loop stuck past its timeout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StallAction {
+    /// Log an error, and the recovery if the loop comes back
+    Log,
+    /// Log an error, then abort the process so a supervisor restarts it
+    Abort,
+}
+
+impl FromStr for StallAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "log" => Ok(StallAction::Log),
+            "abort" => Ok(StallAction::Abort),
+            other => Err(format!("expected log or abort, got {}", other)),
+        }
+    }
+}
+
+/// How far the recv loop has got, written by the loop and read by the stall watchdog's thread
+#[derive(Debug)]
+pub struct LoopProgress<C: Clock = SystemClock> {
+    clock: C,
+    started: Instant,
+    /// Milliseconds from `started` to the loop's last turn
+    last_turn_ms: AtomicU64,
+    /// Sequence number of the message being scanned, 0 between messages
+    scanning: AtomicU64,
+}
+
+impl Default for LoopProgress {
+    fn default() -> Self {
+        Self::with_clock(SystemClock)
+    }
+}
+
+impl<C: Clock> LoopProgress<C> {
+    /// Progress with turns timed by `clock`, counting from now
+    pub fn with_clock(clock: C) -> Self {
+        let started = clock.now();
+        Self { clock, started, last_turn_ms: AtomicU64::new(0), scanning: AtomicU64::new(0) }
+    }
+
+    /// Record a turn of the loop
+    pub fn turn(&self) {
+        let elapsed = self.clock.now().saturating_duration_since(self.started);
+        self.last_turn_ms.store(elapsed.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    /// Record that message `msg_seq` is being scanned, or with 0 that the scan is over
+    pub fn scanning(&self, msg_seq: u64) {
+        self.scanning.store(msg_seq, Ordering::Relaxed);
+    }
+
+    /// Time from the last turn to `now`
+    fn idle_for(&self, now: Instant) -> Duration {
+        let last_turn = Duration::from_millis(self.last_turn_ms.load(Ordering::Relaxed));
+        now.saturating_duration_since(self.started).saturating_sub(last_turn)
+    }
+}
+
+/// A change in whether the recv loop is stuck
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StallEvent {
+    /// The loop hasn't turned for the timeout, while scanning the message with this sequence number, if any
+    Stalled { idle_for: Duration, scanning: Option<u64> },
+    /// The loop turned again after a stall
+    Recovered,
+}
+
+/// Whether a recv loop has stopped turning, checked whenever its owner asks
+pub struct StallCheck<C: Clock = SystemClock> {
+    progress: Arc<LoopProgress<C>>,
+    timeout: Duration,
+    stalled: bool,
+}
+
+impl<C: Clock> StallCheck<C> {
+    pub fn new(progress: Arc<LoopProgress<C>>, timeout: Duration) -> Self {
+        Self { progress, timeout, stalled: false }
+    }
+
+    /// Check the loop as of `now`, returning a stall as it starts, however long it lasts, and the recovery from it
+    pub fn check(&mut self, now: Instant) -> Option<StallEvent> {
+        let idle_for = self.progress.idle_for(now);
+        match (self.stalled, idle_for >= self.timeout) {
+            (false, true) => {
+                self.stalled = true;
+                let scanning = Some(self.progress.scanning.load(Ordering::Relaxed)).filter(|&msg_seq| msg_seq != 0);
+                Some(StallEvent::Stalled { idle_for, scanning })
+            }
+            (true, false) => {
+                self.stalled = false;
+                Some(StallEvent::Recovered)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Thread checking that a recv loop keeps turning; stops when dropped
+pub struct StallWatchdog {
+    stop: Arc<AtomicBool>,
+    stalls: Arc<AtomicU64>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl StallWatchdog {
+    /// Check `progress` a few times per `timeout`, by its clock, acting on a loop that hasn't turned for that long.
+    /// Log lines are in the caller's span, so they name its pipeline
+    pub fn spawn<C: Clock + Send + Sync + 'static>(
+        progress: Arc<LoopProgress<C>>,
+        timeout: Duration,
+        action: StallAction,
+    ) -> io::Result<Self> {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stalls = Arc::new(AtomicU64::new(0));
+        let (stopped, counted, span) = (stop.clone(), stalls.clone(), Span::current());
+        let check_every = (timeout / 4).clamp(Duration::from_millis(1), Duration::from_secs(1));
+        progress.turn();
+        let mut check = StallCheck::new(progress.clone(), timeout);
+
+        let thread = std::thread::Builder::new().name("stall-watchdog".into()).spawn(move || {
+            let _span = span.enter();
+            while !stopped.load(Ordering::Relaxed) {
+                std::thread::park_timeout(check_every);
+                match check.check(progress.clock.now()) {
+                    Some(StallEvent::Stalled { idle_for, scanning }) => {
+                        counted.fetch_add(1, Ordering::Relaxed);
+                        let doing = match scanning {
+                            None => "outside any message scan".to_string(),
+                            Some(msg_seq) => format!("scanning message #{}", msg_seq),
+                        };
+                        error!("🧊 Recv loop stuck for {:.1}s, {}", idle_for.as_secs_f64(), doing);
+                        if action == StallAction::Abort {
+                            error!("🧊 Aborting so the process can be restarted (STALL_ACTION=abort)");
+                            std::process::abort();
+                        }
+                    }
+                    Some(StallEvent::Recovered) => info!("✅ Recv loop turning again"),
+                    None => {}
+                }
+            }
+        })?;
+        Ok(Self { stop, stalls, thread: Some(thread) })
+    }
+
+    /// Stalls noticed so far; one that lasts several timeouts counts once
+    pub fn stalls(&self) -> u64 {
+        self.stalls.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for StallWatchdog {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
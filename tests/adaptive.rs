@@ -0,0 +1,57 @@
+use std::time::{Duration, Instant};
+
+use solana_sdk::pubkey::Pubkey;
+use test_shreds::{
+    adaptive::{AdaptiveVerbosity, LogMode},
+    detector::Detection,
+};
+
+fn create() -> Detection {
+    Detection::Create {
+        mint: Pubkey::new_unique(),
+        bonding_curve: Pubkey::new_unique(),
+        creator: Pubkey::new_unique(),
+        associated_bonding_curve: None,
+        metadata: None,
+        accounts: None,
+        compute_budget: None,
+        token_accounts: Vec::new(),
+        event: None,
+    }
+}
+
+/// Record `n` detections, then close a one-second bucket
+fn bucket(verbosity: &mut AdaptiveVerbosity, now: &mut Instant, n: usize) -> Option<LogMode> {
+    for _ in 0..n {
+        verbosity.record(&create());
+    }
+    *now += Duration::from_secs(1);
+    verbosity.poll(*now).expect("bucket elapsed").switched_to
+}
+
+#[test]
+fn switches_with_hysteresis() {
+    let mut now = Instant::now();
+    let mut verbosity = AdaptiveVerbosity::new(10.0, 4.0, Duration::from_secs(1), now);
+
+    assert_eq!(bucket(&mut verbosity, &mut now, 9), None);
+    assert_eq!(bucket(&mut verbosity, &mut now, 10), Some(LogMode::Summary));
+    // Between the thresholds: stays in summary mode
+    assert_eq!(bucket(&mut verbosity, &mut now, 6), None);
+    assert_eq!(verbosity.mode(), LogMode::Summary);
+    assert_eq!(bucket(&mut verbosity, &mut now, 3), Some(LogMode::PerEvent));
+    assert_eq!(verbosity.mode(), LogMode::PerEvent);
+}
+
+#[test]
+fn buckets_close_only_when_due() {
+    let start = Instant::now();
+    let mut verbosity = AdaptiveVerbosity::new(10.0, 5.0, Duration::from_secs(1), start);
+    verbosity.record(&create());
+
+    assert!(verbosity.poll(start + Duration::from_millis(500)).is_none());
+    let closed = verbosity.poll(start + Duration::from_secs(2)).unwrap();
+    assert_eq!(closed.counts.creates, 1);
+    assert_eq!(closed.mode, LogMode::PerEvent);
+    assert!((closed.rate - 0.5).abs() < 1e-9);
+}
@@ -0,0 +1,171 @@
+//! Full runs over a simulated lossy, reordering network: the end-to-end counterpart to `fragment_ordering.rs`.
+
+mod common;
+
+use std::{
+    collections::{BTreeSet, HashSet},
+    io,
+    net::SocketAddr,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use common::{create_entries, Capture};
+use solana_sdk::pubkey::Pubkey;
+use test_shreds::{
+    clock::MockClock,
+    detector::{Detection, DetectorConfig, PUMPFUN_PROGRAM_ID},
+    pipeline::Pipeline,
+    reassembler::{fragment_message, FragmentReassembler, DEFAULT_MAX_FRAGMENT_AGE},
+    sink::{SinkHandle, Sinks},
+    source::MockSource,
+};
+
+/// Messages sent per run
+const MESSAGES: usize = 60;
+
+/// xorshift64*, so a seed always yields the same network
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// True with probability `p`
+    fn chance(&mut self, p: f64) -> bool {
+        ((self.next() >> 11) as f64) < p * (1u64 << 53) as f64
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+/// What happens to fragments on the way
+struct Network {
+    /// Probability of each fragment being lost
+    loss: f64,
+    /// How far ahead of its place a fragment can arrive
+    reorder: usize,
+    seed: u64,
+}
+
+/// What arrived, and which messages lost fragments
+struct Delivery {
+    /// Fragments in arrival order
+    fragments: Vec<Vec<u8>>,
+    /// Messages that lost every fragment, so the receiver never sees them
+    vanished: BTreeSet<usize>,
+    /// Messages that lost some fragments but not all, so they start reassembling and expire
+    broken: BTreeSet<usize>,
+}
+
+impl Network {
+    /// Send `sent`, each message's fragments tagged with its index
+    fn transmit(&self, sent: Vec<(usize, Vec<u8>)>) -> Delivery {
+        let mut rng = Rng::new(self.seed);
+        let mut lost = BTreeSet::new();
+        let mut received = BTreeSet::new();
+        let mut fragments = Vec::new();
+        for (message, fragment) in sent {
+            if rng.chance(self.loss) {
+                lost.insert(message);
+            } else {
+                received.insert(message);
+                fragments.push(fragment);
+            }
+        }
+        // Each fragment swaps with one up to `reorder` places later, a bounded shuffle like a congested path's
+        for i in 0..fragments.len() {
+            let j = (i + rng.below(self.reorder + 1)).min(fragments.len() - 1);
+            fragments.swap(i, j);
+        }
+        let vanished = lost.difference(&received).copied().collect();
+        let broken = lost.intersection(&received).copied().collect();
+        Delivery { fragments, vanished, broken }
+    }
+}
+
+/// Send `MESSAGES` creates over `network` through a full pipeline, checking that exactly the intact ones are
+/// detected and the broken ones expire
+async fn run_over(network: Network) -> Delivery {
+    let clock = MockClock::new();
+    let config = DetectorConfig::new(Pubkey::from_str(PUMPFUN_PROGRAM_ID).unwrap(), false, None);
+    let reassembler = FragmentReassembler::with_clock(clock.clone(), DEFAULT_MAX_FRAGMENT_AGE);
+    let captured = Arc::new(Mutex::new(Vec::new()));
+    let mut sinks = Sinks::default();
+    sinks.push(SinkHandle::spawn("capture", Capture(captured.clone()), MESSAGES).unwrap());
+    let mut pipeline = Pipeline::new(config, reassembler, clock.clone()).with_outputs(Arc::new(sinks), None);
+    let metrics = pipeline.metrics();
+
+    let mints: Vec<Pubkey> = (0..MESSAGES).map(|_| Pubkey::new_unique()).collect();
+    let sent = mints.iter().enumerate().flat_map(|(i, mint)| {
+        fragment_message(i as u32 + 1, &create_entries(*mint), 200).into_iter().map(move |fragment| (i, fragment))
+    });
+    let delivery = network.transmit(sent.collect());
+
+    let from: SocketAddr = "127.0.0.1:9000".parse().unwrap();
+    let mut source = MockSource::new(clock.clone());
+    for fragment in &delivery.fragments {
+        source.push(Duration::from_millis(1), fragment.clone(), from);
+    }
+    // Unfragmented and undecodable; moves the clock past the fragment age so cleanup expires the broken messages
+    source.push(DEFAULT_MAX_FRAGMENT_AGE * 2, b"noise".to_vec(), from);
+    let err = pipeline.run(&mut source, std::future::pending()).await.unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    let reassembler = pipeline.finish().await;
+
+    let lossy: HashSet<usize> = delivery.vanished.union(&delivery.broken).copied().collect();
+    let intact: HashSet<Pubkey> = (0..MESSAGES).filter(|i| !lossy.contains(i)).map(|i| mints[i]).collect();
+    let detected: Vec<Pubkey> = captured
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|event| match event.detection {
+            Detection::Create { mint, .. } => mint,
+            ref other => panic!("unexpected detection {:?}", other),
+        })
+        .collect();
+    assert_eq!(detected.len(), intact.len(), "an intact message was missed or one detected twice");
+    assert_eq!(detected.into_iter().collect::<HashSet<_>>(), intact);
+
+    let counts = metrics.snapshot();
+    assert_eq!(counts.creates as usize, intact.len());
+    assert_eq!(counts.reassembled as usize, intact.len());
+    assert_eq!(counts.expired as usize, delivery.broken.len());
+    // The noise is the only message that fails to decode
+    assert_eq!((counts.messages as usize, counts.undecodable), (intact.len() + 1, 1));
+    assert_eq!(reassembler.pending(), 0);
+    delivery
+}
+
+#[tokio::test]
+async fn detects_every_message_through_reordering_alone() {
+    for seed in 0..4 {
+        let delivery = run_over(Network { loss: 0.0, reorder: 16, seed }).await;
+        assert!(delivery.vanished.is_empty() && delivery.broken.is_empty());
+    }
+}
+
+#[tokio::test]
+async fn detects_intact_messages_and_expires_broken_ones_under_loss_and_reordering() {
+    for seed in 0..4 {
+        let delivery = run_over(Network { loss: 0.05, reorder: 8, seed }).await;
+        assert!(!delivery.broken.is_empty(), "seed {} lost no fragments; the run proves nothing", seed);
+    }
+}
+
+#[tokio::test]
+async fn survives_heavy_loss() {
+    let delivery = run_over(Network { loss: 0.4, reorder: 32, seed: 7 }).await;
+    assert!(!delivery.vanished.is_empty() && !delivery.broken.is_empty());
+}
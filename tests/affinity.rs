@@ -0,0 +1,15 @@
+use test_shreds::affinity::parse_cpu_list;
+
+#[test]
+fn parses_cores_and_ranges() {
+    assert_eq!(parse_cpu_list("2").unwrap(), vec![2]);
+    assert_eq!(parse_cpu_list("0, 2,4-6").unwrap(), vec![0, 2, 4, 5, 6]);
+}
+
+#[test]
+fn rejects_malformed_lists() {
+    assert!(parse_cpu_list("").is_err());
+    assert!(parse_cpu_list("a").is_err());
+    assert!(parse_cpu_list("5-3").is_err());
+    assert!(parse_cpu_list("1-").is_err());
+}
@@ -0,0 +1,50 @@
+use std::net::IpAddr;
+
+use test_shreds::allowlist::{parse_ranges, Admission, SourceAllowList, MAX_REMEMBERED_REJECTED};
+
+fn ip(s: &str) -> IpAddr {
+    s.parse().unwrap()
+}
+
+#[test]
+fn allows_exact_addresses_and_cidr_ranges() {
+    let mut list = SourceAllowList::new(parse_ranges("10.0.0.5, 192.168.1.0/24,2001:db8::/32").unwrap());
+
+    assert_eq!(list.check(ip("10.0.0.5")), Admission::Allowed);
+    assert_eq!(list.check(ip("192.168.1.200")), Admission::Allowed);
+    assert_eq!(list.check(ip("2001:db8::1")), Admission::Allowed);
+    // A dual-stack socket reports IPv4 senders as mapped IPv6 addresses
+    assert_eq!(list.check(ip("::ffff:10.0.0.5")), Admission::Allowed);
+
+    assert_eq!(list.check(ip("10.0.0.6")), Admission::Rejected { first: true });
+    assert_eq!(list.check(ip("10.0.0.6")), Admission::Rejected { first: false });
+    assert_eq!(list.check(ip("192.168.2.1")), Admission::Rejected { first: true });
+    assert_eq!(list.check(ip("2001:db9::1")), Admission::Rejected { first: true });
+    assert_eq!(list.rejected_sources(), 3);
+}
+
+#[test]
+fn a_zero_prefix_matches_its_whole_family() {
+    let mut list = SourceAllowList::new(parse_ranges("0.0.0.0/0").unwrap());
+    assert_eq!(list.check(ip("203.0.113.9")), Admission::Allowed);
+    assert_eq!(list.check(ip("2001:db8::1")), Admission::Rejected { first: true });
+}
+
+#[test]
+fn rejects_malformed_ranges() {
+    assert!(parse_ranges("").is_err());
+    assert!(parse_ranges("10.0.0.0/33").is_err());
+    assert!(parse_ranges("10.0.0/8").is_err());
+    assert!(parse_ranges("proxy.local").is_err());
+    assert_eq!(parse_ranges("2001:db8::/128").unwrap()[0].to_string(), "2001:db8::/128");
+}
+
+#[test]
+fn stops_reporting_new_sources_past_the_cap() {
+    let mut list = SourceAllowList::new(parse_ranges("10.0.0.1").unwrap());
+    for i in 0..MAX_REMEMBERED_REJECTED as u32 {
+        assert_eq!(list.check(IpAddr::from((0xac10_0000 + i).to_be_bytes())), Admission::Rejected { first: true });
+    }
+    assert_eq!(list.check(ip("203.0.113.9")), Admission::Rejected { first: false });
+    assert_eq!(list.rejected_sources(), MAX_REMEMBERED_REJECTED);
+}
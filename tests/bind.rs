@@ -0,0 +1,27 @@
+#![cfg(target_os = "linux")]
+
+use std::io;
+
+use test_shreds::source::bind_udp;
+use tokio::net::UdpSocket;
+
+#[tokio::test]
+async fn binds_to_an_interface() {
+    let socket = match bind_udp("127.0.0.1:0", Some("lo")).await {
+        Ok(socket) => socket,
+        // Kernels before 5.7 only let privileged processes bind to a device
+        Err(e) if e.kind() == io::ErrorKind::PermissionDenied => return,
+        Err(e) => panic!("{}", e),
+    };
+    let sender = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    sender.send_to(b"shred", socket.local_addr().unwrap()).await.unwrap();
+    let mut buf = [0u8; 16];
+    let (len, from) = socket.recv_from(&mut buf).await.unwrap();
+    assert_eq!((&buf[..len], from), (&b"shred"[..], sender.local_addr().unwrap()));
+}
+
+#[tokio::test]
+async fn reports_a_missing_interface() {
+    let err = bind_udp("127.0.0.1:0", Some("nosuchif0")).await.unwrap_err();
+    assert!(matches!(err.kind(), io::ErrorKind::NotFound | io::ErrorKind::PermissionDenied), "{}", err);
+}
@@ -0,0 +1,89 @@
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use solana_sdk::pubkey::Pubkey;
+use test_shreds::{
+    detector::Detection,
+    sink::{
+        breaker::{BreakerConfig, BreakerSink, BreakerState, CircuitBreaker},
+        DetectionEvent, Sink,
+    },
+};
+
+#[test]
+fn opens_after_threshold_and_probes_after_cooldown() {
+    let start = Instant::now();
+    let mut breaker = CircuitBreaker::new(3, Duration::from_secs(10));
+
+    breaker.failure(start);
+    breaker.failure(start);
+    breaker.success();
+    breaker.failure(start);
+    breaker.failure(start);
+    assert_eq!(breaker.state(), BreakerState::Closed);
+    breaker.failure(start);
+    assert_eq!(breaker.state(), BreakerState::Open { until: start + Duration::from_secs(10) });
+    assert!(!breaker.allow(start + Duration::from_secs(9)));
+
+    // A failed probe reopens at once
+    assert!(breaker.allow(start + Duration::from_secs(10)));
+    assert_eq!(breaker.state(), BreakerState::HalfOpen);
+    breaker.failure(start + Duration::from_secs(10));
+    assert!(!breaker.allow(start + Duration::from_secs(19)));
+
+    assert!(breaker.allow(start + Duration::from_secs(20)));
+    breaker.success();
+    assert_eq!(breaker.state(), BreakerState::Closed);
+}
+
+/// Fails every write while `failing` is set, counting attempts
+struct Flaky {
+    failing: Arc<AtomicBool>,
+    attempts: Arc<AtomicU32>,
+}
+
+impl Sink for Flaky {
+    fn write(&mut self, _: &DetectionEvent) -> io::Result<()> {
+        self.attempts.fetch_add(1, Ordering::Relaxed);
+        match self.failing.load(Ordering::Relaxed) {
+            true => Err(io::Error::new(io::ErrorKind::ConnectionRefused, "down")),
+            false => Ok(()),
+        }
+    }
+}
+
+#[test]
+fn retries_then_drops_while_open() {
+    let failing = Arc::new(AtomicBool::new(true));
+    let attempts = Arc::new(AtomicU32::new(0));
+    let config = BreakerConfig {
+        failure_threshold: 2,
+        open_for: Duration::from_secs(3600),
+        max_retries: 1,
+        initial_backoff: Duration::ZERO,
+        max_backoff: Duration::ZERO,
+    };
+    let flaky = Flaky { failing: failing.clone(), attempts: attempts.clone() };
+    let mut sink = BreakerSink::new("flaky", flaky, config);
+    let event = DetectionEvent::new(
+        Detection::Migration { mint: Pubkey::new_unique(), pool: Pubkey::new_unique(), from_pumpfun: true },
+        1,
+    );
+
+    assert!(sink.write(&event).is_err());
+    assert_eq!(attempts.load(Ordering::Relaxed), 2);
+    assert_eq!(sink.state(), BreakerState::Closed);
+    assert!(sink.write(&event).is_err());
+    assert!(matches!(sink.state(), BreakerState::Open { .. }));
+
+    // Open: not attempted, even once the destination is back
+    failing.store(false, Ordering::Relaxed);
+    sink.write(&event).unwrap();
+    assert_eq!(attempts.load(Ordering::Relaxed), 4);
+}
@@ -0,0 +1,67 @@
+use std::{fs::File, net::SocketAddr, path::Path};
+
+use test_shreds::{
+    capture::{CaptureConfig, PacketCapture},
+    pcap::PcapReader,
+    reassembler::FragmentReassembler,
+};
+
+fn config(name: &str) -> CaptureConfig {
+    let dir = std::env::temp_dir().join(format!("test_shreds_{}_{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    CaptureConfig {
+        path: dir.join("capture.pcap"),
+        sample_every: 0,
+        on_failure: false,
+        max_packets: 1000,
+        max_bytes: 1_000_000,
+    }
+}
+
+fn read_back(path: &Path) -> Vec<Vec<u8>> {
+    let mut reader = PcapReader::new(File::open(path).unwrap()).unwrap();
+    let mut payloads = Vec::new();
+    while let Some(packet) = reader.next_packet().unwrap() {
+        payloads.push(packet.payload);
+    }
+    assert_eq!(reader.skipped, 0);
+    payloads
+}
+
+#[test]
+fn samples_every_nth_datagram_and_replays_failed_messages() {
+    let src: SocketAddr = "10.0.0.1:9000".parse().unwrap();
+    let mut capture = PacketCapture::create(CaptureConfig { sample_every: 3, on_failure: true, ..config("capture") })
+        .unwrap();
+    for i in 0..7u8 {
+        capture.packet(&[i; 10], src);
+    }
+    // Bigger than one datagram, so it's written in fragments
+    let failed: Vec<u8> = (0..5000).map(|i| i as u8).collect();
+    capture.failed(&failed, "[::1]:9000".parse().unwrap());
+    let path = capture.path().to_path_buf();
+    let stats = capture.finish();
+    assert_eq!((stats.sampled, stats.failed_messages, stats.packets), (2, 1, 7));
+    assert_eq!(std::fs::metadata(&path).unwrap().len(), stats.bytes);
+
+    let payloads = read_back(&path);
+    assert_eq!(payloads[..2], [vec![2; 10], vec![5; 10]]);
+    let mut reassembler = FragmentReassembler::new();
+    let reassembled: Vec<_> = payloads[2..].iter().filter_map(|p| reassembler.process_packet(p)).collect();
+    assert_eq!(reassembled, vec![failed]);
+}
+
+#[test]
+fn stops_at_the_packet_bound() {
+    let src: SocketAddr = "10.0.0.1:9000".parse().unwrap();
+    let config = CaptureConfig { sample_every: 1, on_failure: true, max_packets: 3, ..config("capture_bound") };
+    let mut capture = PacketCapture::create(config).unwrap();
+    for i in 0..5u8 {
+        capture.packet(&[i; 10], src);
+    }
+    // Once full, nothing more is written, failures included
+    capture.failed(&[0; 10], src);
+    let path = capture.path().to_path_buf();
+    assert_eq!(capture.finish().packets, 3);
+    assert_eq!(read_back(&path).len(), 3);
+}
@@ -0,0 +1,36 @@
+use std::time::{Duration, Instant};
+
+use solana_sdk::pubkey::Pubkey;
+use test_shreds::coalesce::BuyCoalescer;
+
+#[test]
+fn aggregates_buys_until_window_closes() {
+    let start = Instant::now();
+    let mint = Pubkey::new_unique();
+    let mut coalescer = BuyCoalescer::new(Duration::from_millis(500), 16);
+
+    assert_eq!(coalescer.push(mint, 1_000, start), None);
+    assert_eq!(coalescer.push(mint, 2_000, start + Duration::from_millis(100)), None);
+    assert!(coalescer.flush_expired(start + Duration::from_millis(499)).is_empty());
+
+    let flushed = coalescer.flush_expired(start + Duration::from_millis(500));
+    assert_eq!(flushed.len(), 1);
+    assert_eq!(flushed[0].count, 2);
+    assert_eq!(flushed[0].total_sol_cost, 3_000);
+    assert_eq!(coalescer.pending(), 0);
+}
+
+#[test]
+fn evicts_oldest_mint_when_full() {
+    let start = Instant::now();
+    let (first, second, third) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let mut coalescer = BuyCoalescer::new(Duration::from_secs(1), 2);
+
+    coalescer.push(first, 1, start);
+    coalescer.push(second, 1, start + Duration::from_millis(1));
+    let evicted = coalescer.push(third, 1, start + Duration::from_millis(2)).unwrap();
+
+    assert_eq!(evicted.mint, first);
+    assert_eq!(coalescer.pending(), 2);
+    assert_eq!(coalescer.flush_all().len(), 2);
+}
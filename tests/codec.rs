@@ -0,0 +1,78 @@
+use solana_sdk::pubkey::Pubkey;
+use test_shreds::{
+    codec::{decode, encode, CodecError, FORMAT_VERSION},
+    detector::{Detection, InstructionKind},
+    enrich::BondingCurveState,
+    sink::DetectionEvent,
+};
+
+fn event(detection: Detection) -> DetectionEvent {
+    DetectionEvent {
+        timestamp_ms: 1_760_000_000_000,
+        msg_seq: 42,
+        detection,
+        bonding_curve_state: None,
+        context: None,
+        status: None,
+        detection_id: None,
+        late: false,
+        score: None,
+        usd_value: None,
+        emitted_at: None,
+    }
+}
+
+#[test]
+fn round_trips_every_kind() {
+    let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let mut create = event(Detection::Create { mint: a, bonding_curve: b, creator: c, associated_bonding_curve: None, metadata: None, accounts: None, compute_budget: None, token_accounts: Vec::new(), event: None });
+    create.bonding_curve_state = Some(BondingCurveState {
+        virtual_token_reserves: 1,
+        virtual_sol_reserves: 2,
+        real_token_reserves: 3,
+        real_sol_reserves: 4,
+        token_total_supply: 5,
+        complete: false,
+    });
+    let events = [
+        create,
+        event(Detection::Buy { mint: a, buyer: b, token_amount: 7, max_sol_cost: 8, event: None }),
+        event(Detection::Migration { mint: a, pool: c, from_pumpfun: true }),
+        event(Detection::SlotComplete { slot: 312_345_678, detections: 2 }),
+        event(Detection::MintInit { mint: a, decimals: 9, authority: b, token_2022: true }),
+        event(Detection::Unknown { program: c, discriminator: vec![1, 2, 3], data_hex: "010203".into(), data_len: 300 }),
+        event(Detection::Maintenance { instruction: InstructionKind::CloseUserVolumeAccumulator, account: b, user: c }),
+        event(Detection::CreateWithDevBuy {
+            mint: a,
+            bonding_curve: b,
+            creator: c,
+            dev_buy_sol: 1_500_000_000,
+            dev_buy_tokens: u64::MAX,
+            associated_bonding_curve: None,
+            metadata: None,
+            accounts: None,
+            compute_budget: None,
+            token_accounts: Vec::new(),
+            event: None,
+        }),
+    ];
+
+    for original in events {
+        let frame = encode(&original);
+        assert_eq!(frame[0], FORMAT_VERSION);
+        let decoded = decode(&frame).unwrap();
+        assert_eq!(decoded.detection, original.detection);
+        assert_eq!(decoded.bonding_curve_state, original.bonding_curve_state);
+        assert_eq!((decoded.timestamp_ms, decoded.msg_seq), (original.timestamp_ms, original.msg_seq));
+    }
+}
+
+#[test]
+fn rejects_unknown_versions_and_truncation() {
+    let key = Pubkey::new_unique();
+    let mut frame = encode(&event(Detection::Migration { mint: key, pool: key, from_pumpfun: false }));
+
+    assert_eq!(decode(&frame[..frame.len() - 1]).unwrap_err(), CodecError::Truncated);
+    frame[0] = FORMAT_VERSION + 1;
+    assert_eq!(decode(&frame).unwrap_err(), CodecError::UnsupportedVersion(FORMAT_VERSION + 1));
+}
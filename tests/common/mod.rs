@@ -0,0 +1,105 @@
+//! Fixtures shared by the integration tests; each test crate uses only some of them.
+#![allow(dead_code)]
+
+use std::{
+    io,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+use solana_entry::entry::Entry;
+use solana_sdk::{
+    hash::Hash,
+    instruction::{AccountMeta, Instruction},
+    message::Message,
+    pubkey::Pubkey,
+    transaction::{Transaction, VersionedTransaction},
+};
+use test_shreds::{
+    detector::{BUY_DISC, CREATE_DISC, PUMPFUN_PROGRAM_ID},
+    events::CREATE_EVENT_DISC,
+    sink::{DetectionEvent, Sink},
+};
+
+pub fn program_id() -> Pubkey {
+    Pubkey::from_str(PUMPFUN_PROGRAM_ID).unwrap()
+}
+
+/// Collects every event it is handed
+pub struct Capture(pub Arc<Mutex<Vec<DetectionEvent>>>);
+
+impl Sink for Capture {
+    fn write(&mut self, event: &DetectionEvent) -> io::Result<()> {
+        self.0.lock().unwrap().push(event.clone());
+        Ok(())
+    }
+}
+
+/// A serialized entry holding one pumpfun create for `mint`
+pub fn create_entries(mint: Pubkey) -> Vec<u8> {
+    let mut accounts: Vec<AccountMeta> = (0..8).map(|_| AccountMeta::new(Pubkey::new_unique(), false)).collect();
+    accounts[0] = AccountMeta::new(mint, true);
+    let ix = Instruction::new_with_bytes(program_id(), &CREATE_DISC, accounts);
+    let tx = Transaction::new_unsigned(Message::new(&[ix], Some(&Pubkey::new_unique())));
+    let entries = vec![Entry {
+        num_hashes: 1,
+        hash: Hash::default(),
+        transactions: vec![VersionedTransaction::from(tx)],
+    }];
+    bincode::serialize(&entries).unwrap()
+}
+
+/// A serialized entry holding one pumpfun buy of `mint`, spending at most `max_sol_cost` lamports
+pub fn buy_entries(mint: Pubkey, max_sol_cost: u64) -> Vec<u8> {
+    let mut accounts: Vec<AccountMeta> = (0..8).map(|_| AccountMeta::new(Pubkey::new_unique(), false)).collect();
+    accounts[2] = AccountMeta::new(mint, false);
+    let data = [&BUY_DISC[..], &1_000u64.to_le_bytes(), &max_sol_cost.to_le_bytes()].concat();
+    let ix = Instruction::new_with_bytes(program_id(), &data, accounts);
+    let tx = Transaction::new_unsigned(Message::new(&[ix], Some(&Pubkey::new_unique())));
+    let entries = vec![Entry {
+        num_hashes: 1,
+        hash: Hash::default(),
+        transactions: vec![VersionedTransaction::from(tx)],
+    }];
+    bincode::serialize(&entries).unwrap()
+}
+
+fn string(out: &mut Vec<u8>, s: &[u8]) {
+    out.extend_from_slice(&(s.len() as u32).to_le_bytes());
+    out.extend_from_slice(s);
+}
+
+/// A CreateEvent in the current layout; `legacy` stops after `user`, as programs before the creator-fee upgrade did
+pub fn create_event(mint: Pubkey, creator: Pubkey, legacy: bool) -> Vec<u8> {
+    create_event_named([b"Moon", b"MOON", b"https://example.com/moon.json"], mint, creator, legacy)
+}
+
+pub fn create_event_named(strings: [&[u8]; 3], mint: Pubkey, creator: Pubkey, legacy: bool) -> Vec<u8> {
+    let mut out = CREATE_EVENT_DISC.to_vec();
+    for s in strings {
+        string(&mut out, s);
+    }
+    for key in [mint, Pubkey::new_unique(), creator] {
+        out.extend_from_slice(key.as_ref());
+    }
+    if !legacy {
+        out.extend_from_slice(creator.as_ref());
+        out.extend_from_slice(&1_760_000_000i64.to_le_bytes());
+        for value in [1_073_000_000_000_000u64, 30_000_000_000, 793_100_000_000_000, 1_000_000_000_000_000] {
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+    out
+}
+
+/// Log lines of a top-level pumpfun instruction logging `events`
+pub fn pumpfun_logs(events: &[Vec<u8>]) -> Vec<String> {
+    let program = program_id();
+    let mut logs = vec![format!("Program {} invoke [1]", program), "Program log: Instruction: Create".to_string()];
+    logs.extend(events.iter().map(|e| format!("Program data: {}", BASE64.encode(e))));
+    logs.push(format!("Program {} consumed 52000 of 200000 compute units", program));
+    logs.push(format!("Program {} success", program));
+    logs
+}
@@ -0,0 +1,31 @@
+use std::time::{Duration, Instant};
+
+use solana_sdk::pubkey::Pubkey;
+use test_shreds::cooldown::CreatorCooldown;
+
+#[test]
+fn suppresses_creator_until_cooldown_passes() {
+    let start = Instant::now();
+    let (spammer, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut cooldown = CreatorCooldown::new(Duration::from_secs(60));
+
+    assert!(cooldown.allow(spammer, start));
+    assert!(!cooldown.allow(spammer, start + Duration::from_secs(30)));
+    assert!(cooldown.allow(other, start + Duration::from_secs(30)));
+    // Measured from the reported create, not the suppressed one
+    assert!(cooldown.allow(spammer, start + Duration::from_secs(60)));
+    assert!(!cooldown.allow(spammer, start + Duration::from_secs(61)));
+}
+
+#[test]
+fn prunes_expired_creators() {
+    let start = Instant::now();
+    let mut cooldown = CreatorCooldown::new(Duration::from_secs(10));
+    cooldown.allow(Pubkey::new_unique(), start);
+    cooldown.allow(Pubkey::new_unique(), start + Duration::from_secs(5));
+
+    cooldown.prune(start + Duration::from_secs(12));
+    assert_eq!(cooldown.tracked(), 1);
+    cooldown.prune(start + Duration::from_secs(15));
+    assert_eq!(cooldown.tracked(), 0);
+}
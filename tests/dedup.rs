@@ -0,0 +1,29 @@
+use std::time::{Duration, Instant};
+
+use test_shreds::dedup::DuplicateFilter;
+
+#[test]
+fn flags_repeats_within_window() {
+    let start = Instant::now();
+    let mut filter = DuplicateFilter::new(Duration::from_secs(1), 1024, start);
+
+    assert!(!filter.check(b"a", start));
+    assert!(!filter.check(b"b", start));
+    assert!(filter.check(b"a", start + Duration::from_millis(500)));
+    // Still remembered one generation later
+    assert!(filter.check(b"b", start + Duration::from_millis(1500)));
+    // Forgotten once two windows pass without it
+    assert!(!filter.check(b"a", start + Duration::from_secs(3)));
+}
+
+#[test]
+fn rolls_over_when_full() {
+    let start = Instant::now();
+    let mut filter = DuplicateFilter::new(Duration::from_secs(60), 2, start);
+
+    for packet in [b"a", b"b", b"c", b"d"] {
+        assert!(!filter.check(packet, start));
+    }
+    assert!(!filter.check(b"a", start));
+    assert!(filter.check(b"d", start));
+}
@@ -0,0 +1,784 @@
+use std::str::FromStr;
+
+use solana_entry::entry::Entry;
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction,
+    hash::Hash,
+    instruction::{AccountMeta, Instruction},
+    message::{Message, VersionedMessage},
+    pubkey::Pubkey,
+    transaction::{Transaction, VersionedTransaction},
+};
+use test_shreds::detector::{
+    compute_budget, decode_create_args, decode_initialize_mint, metadata_pda, parse_layouts, process_entries, ArgsError,
+    ComputeBudget, CreateArgs, Detection, DetectorConfig, InstructionKind, InstructionLayout, MigrationConfig,
+    ProcessError, Role, TokenAccount, ASSOCIATED_BONDING_CURVE_INDEX, ASSOCIATED_TOKEN_PROGRAM_ID, BUY_DISC,
+    CLOSE_USER_VOLUME_ACCUMULATOR_DISC, COLLECT_CREATOR_FEE_DISC, CREATE_DISC, CREATE_MIN_ACCOUNTS, CREATE_POOL_DISC,
+    EXTEND_ACCOUNT_DISC, GLOBAL_INDEX, METADATA_INDEX, PUMPFUN_PROGRAM_ID, PUMPSWAP_PROGRAM_ID, SELL_DISC,
+    TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID, WITHDRAW_DISC,
+};
+
+fn config() -> DetectorConfig {
+    DetectorConfig::new(Pubkey::from_str(PUMPFUN_PROGRAM_ID).unwrap(), false, None)
+}
+
+/// A serialized entry holding one pumpfun create referencing `accounts` accounts
+fn create_entries_with(accounts: usize) -> Vec<u8> {
+    let accounts = (0..accounts).map(|_| AccountMeta::new(Pubkey::new_unique(), false)).collect();
+    let ix = Instruction::new_with_bytes(config().program_id, &CREATE_DISC, accounts);
+    let tx = Transaction::new_unsigned(Message::new(&[ix], Some(&Pubkey::new_unique())));
+    let entries = vec![Entry {
+        num_hashes: 1,
+        hash: Hash::default(),
+        transactions: vec![VersionedTransaction::from(tx)],
+    }];
+    bincode::serialize(&entries).unwrap()
+}
+
+fn create_entries() -> Vec<u8> {
+    create_entries_with(CREATE_MIN_ACCOUNTS)
+}
+
+#[test]
+fn rejects_absurd_length_prefix() {
+    // One entry whose transaction count claims 2^40 elements
+    let mut data = 1u64.to_le_bytes().to_vec();
+    data.extend_from_slice(&1u64.to_le_bytes());
+    data.extend_from_slice(&[0; 32]);
+    data.extend_from_slice(&(1u64 << 40).to_le_bytes());
+    data.extend_from_slice(&[0xff; 64]);
+
+    assert!(process_entries(&data, &config(), 1).is_err());
+}
+
+#[test]
+fn decode_limit_bounds_valid_messages() {
+    let data = create_entries();
+    let mut config = config();
+
+    config.decode_limit = data.len() as u64;
+    let processed = process_entries(&data, &config, 1).unwrap();
+    assert_eq!(processed.detections.len(), 1);
+
+    config.decode_limit = data.len() as u64 / 2;
+    let limit = config.decode_limit;
+    assert!(matches!(process_entries(&data, &config, 2), Err(ProcessError::SizeLimit { limit: l }) if l == limit));
+}
+
+
+#[test]
+fn skips_create_with_too_few_accounts() {
+    let processed = process_entries(&create_entries_with(CREATE_MIN_ACCOUNTS - 1), &config(), 1).unwrap();
+    assert_eq!(processed.transactions, 1);
+    assert!(processed.detections.is_empty());
+}
+
+#[test]
+fn detects_create_in_fixture() {
+    // See tests/fixtures/README.md for what the fixture holds
+    let data = include_bytes!("fixtures/pumpfun_create.bin");
+
+    let processed = process_entries(data, &config(), 1).unwrap();
+    assert_eq!((processed.entries, processed.transactions), (3, 2));
+    assert_eq!(processed.detections, vec![Detection::Create {
+        mint: Pubkey::from_str("GmaDrppBC7P5ARKV8g3djiwP89vz1jLK23V2GBjuAEGB").unwrap(),
+        bonding_curve: Pubkey::from_str("62QBCZYVD5otiQP21Ls6tfVscDhuhD74Ri6zBMFpCTdo").unwrap(),
+        creator: Pubkey::from_str("J2xccRtuG43drESLYznHhLhQkLTdfepcKYbiQ9BsJVaf").unwrap(),
+        associated_bonding_curve: None,
+        metadata: None,
+        accounts: None,
+        compute_budget: Some(ComputeBudget { unit_limit: Some(250_000), unit_price: Some(1_000_000) }),
+        token_accounts: Vec::new(),
+        event: None,
+    }]);
+    assert_eq!(processed.oversized_args, 0);
+}
+
+#[test]
+fn ignores_trailing_bytes_after_entries() {
+    let mut data = include_bytes!("fixtures/pumpfun_create.bin").to_vec();
+    assert_eq!(process_entries(&data, &config(), 1).unwrap().trailing_bytes, 0);
+    data.extend_from_slice(b"footer from a newer proxy");
+
+    for config in [config(), partial_config()] {
+        let processed = process_entries(&data, &config, 1).unwrap();
+            assert_eq!(processed.failed_entry, None);
+        assert!(matches!(processed.detections[..], [Detection::Create { .. }]));
+        assert_eq!(processed.trailing_bytes, 25);
+    }
+}
+
+#[test]
+fn decodes_compute_budget() {
+    let payer = Pubkey::new_unique();
+    let message = |ixs: &[Instruction]| VersionedMessage::Legacy(Message::new(ixs, Some(&payer)));
+    let create = Instruction::new_with_bytes(config().program_id, &CREATE_DISC, vec![]);
+
+    assert_eq!(compute_budget(&message(std::slice::from_ref(&create))), None);
+    let price_only = message(&[ComputeBudgetInstruction::set_compute_unit_price(5_000), create.clone()]);
+    let budget = compute_budget(&price_only).unwrap();
+    assert_eq!((budget.unit_limit, budget.unit_price, budget.priority_fee()), (None, Some(5_000), None));
+
+    let both = ComputeBudget { unit_limit: Some(200_001), unit_price: Some(5_000) };
+    assert_eq!(both.priority_fee(), Some(1_001));
+
+    // A truncated argument is ignored rather than misread
+    let truncated = Instruction::new_with_bytes(solana_sdk::compute_budget::ID, &[2, 0x40], vec![]);
+    assert_eq!(compute_budget(&message(&[truncated, create])), None);
+}
+
+#[test]
+fn attaches_create_accounts_when_enabled() {
+    let data = include_bytes!("fixtures/pumpfun_create.bin");
+    let mut config = config();
+    config.create_accounts = true;
+
+    let processed = process_entries(data, &config, 1).unwrap();
+    let Detection::Create {
+        mint, bonding_curve, creator, associated_bonding_curve, metadata, accounts: Some(accounts), ..
+    } = &processed.detections[0]
+    else {
+        panic!("expected a create with accounts: {:?}", processed.detections);
+    };
+    assert!(accounts.len() >= CREATE_MIN_ACCOUNTS);
+    assert_eq!((&accounts[0], &accounts[2], &accounts[7]), (mint, bonding_curve, creator));
+    assert_eq!(associated_bonding_curve.as_ref(), Some(&accounts[ASSOCIATED_BONDING_CURVE_INDEX]));
+    assert_eq!(metadata.as_ref(), Some(&accounts[METADATA_INDEX]));
+}
+
+#[test]
+fn verifies_metadata_account_against_the_mints_pda() {
+    let mut config = config();
+    config.verify_metadata = true;
+
+    let fixture = process_entries(include_bytes!("fixtures/pumpfun_create.bin"), &config, 1).unwrap();
+    assert_eq!(fixture.metadata_mismatches, 0);
+    let Detection::Create { mint, .. } = &fixture.detections[0] else { panic!("expected a create") };
+    assert_eq!(metadata_pda(mint).to_string(), "F52VYq1r1F5wNYfD3QLLbPT1U9wvdFrf8mTNwUudiZrq");
+
+    // Random accounts: the create is still reported, and the mismatch counted
+    let processed = process_entries(&create_entries(), &config, 2).unwrap();
+    assert_eq!((processed.detections.len(), processed.metadata_mismatches), (1, 1));
+
+    // Without verification, nothing is derived or counted
+    config.verify_metadata = false;
+    assert_eq!(process_entries(&create_entries(), &config, 3).unwrap().metadata_mismatches, 0);
+}
+
+#[test]
+fn reports_global_account_when_enabled() {
+    let data = include_bytes!("fixtures/pumpfun_create.bin");
+    assert!(process_entries(data, &config(), 1).unwrap().globals.is_empty());
+
+    let mut config = config();
+    config.global_index = Some(GLOBAL_INDEX);
+    config.create_accounts = true;
+    let processed = process_entries(data, &config, 1).unwrap();
+    let Detection::Create { accounts: Some(accounts), .. } = &processed.detections[0] else {
+        panic!("expected a create with accounts: {:?}", processed.detections);
+    };
+    assert_eq!(processed.globals, vec![accounts[GLOBAL_INDEX]]);
+    assert_eq!(processed.globals[0].to_string(), "4wTV1YmiEkRvAtNtsSGPtUrqRYQMe5SKy2uB4Jjaxnjf");
+}
+
+#[test]
+fn skips_tick_only_messages() {
+    let tick = || Entry { num_hashes: 12_500, hash: Hash::new_unique(), transactions: vec![] };
+    let ticks: Vec<Entry> = (0..3).map(|_| tick()).collect();
+    let processed = process_entries(&bincode::serialize(&ticks).unwrap(), &config(), 1).unwrap();
+    assert!(processed.tick_only);
+    assert_eq!((processed.entries, processed.transactions, processed.detections.len()), (3, 0, 0));
+
+    let processed = process_entries(&create_entries(), &config(), 2).unwrap();
+    assert!(!processed.tick_only);
+    assert_eq!(processed.detections.len(), 1);
+}
+
+#[test]
+fn skips_degenerate_transactions() {
+    let mut entries: Vec<Entry> = bincode::deserialize(&create_entries()).unwrap();
+    let no_instructions = Transaction::new_unsigned(Message::new(&[], Some(&Pubkey::new_unique())));
+    entries[0].transactions.insert(0, VersionedTransaction::from(no_instructions));
+    entries[0].transactions.insert(0, VersionedTransaction::default());
+
+    let processed = process_entries(&bincode::serialize(&entries).unwrap(), &config(), 1).unwrap();
+    assert_eq!((processed.transactions, processed.malformed), (3, 2));
+    assert_eq!(processed.detections.len(), 1);
+}
+
+/// A pumpfun instruction of `disc` whose account at index 0 and 2 is `mint`, so creates and buys report it
+fn instruction(disc: &[u8; 8], mint: Pubkey) -> Instruction {
+    let mut accounts: Vec<AccountMeta> =
+        (0..CREATE_MIN_ACCOUNTS).map(|_| AccountMeta::new(Pubkey::new_unique(), false)).collect();
+    accounts[0] = AccountMeta::new(mint, false);
+    accounts[2] = AccountMeta::new(mint, false);
+    Instruction::new_with_bytes(config().program_id, &[disc.as_slice(), &[0; 16]].concat(), accounts)
+}
+
+#[test]
+fn consults_registered_decoders_after_built_in_ones() {
+    let launchpad = Pubkey::new_unique();
+    let launch_disc = [1, 2, 3, 4, 5, 6, 7, 8];
+    let mut config = config();
+    // Another launchpad's create: 0: mint, 1: creator
+    config.decoders.register(launchpad, launch_disc, |_: &[u8], accounts: &[Pubkey]| {
+        Some(Detection::Create {
+            mint: accounts[0],
+            bonding_curve: Pubkey::default(),
+            creator: *accounts.get(1)?,
+            associated_bonding_curve: None,
+            metadata: None,
+            accounts: None,
+            compute_budget: None,
+            token_accounts: Vec::new(),
+            event: None,
+        })
+    });
+    // Buys aren't watched, so a decoder can take them over
+    config.decoders.register(config.program_id, BUY_DISC, |_: &[u8], accounts: &[Pubkey]| {
+        Some(Detection::Migration { mint: accounts[2], pool: Pubkey::default(), from_pumpfun: true })
+    });
+    // Never consulted: pumpfun creates are built in
+    config.decoders.register(config.program_id, CREATE_DISC, |_: &[u8], _: &[Pubkey]| panic!("built-in shadowed"));
+
+    let (mint, creator, bought) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let launch = |disc: &[u8]| {
+        let accounts = vec![AccountMeta::new(mint, false), AccountMeta::new_readonly(creator, false)];
+        Instruction::new_with_bytes(launchpad, disc, accounts)
+    };
+    let ixs = [
+        launch(&launch_disc),
+        launch(&[9; 8]),
+        launch(&launch_disc[..4]),
+        instruction(&BUY_DISC, bought),
+        instruction(&CREATE_DISC, Pubkey::new_unique()),
+    ];
+    let tx = Transaction::new_unsigned(Message::new(&ixs, Some(&Pubkey::new_unique())));
+    let entries = vec![Entry { num_hashes: 1, hash: Hash::default(), transactions: vec![tx.into()] }];
+
+    let processed = process_entries(&bincode::serialize(&entries).unwrap(), &config, 1).unwrap();
+    assert_eq!(processed.detections.len(), 3);
+    let launched = &processed.detections[0];
+    assert!(matches!(launched, Detection::Create { mint: m, creator: c, .. } if *m == mint && *c == creator));
+    assert!(matches!(processed.detections[1], Detection::Migration { mint: m, .. } if m == bought));
+    assert!(matches!(processed.detections[2], Detection::Create { .. }));
+}
+
+#[test]
+fn attaches_token_accounts_created_for_the_mint() {
+    let (mint, other_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (dev, sniper) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let ata = |wallet: Pubkey, mint: Pubkey, data: &[u8]| {
+        let account = Pubkey::new_unique();
+        let accounts = [Pubkey::new_unique(), account, wallet, mint].map(|key| AccountMeta::new(key, false));
+        (account, Instruction::new_with_bytes(ASSOCIATED_TOKEN_PROGRAM_ID, data, accounts.to_vec()))
+    };
+    let (dev_ata, dev_ix) = ata(dev, mint, &[]);
+    let (sniper_ata, sniper_ix) = ata(sniper, mint, &[1]);
+    let (_, other_ix) = ata(sniper, other_mint, &[1]);
+    // Tag 2 is RecoverNested, not a creation
+    let (_, recover_ix) = ata(sniper, mint, &[2]);
+    let tx = |ixs: &[Instruction]| {
+        VersionedTransaction::from(Transaction::new_unsigned(Message::new(ixs, Some(&Pubkey::new_unique()))))
+    };
+    let transactions = vec![
+        tx(&[instruction(&CREATE_DISC, mint), dev_ix.clone(), dev_ix]),
+        tx(&[sniper_ix, other_ix, recover_ix]),
+    ];
+    let data = bincode::serialize(&vec![Entry { num_hashes: 1, hash: Hash::default(), transactions }]).unwrap();
+
+    let Detection::Create { token_accounts, .. } = &process_entries(&data, &config(), 1).unwrap().detections[0] else {
+        panic!("expected a create");
+    };
+    assert!(token_accounts.is_empty());
+
+    let mut config = config();
+    config.token_accounts = true;
+    let processed = process_entries(&data, &config, 1).unwrap();
+    assert_eq!(processed.detections.len(), 1);
+    let Detection::Create { token_accounts, .. } = &processed.detections[0] else {
+        panic!("expected a create: {:?}", processed.detections);
+    };
+    let expected = [TokenAccount { owner: dev, account: dev_ata }, TokenAccount { owner: sniper, account: sniper_ata }];
+    assert_eq!(token_accounts, &expected);
+}
+
+#[test]
+fn detections_follow_message_order() {
+    let mints: Vec<Pubkey> = (0..5).map(|_| Pubkey::new_unique()).collect();
+    let tx = |ixs: &[Instruction]| {
+        VersionedTransaction::from(Transaction::new_unsigned(Message::new(ixs, Some(&Pubkey::new_unique()))))
+    };
+    let entry = |transactions| Entry { num_hashes: 1, hash: Hash::default(), transactions };
+    let entries = vec![
+        entry(vec![
+            tx(&[instruction(&BUY_DISC, mints[0]), instruction(&CREATE_DISC, mints[1])]),
+            tx(&[instruction(&CREATE_DISC, mints[2])]),
+        ]),
+        entry(vec![tx(&[instruction(&CREATE_DISC, mints[3]), instruction(&BUY_DISC, mints[4])])]),
+    ];
+    let data = bincode::serialize(&entries).unwrap();
+    let config = DetectorConfig::new(config().program_id, true, None);
+
+    let detected: Vec<Pubkey> = process_entries(&data, &config, 1)
+        .unwrap()
+        .detections
+        .iter()
+        .filter_map(|d| match d {
+            Detection::Create { mint, .. }
+            | Detection::CreateWithDevBuy { mint, .. }
+            | Detection::Buy { mint, .. }
+            | Detection::Migration { mint, .. } => Some(*mint),
+            Detection::MintInit { .. }
+            | Detection::Unknown { .. }
+            | Detection::Maintenance { .. }
+            | Detection::Heartbeat { .. }
+            | Detection::SlotComplete { .. } => None,
+        })
+        .collect();
+    assert_eq!(detected, mints);
+}
+
+#[test]
+fn counts_admin_instructions_apart_from_detections() {
+    let instruction = |disc: &[u8]| {
+        let accounts = (0..CREATE_MIN_ACCOUNTS).map(|_| AccountMeta::new(Pubkey::new_unique(), false)).collect();
+        Instruction::new_with_bytes(config().program_id, disc, accounts)
+    };
+    let tx = Transaction::new_unsigned(Message::new(
+        &[instruction(&WITHDRAW_DISC), instruction(&COLLECT_CREATOR_FEE_DISC), instruction(&CREATE_DISC)],
+        Some(&Pubkey::new_unique()),
+    ));
+    let entries = vec![Entry { num_hashes: 1, hash: Hash::default(), transactions: vec![tx.into()] }];
+    let data = bincode::serialize(&entries).unwrap();
+    let mut config = config();
+
+    assert_eq!(process_entries(&data, &config, 1).unwrap().admin, 0);
+    config.watch(InstructionKind::Withdraw);
+    config.watch(InstructionKind::CollectCreatorFee);
+    assert!(config.watches(InstructionKind::Withdraw));
+    let processed = process_entries(&data, &config, 2).unwrap();
+    assert_eq!((processed.admin, processed.detections.len()), (2, 1));
+}
+
+#[test]
+fn reports_maintenance_instructions_as_informational_detections() {
+    let (account, user, volume_accumulator) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let instruction = |disc: &[u8], accounts: [Pubkey; 2]| {
+        let accounts = accounts.iter().map(|&key| AccountMeta::new(key, false)).collect();
+        Instruction::new_with_bytes(config().program_id, disc, accounts)
+    };
+    let tx = Transaction::new_unsigned(Message::new(
+        &[
+            instruction(&EXTEND_ACCOUNT_DISC, [account, user]),
+            instruction(&CLOSE_USER_VOLUME_ACCUMULATOR_DISC, [user, volume_accumulator]),
+        ],
+        Some(&user),
+    ));
+    let entries = vec![Entry { num_hashes: 1, hash: Hash::default(), transactions: vec![tx.into()] }];
+    let data = bincode::serialize(&entries).unwrap();
+    let mut config = config();
+
+    // Known by name, so not unknown either when they aren't watched
+    config.unknown_data_limit = Some(16);
+    assert!(process_entries(&data, &config, 1).unwrap().detections.is_empty());
+    config.watch(InstructionKind::ExtendAccount);
+    config.watch(InstructionKind::CloseUserVolumeAccumulator);
+    let processed = process_entries(&data, &config, 2).unwrap();
+    let expected = [
+        Detection::Maintenance { instruction: InstructionKind::ExtendAccount, account, user },
+        Detection::Maintenance {
+            instruction: InstructionKind::CloseUserVolumeAccumulator,
+            account: volume_accumulator,
+            user,
+        },
+    ];
+    assert_eq!((processed.detections.as_slice(), processed.admin), (&expected[..], 0));
+    let json = serde_json::to_value(&processed.detections[0]).unwrap();
+    assert_eq!((json["kind"].as_str(), json["instruction"].as_str()), (Some("maintenance"), Some("extend_account")));
+}
+
+#[test]
+fn folds_the_creators_buy_into_their_create() {
+    let (mint, creator, sniper) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let accounts = |keys: &[(usize, Pubkey)]| {
+        let mut accounts: Vec<AccountMeta> =
+            (0..CREATE_MIN_ACCOUNTS).map(|_| AccountMeta::new(Pubkey::new_unique(), false)).collect();
+        for &(i, key) in keys {
+            accounts[i] = AccountMeta::new(key, false);
+        }
+        accounts
+    };
+    let buy = |buyer: Pubkey, tokens: u64, lamports: u64| {
+        let data = [&BUY_DISC[..], &tokens.to_le_bytes(), &lamports.to_le_bytes()].concat();
+        Instruction::new_with_bytes(config().program_id, &data, accounts(&[(2, mint), (6, buyer)]))
+    };
+    let create = Instruction::new_with_bytes(config().program_id, &CREATE_DISC, accounts(&[(0, mint), (7, creator)]));
+    let tx = Transaction::new_unsigned(Message::new(
+        &[create, buy(sniper, 5, 6), buy(creator, 1_000, 2_000), buy(creator, 7, 8)],
+        Some(&creator),
+    ));
+    let entries = vec![Entry { num_hashes: 1, hash: Hash::default(), transactions: vec![tx.into()] }];
+    let data = bincode::serialize(&entries).unwrap();
+    let mut config = config();
+
+    config.dev_buys = true;
+    let processed = process_entries(&data, &config, 1).unwrap();
+    assert_eq!(processed.detections.len(), 1);
+    // No logs, so the buy's cap stands in for its cost
+    assert!(matches!(
+        &processed.detections[0],
+        Detection::CreateWithDevBuy { mint: m, creator: c, dev_buy_sol: 2_000, dev_buy_tokens: 1_000, .. }
+            if *m == mint && *c == creator
+    ));
+    assert_eq!(processed.detections[0].kind(), "create_with_dev_buy");
+
+    // Only the first of the creator's buys is folded; the others stay buys when buys are watched
+    config.watch(InstructionKind::Buy);
+    let kinds: Vec<&str> = process_entries(&data, &config, 2).unwrap().detections.iter().map(|d| d.kind()).collect();
+    assert_eq!(kinds, ["create_with_dev_buy", "buy", "buy"]);
+
+    config.dev_buys = false;
+    let kinds: Vec<&str> = process_entries(&data, &config, 3).unwrap().detections.iter().map(|d| d.kind()).collect();
+    assert_eq!(kinds, ["create", "buy", "buy", "buy"]);
+}
+
+#[test]
+fn folded_creates_keep_their_accounts_and_token_accounts() {
+    let (mint, creator, creator_ata) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let mut accounts: Vec<AccountMeta> =
+        (0..CREATE_MIN_ACCOUNTS).map(|_| AccountMeta::new(Pubkey::new_unique(), false)).collect();
+    accounts[0] = AccountMeta::new(mint, false);
+    accounts[7] = AccountMeta::new(creator, false);
+    let create = Instruction::new_with_bytes(config().program_id, &CREATE_DISC, accounts.clone());
+    let ata_accounts = [Pubkey::new_unique(), creator_ata, creator, mint].map(|key| AccountMeta::new(key, false));
+    let ata = Instruction::new_with_bytes(ASSOCIATED_TOKEN_PROGRAM_ID, &[1], ata_accounts.to_vec());
+    accounts[2] = AccountMeta::new(mint, false);
+    accounts[6] = AccountMeta::new(creator, false);
+    let data = [&BUY_DISC[..], &1_000u64.to_le_bytes(), &2_000u64.to_le_bytes()].concat();
+    let buy = Instruction::new_with_bytes(config().program_id, &data, accounts);
+    let tx = Transaction::new_unsigned(Message::new(&[create, ata, buy], Some(&creator)));
+    let entries = vec![Entry { num_hashes: 1, hash: Hash::default(), transactions: vec![tx.into()] }];
+    let mut config = config();
+    config.dev_buys = true;
+    config.token_accounts = true;
+    config.create_accounts = true;
+
+    let processed = process_entries(&bincode::serialize(&entries).unwrap(), &config, 1).unwrap();
+    let [Detection::CreateWithDevBuy {
+        mint: m, associated_bonding_curve, metadata, accounts: Some(accounts), token_accounts, ..
+    }] = &processed.detections[..]
+    else {
+        panic!("expected a create with its dev buy folded in: {:?}", processed.detections);
+    };
+    assert_eq!((&accounts[0], &accounts[7]), (m, &creator));
+    assert_eq!(associated_bonding_curve.as_ref(), Some(&accounts[ASSOCIATED_BONDING_CURVE_INDEX]));
+    assert_eq!(metadata.as_ref(), Some(&accounts[METADATA_INDEX]));
+    assert_eq!(token_accounts, &[TokenAccount { owner: creator, account: creator_ata }]);
+}
+
+#[test]
+fn reports_undecoded_instructions_of_watched_programs() {
+    let program = config().program_id;
+    let ixs = [
+        instruction(&[9; 8], Pubkey::new_unique()),
+        // Known by name, though neither is watched
+        instruction(&SELL_DISC, Pubkey::new_unique()),
+        instruction(&BUY_DISC, Pubkey::new_unique()),
+        Instruction::new_with_bytes(program, &[7, 7, 7], vec![]),
+        Instruction::new_with_bytes(Pubkey::new_unique(), &[9; 8], vec![]),
+    ];
+    let tx = Transaction::new_unsigned(Message::new(&ixs, Some(&Pubkey::new_unique())));
+    let entries = vec![Entry { num_hashes: 1, hash: Hash::default(), transactions: vec![tx.into()] }];
+    let data = bincode::serialize(&entries).unwrap();
+    let mut config = config();
+
+    assert!(process_entries(&data, &config, 1).unwrap().detections.is_empty());
+    config.unknown_data_limit = Some(10);
+    let processed = process_entries(&data, &config, 2).unwrap();
+    // Cut to 10 of the 24 bytes
+    let data_hex = format!("{}0000", "09".repeat(8));
+    let expected = [
+        Detection::Unknown { program, discriminator: vec![9; 8], data_hex, data_len: 24 },
+        Detection::Unknown { program, discriminator: vec![7, 7, 7], data_hex: "070707".to_string(), data_len: 3 },
+    ];
+    assert_eq!(processed.detections, expected);
+}
+
+#[test]
+fn decodes_each_program_with_its_own_layout() {
+    let raydium = Pubkey::new_unique();
+    let pumpswap = Pubkey::from_str(PUMPSWAP_PROGRAM_ID).unwrap();
+    let migration =
+        MigrationConfig { program_id: pumpswap, discriminator: CREATE_POOL_DISC.to_vec(), mint_index: 3, pool_index: 0 };
+    let mut config = DetectorConfig::new(config().program_id, false, Some(migration));
+    let file = format!(
+        r#"{{"{}": [{{"kind": "migration", "discriminator": [1], "accounts": {{"pool": 4, "mint": 8}}}}],
+            "{}": [{{"kind": "buy", "accounts": {{"mint": 0, "user": 1}}}}]}}"#,
+        raydium,
+        config.program_id
+    );
+    let unwatched = config.use_layouts(parse_layouts(&file).unwrap());
+    assert_eq!(unwatched.len(), 1);
+    assert_eq!(unwatched[0].1.kind, InstructionKind::Buy);
+    config.validate().unwrap();
+    assert_eq!(config.programs_watching(InstructionKind::Migration).collect::<Vec<_>>(), [&raydium]);
+
+    let accounts: Vec<Pubkey> = (0..9).map(|_| Pubkey::new_unique()).collect();
+    let ix = |program_id, data: &[u8], count: usize| {
+        let metas = accounts[..count].iter().map(|&key| AccountMeta::new(key, false)).collect();
+        Instruction::new_with_bytes(program_id, data, metas)
+    };
+    let ixs = [ix(raydium, &[1, 0, 0], 9), ix(raydium, &[1], 8), ix(pumpswap, &CREATE_POOL_DISC, 9)];
+    let tx = Transaction::new_unsigned(Message::new(&ixs, Some(&Pubkey::new_unique())));
+    let entries = vec![Entry { num_hashes: 1, hash: Hash::default(), transactions: vec![tx.into()] }];
+
+    let processed = process_entries(&bincode::serialize(&entries).unwrap(), &config, 1).unwrap();
+    assert_eq!(
+        processed.detections,
+        [Detection::Migration { mint: accounts[8], pool: accounts[4], from_pumpfun: false }]
+    );
+}
+
+#[test]
+fn reads_arguments_after_discriminators_of_any_length() {
+    let program = Pubkey::new_unique();
+    let mut config = config();
+    let file = format!(
+        r#"{{"{}": [{{"kind": "buy", "discriminator": [3], "accounts": {{"mint": 0, "user": 1}}}},
+                    {{"kind": "create", "discriminator": [9, 9, 9, 9],
+                      "accounts": {{"mint": 0, "bonding_curve": 1, "creator": 2}}}}]}}"#,
+        program
+    );
+    config.watch(InstructionKind::Buy);
+    assert!(config.use_layouts(parse_layouts(&file).unwrap()).is_empty());
+    config.validate().unwrap();
+
+    let (mint, buyer) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let metas = |keys: &[Pubkey]| keys.iter().map(|&key| AccountMeta::new(key, false)).collect();
+    let buy = [&[3][..], &1_000u64.to_le_bytes(), &2_000u64.to_le_bytes()].concat();
+    let create = [&[9, 9, 9, 9][..], &create_data(&[b"Token", b"TKN", b"uri"])[CREATE_DISC.len()..]].concat();
+    let ixs = [
+        Instruction::new_with_bytes(program, &create, metas(&[mint, Pubkey::new_unique(), buyer])),
+        Instruction::new_with_bytes(program, &buy, metas(&[mint, buyer])),
+        // One byte short of the arguments
+        Instruction::new_with_bytes(program, &buy[..16], metas(&[mint, buyer])),
+    ];
+    let tx = Transaction::new_unsigned(Message::new(&ixs, Some(&buyer)));
+    let entries = vec![Entry { num_hashes: 1, hash: Hash::default(), transactions: vec![tx.into()] }];
+
+    let processed = process_entries(&bincode::serialize(&entries).unwrap(), &config, 1).unwrap();
+    assert_eq!(processed.detections.len(), 2);
+    assert_eq!(
+        processed.detections[1],
+        Detection::Buy { mint, buyer, token_amount: 1_000, max_sol_cost: 2_000, event: None }
+    );
+    assert_eq!(processed.oversized_args, 0);
+    let args = decode_create_args(&create, 4, &config).unwrap();
+    assert_eq!((args.name.as_str(), args.symbol.as_str(), args.uri.as_str()), ("Token", "TKN", "uri"));
+}
+
+#[test]
+fn matches_anchor_and_spl_style_discriminators() {
+    let (pumpfun, token_like) = (config().program_id, Pubkey::new_unique());
+    let mut config = config();
+    config.watch(InstructionKind::ExtendAccount);
+    config.watch(InstructionKind::CloseUserVolumeAccumulator);
+    // Tag-byte discriminators of two lengths, beside pump.fun's 8-byte ones
+    let mut extend = InstructionLayout::builtin(InstructionKind::ExtendAccount);
+    extend.discriminator = vec![1];
+    config.set_layout(token_like, extend);
+    let mut close = InstructionLayout::builtin(InstructionKind::CloseUserVolumeAccumulator);
+    close.discriminator = vec![2, 0];
+    config.set_layout(token_like, close);
+    config.validate().unwrap();
+
+    let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let ix = |program_id, data: &[u8]| {
+        Instruction::new_with_bytes(program_id, data, vec![AccountMeta::new(first, false), AccountMeta::new(second, false)])
+    };
+    let ixs = [
+        ix(pumpfun, &EXTEND_ACCOUNT_DISC),
+        ix(token_like, &[1, 5, 5]),
+        ix(token_like, &[2, 0]),
+        ix(pumpfun, &CLOSE_USER_VOLUME_ACCUMULATOR_DISC),
+        // Unmatched: another tag of the same length, one shorter than the discriminator, and a tag under pump.fun
+        ix(token_like, &[2, 1]),
+        ix(token_like, &[2]),
+        ix(pumpfun, &[1, 0, 0, 0, 0, 0, 0, 0]),
+    ];
+    let tx = Transaction::new_unsigned(Message::new(&ixs, Some(&Pubkey::new_unique())));
+    let entries = vec![Entry { num_hashes: 1, hash: Hash::default(), transactions: vec![tx.into()] }];
+
+    let processed = process_entries(&bincode::serialize(&entries).unwrap(), &config, 1).unwrap();
+    let extend = Detection::Maintenance { instruction: InstructionKind::ExtendAccount, account: first, user: second };
+    let close =
+        Detection::Maintenance { instruction: InstructionKind::CloseUserVolumeAccumulator, account: second, user: first };
+    assert_eq!(processed.detections, [extend.clone(), extend, close.clone(), close]);
+}
+
+#[test]
+fn rejects_incomplete_or_overlapping_layouts() {
+    let program = Pubkey::new_unique();
+    let missing_pool = format!(r#"{{"{}": [{{"kind": "migration", "accounts": {{"mint": 3}}}}]}}"#, program);
+    assert!(parse_layouts(&missing_pool).unwrap_err().contains("Pool"));
+    assert!(parse_layouts(r#"{"not-a-key": []}"#).is_err());
+
+    let mut config = config();
+    config.watch(InstructionKind::Withdraw);
+    config.validate().unwrap();
+    let mut shadowing = InstructionLayout::builtin(InstructionKind::Migration);
+    shadowing.discriminator = WITHDRAW_DISC[..4].to_vec();
+    config.set_layout(config.program_id, shadowing);
+    assert!(config.validate().unwrap_err().contains("overlapping"));
+    assert_eq!(InstructionLayout::builtin(InstructionKind::Create).index(Role::Creator), Some(CREATE_MIN_ACCOUNTS - 1));
+}
+
+#[test]
+fn detects_mint_inits_of_both_token_programs() {
+    let (mints, authority) = ([Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()], Pubkey::new_unique());
+    // Tag, decimals, mint authority, then a freeze authority option
+    let data = |tag: u8, decimals: u8, freeze: Option<Pubkey>| {
+        let mut data = vec![tag, decimals];
+        data.extend_from_slice(authority.as_ref());
+        match freeze {
+            Some(freeze) => data.extend(std::iter::once(1).chain(freeze.to_bytes())),
+            None => data.push(0),
+        }
+        data
+    };
+    let init = |program, data: Vec<u8>, mint| Instruction::new_with_bytes(program, &data, vec![AccountMeta::new(mint, false)]);
+    let tx = Transaction::new_unsigned(Message::new(
+        &[
+            init(TOKEN_PROGRAM_ID, data(0, 6, None), mints[0]),
+            init(TOKEN_2022_PROGRAM_ID, data(20, 9, Some(Pubkey::new_unique())), mints[1]),
+            // MintTo, and an InitializeMint cut off inside the authority
+            init(TOKEN_PROGRAM_ID, vec![7, 0, 0, 0, 0, 0, 0, 0, 0], mints[2]),
+            init(TOKEN_PROGRAM_ID, data(0, 6, None)[..20].to_vec(), mints[2]),
+        ],
+        Some(&Pubkey::new_unique()),
+    ));
+    let entries = vec![Entry { num_hashes: 1, hash: Hash::default(), transactions: vec![tx.into()] }];
+    let data = bincode::serialize(&entries).unwrap();
+    let mut config = config();
+
+    assert!(process_entries(&data, &config, 1).unwrap().detections.is_empty());
+    config.mint_inits = true;
+    assert_eq!(process_entries(&data, &config, 2).unwrap().detections, vec![
+        Detection::MintInit { mint: mints[0], decimals: 6, authority, token_2022: false },
+        Detection::MintInit { mint: mints[1], decimals: 9, authority, token_2022: true },
+    ]);
+    assert_eq!(decode_initialize_mint(&[]), None);
+}
+
+/// Three serialized creates, and the offset where the last one starts
+fn three_creates() -> (Vec<u8>, usize) {
+    let entries: Vec<Entry> = bincode::deserialize(&create_entries()).unwrap();
+    let entries = [entries.clone(), entries.clone(), entries].concat();
+    let data = bincode::serialize(&entries).unwrap();
+    let last = data.len() - bincode::serialize(&entries[2]).unwrap().len();
+    (data, last)
+}
+
+fn partial_config() -> DetectorConfig {
+    let mut config = config();
+    config.partial_decode = true;
+    config
+}
+
+#[test]
+fn partial_decode_keeps_entries_before_corruption() {
+    let (data, last) = three_creates();
+
+    let truncated = &data[..last + 20];
+    assert!(matches!(process_entries(truncated, &config(), 1), Err(ProcessError::Deserialize(_))));
+    let processed = process_entries(truncated, &partial_config(), 1).unwrap();
+    assert_eq!((processed.entries, processed.detections.len(), processed.failed_entry), (2, 2, Some(2)));
+
+    // A garbage transaction count in the last entry, after num_hashes and the hash
+    let mut corrupt = data.clone();
+    corrupt[last + 40..last + 48].copy_from_slice(&u64::MAX.to_le_bytes());
+    let processed = process_entries(&corrupt, &partial_config(), 1).unwrap();
+    assert_eq!((processed.entries, processed.failed_entry), (2, Some(2)));
+
+    let processed = process_entries(&data, &partial_config(), 1).unwrap();
+    assert_eq!((processed.entries, processed.detections.len(), processed.failed_entry), (3, 3, None));
+}
+
+#[test]
+fn partial_decode_with_corrupt_first_entry_is_undecodable() {
+    let (data, _) = three_creates();
+
+    assert!(matches!(process_entries(&data[..30], &partial_config(), 1), Err(ProcessError::Deserialize(_))));
+}
+
+/// Create instruction data: the discriminator, then each string as a u32 LE length and its bytes
+fn create_data(strings: &[&[u8]]) -> Vec<u8> {
+    let mut data = CREATE_DISC.to_vec();
+    for s in strings {
+        data.extend_from_slice(&(s.len() as u32).to_le_bytes());
+        data.extend_from_slice(s);
+    }
+    data
+}
+
+#[test]
+fn decodes_create_args() {
+    let mut data = create_data(&[b"Token", b"TKN", b"https://example.com/t.json"]);
+    // Newer program versions append the creator
+    data.extend_from_slice(&[7; 32]);
+    let args = decode_create_args(&data, CREATE_DISC.len(), &config()).unwrap();
+    assert_eq!(
+        args,
+        CreateArgs { name: "Token".into(), symbol: "TKN".into(), uri: "https://example.com/t.json".into() }
+    );
+
+    assert_eq!(decode_create_args(&data[..20], CREATE_DISC.len(), &config()), Err(ArgsError::Truncated));
+    // Invalid UTF-8 is replaced rather than rejected, and control characters escaped
+    let hostile = create_data(&[b"\xff\xfeA", b"T\nK", b""]);
+    let args = decode_create_args(&hostile, CREATE_DISC.len(), &config()).unwrap();
+    assert_eq!((args.name.as_str(), args.symbol.as_str()), ("\u{fffd}\u{fffd}A", "T\\u{a}K"));
+}
+
+#[test]
+fn rejects_oversized_create_args() {
+    // A 4 GiB name declared in a dozen bytes; rejected on the prefix alone
+    let mut data = CREATE_DISC.to_vec();
+    data.extend_from_slice(&u32::MAX.to_le_bytes());
+    data.extend_from_slice(b"name");
+    let err = decode_create_args(&data, CREATE_DISC.len(), &config()).unwrap_err();
+    assert_eq!(err, ArgsError::StringTooLong { len: u32::MAX as usize, limit: 256 });
+    assert!(err.is_limit());
+
+    let long_uri = vec![b'a'; 300];
+    let data = create_data(&[b"Token", b"TKN", &long_uri]);
+    let mut config = config();
+    let err = decode_create_args(&data, CREATE_DISC.len(), &config);
+    assert!(matches!(err, Err(ArgsError::StringTooLong { len: 300, .. })));
+    config.arg_string_limit = 300;
+    assert!(decode_create_args(&data, CREATE_DISC.len(), &config).is_ok());
+    config.args_limit = 100;
+    assert_eq!(
+        decode_create_args(&data, CREATE_DISC.len(), &config),
+        Err(ArgsError::TooLarge { len: data.len() - CREATE_DISC.len(), limit: 100 })
+    );
+}
+
+#[test]
+fn counts_creates_with_oversized_args() {
+    let mint = Pubkey::new_unique();
+    let mut data = CREATE_DISC.to_vec();
+    data.extend_from_slice(&u32::MAX.to_le_bytes());
+    let mut ix = instruction(&CREATE_DISC, mint);
+    ix.data = data;
+    let tx = Transaction::new_unsigned(Message::new(&[ix], Some(&Pubkey::new_unique())));
+    let entries = vec![Entry { num_hashes: 1, hash: Hash::default(), transactions: vec![tx.into()] }];
+
+    let processed = process_entries(&bincode::serialize(&entries).unwrap(), &config(), 1).unwrap();
+    assert_eq!(processed.oversized_args, 1);
+    // Still reported; the accounts are what identify the token
+    assert!(matches!(processed.detections[..], [Detection::Create { mint: m, .. }] if m == mint));
+
+    // Missing arguments are malformed, not oversized
+    assert_eq!(process_entries(&create_entries(), &config(), 1).unwrap().oversized_args, 0);
+}
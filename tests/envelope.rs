@@ -0,0 +1,102 @@
+use std::str::FromStr;
+
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use test_shreds::{
+    detector::{process_message, Detection, DetectorConfig, ProcessError, PUMPFUN_PROGRAM_ID},
+    envelope::{open, open_with_limit, wrap, EnvelopeError, MessageContext, TransactionLogs, ENVELOPE_VERSION},
+    sink::DetectionEvent,
+};
+
+fn context() -> MessageContext {
+    MessageContext {
+        slot: 312_000_000,
+        first_shred_index: 0,
+        last_shred_index: 31,
+        captured_at_us: 1_760_000_000_000_000,
+        proxy: "proxy-1".to_string(),
+        logs: Vec::new(),
+    }
+}
+
+#[test]
+fn round_trips_and_passes_bare_messages_through() {
+    let entries = include_bytes!("fixtures/pumpfun_create.bin");
+    let wrapped = wrap(&context(), entries);
+    assert_eq!(open(&wrapped), Ok((Some(context()), &entries[..])));
+    assert_eq!(open(entries), Ok((None, &entries[..])));
+}
+
+#[test]
+fn opens_version_1_envelopes_without_meta() {
+    let entries = include_bytes!("fixtures/pumpfun_create.bin");
+    let mut context = context();
+    context.logs = vec![TransactionLogs { signature: Signature::default(), logs: vec!["Program log: hi".to_string()] }];
+    let wrapped = wrap(&context, entries);
+    assert_eq!(open(&wrapped), Ok((Some(context.clone()), &entries[..])));
+
+    // Version 1 is version 2 without the meta length and meta
+    let meta_start = 31 + context.proxy.len();
+    let meta_len = u32::from_le_bytes(wrapped[meta_start..meta_start + 4].try_into().unwrap()) as usize;
+    let mut v1 = wrapped.clone();
+    v1.drain(meta_start..meta_start + 4 + meta_len);
+    v1[4] = 1;
+    context.logs.clear();
+    assert_eq!(open(&v1), Ok((Some(context), &entries[..])));
+}
+
+#[test]
+fn rejects_malformed_envelopes() {
+    let wrapped = wrap(&context(), b"");
+    assert_eq!(open(&wrapped[..20]), Err(EnvelopeError::Truncated));
+
+    let mut future = wrapped.clone();
+    future[4] = ENVELOPE_VERSION + 1;
+    assert_eq!(open(&future), Err(EnvelopeError::UnsupportedVersion(ENVELOPE_VERSION + 1)));
+
+    // The hostname's last byte, ahead of the meta length and an empty log list
+    let mut bad_host = wrapped.clone();
+    let last = bad_host.len() - 13;
+    bad_host[last] = 0xff;
+    assert_eq!(open(&bad_host), Err(EnvelopeError::InvalidHostname));
+
+    // The top byte of the log list's length
+    let mut bad_meta = wrapped;
+    let last = bad_meta.len() - 1;
+    bad_meta[last] = 0xff;
+    assert_eq!(open(&bad_meta), Err(EnvelopeError::InvalidMeta));
+}
+
+#[test]
+fn bounds_the_meta_by_the_decode_limit() {
+    let entries = include_bytes!("fixtures/pumpfun_create.bin");
+    let mut context = context();
+    context.logs = vec![TransactionLogs { signature: Signature::default(), logs: vec!["Program log: hi".repeat(64)] }];
+    let wrapped = wrap(&context, entries);
+    assert_eq!(open_with_limit(&wrapped, 4096), Ok((Some(context), &entries[..])));
+    assert_eq!(open_with_limit(&wrapped, 512), Err(EnvelopeError::MetaSizeLimit { limit: 512 }));
+
+    // The detector applies its own limit to the meta as well as the entries
+    let mut config = DetectorConfig::new(Pubkey::from_str(PUMPFUN_PROGRAM_ID).unwrap(), false, None);
+    config.decode_limit = 512;
+    let err = process_message(&wrapped, &config, 1).unwrap_err();
+    assert!(matches!(err, ProcessError::Envelope(EnvelopeError::MetaSizeLimit { limit: 512 })), "{}", err);
+}
+
+#[test]
+fn annotates_detections_with_the_envelope() {
+    let config = DetectorConfig::new(Pubkey::from_str(PUMPFUN_PROGRAM_ID).unwrap(), false, None);
+    let message = wrap(&context(), include_bytes!("fixtures/pumpfun_create.bin"));
+    let processed = process_message(&message, &config, 1).unwrap();
+    assert!(matches!(processed.detections[..], [Detection::Create { .. }]));
+    assert_eq!(processed.context, Some(context()));
+
+    let mut event = DetectionEvent::new(processed.detections[0].clone(), 1);
+    event.context = processed.context;
+    let json: serde_json::Value = serde_json::to_value(&event).unwrap();
+    assert_eq!(json["context"]["slot"], 312_000_000);
+    assert_eq!(json["context"]["proxy"], "proxy-1");
+
+    let mut truncated = wrap(&context(), b"");
+    truncated.truncate(10);
+    assert!(matches!(process_message(&truncated, &config, 2), Err(ProcessError::Envelope(_))));
+}
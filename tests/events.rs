@@ -0,0 +1,229 @@
+mod common;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use common::{create_event, create_event_named, program_id, pumpfun_logs};
+use solana_entry::entry::Entry;
+use solana_sdk::{
+    hash::Hash,
+    instruction::{AccountMeta, Instruction},
+    message::Message,
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::{Transaction, VersionedTransaction},
+};
+use test_shreds::{
+    detector::{process_entries, process_message, Detection, DetectorConfig, BUY_DISC},
+    envelope::{wrap, MessageContext, TransactionLogs},
+    events::{decode_event, parse_logs, tx_status, PumpEvent, TxStatus, TRADE_EVENT_DISC},
+};
+
+fn trade_event(mint: Pubkey, user: Pubkey, sol_amount: u64, is_buy: bool) -> Vec<u8> {
+    let mut out = TRADE_EVENT_DISC.to_vec();
+    out.extend_from_slice(mint.as_ref());
+    out.extend_from_slice(&sol_amount.to_le_bytes());
+    out.extend_from_slice(&5_000u64.to_le_bytes());
+    out.push(is_buy as u8);
+    out.extend_from_slice(user.as_ref());
+    out.extend_from_slice(&1_760_000_000i64.to_le_bytes());
+    out.extend_from_slice(&31_000_000_000u64.to_le_bytes());
+    out.extend_from_slice(&1_000_000_000_000_000u64.to_le_bytes());
+    // Newer programs append fees and more; ignored
+    out.extend_from_slice(&[0; 40]);
+    out
+}
+
+#[test]
+fn decodes_both_create_event_layouts() {
+    let (mint, creator) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let Some(PumpEvent::Create(event)) = decode_event(&create_event(mint, creator, false)) else {
+        panic!("expected a create event");
+    };
+    assert_eq!((event.name.as_str(), event.symbol.as_str()), ("Moon", "MOON"));
+    assert_eq!((event.mint, event.user, event.creator), (mint, creator, Some(creator)));
+    assert_eq!(event.timestamp, Some(1_760_000_000));
+    assert_eq!(event.reserves.unwrap().virtual_sol_reserves, 30_000_000_000);
+
+    let Some(PumpEvent::Create(legacy)) = decode_event(&create_event(mint, creator, true)) else {
+        panic!("expected a create event");
+    };
+    assert_eq!((legacy.creator, legacy.timestamp, legacy.reserves), (None, None, None));
+
+    let Some(PumpEvent::Trade(trade)) = decode_event(&trade_event(mint, creator, 42, true)) else {
+        panic!("expected a trade event");
+    };
+    assert_eq!((trade.sol_amount, trade.token_amount, trade.is_buy), (42, 5_000, true));
+
+    let truncated = create_event(mint, creator, true);
+    assert_eq!(decode_event(&truncated[..truncated.len() - 1]), None);
+    assert_eq!(decode_event(&[0; 64]), None);
+}
+
+#[test]
+fn sanitizes_adversarial_create_event_strings() {
+    let (mint, creator) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let Some(PumpEvent::Create(clean)) = decode_event(&create_event(mint, creator, true)) else {
+        panic!("expected a create event");
+    };
+    assert!(!clean.suspicious && clean.raw.is_none());
+
+    // A right-to-left override flipping how the name displays, a terminal escape, a forged log line and bad UTF-8
+    let name = "Moon\u{202e}DOG".as_bytes();
+    let symbol = b"\x1b[2J\nWARN fake";
+    let uri = b"\xff\xfe";
+    let Some(PumpEvent::Create(event)) = decode_event(&create_event_named([name, symbol, uri], mint, creator, true)) else {
+        panic!("expected a create event despite the strings");
+    };
+    assert_eq!(event.name, "Moon\\u{202e}DOG");
+    assert_eq!(event.symbol, "\\u{1b}[2J\\u{a}WARN fake");
+    assert_eq!(event.uri, "\u{fffd}\u{fffd}");
+    assert!(event.suspicious);
+    let raw = event.raw.as_ref().expect("raw bytes kept when sanitizing changed them");
+    assert_eq!(raw.name, name);
+    assert_eq!((raw.symbol.as_slice(), raw.uri.as_slice()), (symbol.as_slice(), uri.as_slice()));
+
+    let json = serde_json::to_value(&event).unwrap();
+    assert_eq!(json["raw"]["uri"], "//4=");
+    assert_eq!(json["suspicious"], true);
+    // Nothing a terminal would act on survives into the text
+    assert!(!serde_json::to_string(&event).unwrap().contains(['\n', '\u{1b}', '\u{202e}']));
+
+    // Zero-width characters are suspicious too, even though they render as nothing
+    let Some(PumpEvent::Create(hidden)) =
+        decode_event(&create_event_named(["Pe\u{200b}pe".as_bytes(), b"PEPE", b""], mint, creator, true))
+    else {
+        panic!("expected a create event");
+    };
+    assert_eq!((hidden.name.as_str(), hidden.suspicious), ("Pe\\u{200b}pe", true));
+}
+
+#[test]
+fn only_reads_data_logged_by_pumpfun_itself() {
+    let program = program_id();
+    let other = Pubkey::new_unique();
+    let event = |mint| format!("Program data: {}", BASE64.encode(create_event(mint, Pubkey::new_unique(), true)));
+    let (ours, theirs, after) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let logs = vec![
+        format!("Program {} invoke [1]", other),
+        event(theirs),
+        // A wrapper program calling pumpfun
+        format!("Program {} invoke [2]", program),
+        event(ours),
+        "Program data: not base64!".to_string(),
+        format!("Program {} success", program),
+        event(theirs),
+        format!("Program {} failed: custom program error: 0x1", other),
+        event(theirs),
+        format!("Program {} invoke [1]", program),
+        event(after),
+        format!("Program {} success", program),
+    ];
+    let mints: Vec<Pubkey> = parse_logs(&logs, &program)
+        .into_iter()
+        .map(|e| match e {
+            PumpEvent::Create(create) => create.mint,
+            PumpEvent::Trade(trade) => trade.mint,
+        })
+        .collect();
+    assert_eq!(mints, [ours, after]);
+}
+
+#[test]
+fn reads_transaction_status_from_logs() {
+    let mut logs = pumpfun_logs(&[]);
+    assert_eq!(tx_status(&logs), TxStatus::Succeeded);
+    assert_eq!(tx_status(&[]), TxStatus::Unknown);
+
+    let truncated = [logs[0].clone(), "Log truncated".to_string()];
+    assert_eq!(tx_status(&truncated), TxStatus::Unknown);
+
+    // pumpfun succeeded, but a later instruction failed and took the whole transaction with it
+    let other = Pubkey::new_unique();
+    logs.push(format!("Program {} invoke [1]", other));
+    logs.push(format!("Program {} failed: custom program error: 0x1771", other));
+    assert_eq!(tx_status(&logs), TxStatus::Failed);
+}
+
+#[test]
+fn merges_logged_events_into_detections() {
+    let data = include_bytes!("fixtures/pumpfun_create.bin");
+    let mut entries: Vec<Entry> = bincode::deserialize(data).unwrap();
+    let mut config = DetectorConfig::new(program_id(), true, None);
+    let Detection::Create { mint, creator, .. } = process_entries(data, &config, 1).unwrap().detections[0].clone() else {
+        panic!("expected the fixture's create");
+    };
+    // The fixture's last entry holds the create
+    let create_signature = entries.last().unwrap().transactions[0].signatures[0];
+    let fee_creator = Pubkey::new_unique();
+
+    // A buy whose instruction we see, and a create and buy made through another program, which we don't
+    let (buyer, wrapped_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut accounts: Vec<AccountMeta> = (0..7).map(|_| AccountMeta::new(Pubkey::new_unique(), false)).collect();
+    accounts[2] = AccountMeta::new(mint, false);
+    accounts[6] = AccountMeta::new(buyer, true);
+    let buy_data = [BUY_DISC.as_slice(), &5_000u64.to_le_bytes(), &2_000_000_000u64.to_le_bytes()].concat();
+    let buy = Instruction::new_with_bytes(program_id(), &buy_data, accounts);
+    let mut buy_tx = VersionedTransaction::from(Transaction::new_unsigned(Message::new(&[buy], Some(&buyer))));
+    buy_tx.signatures = vec![Signature::new_unique()];
+    let wrapper = Instruction::new_with_bytes(Pubkey::new_unique(), &[1], vec![]);
+    let mut wrapper_tx = VersionedTransaction::from(Transaction::new_unsigned(Message::new(&[wrapper], Some(&buyer))));
+    wrapper_tx.signatures = vec![Signature::new_unique()];
+    entries.push(Entry { num_hashes: 1, hash: Hash::default(), transactions: vec![buy_tx.clone(), wrapper_tx.clone()] });
+
+    let logs = vec![
+        TransactionLogs {
+            signature: create_signature,
+            logs: pumpfun_logs(&[create_event(mint, fee_creator, false)]),
+        },
+        TransactionLogs {
+            signature: buy_tx.signatures[0],
+            logs: pumpfun_logs(&[trade_event(mint, buyer, 1_500_000_000, true), trade_event(mint, buyer, 9, false)]),
+        },
+        TransactionLogs {
+            signature: wrapper_tx.signatures[0],
+            logs: [
+                pumpfun_logs(&[create_event(wrapped_mint, buyer, false), trade_event(wrapped_mint, buyer, 7, true)]),
+                vec!["Program 11111111111111111111111111111111 failed: insufficient lamports".to_string()],
+            ]
+            .concat(),
+        },
+    ];
+    let context = MessageContext {
+        slot: 1,
+        first_shred_index: 0,
+        last_shred_index: 0,
+        captured_at_us: 0,
+        proxy: "proxy-1".to_string(),
+        logs,
+    };
+    let message = wrap(&context, &bincode::serialize(&entries).unwrap());
+
+    let processed = process_message(&message, &config, 1).unwrap();
+    assert_eq!(processed.events, 5);
+    assert!(processed.context.unwrap().logs.is_empty());
+    let [
+        Detection::Create { mint: m, creator: c, event: Some(create), .. },
+        Detection::Buy { max_sol_cost, event: Some(bought), .. },
+        Detection::Create { mint: wrapped, event: Some(_), .. },
+        Detection::Buy { max_sol_cost: wrapped_cost, .. },
+    ] = &processed.detections[..]
+    else {
+        panic!("expected two creates and two buys: {:?}", processed.detections);
+    };
+    // The event's creator is authoritative
+    assert_eq!((*m, *c, create.user), (mint, fee_creator, fee_creator));
+    assert_ne!(creator, fee_creator);
+    // The instruction's slippage cap stays; the event has what was actually paid
+    assert_eq!((*max_sol_cost, bought.sol_amount), (2_000_000_000, 1_500_000_000));
+    assert_eq!((*wrapped, *wrapped_cost), (wrapped_mint, 7));
+    // The wrapper transaction failed, so its create and buy never happened
+    assert_eq!(processed.statuses, [TxStatus::Succeeded, TxStatus::Succeeded, TxStatus::Failed, TxStatus::Failed]);
+
+    // Without buys enabled, trade events don't add buys either
+    config = DetectorConfig::new(program_id(), false, None);
+    let processed = process_message(&message, &config, 1).unwrap();
+    let kinds: Vec<bool> = processed.detections.iter().map(|d| matches!(d, Detection::Create { .. })).collect();
+    assert_eq!(kinds, [true, true]);
+    assert_eq!(processed.statuses, [TxStatus::Succeeded, TxStatus::Failed]);
+    // Without the envelope's logs, nothing is known
+    assert_eq!(process_entries(data, &config, 1).unwrap().statuses, [TxStatus::Unknown]);
+}
@@ -0,0 +1,95 @@
+#![cfg(unix)]
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Read},
+    os::unix::fs::{FileTypeExt, OpenOptionsExt},
+    path::{Path, PathBuf},
+};
+
+use solana_sdk::pubkey::Pubkey;
+use test_shreds::{
+    detector::Detection,
+    sink::{fifo::FifoSink, DetectionEvent, Sink},
+};
+
+fn temp_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("test_shreds_{}_{}", name, std::process::id()));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+fn event(msg_seq: u64) -> DetectionEvent {
+    let detection = Detection::Migration { mint: Pubkey::new_unique(), pool: Pubkey::new_unique(), from_pumpfun: true };
+    DetectionEvent::new(detection, msg_seq)
+}
+
+/// Open `path` for reading without waiting for a writer, as a script's `read` would once the pipe exists
+fn reader(path: &Path) -> File {
+    OpenOptions::new().read(true).custom_flags(libc::O_NONBLOCK).open(path).unwrap()
+}
+
+/// The `msg_seq` of every line waiting in the pipe
+fn read_lines(reader: &mut File) -> Vec<u64> {
+    let mut text = String::new();
+    match reader.read_to_string(&mut text) {
+        Ok(_) => {}
+        Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+        Err(e) => panic!("{}", e),
+    }
+    let line = |line: &str| serde_json::from_str::<serde_json::Value>(line).unwrap()["msg_seq"].as_u64().unwrap();
+    text.lines().map(line).collect()
+}
+
+#[test]
+fn drops_events_while_no_reader_is_attached() {
+    let path = temp_path("fifo_reader");
+    let mut sink = FifoSink::open(&path).unwrap();
+    assert!(fs::metadata(&path).unwrap().file_type().is_fifo());
+
+    sink.write(&event(1)).unwrap();
+    assert_eq!(sink.dropped(), 1);
+
+    let mut first = reader(&path);
+    sink.write(&event(2)).unwrap();
+    sink.write(&event(3)).unwrap();
+    assert_eq!(read_lines(&mut first), vec![2, 3]);
+
+    // The reader goes away, and the next one picks up from there
+    drop(first);
+    sink.write(&event(4)).unwrap();
+    assert_eq!(sink.dropped(), 2);
+    let mut second = reader(&path);
+    sink.write(&event(5)).unwrap();
+    assert_eq!(read_lines(&mut second), vec![5]);
+    assert_eq!(sink.dropped(), 2);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn keeps_lines_whole_when_the_pipe_fills() {
+    let path = temp_path("fifo_full");
+    let mut sink = FifoSink::open(&path).unwrap();
+    let mut reader = reader(&path);
+
+    // Far more than a pipe buffer holds, with nothing read meanwhile
+    for seq in 0..2000 {
+        sink.write(&event(seq)).unwrap();
+    }
+    assert!(sink.dropped() > 0);
+    let mut seen = read_lines(&mut reader);
+    sink.write(&event(2000)).unwrap();
+    seen.extend(read_lines(&mut reader));
+    assert_eq!(seen.len() as u64 + sink.dropped(), 2001);
+    assert!(seen.windows(2).all(|pair| pair[0] < pair[1]));
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn rejects_a_path_that_is_not_a_fifo() {
+    let path = temp_path("fifo_regular");
+    fs::write(&path, b"").unwrap();
+    let err = FifoSink::open(&path).err().unwrap();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,77 @@
+use solana_sdk::pubkey::Pubkey;
+use test_shreds::{detector::Detection, filter::Filter};
+
+fn no_lists(_: &str) -> Option<Vec<String>> {
+    None
+}
+
+fn create(creator: Pubkey) -> Detection {
+    Detection::Create { mint: Pubkey::new_unique(), bonding_curve: Pubkey::new_unique(), creator, associated_bonding_curve: None, metadata: None, accounts: None, compute_budget: None, token_accounts: Vec::new(), event: None }
+}
+
+fn buy(lamports: u64) -> Detection {
+    Detection::Buy { mint: Pubkey::new_unique(), buyer: Pubkey::new_unique(), token_amount: 1_000, max_sol_cost: lamports, event: None }
+}
+
+#[test]
+fn combines_kind_amount_and_list_predicates() {
+    let watched = Pubkey::new_unique();
+    let lists = |name: &str| (name == "watchlist").then(|| vec![watched.to_string()]);
+    let filter = Filter::parse("kind == create && creator in watchlist || kind == buy && sol_amount > 1.0", lists).unwrap();
+
+    assert!(filter.matches(&create(watched)));
+    assert!(!filter.matches(&create(Pubkey::new_unique())));
+    assert!(filter.matches(&buy(1_500_000_000)));
+    assert!(!filter.matches(&buy(1_000_000_000)));
+}
+
+#[test]
+fn missing_fields_never_match() {
+    let creator = Pubkey::new_unique();
+    let filter = Filter::parse(&format!("creator != {}", creator), no_lists).unwrap();
+    assert!(filter.matches(&create(Pubkey::new_unique())));
+    assert!(!filter.matches(&create(creator)));
+    assert!(!filter.matches(&buy(1)));
+
+    let filter = Filter::parse("!(sol_amount < 1) && !from_pumpfun", no_lists).unwrap();
+    assert!(filter.matches(&buy(2_000_000_000)));
+    // Negation applies after the missing field made the comparison false
+    assert!(filter.matches(&create(creator)));
+}
+
+#[test]
+fn matches_mint_inits_by_kind_and_mint() {
+    let mint = Pubkey::new_unique();
+    let init = Detection::MintInit { mint, decimals: 6, authority: Pubkey::new_unique(), token_2022: false };
+    let filter = Filter::parse(&format!("kind == mint_init && mint == {}", mint), no_lists).unwrap();
+    assert!(filter.matches(&init));
+    assert!(!filter.matches(&create(Pubkey::new_unique())));
+}
+
+#[test]
+fn matches_unknown_instructions_by_program() {
+    let program = Pubkey::new_unique();
+    let unknown = Detection::Unknown { program, discriminator: vec![1; 8], data_hex: "01".repeat(8), data_len: 8 };
+    let filter = Filter::parse(&format!("kind == unknown && program == {}", program), no_lists).unwrap();
+    assert!(filter.matches(&unknown));
+    assert!(!filter.matches(&create(program)));
+    assert!(!Filter::parse("mint == 11111111111111111111111111111111", no_lists).unwrap().matches(&unknown));
+}
+
+#[test]
+fn rejects_invalid_expressions() {
+    for (expr, error) in [
+        ("kind == token", "unknown kind"),
+        ("price > 1", "unknown field"),
+        ("creator in friends", "unknown list"),
+        ("mint == notakey", "not a valid pubkey"),
+        ("sol_amount in watchlist", "needs a pubkey field"),
+        ("kind < buy", "only supports"),
+        ("(kind == buy", "missing ')'"),
+        ("kind == buy buy", "after the end"),
+        ("kind = buy", "unexpected '='"),
+    ] {
+        let err = Filter::parse(expr, no_lists).unwrap_err();
+        assert!(err.contains(error), "{}: {}", expr, err);
+    }
+}
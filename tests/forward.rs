@@ -0,0 +1,25 @@
+use std::{net::UdpSocket, time::Duration};
+
+use test_shreds::forward::{ForwardStats, Forwarder};
+
+#[test]
+fn copies_every_datagram_to_each_target() {
+    let receivers: Vec<UdpSocket> = (0..2).map(|_| UdpSocket::bind("127.0.0.1:0").unwrap()).collect();
+    let targets = receivers.iter().map(|r| r.local_addr().unwrap()).collect();
+    let forwarder = Forwarder::spawn(targets, 16).unwrap();
+
+    for datagram in [&b"first"[..], b"second"] {
+        forwarder.forward(datagram);
+    }
+    for receiver in &receivers {
+        receiver.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let mut buf = [0u8; 64];
+        for expected in [&b"first"[..], b"second"] {
+            let (len, _) = receiver.recv_from(&mut buf).unwrap();
+            assert_eq!(&buf[..len], expected);
+        }
+    }
+
+    let stats = forwarder.shutdown();
+    assert_eq!(stats, ForwardStats { sent: 4, failed: 0, dropped: 0 });
+}
@@ -0,0 +1,82 @@
+use std::time::Duration;
+
+use test_shreds::{
+    clock::MockClock,
+    reassembler::{fragment_message, FragmentReassembler, DEFAULT_MAX_FRAGMENT_AGE},
+};
+
+fn payload(len: usize, seed: u8) -> Vec<u8> {
+    (0..len).map(|i| (i as u8).wrapping_mul(31).wrapping_add(seed)).collect()
+}
+
+/// Feed packets in order, collecting every completed message
+fn feed(reassembler: &mut FragmentReassembler<MockClock>, packets: &[Vec<u8>]) -> Vec<Vec<u8>> {
+    packets.iter().filter_map(|p| reassembler.process_packet(p)).collect()
+}
+
+#[test]
+fn reverse_order_reassembles() {
+    let message = payload(5000, 1);
+    let mut fragments = fragment_message(7, &message, 1200);
+    assert_eq!(fragments.len(), 5);
+    fragments.reverse();
+
+    let mut reassembler = FragmentReassembler::with_clock(MockClock::new(), DEFAULT_MAX_FRAGMENT_AGE);
+    assert_eq!(feed(&mut reassembler, &fragments), vec![message]);
+    assert_eq!(reassembler.pending(), 0);
+}
+
+#[test]
+fn missing_fragment_never_completes_and_is_cleaned_up() {
+    let message = payload(3000, 2);
+    let mut fragments = fragment_message(8, &message, 1000);
+    fragments.remove(1);
+
+    let clock = MockClock::new();
+    let mut reassembler = FragmentReassembler::with_clock(clock.clone(), DEFAULT_MAX_FRAGMENT_AGE);
+    assert!(feed(&mut reassembler, &fragments).is_empty());
+    assert_eq!(reassembler.pending(), 1);
+
+    clock.advance(DEFAULT_MAX_FRAGMENT_AGE - Duration::from_millis(1));
+    reassembler.cleanup_old();
+    assert_eq!(reassembler.pending(), 1);
+
+    clock.advance(Duration::from_millis(1));
+    reassembler.cleanup_old();
+    assert_eq!(reassembler.pending(), 0);
+}
+
+#[test]
+fn duplicate_fragments_do_not_corrupt_output() {
+    let message = payload(2500, 3);
+    let fragments = fragment_message(9, &message, 1000);
+    let duplicated = vec![
+        fragments[0].clone(),
+        fragments[0].clone(),
+        fragments[1].clone(),
+        fragments[1].clone(),
+        fragments[2].clone(),
+    ];
+
+    let mut reassembler = FragmentReassembler::with_clock(MockClock::new(), DEFAULT_MAX_FRAGMENT_AGE);
+    assert_eq!(feed(&mut reassembler, &duplicated), vec![message]);
+}
+
+#[test]
+fn interleaved_messages_reassemble_independently() {
+    let first = payload(3000, 4);
+    let second = payload(2000, 5);
+    let a = fragment_message(10, &first, 1000);
+    let b = fragment_message(11, &second, 1000);
+    let interleaved = vec![
+        a[0].clone(),
+        b[1].clone(),
+        a[2].clone(),
+        b[0].clone(),
+        a[1].clone(),
+    ];
+
+    let mut reassembler = FragmentReassembler::with_clock(MockClock::new(), DEFAULT_MAX_FRAGMENT_AGE);
+    assert_eq!(feed(&mut reassembler, &interleaved), vec![second, first]);
+    assert_eq!(reassembler.pending(), 0);
+}
@@ -0,0 +1,180 @@
+#![cfg(feature = "geyser")]
+
+use std::{
+    pin::Pin,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use solana_sdk::{
+    hash::Hash,
+    instruction::{AccountMeta, Instruction},
+    message::{v0, Message, VersionedMessage},
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::VersionedTransaction,
+};
+use test_shreds::{
+    detector::{process_entries, Detection, DetectorConfig, CREATE_DISC, CREATE_MIN_ACCOUNTS, PUMPFUN_PROGRAM_ID},
+    source::{
+        geyser::{
+            proto::{
+                self,
+                geyser_server::{Geyser, GeyserServer},
+                subscribe_update::UpdateOneof,
+                SubscribeRequest, SubscribeUpdate, SubscribeUpdateTransaction, SubscribeUpdateTransactionInfo,
+            },
+            versioned_transaction, GeyserConfig, GeyserConnector,
+        },
+        Commitment, PacketSource,
+    },
+};
+use tokio_stream::{wrappers::TcpListenerStream, Stream, StreamExt};
+use tonic::{Request, Response, Status, Streaming};
+
+fn create_transaction(mint: Pubkey) -> VersionedTransaction {
+    let program_id = Pubkey::from_str(PUMPFUN_PROGRAM_ID).unwrap();
+    let mut accounts: Vec<AccountMeta> =
+        (0..CREATE_MIN_ACCOUNTS).map(|_| AccountMeta::new(Pubkey::new_unique(), false)).collect();
+    accounts[0] = AccountMeta::new(mint, true);
+    let ix = Instruction::new_with_bytes(program_id, &CREATE_DISC, accounts);
+    let message = Message::new_with_blockhash(&[ix], Some(&Pubkey::new_unique()), &Hash::new_unique());
+    VersionedTransaction { signatures: vec![Signature::new_unique()], message: VersionedMessage::Legacy(message) }
+}
+
+/// The transaction as Yellowstone sends it
+fn to_proto(tx: &VersionedTransaction) -> proto::Transaction {
+    let message = &tx.message;
+    let header = message.header();
+    let lookups = message.address_table_lookups().unwrap_or_default();
+    proto::Transaction {
+        signatures: tx.signatures.iter().map(|s| s.as_ref().to_vec()).collect(),
+        message: Some(proto::Message {
+            header: Some(proto::MessageHeader {
+                num_required_signatures: header.num_required_signatures.into(),
+                num_readonly_signed_accounts: header.num_readonly_signed_accounts.into(),
+                num_readonly_unsigned_accounts: header.num_readonly_unsigned_accounts.into(),
+            }),
+            account_keys: message.static_account_keys().iter().map(|k| k.to_bytes().to_vec()).collect(),
+            recent_blockhash: message.recent_blockhash().to_bytes().to_vec(),
+            instructions: message
+                .instructions()
+                .iter()
+                .map(|ix| proto::CompiledInstruction {
+                    program_id_index: ix.program_id_index.into(),
+                    accounts: ix.accounts.clone(),
+                    data: ix.data.clone(),
+                })
+                .collect(),
+            versioned: matches!(message, VersionedMessage::V0(_)),
+            address_table_lookups: lookups
+                .iter()
+                .map(|l| proto::MessageAddressTableLookup {
+                    account_key: l.account_key.to_bytes().to_vec(),
+                    writable_indexes: l.writable_indexes.clone(),
+                    readonly_indexes: l.readonly_indexes.clone(),
+                })
+                .collect(),
+        }),
+    }
+}
+
+#[test]
+fn converts_yellowstone_transactions() {
+    let legacy = create_transaction(Pubkey::new_unique());
+    assert_eq!(versioned_transaction(to_proto(&legacy)), Some(legacy.clone()));
+
+    let VersionedMessage::Legacy(message) = &legacy.message else { unreachable!() };
+    let v0 = VersionedTransaction {
+        signatures: legacy.signatures.clone(),
+        message: VersionedMessage::V0(v0::Message {
+            header: message.header,
+            account_keys: message.account_keys.clone(),
+            recent_blockhash: message.recent_blockhash,
+            instructions: message.instructions.clone(),
+            address_table_lookups: vec![v0::MessageAddressTableLookup {
+                account_key: Pubkey::new_unique(),
+                writable_indexes: vec![0, 2],
+                readonly_indexes: vec![1],
+            }],
+        }),
+    };
+    assert_eq!(versioned_transaction(to_proto(&v0)), Some(v0));
+
+    let mut truncated = to_proto(&legacy);
+    truncated.message.as_mut().unwrap().account_keys[0].pop();
+    assert_eq!(versioned_transaction(truncated), None);
+}
+
+/// Each subscription's `x-token` and first request
+type Seen = Arc<Mutex<Vec<(Option<String>, SubscribeRequest)>>>;
+
+/// Streams `updates`, then keeps the subscription open
+struct MockGeyser {
+    updates: Vec<SubscribeUpdate>,
+    seen: Seen,
+}
+
+type UpdateStream = Pin<Box<dyn Stream<Item = Result<SubscribeUpdate, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl Geyser for MockGeyser {
+    type SubscribeStream = UpdateStream;
+
+    async fn subscribe(
+        &self,
+        request: Request<Streaming<SubscribeRequest>>,
+    ) -> Result<Response<Self::SubscribeStream>, Status> {
+        let token = request.metadata().get("x-token").map(|v| v.to_str().unwrap().to_string());
+        let mut requests = request.into_inner();
+        let first = requests.message().await?.expect("subscription request");
+        self.seen.lock().unwrap().push((token, first));
+        let updates = tokio_stream::iter(self.updates.clone().into_iter().map(Ok)).chain(tokio_stream::pending());
+        Ok(Response::new(Box::pin(updates)))
+    }
+}
+
+#[tokio::test]
+async fn streams_transactions_as_entry_datagrams() {
+    let mint = Pubkey::new_unique();
+    let update = SubscribeUpdate {
+        filters: vec!["watched".into()],
+        update_oneof: Some(UpdateOneof::Transaction(SubscribeUpdateTransaction {
+            transaction: Some(SubscribeUpdateTransactionInfo {
+                transaction: Some(to_proto(&create_transaction(mint))),
+                ..Default::default()
+            }),
+            slot: 7,
+        })),
+    };
+    let seen = Seen::default();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let service = GeyserServer::new(MockGeyser { updates: vec![update], seen: seen.clone() });
+    tokio::spawn(
+        tonic::transport::Server::builder().add_service(service).serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+
+    let config = DetectorConfig::new(Pubkey::from_str(PUMPFUN_PROGRAM_ID).unwrap(), false, None);
+    let connector = GeyserConnector::new(GeyserConfig {
+        endpoint: format!("http://{}", addr),
+        x_token: Some("secret".into()),
+        commitment: Commitment::Confirmed,
+        accounts: vec![config.program_id],
+    })
+    .unwrap();
+    let mut source = connector.connect().await.unwrap();
+
+    let mut buf = vec![0u8; 65536];
+    let (len, from) = tokio::time::timeout(Duration::from_secs(5), source.recv_from(&mut buf)).await.unwrap().unwrap();
+    assert_eq!(from, addr);
+    let processed = process_entries(&buf[..len], &config, 1).unwrap();
+    assert!(matches!(processed.detections[..], [Detection::Create { mint: m, .. }] if m == mint));
+
+    let seen = seen.lock().unwrap();
+    let (token, request) = &seen[0];
+    assert_eq!(token.as_deref(), Some("secret"));
+    assert_eq!(request.commitment, Some(proto::CommitmentLevel::Confirmed as i32));
+    assert_eq!(request.transactions["watched"].account_include, [PUMPFUN_PROGRAM_ID]);
+}
@@ -0,0 +1,67 @@
+#![cfg(feature = "grpc")]
+
+use std::time::Duration;
+
+use solana_sdk::pubkey::Pubkey;
+use test_shreds::{
+    detector::Detection,
+    sink::{
+        grpc::{
+            proto::{detection_event, detection_stream_client::DetectionStreamClient, Kind, SubscribeRequest},
+            GrpcSink,
+        },
+        DetectionEvent, Sink,
+    },
+};
+
+fn create(creator: Pubkey) -> Detection {
+    Detection::Create {
+        mint: Pubkey::new_unique(),
+        bonding_curve: Pubkey::new_unique(),
+        creator,
+        associated_bonding_curve: None,
+        metadata: None,
+        accounts: None,
+        compute_budget: None,
+        token_accounts: Vec::new(),
+        event: None,
+    }
+}
+
+#[tokio::test]
+async fn subscribers_receive_only_matching_detections() {
+    let addr = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+    let mut sink = GrpcSink::serve(&addr.to_string(), 16).unwrap();
+
+    let mut client = DetectionStreamClient::connect(format!("http://{}", addr)).await.unwrap();
+    let watched = Pubkey::new_unique();
+    let request = SubscribeRequest {
+        kinds: vec![Kind::Create as i32],
+        creators: vec![watched.to_string()],
+    };
+    let mut stream = client.subscribe(request).await.unwrap().into_inner();
+    // The subscription is registered once the response headers arrive
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let buy = Detection::Buy {
+        mint: Pubkey::new_unique(),
+        buyer: Pubkey::new_unique(),
+        token_amount: 1,
+        max_sol_cost: 2,
+        event: None,
+    };
+    sink.write(&DetectionEvent::new(buy, 1)).unwrap();
+    sink.write(&DetectionEvent::new(create(Pubkey::new_unique()), 2)).unwrap();
+    sink.write(&DetectionEvent::new(create(watched), 3)).unwrap();
+
+    let event = tokio::time::timeout(Duration::from_secs(5), stream.message())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert_eq!(event.msg_seq, 3);
+    match event.detection {
+        Some(detection_event::Detection::Create(create)) => assert_eq!(create.creator, watched.to_string()),
+        other => panic!("unexpected detection: {:?}", other),
+    }
+}
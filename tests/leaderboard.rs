@@ -0,0 +1,39 @@
+use solana_sdk::pubkey::Pubkey;
+use test_shreds::leaderboard::{CreatorLeaderboard, TopCreator};
+
+#[test]
+fn ranks_creators_and_resets_each_window() {
+    let (spammer, busy, quiet) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let mut leaderboard = CreatorLeaderboard::new(2, 16);
+    for creator in [busy, spammer, quiet, spammer, busy, spammer] {
+        leaderboard.record(creator);
+    }
+
+    let top = leaderboard.take();
+    assert_eq!(
+        top,
+        vec![TopCreator { creator: spammer, creates: 3 }, TopCreator { creator: busy, creates: 2 }]
+    );
+    assert_eq!(leaderboard.tracked(), 0);
+    assert!(leaderboard.take().is_empty());
+    assert_eq!(
+        serde_json::to_string(&top[0]).unwrap(),
+        format!(r#"{{"creator":"{}","creates":3}}"#, spammer)
+    );
+}
+
+#[test]
+fn evicts_the_fewest_creates_when_full() {
+    let (spammer, one_off) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut leaderboard = CreatorLeaderboard::new(10, 2);
+    leaderboard.record(spammer);
+    leaderboard.record(spammer);
+    leaderboard.record(one_off);
+
+    let newcomer = Pubkey::new_unique();
+    leaderboard.record(newcomer);
+    assert_eq!(leaderboard.tracked(), 2);
+    let top = leaderboard.take();
+    assert_eq!(top[0], TopCreator { creator: spammer, creates: 2 });
+    assert_eq!(top[1], TopCreator { creator: newcomer, creates: 1 });
+}
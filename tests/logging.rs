@@ -0,0 +1,69 @@
+use std::fs;
+
+use test_shreds::logging::{self, FileLogConfig, LogFormat, LogRotation};
+use tracing_appender::rolling::Rotation;
+
+#[test]
+fn parses_formats_and_rotations_in_any_case() {
+    for (value, format) in [
+        ("full", LogFormat::Full),
+        ("compact", LogFormat::Compact),
+        ("Pretty", LogFormat::Pretty),
+        ("JSON", LogFormat::Json),
+    ] {
+        assert_eq!(value.parse(), Ok(format));
+    }
+    for (value, rotation, appender) in [
+        ("minutely", LogRotation::Minutely, Rotation::MINUTELY),
+        ("Hourly", LogRotation::Hourly, Rotation::HOURLY),
+        ("DAILY", LogRotation::Daily, Rotation::DAILY),
+        ("never", LogRotation::Never, Rotation::NEVER),
+    ] {
+        assert_eq!(value.parse(), Ok(rotation));
+        assert_eq!(Rotation::from(rotation), appender);
+    }
+}
+
+#[test]
+fn rejects_unknown_formats_and_rotations() {
+    for value in ["", "jsonl", "text", " json"] {
+        assert_eq!(value.parse::<LogFormat>(), Err(format!("unknown log format: {}", value.to_ascii_lowercase())));
+    }
+    for value in ["", "weekly", "1h", "daily "] {
+        assert_eq!(value.parse::<LogRotation>(), Err(format!("unknown log rotation: {}", value)));
+    }
+}
+
+/// The only test here that installs the global subscriber, which a process can do once
+#[test]
+fn writes_the_file_layer_in_its_own_format() {
+    let dir = std::env::temp_dir().join(format!("test_shreds_logging_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let config = FileLogConfig {
+        path: dir.join("detector.log"),
+        rotation: LogRotation::Never,
+        retain: 0,
+        format: LogFormat::Json,
+    };
+
+    let logging = logging::init("info", LogFormat::Compact, Some(&config)).unwrap();
+    tracing::info!(mint = "abc", "kept");
+    assert!(logging.set_filter("not a [filter").is_err());
+    logging.set_filter("warn").unwrap();
+    tracing::info!("filtered out");
+    tracing::warn!("still kept");
+    // Dropping the guard flushes the file writer
+    drop(logging);
+
+    let lines: Vec<serde_json::Value> = fs::read_to_string(dir.join("detector.log"))
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("a JSON line"))
+        .collect();
+    let messages: Vec<_> = lines.iter().map(|line| line["fields"]["message"].as_str().unwrap()).collect();
+    assert_eq!(messages, ["kept", "still kept"]);
+    assert_eq!(lines[0]["fields"]["mint"], "abc");
+    assert_eq!(lines[1]["level"], "WARN");
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,551 @@
+mod common;
+
+use std::{
+    io,
+    net::SocketAddr,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use common::{buy_entries, create_entries, Capture};
+use solana_sdk::pubkey::Pubkey;
+use test_shreds::{
+    allowlist::{parse_ranges, SourceAllowList},
+    clock::{Clock, MockClock},
+    detector::{Detection, DetectorConfig, PUMPFUN_PROGRAM_ID},
+    envelope::{wrap, MessageContext},
+    pipeline::Pipeline,
+    price::SolPrice,
+    ratelimit::{SourceDrops, SourceRateLimiter},
+    reaction::ReactionTracker,
+    reassembler::{fragment_message, FragmentReassembler, DEFAULT_MAX_FRAGMENT_AGE},
+    score::{CreateScorer, ScoreConfig},
+    sink::{DetectionEvent, Sink, SinkHandle, Sinks},
+    source::MockSource,
+};
+
+/// Says when its worker has taken an event, then holds it there until released, so the queue behind it fills up
+struct Stall {
+    taken: std::sync::mpsc::Sender<()>,
+    release: std::sync::mpsc::Receiver<()>,
+}
+
+impl Sink for Stall {
+    fn write(&mut self, _: &DetectionEvent) -> io::Result<()> {
+        let _ = self.taken.send(());
+        let _ = self.release.recv();
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn detects_create_from_scripted_fragments() {
+    let clock = MockClock::new();
+    let config = DetectorConfig::new(Pubkey::from_str(PUMPFUN_PROGRAM_ID).unwrap(), false, None);
+    let reassembler = FragmentReassembler::with_clock(clock.clone(), DEFAULT_MAX_FRAGMENT_AGE);
+    let captured = Arc::new(Mutex::new(Vec::new()));
+    let mut sinks = Sinks::default();
+    sinks.push(SinkHandle::spawn("capture", Capture(captured.clone()), 16).unwrap());
+    let mut pipeline = Pipeline::new(config, reassembler, clock.clone())
+        .with_outputs(Arc::new(sinks), None)
+        .with_stats_interval(Duration::from_secs(15));
+
+    let metrics = pipeline.metrics();
+    let mint = Pubkey::new_unique();
+    let from: SocketAddr = "127.0.0.1:9000".parse().unwrap();
+    let fragments = fragment_message(1, &create_entries(mint), 200);
+    assert!(fragments.len() > 1);
+    let mut source = MockSource::new(clock.clone());
+    for fragment in fragments.iter().rev() {
+        source.push(Duration::from_millis(1), fragment.clone(), from);
+    }
+    // Unfragmented and undecodable; only there to move the clock past the stats interval
+    source.push(Duration::from_secs(15), b"noise".to_vec(), from);
+
+    let err = pipeline.run(&mut source, std::future::pending()).await.unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    assert_eq!(source.remaining(), 0);
+    assert_eq!(pipeline.msg_seq(), 2);
+
+    let window = pipeline.last_window().expect("stats window emitted");
+    assert_eq!(window.packets, fragments.len() as u64 + 1);
+    assert_eq!(window.entries, 1);
+    assert_eq!(window.transactions, 1);
+    assert_eq!(window.max_message_transactions, 1);
+    assert_eq!(window.creates, 1);
+    assert_eq!((window.min_packet, window.max_packet), (5, fragments[0].len()));
+    // The create's fragments, then the noise as a message of its own
+    assert_eq!(window.message_fragments, fragments.len() as u64 + 1);
+    assert_eq!(window.max_message_fragments as usize, fragments.len());
+    let run = pipeline.run_stats();
+    assert_eq!((run.totals.packets, run.totals.creates), (window.packets, 1));
+    assert_eq!(run.peak_pending, 1);
+    let counts = metrics.snapshot();
+    assert_eq!((counts.packets, counts.messages), (window.packets, 2));
+    assert_eq!((counts.creates, counts.buys, counts.undecodable, counts.dropped), (1, 0, 1, 0));
+
+    pipeline.finish().await;
+    let events = captured.lock().unwrap();
+    assert_eq!(events.len(), 1);
+    assert!(matches!(events[0].detection, Detection::Create { mint: m, .. } if m == mint));
+    assert_eq!(events[0].msg_seq, 1);
+}
+
+#[tokio::test]
+async fn values_buys_in_usd_only_with_a_price() {
+    for (price, expected) in [(Some(SolPrice::fixed(150.0)), Some(300.0)), (None, None)] {
+        let clock = MockClock::new();
+        let config = DetectorConfig::new(Pubkey::from_str(PUMPFUN_PROGRAM_ID).unwrap(), true, None);
+        let reassembler = FragmentReassembler::with_clock(clock.clone(), DEFAULT_MAX_FRAGMENT_AGE);
+        let captured = Arc::new(Mutex::new(Vec::new()));
+        let mut sinks = Sinks::default();
+        sinks.push(SinkHandle::spawn("capture", Capture(captured.clone()), 16).unwrap());
+        let mut pipeline = Pipeline::new(config, reassembler, clock.clone()).with_outputs(Arc::new(sinks), None);
+        if let Some(price) = price {
+            pipeline = pipeline.with_price(price);
+        }
+
+        let from: SocketAddr = "127.0.0.1:9000".parse().unwrap();
+        let mut source = MockSource::new(clock.clone());
+        source.push(Duration::from_millis(1), buy_entries(Pubkey::new_unique(), 2_000_000_000), from);
+        pipeline.run(&mut source, std::future::pending()).await.unwrap_err();
+        pipeline.finish().await;
+
+        let events = captured.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        assert!(matches!(events[0].detection, Detection::Buy { max_sol_cost: 2_000_000_000, .. }));
+        assert_eq!(events[0].usd_value, expected);
+        assert_eq!(serde_json::to_value(&events[0]).unwrap().get("usd_value").is_some(), expected.is_some());
+    }
+}
+
+#[tokio::test]
+async fn snapshots_each_stats_window() {
+    let clock = MockClock::new();
+    let config = DetectorConfig::new(Pubkey::from_str(PUMPFUN_PROGRAM_ID).unwrap(), false, None);
+    let reassembler = FragmentReassembler::with_clock(clock.clone(), DEFAULT_MAX_FRAGMENT_AGE);
+    let mut pipeline = Pipeline::new(config, reassembler, clock.clone()).with_structured_stats();
+
+    let from: SocketAddr = "127.0.0.1:9000".parse().unwrap();
+    let fragments = fragment_message(1, &create_entries(Pubkey::new_unique()), 200);
+    let mut source = MockSource::new(clock.clone());
+    for fragment in &fragments {
+        source.push(Duration::from_millis(1), fragment.clone(), from);
+    }
+    source.push(Duration::from_secs(15), b"noise".to_vec(), from);
+    pipeline.run(&mut source, std::future::pending()).await.unwrap_err();
+
+    let snapshot = pipeline.last_snapshot().expect("stats window emitted");
+    assert_eq!(snapshot.packets, pipeline.last_window().unwrap().packets);
+    assert_eq!((snapshot.messages, snapshot.creates, snapshot.undecodable), (2, 1, 1));
+    assert_eq!((snapshot.reassembled, snapshot.expired, snapshot.pending), (1, 0, 0));
+    assert_eq!(snapshot.loss_rate, Some(0.0));
+    let json = serde_json::to_value(snapshot).unwrap();
+    assert_eq!(json["creates"], 1);
+    assert!(json.get("reaction_p50_us").is_none());
+}
+
+#[tokio::test]
+async fn stops_on_shutdown() {
+    let clock = MockClock::new();
+    let config = DetectorConfig::new(Pubkey::from_str(PUMPFUN_PROGRAM_ID).unwrap(), false, None);
+    let reassembler = FragmentReassembler::with_clock(clock.clone(), DEFAULT_MAX_FRAGMENT_AGE);
+    let mut pipeline = Pipeline::new(config, reassembler, clock.clone());
+
+    let mut source = MockSource::new(clock);
+    source.push(Duration::ZERO, b"noise".to_vec(), "127.0.0.1:9000".parse().unwrap());
+    pipeline.run(&mut source, std::future::ready(())).await.unwrap();
+    assert!(pipeline.last_window().is_none());
+}
+
+#[tokio::test]
+async fn stops_after_max_packets() {
+    let clock = MockClock::new();
+    let config = DetectorConfig::new(Pubkey::from_str(PUMPFUN_PROGRAM_ID).unwrap(), false, None);
+    let reassembler = FragmentReassembler::with_clock(clock.clone(), DEFAULT_MAX_FRAGMENT_AGE);
+    let mut pipeline = Pipeline::new(config, reassembler, clock.clone()).with_max_packets(3);
+
+    let mut source = MockSource::new(clock);
+    for _ in 0..5 {
+        source.push(Duration::ZERO, b"noise".to_vec(), "127.0.0.1:9000".parse().unwrap());
+    }
+    pipeline.run(&mut source, std::future::pending()).await.unwrap();
+    assert_eq!(source.remaining(), 2);
+    assert_eq!(pipeline.metrics().snapshot().packets, 3);
+}
+
+#[tokio::test]
+async fn stops_after_max_runtime_unless_packet_limit_hits_first() {
+    let clock = MockClock::new();
+    let config = DetectorConfig::new(Pubkey::from_str(PUMPFUN_PROGRAM_ID).unwrap(), false, None);
+    let reassembler = FragmentReassembler::with_clock(clock.clone(), DEFAULT_MAX_FRAGMENT_AGE);
+    let mut pipeline = Pipeline::new(config.clone(), reassembler, clock.clone())
+        .with_max_runtime(Duration::from_millis(2500))
+        .with_max_packets(10);
+    let mut source = MockSource::new(clock.clone());
+    for _ in 0..5 {
+        source.push(Duration::from_secs(1), b"noise".to_vec(), "127.0.0.1:9000".parse().unwrap());
+    }
+    pipeline.run(&mut source, std::future::pending()).await.unwrap();
+    assert_eq!(source.remaining(), 2);
+
+    let reassembler = FragmentReassembler::with_clock(clock.clone(), DEFAULT_MAX_FRAGMENT_AGE);
+    let mut pipeline = Pipeline::new(config, reassembler, clock.clone())
+        .with_max_runtime(Duration::from_millis(2500))
+        .with_max_packets(2);
+    let mut source = MockSource::new(clock);
+    for _ in 0..5 {
+        source.push(Duration::from_secs(1), b"noise".to_vec(), "127.0.0.1:9000".parse().unwrap());
+    }
+    pipeline.run(&mut source, std::future::pending()).await.unwrap();
+    assert_eq!(source.remaining(), 3);
+}
+
+#[tokio::test]
+async fn answers_buffer_requests_between_datagrams() {
+    let clock = MockClock::new();
+    let config = DetectorConfig::new(Pubkey::from_str(PUMPFUN_PROGRAM_ID).unwrap(), false, None);
+    let reassembler = FragmentReassembler::with_clock(clock.clone(), DEFAULT_MAX_FRAGMENT_AGE);
+    let (requests, received) = tokio::sync::mpsc::channel(1);
+    let mut pipeline = Pipeline::new(config, reassembler, clock.clone()).with_buffer_inspection(received, 10);
+    let from: SocketAddr = "127.0.0.1:9000".parse().unwrap();
+
+    let mut source = MockSource::new(clock.clone());
+    let fragments = fragment_message(3, &create_entries(Pubkey::new_unique()), 64);
+    source.push(Duration::ZERO, fragments[1].clone(), from);
+    let _ = pipeline.run(&mut source, std::future::pending()).await;
+
+    let (reply, report) = tokio::sync::oneshot::channel();
+    requests.send(reply).await.unwrap();
+    let _ = pipeline.run(&mut source, std::future::pending()).await;
+    let report = report.await.unwrap();
+    assert_eq!(report.pending, 1);
+    assert_eq!(report.buffers[0].message_id, 3);
+    assert_eq!(report.buffers[0].source, Some(from));
+    assert_eq!(report.buffers[0].received, 1);
+    assert_eq!(report.buffers[0].missing[0], 0);
+}
+
+#[tokio::test]
+async fn reports_pressure_of_the_least_loaded_sink() {
+    let (taken, worker_took) = std::sync::mpsc::channel();
+    let (release, stalled) = std::sync::mpsc::channel();
+    let mut sinks = Sinks::default();
+    assert_eq!(sinks.pressure(), None);
+    sinks.push(SinkHandle::spawn("stall", Stall { taken, release: stalled }, 4).unwrap());
+    let heartbeat = || DetectionEvent::new(Detection::Heartbeat { packets: 0, pending: 0 }, 0);
+    // The worker takes the first and stalls on it, then the queue fills and the last is dropped
+    sinks.emit(heartbeat());
+    worker_took.recv().unwrap();
+    for _ in 0..5 {
+        sinks.emit(heartbeat());
+    }
+    assert_eq!(sinks.pressure(), Some(1.0));
+    assert_eq!(sinks.handles()[0].dropped(), 1);
+
+    let captured = Arc::new(Mutex::new(Vec::new()));
+    sinks.push(SinkHandle::spawn("capture", Capture(captured), 4).unwrap());
+    assert_eq!(sinks.pressure(), Some(0.0));
+
+    // Released, the worker takes the four queued behind the first
+    drop(release);
+    for _ in 0..4 {
+        worker_took.recv().unwrap();
+    }
+    assert_eq!(sinks.handles()[0].pressure(), 0.0);
+}
+
+/// Sends the size of each batch it is handed
+struct Batches(std::sync::mpsc::Sender<usize>);
+
+impl Sink for Batches {
+    fn write(&mut self, _: &DetectionEvent) -> io::Result<()> {
+        let _ = self.0.send(1);
+        Ok(())
+    }
+
+    fn write_batch(&mut self, events: &[Arc<DetectionEvent>]) -> io::Result<()> {
+        let _ = self.0.send(events.len());
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn batches_sink_writes_over_the_window_and_on_shutdown() {
+    let (written, batches) = std::sync::mpsc::channel();
+    let (end_window, window_ended) = std::sync::mpsc::channel::<()>();
+    // Each window lasts until the test ends it, or stops ending them
+    let wait = move |window| {
+        assert_eq!(window, Duration::from_millis(50));
+        let _ = window_ended.recv();
+    };
+    let mut sinks = Sinks::default();
+    let handle = SinkHandle::spawn_batched_with("batches", Batches(written), 16, Duration::from_millis(50), wait);
+    sinks.push(handle.unwrap());
+    let heartbeat = || DetectionEvent::new(Detection::Heartbeat { packets: 0, pending: 0 }, 0);
+
+    for _ in 0..3 {
+        sinks.emit(heartbeat());
+    }
+    end_window.send(()).unwrap();
+    assert_eq!(batches.recv().unwrap(), 3);
+
+    // Shutting down mid-window still writes the batch being gathered
+    sinks.emit(heartbeat());
+    sinks.emit(heartbeat());
+    drop(end_window);
+    sinks.shutdown();
+    assert_eq!(batches.try_iter().collect::<Vec<_>>(), vec![2]);
+}
+
+#[tokio::test]
+async fn applies_swapped_detector_config() {
+    let clock = MockClock::new();
+    let config = DetectorConfig::new(Pubkey::from_str(PUMPFUN_PROGRAM_ID).unwrap(), false, None);
+    let reassembler = FragmentReassembler::with_clock(clock.clone(), DEFAULT_MAX_FRAGMENT_AGE);
+    let captured = Arc::new(Mutex::new(Vec::new()));
+    let mut sinks = Sinks::default();
+    sinks.push(SinkHandle::spawn("capture", Capture(captured.clone()), 16).unwrap());
+    let mut pipeline = Pipeline::new(config, reassembler, clock.clone()).with_outputs(Arc::new(sinks), None);
+    let from: SocketAddr = "127.0.0.1:9000".parse().unwrap();
+
+    let mut source = MockSource::new(clock.clone());
+    source.push(Duration::ZERO, create_entries(Pubkey::new_unique()), from);
+    let _ = pipeline.run(&mut source, std::future::pending()).await;
+
+    // Watch a different program from the next message on
+    pipeline
+        .detector_config()
+        .store(Arc::new(DetectorConfig::new(Pubkey::new_unique(), false, None)));
+    source.push(Duration::ZERO, create_entries(Pubkey::new_unique()), from);
+    let _ = pipeline.run(&mut source, std::future::pending()).await;
+
+    assert_eq!(pipeline.msg_seq(), 2);
+    pipeline.finish().await;
+    assert_eq!(captured.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn sends_heartbeats_to_sinks() {
+    let clock = MockClock::new();
+    let config = DetectorConfig::new(Pubkey::from_str(PUMPFUN_PROGRAM_ID).unwrap(), false, None);
+    let reassembler = FragmentReassembler::with_clock(clock.clone(), DEFAULT_MAX_FRAGMENT_AGE);
+    let captured = Arc::new(Mutex::new(Vec::new()));
+    let mut sinks = Sinks::default();
+    sinks.push(SinkHandle::spawn("capture", Capture(captured.clone()), 16).unwrap());
+    let mut pipeline = Pipeline::new(config, reassembler, clock.clone())
+        .with_outputs(Arc::new(sinks), None)
+        .with_heartbeat(Duration::from_secs(1));
+
+    let from: SocketAddr = "127.0.0.1:9000".parse().unwrap();
+    let mut source = MockSource::new(clock.clone());
+    source.push(Duration::ZERO, b"noise".to_vec(), from);
+    source.push(Duration::from_millis(600), b"noise".to_vec(), from);
+    source.push(Duration::from_millis(500), b"noise".to_vec(), from);
+    let _ = pipeline.run(&mut source, std::future::pending()).await;
+
+    pipeline.finish().await;
+    let events = captured.lock().unwrap();
+    assert_eq!(events.len(), 1);
+    assert!(matches!(events[0].detection, Detection::Heartbeat { packets: 3, pending: 0 }));
+}
+
+#[tokio::test]
+async fn flushes_slots_on_timeout_while_the_source_is_quiet() {
+    let clock = MockClock::new();
+    let config = DetectorConfig::new(Pubkey::from_str(PUMPFUN_PROGRAM_ID).unwrap(), false, None);
+    let reassembler = FragmentReassembler::with_clock(clock.clone(), DEFAULT_MAX_FRAGMENT_AGE);
+    let captured = Arc::new(Mutex::new(Vec::new()));
+    let mut sinks = Sinks::default();
+    sinks.push(SinkHandle::spawn("capture", Capture(captured.clone()), 16).unwrap());
+    // Nothing else that housekeeping polls is enabled
+    let mut pipeline = Pipeline::new(config, reassembler, clock.clone())
+        .with_outputs(Arc::new(sinks), None)
+        .with_slot_batching(Duration::from_millis(400));
+    let metrics = pipeline.metrics();
+
+    let context = MessageContext {
+        slot: 100,
+        first_shred_index: 0,
+        last_shred_index: 1,
+        captured_at_us: 0,
+        proxy: "proxy".to_string(),
+        logs: Vec::new(),
+    };
+    let mut source = MockSource::new(clock.clone());
+    source.push(Duration::ZERO, wrap(&context, &create_entries(Pubkey::new_unique())), "127.0.0.1:9000".parse().unwrap());
+    source.stay_open();
+    // Once the create is in, the slot times out with no further packet to close it
+    let flushed = async {
+        while metrics.snapshot().messages == 0 {
+            tokio::task::yield_now().await;
+        }
+        clock.advance(Duration::from_millis(400));
+        while captured.lock().unwrap().len() < 2 {
+            tokio::task::yield_now().await;
+        }
+    };
+    let run = tokio::time::timeout(Duration::from_secs(5), pipeline.run(&mut source, flushed)).await;
+    run.expect("the slot was never flushed").unwrap();
+
+    let events = captured.lock().unwrap();
+    assert!(matches!(events[0].detection, Detection::Create { .. }));
+    assert!(matches!(events[1].detection, Detection::SlotComplete { slot: 100, detections: 1 }));
+}
+
+#[tokio::test]
+async fn reports_reassembly_completion_ratio() {
+    let clock = MockClock::new();
+    let config = DetectorConfig::new(Pubkey::from_str(PUMPFUN_PROGRAM_ID).unwrap(), false, None);
+    let reassembler = FragmentReassembler::with_clock(clock.clone(), DEFAULT_MAX_FRAGMENT_AGE);
+    let mut pipeline = Pipeline::new(config, reassembler, clock.clone())
+        .with_stats_interval(Duration::from_secs(5))
+        .with_completion_alert(0.9);
+    let metrics = pipeline.metrics();
+
+    let from: SocketAddr = "127.0.0.1:9000".parse().unwrap();
+    let mut source = MockSource::new(clock.clone());
+    for fragment in fragment_message(1, &create_entries(Pubkey::new_unique()), 200) {
+        source.push(Duration::ZERO, fragment, from);
+    }
+    // Never completed; still in flight when the first window closes
+    source.push(Duration::ZERO, fragment_message(2, &[0; 400], 200)[0].clone(), from);
+    source.push(Duration::from_secs(6), b"noise".to_vec(), from);
+    let _ = pipeline.run(&mut source, std::future::pending()).await;
+    let window = pipeline.last_window().unwrap();
+    assert_eq!((window.reassembled, window.expired), (1, 0));
+    assert_eq!(window.completion_ratio(), Some(1.0));
+
+    // Expires in the second window, and is counted there
+    source.push(Duration::from_secs(10), b"noise".to_vec(), from);
+    let _ = pipeline.run(&mut source, std::future::pending()).await;
+    let window = pipeline.last_window().unwrap();
+    assert_eq!((window.reassembled, window.expired), (0, 1));
+    assert_eq!(window.completion_ratio(), Some(0.0));
+    let counts = metrics.snapshot();
+    assert_eq!((counts.reassembled, counts.expired), (1, 1));
+    assert_eq!(counts.completion_ratio(), Some(0.5));
+}
+
+#[tokio::test]
+async fn drops_datagrams_over_a_sources_rate_limit() {
+    let clock = MockClock::new();
+    let config = DetectorConfig::new(Pubkey::from_str(PUMPFUN_PROGRAM_ID).unwrap(), false, None);
+    let reassembler = FragmentReassembler::with_clock(clock.clone(), DEFAULT_MAX_FRAGMENT_AGE);
+    let mut pipeline = Pipeline::new(config, reassembler, clock.clone())
+        .with_rate_limit(SourceRateLimiter::new(1.0, 2.0, 16, clock.now()))
+        .with_stats_interval(Duration::from_secs(5));
+
+    let flooder: SocketAddr = "10.0.0.1:9000".parse().unwrap();
+    let proxy: SocketAddr = "10.0.0.2:9000".parse().unwrap();
+    let mut source = MockSource::new(clock.clone());
+    for _ in 0..5 {
+        source.push(Duration::from_millis(1), b"flood".to_vec(), flooder);
+    }
+    let mint = Pubkey::new_unique();
+    source.push(Duration::from_millis(1), fragment_message(1, &create_entries(mint), 2000).remove(0), proxy);
+    source.push(Duration::from_secs(5), b"noise".to_vec(), proxy);
+
+    pipeline.run(&mut source, std::future::pending()).await.unwrap_err();
+    let window = pipeline.last_window().expect("stats window emitted");
+    assert_eq!((window.packets, window.rate_limited, window.creates), (7, 3, 1));
+    assert_eq!(window.rate_limited_sources, vec![SourceDrops { source: Some(flooder.ip()), dropped: 3 }]);
+    assert_eq!(pipeline.metrics().snapshot().rate_limited, 3);
+}
+
+#[tokio::test]
+async fn rejects_datagrams_from_sources_outside_the_allow_list() {
+    let clock = MockClock::new();
+    let config = DetectorConfig::new(Pubkey::from_str(PUMPFUN_PROGRAM_ID).unwrap(), false, None);
+    let reassembler = FragmentReassembler::with_clock(clock.clone(), DEFAULT_MAX_FRAGMENT_AGE);
+    let mut pipeline = Pipeline::new(config, reassembler, clock.clone())
+        .with_source_allow_list(SourceAllowList::new(parse_ranges("10.0.0.0/30").unwrap()))
+        .with_stats_interval(Duration::from_secs(5));
+
+    let stray: SocketAddr = "10.0.0.9:9000".parse().unwrap();
+    let proxy: SocketAddr = "10.0.0.2:9000".parse().unwrap();
+    let mut source = MockSource::new(clock.clone());
+    let create = |id| fragment_message(id, &create_entries(Pubkey::new_unique()), 2000).remove(0);
+    for id in 1..=3 {
+        source.push(Duration::from_millis(1), create(id), stray);
+    }
+    source.push(Duration::from_millis(1), create(4), proxy);
+    source.push(Duration::from_secs(5), b"noise".to_vec(), proxy);
+
+    pipeline.run(&mut source, std::future::pending()).await.unwrap_err();
+    let window = pipeline.last_window().expect("stats window emitted");
+    assert_eq!((window.packets, window.rejected, window.rejected_sources, window.creates), (5, 3, 1, 1));
+    assert_eq!(pipeline.metrics().snapshot().rejected, 3);
+}
+
+/// Acts on every detection a fixed delay after it was emitted
+struct Bot(Arc<ReactionTracker>);
+
+impl Sink for Bot {
+    fn write(&mut self, event: &DetectionEvent) -> io::Result<()> {
+        let (id, emitted_at) = (event.detection_id.unwrap(), event.emitted_at.unwrap());
+        self.0.mark_acted_at(id, emitted_at + Duration::from_millis(3));
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn stamps_detections_for_reaction_tracking() {
+    let clock = MockClock::new();
+    let config = DetectorConfig::new(Pubkey::from_str(PUMPFUN_PROGRAM_ID).unwrap(), false, None);
+    let reassembler = FragmentReassembler::with_clock(clock.clone(), DEFAULT_MAX_FRAGMENT_AGE);
+    let tracker = Arc::new(ReactionTracker::default());
+    let captured = Arc::new(Mutex::new(Vec::new()));
+    let mut sinks = Sinks::default();
+    sinks.push(SinkHandle::spawn("bot", Bot(tracker.clone()), 16).unwrap());
+    sinks.push(SinkHandle::spawn("capture", Capture(captured.clone()), 16).unwrap());
+    let mut pipeline = Pipeline::new(config, reassembler, clock.clone())
+        .with_outputs(Arc::new(sinks), None)
+        .with_reaction_tracking(tracker.clone());
+
+    let from: SocketAddr = "127.0.0.1:9000".parse().unwrap();
+    let mut source = MockSource::new(clock.clone());
+    for _ in 0..2 {
+        source.push(Duration::from_millis(1), create_entries(Pubkey::new_unique()), from);
+    }
+    pipeline.run(&mut source, std::future::pending()).await.unwrap_err();
+    pipeline.finish().await;
+
+    let ids: Vec<_> = captured.lock().unwrap().iter().map(|e| e.detection_id).collect();
+    assert_eq!(ids, [Some(0), Some(1)]);
+    let stats = tracker.stats();
+    assert_eq!((stats.emitted, stats.unknown, stats.histogram.count()), (2, 0, 2));
+    assert_eq!(stats.histogram.max(), Some(Duration::from_millis(3)));
+}
+
+#[tokio::test]
+async fn scores_creates_and_suppresses_those_under_the_minimum() {
+    // Each create has a new creator and nothing else going for it, so scores 20 under the default weights
+    for (min_score, sent) in [(20, 2), (21, 0)] {
+        let clock = MockClock::new();
+        let config = DetectorConfig::new(Pubkey::from_str(PUMPFUN_PROGRAM_ID).unwrap(), false, None);
+        let reassembler = FragmentReassembler::with_clock(clock.clone(), DEFAULT_MAX_FRAGMENT_AGE);
+        let captured = Arc::new(Mutex::new(Vec::new()));
+        let mut sinks = Sinks::default();
+        sinks.push(SinkHandle::spawn("capture", Capture(captured.clone()), 16).unwrap());
+        let scorer = CreateScorer::new(ScoreConfig { min_score, ..ScoreConfig::default() });
+        let mut pipeline = Pipeline::new(config, reassembler, clock.clone())
+            .with_outputs(Arc::new(sinks), None)
+            .with_create_scoring(scorer);
+
+        let from: SocketAddr = "127.0.0.1:9000".parse().unwrap();
+        let mut source = MockSource::new(clock.clone());
+        for _ in 0..2 {
+            source.push(Duration::from_millis(1), create_entries(Pubkey::new_unique()), from);
+        }
+        source.push(Duration::from_secs(15), b"noise".to_vec(), from);
+        pipeline.run(&mut source, std::future::pending()).await.unwrap_err();
+
+        let window = pipeline.last_window().unwrap();
+        assert_eq!((window.scores, window.creates, window.low_scores), ([0, 2, 0, 0, 0], 2, 2 - sent));
+        pipeline.finish().await;
+        let scores: Vec<_> = captured.lock().unwrap().iter().map(|e| e.score).collect();
+        assert_eq!(scores, vec![Some(20); sent]);
+    }
+}
@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+use test_shreds::price::{format_sol, usd_value, SolPrice};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+#[test]
+fn formats_sol_with_usd_only_when_priced() {
+    assert_eq!(format_sol(1_234_500_000, None), "1.2345 SOL");
+    assert_eq!(format_sol(1_500_000_000, Some(150.0)), "1.5000 SOL ≈ $225.00");
+    assert_eq!(format_sol(0, Some(150.0)), "0.0000 SOL ≈ $0.00");
+    assert_eq!(usd_value(2_000_000_000, Some(150.0)), Some(300.0));
+    assert_eq!(usd_value(2_000_000_000, None), None);
+}
+
+#[test]
+fn unusable_prices_count_as_unknown() {
+    assert_eq!(SolPrice::default().usd(), None);
+    assert_eq!(SolPrice::fixed(150.0).usd(), Some(150.0));
+    for usd in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+        assert_eq!(SolPrice::fixed(usd).usd(), None, "{}", usd);
+    }
+}
+
+/// Answers the first request with `body` and every later one with a 503, one connection each
+async fn serve_then_fail(listener: TcpListener, body: &'static str) {
+    for served in 0.. {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request = Vec::new();
+        let mut buf = [0; 1024];
+        while !request.ends_with(b"\r\n\r\n") {
+            match stream.read(&mut buf).await {
+                Ok(0) | Err(_) => break,
+                Ok(n) => request.extend_from_slice(&buf[..n]),
+            }
+        }
+        let response = match served {
+            0 => format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+                 Connection: close\r\n\r\n{}",
+                body.len(),
+                body
+            ),
+            _ => "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+        };
+        let _ = stream.write_all(response.as_bytes()).await;
+    }
+}
+
+/// Poll `price` until it reads `expected`, for up to five seconds
+async fn wait_for(price: &SolPrice, expected: Option<f64>) {
+    for _ in 0..500 {
+        if price.usd() == expected {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("price stayed at {:?}, expected {:?}", price.usd(), expected);
+}
+
+#[tokio::test]
+async fn refresher_falls_back_to_sol_only_once_the_endpoint_fails() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/price", listener.local_addr().unwrap());
+    tokio::spawn(serve_then_fail(listener, r#"{"solana":{"usd":"150.5"}}"#));
+
+    let price = SolPrice::spawn_refresher(url, "/solana/usd".into(), Duration::from_millis(20)).unwrap();
+    wait_for(&price, Some(150.5)).await;
+    // The 503s that follow leave the last price stale, so it's dropped rather than kept
+    wait_for(&price, None).await;
+    assert_eq!(format_sol(1_000_000_000, price.usd()), "1.0000 SOL");
+}
+
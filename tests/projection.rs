@@ -0,0 +1,127 @@
+use std::fs;
+
+use solana_sdk::pubkey::Pubkey;
+use test_shreds::{
+    detector::{ComputeBudget, Detection, InstructionKind, TokenAccount},
+    enrich::BondingCurveState,
+    envelope::MessageContext,
+    events::TxStatus,
+    sink::{
+        jsonl::JsonlSink,
+        projection::{Projection, FIELDS},
+        rotate::RotationConfig,
+        DetectionEvent, Sink,
+    },
+};
+
+fn create(mint: Pubkey) -> DetectionEvent {
+    let detection = Detection::Create {
+        mint,
+        bonding_curve: Pubkey::new_unique(),
+        creator: Pubkey::new_unique(),
+        associated_bonding_curve: Some(Pubkey::new_unique()),
+        metadata: Some(Pubkey::new_unique()),
+        accounts: Some(vec![mint]),
+        compute_budget: Some(ComputeBudget { unit_limit: Some(1), unit_price: Some(2) }),
+        token_accounts: vec![TokenAccount { owner: Pubkey::new_unique(), account: Pubkey::new_unique() }],
+        event: None,
+    };
+    DetectionEvent::new(detection, 7)
+}
+
+#[test]
+fn parses_field_lists() {
+    let projection: Projection = " mint, timestamp_ms ,kind".parse().unwrap();
+    assert_eq!(projection.fields(), Some(&["mint", "timestamp_ms", "kind"][..]));
+    assert_eq!(Projection::default().fields(), None);
+
+    assert!("mint,mints".parse::<Projection>().unwrap_err().contains("\"mints\""));
+    assert!("mint,kind,mint".parse::<Projection>().unwrap_err().contains("twice"));
+    assert!(" , ".parse::<Projection>().is_err());
+}
+
+#[test]
+fn writes_the_listed_fields_in_order() {
+    let mint = Pubkey::new_unique();
+    let event = create(mint);
+    let projection: Projection = "mint,kind,timestamp_ms,score".parse().unwrap();
+    let json = serde_json::to_string(&projection.apply(&event)).unwrap();
+    // No score without create scoring, so it's left out
+    assert_eq!(json, format!(r#"{{"mint":"{}","kind":"create","timestamp_ms":{}}}"#, mint, event.timestamp_ms));
+
+    let everything = serde_json::to_string(&Projection::default().apply(&event)).unwrap();
+    assert_eq!(everything, serde_json::to_string(&event).unwrap());
+}
+
+#[test]
+fn lists_every_field_events_carry() {
+    let key = Pubkey::new_unique;
+    let mut full = create(key());
+    full.bonding_curve_state = Some(BondingCurveState {
+        virtual_token_reserves: 1,
+        virtual_sol_reserves: 2,
+        real_token_reserves: 3,
+        real_sol_reserves: 4,
+        token_total_supply: 5,
+        complete: false,
+    });
+    full.context = Some(MessageContext {
+        slot: 1,
+        first_shred_index: 0,
+        last_shred_index: 1,
+        captured_at_us: 0,
+        proxy: "proxy".to_string(),
+        logs: Vec::new(),
+    });
+    full.status = Some(TxStatus::Succeeded);
+    full.detection_id = Some(1);
+    full.late = true;
+    full.score = Some(50);
+    full.usd_value = Some(1.5);
+    let detections = [
+        Detection::CreateWithDevBuy {
+            mint: key(),
+            bonding_curve: key(),
+            creator: key(),
+            dev_buy_sol: 1,
+            dev_buy_tokens: 2,
+            associated_bonding_curve: None,
+            metadata: None,
+            accounts: None,
+            compute_budget: None,
+            token_accounts: Vec::new(),
+            event: None,
+        },
+        Detection::Buy { mint: key(), buyer: key(), token_amount: 1, max_sol_cost: 2, event: None },
+        Detection::Migration { mint: key(), pool: key(), from_pumpfun: true },
+        Detection::MintInit { mint: key(), decimals: 6, authority: key(), token_2022: false },
+        Detection::Unknown { program: key(), discriminator: vec![1], data_hex: "01".into(), data_len: 1 },
+        Detection::Maintenance { instruction: InstructionKind::ExtendAccount, account: key(), user: key() },
+        Detection::Heartbeat { packets: 1, pending: 2 },
+        Detection::SlotComplete { slot: 1, detections: 2 },
+    ];
+
+    let events = std::iter::once(full).chain(detections.into_iter().map(|d| DetectionEvent::new(d, 1)));
+    for event in events {
+        let json = serde_json::to_value(&event).unwrap();
+        for field in json.as_object().unwrap().keys() {
+            assert!(FIELDS.contains(&field.as_str()), "{} isn't in FIELDS", field);
+        }
+    }
+}
+
+#[test]
+fn jsonl_sink_writes_projected_lines() {
+    let path = std::env::temp_dir().join(format!("test_shreds_projection_{}.jsonl", std::process::id()));
+    let _ = fs::remove_file(&path);
+    let projection = "msg_seq,mint".parse().unwrap();
+    let mut sink = JsonlSink::open(&path, RotationConfig::default()).unwrap().with_projection(projection);
+    let mint = Pubkey::new_unique();
+    sink.write(&create(mint)).unwrap();
+    sink.write(&DetectionEvent::new(Detection::Heartbeat { packets: 1, pending: 0 }, 8)).unwrap();
+    sink.flush().unwrap();
+
+    let written = fs::read_to_string(&path).unwrap();
+    assert_eq!(written, format!("{{\"msg_seq\":7,\"mint\":\"{}\"}}\n{{\"msg_seq\":8}}\n", mint));
+    let _ = fs::remove_file(&path);
+}
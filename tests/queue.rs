@@ -0,0 +1,45 @@
+use std::{io, net::SocketAddr, time::Duration};
+
+use test_shreds::{
+    clock::MockClock,
+    source::{MockSource, PacketSource, QueuePolicy, QueuedSource},
+};
+
+fn scripted(count: u8) -> MockSource {
+    let from: SocketAddr = "127.0.0.1:9000".parse().unwrap();
+    let mut source = MockSource::new(MockClock::new());
+    for i in 0..count {
+        source.push(Duration::ZERO, vec![i], from);
+    }
+    source
+}
+
+#[tokio::test]
+async fn drop_policy_counts_overflow() {
+    let mut queued = QueuedSource::spawn(scripted(5), 2, QueuePolicy::Drop);
+    let mut buf = [0u8; 16];
+
+    assert_eq!(queued.recv_from(&mut buf).await.unwrap().0, 1);
+    assert_eq!(buf[0], 0);
+    queued.recv_from(&mut buf).await.unwrap();
+    assert_eq!(buf[0], 1);
+    let err = queued.recv_from(&mut buf).await.unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+    let stats = queued.take_queue_stats().unwrap();
+    assert_eq!(stats.dropped, 3);
+    assert_eq!(queued.take_queue_stats().unwrap().dropped, 0);
+}
+
+#[tokio::test]
+async fn block_policy_delivers_everything() {
+    let mut queued = QueuedSource::spawn(scripted(5), 1, QueuePolicy::Block);
+    let mut buf = [0u8; 16];
+
+    for i in 0..5 {
+        queued.recv_from(&mut buf).await.unwrap();
+        assert_eq!(buf[0], i);
+    }
+    assert!(queued.recv_from(&mut buf).await.is_err());
+    assert_eq!(queued.take_queue_stats().unwrap().dropped, 0);
+}
@@ -0,0 +1,41 @@
+use std::{
+    net::IpAddr,
+    time::{Duration, Instant},
+};
+
+use test_shreds::ratelimit::{SourceDrops, SourceRateLimiter};
+
+#[test]
+fn allows_bursts_then_the_rate() {
+    let start = Instant::now();
+    let (flooder, other): (IpAddr, IpAddr) = ("10.0.0.1".parse().unwrap(), "10.0.0.2".parse().unwrap());
+    let mut limiter = SourceRateLimiter::new(10.0, 5.0, 16, start);
+
+    assert_eq!((0..8).filter(|_| limiter.allow(flooder, start)).count(), 5);
+    // One source's flood leaves the others alone
+    assert!(limiter.allow(other, start));
+    // 10/s refills one token every 100ms
+    assert!(!limiter.allow(flooder, start + Duration::from_millis(50)));
+    assert!(limiter.allow(flooder, start + Duration::from_millis(150)));
+
+    assert_eq!(limiter.take_dropped(), vec![SourceDrops { source: Some(flooder), dropped: 4 }]);
+    assert!(limiter.take_dropped().is_empty());
+}
+
+#[test]
+fn shares_one_bucket_past_max_sources_and_prunes_idle_ones() {
+    let start = Instant::now();
+    let mut limiter = SourceRateLimiter::new(1.0, 1.0, 1, start);
+    let source = |i: u8| IpAddr::from([10, 0, 0, i]);
+
+    assert!(limiter.allow(source(1), start));
+    // Sources 2 and 3 share the overflow bucket
+    assert!(limiter.allow(source(2), start));
+    assert!(!limiter.allow(source(3), start));
+    assert_eq!(limiter.take_dropped(), vec![SourceDrops { source: None, dropped: 1 }]);
+
+    limiter.prune(start + Duration::from_secs(1));
+    assert_eq!(limiter.tracked(), 0);
+    assert!(limiter.allow(source(3), start + Duration::from_secs(1)));
+    assert_eq!(limiter.tracked(), 1);
+}
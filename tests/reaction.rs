@@ -0,0 +1,69 @@
+use std::time::{Duration, Instant};
+
+use test_shreds::reaction::ReactionTracker;
+
+#[test]
+fn times_the_round_trip_to_acting_on_a_detection() {
+    let tracker = ReactionTracker::new(16);
+    let start = Instant::now();
+    let ids: Vec<u64> = (0..4).map(|i| tracker.emit(start + Duration::from_millis(i))).collect();
+    assert_eq!(ids, [0, 1, 2, 3]);
+
+    assert_eq!(tracker.mark_acted_at(1, start + Duration::from_millis(3)), Some(Duration::from_millis(2)));
+    assert_eq!(tracker.mark_acted_at(3, start + Duration::from_millis(3)), Some(Duration::ZERO));
+    // Acting twice, or on an id never emitted, isn't timed
+    assert_eq!(tracker.mark_acted_at(1, start + Duration::from_millis(9)), None);
+    assert_eq!(tracker.mark_acted_at(99, start), None);
+
+    let stats = tracker.stats();
+    assert_eq!((stats.emitted, stats.unknown, stats.histogram.count()), (4, 2, 2));
+    assert_eq!(stats.histogram.min(), Some(Duration::ZERO));
+    assert_eq!(stats.histogram.max(), Some(Duration::from_millis(2)));
+    assert_eq!(stats.histogram.mean(), Some(Duration::from_millis(1)));
+
+    // The window resets on take; the totals don't
+    assert_eq!(tracker.take_window().count(), 2);
+    assert_eq!(tracker.take_window().count(), 0);
+    assert_eq!(tracker.stats().histogram.count(), 2);
+}
+
+#[test]
+fn forgets_the_oldest_emissions_past_max_outstanding() {
+    let tracker = ReactionTracker::new(2);
+    let now = Instant::now();
+    for _ in 0..3 {
+        tracker.emit(now);
+    }
+    assert_eq!(tracker.mark_acted_at(0, now), None);
+    assert_eq!(tracker.mark_acted_at(1, now), Some(Duration::ZERO));
+    assert_eq!(tracker.mark_acted_at(2, now), Some(Duration::ZERO));
+    assert_eq!(tracker.stats().unknown, 1);
+}
+
+#[test]
+fn reports_percentiles_as_bucket_upper_bounds() {
+    let tracker = ReactionTracker::default();
+    let start = Instant::now();
+    // 90 round trips of 100µs, 9 of 1ms and one of 50ms
+    for (count, micros) in [(90, 100), (9, 1_000), (1, 50_000)] {
+        for _ in 0..count {
+            let id = tracker.emit(start);
+            tracker.mark_acted_at(id, start + Duration::from_micros(micros));
+        }
+    }
+    let histogram = tracker.stats().histogram;
+    assert_eq!(histogram.percentile(0.5), Some(Duration::from_micros(128)));
+    assert_eq!(histogram.percentile(0.9), Some(Duration::from_micros(128)));
+    assert_eq!(histogram.percentile(0.99), Some(Duration::from_micros(1024)));
+    // Capped at the slowest round trip rather than its bucket's 65.5ms bound
+    assert_eq!(histogram.percentile(1.0), Some(Duration::from_millis(50)));
+    let buckets: Vec<_> = histogram.buckets().collect();
+    assert_eq!(
+        buckets,
+        [
+            (Duration::from_micros(128), 90),
+            (Duration::from_micros(1024), 9),
+            (Duration::from_micros(65_536), 1)
+        ]
+    );
+}
@@ -0,0 +1,295 @@
+use std::time::Duration;
+
+use test_shreds::{
+    clock::MockClock,
+    reassembler::{
+        fragment_message, BufferInfo, FragmentReassembler, FragmentSizes, DEFAULT_MAX_FRAGMENT_AGE, MAGIC,
+        MAX_LISTED_MISSING,
+    },
+};
+
+/// Build a SHRD-framed fragment with an explicit header
+fn fragment(message_id: u32, index: u16, total: u16, total_size: u32, payload: &[u8]) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(16 + payload.len());
+    packet.extend_from_slice(MAGIC);
+    packet.extend_from_slice(&message_id.to_le_bytes());
+    packet.extend_from_slice(&index.to_le_bytes());
+    packet.extend_from_slice(&total.to_le_bytes());
+    packet.extend_from_slice(&total_size.to_le_bytes());
+    packet.extend_from_slice(payload);
+    packet
+}
+
+#[test]
+fn drops_message_when_total_size_disagrees() {
+    let mut reassembler = FragmentReassembler::new();
+
+    // Header claims 10 bytes but the fragments only carry 7
+    assert_eq!(reassembler.process_packet(&fragment(1, 0, 2, 10, b"abcd")), None);
+    assert_eq!(reassembler.process_packet(&fragment(1, 1, 2, 10, b"efg")), None);
+
+    assert_eq!(reassembler.pending(), 0);
+    assert_eq!(reassembler.take_stats().size_mismatches, 1);
+}
+
+#[test]
+fn completes_message_when_total_size_matches() {
+    let mut reassembler = FragmentReassembler::new();
+
+    assert_eq!(reassembler.process_packet(&fragment(2, 0, 2, 7, b"abcd")), None);
+    assert_eq!(
+        reassembler.process_packet(&fragment(2, 1, 2, 7, b"efg")),
+        Some(b"abcdefg".to_vec())
+    );
+    assert_eq!(reassembler.take_stats().size_mismatches, 0);
+}
+
+#[test]
+fn single_fragment_message_skips_buffering() {
+    let mut reassembler = FragmentReassembler::new();
+
+    assert_eq!(reassembler.process_packet(&fragment(4, 0, 1, 3, b"abc")), Some(b"abc".to_vec()));
+    assert_eq!(reassembler.pending(), 0);
+
+    // A short payload or a non-zero index is dropped like any size mismatch
+    assert_eq!(reassembler.process_packet(&fragment(5, 0, 1, 4, b"abc")), None);
+    assert_eq!(reassembler.process_packet(&fragment(6, 1, 1, 3, b"abc")), None);
+    assert_eq!(reassembler.pending(), 0);
+    assert_eq!(reassembler.take_stats().size_mismatches, 2);
+}
+
+#[test]
+fn drops_fragments_with_out_of_range_index() {
+    let mut reassembler = FragmentReassembler::new();
+
+    assert_eq!(reassembler.process_packet(&fragment(7, u16::MAX, u16::MAX, 3, b"abc")), None);
+    assert_eq!(reassembler.process_packet(&fragment(8, 0, 0, 3, b"abc")), None);
+    // The first fragment seen fixes the count for the rest of the message
+    assert_eq!(reassembler.process_packet(&fragment(9, 0, 2, 6, b"abc")), None);
+    assert_eq!(reassembler.process_packet(&fragment(9, 2, 3, 6, b"def")), None);
+    assert_eq!(reassembler.pending(), 1);
+    assert_eq!(reassembler.take_stats().invalid_fragments, 3);
+}
+
+#[test]
+fn huge_declared_total_size_is_a_mismatch() {
+    // Without the plausibility check, the assembled length still catches it
+    let mut reassembler = FragmentReassembler::new().with_size_tolerance(0.0);
+
+    assert_eq!(reassembler.process_packet(&fragment(10, 0, 2, u32::MAX, b"abc")), None);
+    assert_eq!(reassembler.process_packet(&fragment(10, 1, 2, u32::MAX, b"def")), None);
+    assert_eq!(reassembler.take_stats().size_mismatches, 1);
+}
+
+#[test]
+fn drops_fragments_with_an_implausible_total_size_before_buffering() {
+    let mut reassembler = FragmentReassembler::new();
+
+    // Zero, far more than two 3-byte fragments could carry, and less than one of them carries
+    for (message_id, total_size) in [(20, 0), (21, u32::MAX), (22, 2)] {
+        assert_eq!(reassembler.process_packet(&fragment(message_id, 0, 2, total_size, b"abc")), None);
+        assert_eq!(reassembler.process_packet(&fragment(message_id, 1, 2, total_size, b"def")), None);
+    }
+    // 100 fragments of 3 bytes can't make 10, and a final fragment can't outweigh its message
+    assert_eq!(reassembler.process_packet(&fragment(23, 0, 100, 10, b"abc")), None);
+    assert_eq!(reassembler.process_packet(&fragment(24, 99, 100, 2, b"abc")), None);
+    assert_eq!(reassembler.pending(), 0);
+    let stats = reassembler.take_stats();
+    assert_eq!((stats.implausible_sizes, stats.size_mismatches), (8, 0));
+
+    // Within the tolerance, a sender's off-by-a-few total still reaches the assembled-length check
+    assert_eq!(reassembler.process_packet(&fragment(25, 0, 2, 10, b"abcd")), None);
+    assert_eq!(reassembler.process_packet(&fragment(25, 1, 2, 10, b"efg")), None);
+    let stats = reassembler.take_stats();
+    assert_eq!((stats.implausible_sizes, stats.size_mismatches), (0, 1));
+}
+
+#[test]
+fn snapshot_restores_in_flight_buffers() {
+    let mut reassembler = FragmentReassembler::new();
+    assert_eq!(reassembler.process_packet(&fragment(3, 0, 2, 7, b"abcd")), None);
+
+    let mut restored = FragmentReassembler::restore(&reassembler.snapshot()).unwrap();
+    assert_eq!(restored.pending(), 1);
+    assert_eq!(
+        restored.process_packet(&fragment(3, 1, 2, 7, b"efg")),
+        Some(b"abcdefg".to_vec())
+    );
+}
+
+#[test]
+fn restore_prunes_buffers_past_max_age() {
+    let clock = MockClock::new();
+    let mut reassembler = FragmentReassembler::with_clock(clock.clone(), DEFAULT_MAX_FRAGMENT_AGE);
+    assert_eq!(reassembler.process_packet(&fragment(4, 0, 2, 7, b"abcd")), None);
+    clock.advance(DEFAULT_MAX_FRAGMENT_AGE);
+    assert_eq!(reassembler.process_packet(&fragment(5, 0, 2, 7, b"hijk")), None);
+    // Not cleaned up yet, so both are in the snapshot
+    assert_eq!(reassembler.pending(), 2);
+
+    let mut restored = FragmentReassembler::restore_with_clock(&reassembler.snapshot(), MockClock::new()).unwrap();
+    assert_eq!(restored.pending(), 1);
+    assert_eq!(restored.missing_fragments(4), None);
+    assert_eq!(restored.missing_fragments(5), Some(vec![1]));
+    assert_eq!(restored.process_packet(&fragment(5, 1, 2, 7, b"lmn")), Some(b"hijklmn".to_vec()));
+}
+
+#[test]
+fn restore_rejects_garbage() {
+    assert!(FragmentReassembler::restore(b"not a snapshot").is_err());
+}
+
+#[test]
+fn tracks_fragment_payload_sizes() {
+    let mut reassembler = FragmentReassembler::new();
+    let payload = vec![7u8; 2500];
+    for packet in fragment_message(1, &payload, 1000) {
+        reassembler.process_packet(&packet);
+    }
+
+    let sizes = reassembler.take_stats().fragment_sizes;
+    assert_eq!((sizes.fragments, sizes.min, sizes.typical, sizes.max), (3, 500, 1000, 1000));
+    assert_eq!(reassembler.take_stats().fragment_sizes, FragmentSizes::default());
+}
+
+#[test]
+fn cleanup_old_removes_exactly_the_expired_buffers() {
+    let clock = MockClock::new();
+    let mut reassembler = FragmentReassembler::with_clock(clock.clone(), DEFAULT_MAX_FRAGMENT_AGE);
+
+    // First fragments at 0s, 2s, 3s and 11s, so at 12s the buffers are 12s, 10s, 9s and 1s old
+    let mut elapsed = 0;
+    for (message_id, at) in [(1, 0), (2, 2), (3, 3), (4, 11)] {
+        clock.advance(Duration::from_secs(at - elapsed));
+        elapsed = at;
+        assert_eq!(reassembler.process_packet(&fragment(message_id, 0, 2, 7, b"abcd")), None);
+    }
+    clock.advance(Duration::from_secs(12 - elapsed));
+    reassembler.cleanup_old();
+
+    // A buffer exactly max_age old has expired
+    assert_eq!(reassembler.pending(), 2);
+    assert_eq!(reassembler.take_stats().expired, 2);
+    for message_id in [3, 4] {
+        assert_eq!(
+            reassembler.process_packet(&fragment(message_id, 1, 2, 7, b"efg")),
+            Some(b"abcdefg".to_vec())
+        );
+    }
+    // The expired messages' first fragments are gone, so their last ones start over
+    assert_eq!(reassembler.process_packet(&fragment(1, 1, 2, 7, b"efg")), None);
+    assert_eq!(reassembler.pending(), 1);
+
+    // Nothing else has aged out yet, so a second pass changes nothing
+    reassembler.cleanup_old();
+    let stats = reassembler.take_stats();
+    assert_eq!((stats.expired, stats.reassembled), (0, 2));
+}
+
+#[test]
+fn rejects_new_messages_beyond_max_pending() {
+    let clock = MockClock::new();
+    let mut reassembler = FragmentReassembler::with_clock(clock.clone(), DEFAULT_MAX_FRAGMENT_AGE).with_max_pending(3);
+
+    for message_id in 0..10 {
+        assert_eq!(reassembler.process_packet(&fragment(message_id, 0, 2, 7, b"abcd")), None);
+    }
+    assert_eq!(reassembler.pending(), 3);
+    assert_eq!(reassembler.take_stats().over_capacity, 7);
+
+    // Pending messages still complete, and single-fragment ones are never buffered
+    assert_eq!(reassembler.process_packet(&fragment(0, 1, 2, 7, b"efg")), Some(b"abcdefg".to_vec()));
+    assert_eq!(reassembler.process_packet(&fragment(20, 0, 1, 3, b"xyz")), Some(b"xyz".to_vec()));
+
+    // Completing one made room for exactly one more
+    assert_eq!(reassembler.process_packet(&fragment(5, 0, 2, 7, b"abcd")), None);
+    assert_eq!(reassembler.process_packet(&fragment(6, 0, 2, 7, b"abcd")), None);
+    assert_eq!((reassembler.pending(), reassembler.take_stats().over_capacity), (3, 1));
+
+    // As does expiry
+    clock.advance(DEFAULT_MAX_FRAGMENT_AGE);
+    reassembler.cleanup_old();
+    assert_eq!(reassembler.process_packet(&fragment(7, 0, 2, 7, b"abcd")), None);
+    assert_eq!((reassembler.pending(), reassembler.take_stats().over_capacity), (1, 0));
+}
+
+#[test]
+fn tracks_missing_fragments_across_bitmap_words() {
+    let mut reassembler = FragmentReassembler::new();
+    let payload: Vec<u8> = (0..130).collect();
+    let fragments = fragment_message(11, &payload, 1);
+    assert_eq!(fragments.len(), 130);
+    assert_eq!(reassembler.missing_fragments(11), None);
+
+    // Word boundaries first, then a repeat that must not count twice
+    for index in [64, 63, 129, 0, 64] {
+        assert_eq!(reassembler.process_packet(&fragments[index]), None);
+    }
+    let missing = reassembler.missing_fragments(11).unwrap();
+    assert_eq!(missing.len(), 126);
+    assert_eq!(&missing[..3], &[1, 2, 3]);
+    assert!(!missing.iter().any(|i| [0, 63, 64, 129].contains(i)));
+    assert_eq!((missing[61], missing[62], missing[125]), (62, 65, 128));
+
+    let mut restored = FragmentReassembler::restore(&reassembler.snapshot()).unwrap();
+    assert_eq!(restored.missing_fragments(11), Some(missing));
+
+    let mut complete = None;
+    for (index, packet) in fragments.iter().enumerate().rev() {
+        if ![0, 63, 64, 129].contains(&index) {
+            complete = restored.process_packet(packet);
+        }
+    }
+    assert_eq!(complete, Some(payload));
+    assert_eq!(restored.missing_fragments(11), None);
+}
+
+#[test]
+fn largest_fragment_index_fills_the_last_bitmap_word() {
+    let mut reassembler = FragmentReassembler::new();
+
+    assert_eq!(reassembler.process_packet(&fragment(12, u16::MAX - 1, u16::MAX, 1, b"a")), None);
+    let missing = reassembler.missing_fragments(12).unwrap();
+    assert_eq!((missing.len(), missing.last()), (u16::MAX as usize - 1, Some(&(u16::MAX - 2))));
+    assert_eq!(reassembler.take_stats().invalid_fragments, 0);
+}
+
+#[test]
+fn inspect_lists_oldest_buffers_first() {
+    let clock = MockClock::new();
+    let mut reassembler = FragmentReassembler::with_clock(clock.clone(), DEFAULT_MAX_FRAGMENT_AGE);
+    let source = "10.0.0.5:8001".parse().unwrap();
+
+    reassembler.process_packet_from(&fragment(7, 2, 4, 8, b"cc"), source);
+    clock.advance(Duration::from_millis(300));
+    reassembler.process_packet(&fragment(8, 0, 1000, 4000, b"aa"));
+    clock.advance(Duration::from_millis(200));
+    reassembler.process_packet(&fragment(9, 0, 2, 4, b"aa"));
+
+    let report = reassembler.inspect(2);
+    assert_eq!(report.pending, 3);
+    assert_eq!(
+        report.buffers[0],
+        BufferInfo { message_id: 7, source: Some(source), age_ms: 500, received: 1, total_fragments: 4, missing: vec![0, 1, 3] }
+    );
+    assert_eq!(report.buffers[1].message_id, 8);
+    assert_eq!(report.buffers[1].source, None);
+    assert_eq!(report.buffers[1].missing.len(), MAX_LISTED_MISSING);
+    assert_eq!(report.buffers.len(), 2);
+}
+
+#[test]
+fn fragments_hint_leaves_reassembly_unchanged() {
+    let payload: Vec<u8> = (0..=255).cycle().take(10_000).collect();
+    // Smaller, equal to and larger than the message's fragment count
+    for hint in [1, 10, 1000] {
+        let mut reassembler = FragmentReassembler::new().with_fragments_hint(hint);
+        let mut packets = fragment_message(7, &payload, 1000);
+        packets.reverse();
+        let (last, rest) = packets.split_last().unwrap();
+        for packet in rest {
+            assert_eq!(reassembler.process_packet(packet), None);
+        }
+        assert_eq!(reassembler.process_packet(last).as_deref(), Some(payload.as_slice()));
+    }
+}
@@ -0,0 +1,25 @@
+use test_shreds::reload::{is_reloadable, parse, parse_lines};
+
+#[test]
+fn accepts_pipeline_scoped_reloadable_keys() {
+    assert!(is_reloadable("DETECT_BUYS"));
+    assert!(is_reloadable("FILTER_LIST_WATCHLIST"));
+    assert!(is_reloadable("PIPELINE_MAIN_DETECTION_FILTER"));
+    assert!(is_reloadable("PIPELINE_RAYDIUM_AMM_FILTER_LIST_WATCHLIST"));
+    assert!(!is_reloadable("PIPELINE_MAIN_UDP_BIND_ADDR"));
+    assert!(!is_reloadable("PIPELINE__DETECT_BUYS"));
+}
+
+#[test]
+fn splits_reloadable_from_ignored() {
+    let overrides = parse("export PIPELINE_A_DETECT_BUYS=true\n# comment\nPIPELINE_A_JSONL_PATH=\"out.jsonl\"\n");
+    assert_eq!(overrides.values.get("PIPELINE_A_DETECT_BUYS").map(String::as_str), Some("true"));
+    assert_eq!(overrides.ignored, vec!["PIPELINE_A_JSONL_PATH"]);
+}
+
+#[test]
+fn parse_lines_keeps_every_key_in_order() {
+    let pairs = parse_lines("UDP_BIND_ADDR=0.0.0.0:9001\n\nexport DETECT_BUYS = \"true\"\nnot a setting\n");
+    let pairs: Vec<(&str, &str)> = pairs.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+    assert_eq!(pairs, [("UDP_BIND_ADDR", "0.0.0.0:9001"), ("DETECT_BUYS", "true")]);
+}
@@ -0,0 +1,68 @@
+use std::{str::FromStr, time::Duration};
+
+use solana_sdk::pubkey::Pubkey;
+use test_shreds::{
+    detector::{DetectorConfig, PUMPFUN_PROGRAM_ID},
+    pcap::PcapReader,
+    reassembler::fragment_message,
+    replay::{replay, ReplaySpeed},
+};
+
+/// Build a raw-IP (link type 101) pcap holding one IPv4/UDP datagram per payload
+fn pcap(packets: &[(Duration, Vec<u8>)]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&0xa1b2c3d4u32.to_le_bytes());
+    out.extend_from_slice(&2u16.to_le_bytes());
+    out.extend_from_slice(&4u16.to_le_bytes());
+    out.extend_from_slice(&[0; 8]);
+    out.extend_from_slice(&65535u32.to_le_bytes());
+    out.extend_from_slice(&101u32.to_le_bytes());
+
+    for (ts, payload) in packets {
+        let udp_len = 8 + payload.len();
+        let mut ip = vec![0x45, 0, 0, 0, 0, 0, 0, 0, 64, 17, 0, 0, 127, 0, 0, 1, 127, 0, 0, 1];
+        ip[2..4].copy_from_slice(&((20 + udp_len) as u16).to_be_bytes());
+        ip.extend_from_slice(&9000u16.to_be_bytes());
+        ip.extend_from_slice(&9001u16.to_be_bytes());
+        ip.extend_from_slice(&(udp_len as u16).to_be_bytes());
+        ip.extend_from_slice(&[0, 0]);
+        ip.extend_from_slice(payload);
+
+        out.extend_from_slice(&(ts.as_secs() as u32).to_le_bytes());
+        out.extend_from_slice(&ts.subsec_micros().to_le_bytes());
+        out.extend_from_slice(&(ip.len() as u32).to_le_bytes());
+        out.extend_from_slice(&(ip.len() as u32).to_le_bytes());
+        out.extend_from_slice(&ip);
+    }
+    out
+}
+
+#[test]
+fn reports_reassembled_and_timed_out_messages() {
+    let complete = fragment_message(1, &[7u8; 3000], 1200);
+    let incomplete = fragment_message(2, &[8u8; 3000], 1200);
+    let start = Duration::from_secs(1_760_000_000);
+    let packets = vec![
+        (start, incomplete[0].clone()),
+        (start + Duration::from_millis(1), complete[2].clone()),
+        (start + Duration::from_millis(2), complete[0].clone()),
+        (start + Duration::from_millis(3), complete[1].clone()),
+    ];
+
+    let config = DetectorConfig::new(Pubkey::from_str(PUMPFUN_PROGRAM_ID).unwrap(), false, None);
+    let capture = pcap(&packets);
+    let reader = PcapReader::new(capture.as_slice()).unwrap();
+    let mut seen = Vec::new();
+    let report = replay(reader, ReplaySpeed::Fast, &config, |msg_seq, _| seen.push(msg_seq)).unwrap();
+
+    assert_eq!(report.packets, 4);
+    assert_eq!(report.messages, 1);
+    assert_eq!(report.timed_out, 1);
+    assert_eq!(report.detections, 0);
+    assert_eq!(seen, vec![1]);
+}
+
+#[test]
+fn rejects_non_pcap_input() {
+    assert!(PcapReader::new(&b"definitely not a capture file"[..]).is_err());
+}
@@ -0,0 +1,50 @@
+use std::{fs, io::Read, path::PathBuf};
+
+use flate2::read::GzDecoder;
+use test_shreds::sink::rotate::{rotated_segments, RotatingFile, RotationConfig};
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("test_shreds_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn rotates_by_size_and_compresses_segments() {
+    let dir = temp_dir("rotate_gzip");
+    let path = dir.join("out.jsonl");
+    let config = RotationConfig { max_bytes: 10, gzip: true, ..Default::default() };
+
+    let mut file = RotatingFile::open(&path, config).unwrap();
+    file.write_all(b"0123456789\n").unwrap();
+    file.write_all(b"second\n").unwrap();
+    drop(file);
+
+    assert_eq!(fs::read(&path).unwrap(), b"second\n");
+    let segments = rotated_segments(&path).unwrap();
+    assert_eq!(segments.len(), 1);
+    assert!(segments[0].1.to_string_lossy().ends_with(".gz"));
+
+    let mut contents = String::new();
+    GzDecoder::new(fs::File::open(&segments[0].1).unwrap()).read_to_string(&mut contents).unwrap();
+    assert_eq!(contents, "0123456789\n");
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn prunes_segments_beyond_retention() {
+    let dir = temp_dir("rotate_retain");
+    let path = dir.join("out.jsonl");
+    let config = RotationConfig { retain: 2, ..Default::default() };
+
+    let mut file = RotatingFile::open(&path, config).unwrap();
+    for _ in 0..4 {
+        file.write_all(b"line\n").unwrap();
+        file.rotate().unwrap();
+    }
+    drop(file);
+
+    assert_eq!(rotated_segments(&path).unwrap().len(), 2);
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,38 @@
+use std::borrow::Cow;
+
+use test_shreds::sanitize::{decode_lossy, is_suspicious, sanitize};
+
+#[test]
+fn leaves_ordinary_text_alone() {
+    for text in ["Moon", "日本語トークン", "🚀 TO THE MOON 🚀", "https://example.com/a?b=c&d=e", ""] {
+        assert!(matches!(sanitize(text), Cow::Borrowed(t) if t == text));
+        assert!(!is_suspicious(text));
+    }
+}
+
+#[test]
+fn escapes_control_bidi_and_zero_width_characters() {
+    assert_eq!(sanitize("a\nb\r\tc\0"), "a\\u{a}b\\u{d}\\u{9}c\\u{0}");
+    assert_eq!(sanitize("\u{1b}[31mred\u{7f}\u{85}"), "\\u{1b}[31mred\\u{7f}\\u{85}");
+    assert_eq!(sanitize("gnp.\u{202e}exe"), "gnp.\\u{202e}exe");
+    assert_eq!(sanitize("\u{2067}x\u{2069}"), "\\u{2067}x\\u{2069}");
+    assert_eq!(sanitize("\u{feff}zero\u{200d}width"), "\\u{feff}zero\\u{200d}width");
+}
+
+#[test]
+fn flags_only_disguising_characters_as_suspicious() {
+    assert!(is_suspicious("gnp.\u{202e}exe"));
+    assert!(is_suspicious("\u{200f}"));
+    assert!(is_suspicious("Pe\u{200b}pe"));
+    // Merely ugly, not disguising
+    assert!(!is_suspicious("line\nbreak"));
+    assert!(!is_suspicious("\u{fffd}"));
+}
+
+#[test]
+fn decodes_invalid_utf8_lossily() {
+    assert_eq!(decode_lossy(b"ok\xff\xfe!"), "ok\u{fffd}\u{fffd}!");
+    // A truncated multi-byte sequence at the end
+    assert_eq!(decode_lossy(&"€".as_bytes()[..2]), "\u{fffd}");
+    assert_eq!(sanitize(&decode_lossy(b"\xc0\x80")), "\u{fffd}\u{fffd}");
+}
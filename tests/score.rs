@@ -0,0 +1,95 @@
+use solana_sdk::pubkey::Pubkey;
+use test_shreds::{
+    detector::{ComputeBudget, Detection},
+    events::CreateEvent,
+    score::{bucket, parse_name_patterns, score, CreateScorer, ScoreConfig, ScoreInputs, ScoreWeights},
+};
+
+fn create(mint: Pubkey, creator: Pubkey, name: Option<&str>, compute_budget: Option<ComputeBudget>) -> Detection {
+    let event = name.map(|name| {
+        Box::new(CreateEvent {
+            name: name.to_string(),
+            symbol: "TKN".to_string(),
+            uri: String::new(),
+            suspicious: false,
+            raw: None,
+            mint,
+            bonding_curve: Pubkey::new_unique(),
+            user: creator,
+            creator: Some(creator),
+            timestamp: None,
+            reserves: None,
+        })
+    });
+    Detection::Create {
+        mint,
+        bonding_curve: Pubkey::new_unique(),
+        creator,
+        associated_bonding_curve: None,
+        metadata: None,
+        accounts: None,
+        compute_budget,
+        token_accounts: Vec::new(),
+        event,
+    }
+}
+
+fn buy(mint: Pubkey, buyer: Pubkey, max_sol_cost: u64) -> Detection {
+    Detection::Buy { mint, buyer, token_amount: 1, max_sol_cost, event: None }
+}
+
+#[test]
+fn weighs_each_factor_against_its_full_value() {
+    let config = ScoreConfig::default();
+    // A new creator alone earns the creator weight, 20 of 100
+    assert_eq!(score(&ScoreInputs::default(), &config), 20);
+    let everything = ScoreInputs {
+        initial_buy: Some(config.full_buy * 2),
+        creator_creates: 0,
+        name_match: true,
+        priority_fee: Some(config.full_priority_fee),
+    };
+    assert_eq!(score(&everything, &config), 100);
+    // Half the full buy earns 20 of its 40, and a creator's third create a third of the creator's 20
+    let inputs = ScoreInputs { initial_buy: Some(config.full_buy / 2), creator_creates: 2, ..Default::default() };
+    assert_eq!(score(&inputs, &config), 27);
+
+    let fee_only = ScoreConfig { weights: "priority_fee=1".parse().unwrap(), ..ScoreConfig::default() };
+    let inputs = ScoreInputs { priority_fee: Some(fee_only.full_priority_fee / 4), ..Default::default() };
+    assert_eq!(score(&inputs, &fee_only), 25);
+    assert_eq!((bucket(0), bucket(19), bucket(20), bucket(99), bucket(100)), (0, 0, 1, 4, 4));
+}
+
+#[test]
+fn parses_weights() {
+    let weights: ScoreWeights = "initial_buy=3, name=1".parse().unwrap();
+    assert_eq!(weights, ScoreWeights { initial_buy: 3.0, creator: 0.0, name: 1.0, priority_fee: 0.0 });
+    assert!("volume=1".parse::<ScoreWeights>().is_err());
+    assert!("name".parse::<ScoreWeights>().is_err());
+    assert!("name=-1".parse::<ScoreWeights>().is_err());
+    assert!("name=0".parse::<ScoreWeights>().is_err());
+    assert_eq!(parse_name_patterns(" Pepe,,CAT "), vec!["pepe", "cat"]);
+}
+
+#[test]
+fn scores_creates_from_their_message_and_creator_history() {
+    let config = ScoreConfig { name_patterns: parse_name_patterns("pepe"), ..ScoreConfig::default() };
+    let (full_buy, full_fee) = (config.full_buy, config.full_priority_fee);
+    let mut scorer = CreateScorer::new(config);
+    let (mint, creator) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let budget = ComputeBudget { unit_limit: Some(1_000_000), unit_price: Some(full_fee) };
+    let message = [
+        create(mint, creator, Some("Baby PEPE"), Some(budget)),
+        // Someone else's buy doesn't count as the initial one
+        buy(mint, Pubkey::new_unique(), full_buy),
+        buy(mint, creator, full_buy),
+    ];
+    assert_eq!(scorer.score(&message, 0), Some(100));
+    assert_eq!(scorer.score(&message, 1), None);
+
+    // The creator's second create, with nothing else going for it
+    let second = [create(Pubkey::new_unique(), creator, Some("Doge"), None)];
+    assert_eq!(scorer.score(&second, 0), Some(10));
+    let unnamed = [create(Pubkey::new_unique(), Pubkey::new_unique(), None, None)];
+    assert_eq!(scorer.score(&unnamed, 0), Some(20));
+}
@@ -0,0 +1,85 @@
+use std::time::{Duration, Instant};
+
+use solana_sdk::pubkey::Pubkey;
+use test_shreds::{
+    detector::Detection,
+    sink::DetectionEvent,
+    slotbatch::{SlotBatch, SlotBatcher},
+};
+
+fn event(msg_seq: u64) -> DetectionEvent {
+    let detection = Detection::Migration { mint: Pubkey::new_unique(), pool: Pubkey::new_unique(), from_pumpfun: true };
+    DetectionEvent::new(detection, msg_seq)
+}
+
+/// Each batch as (slot, msg_seq of its events)
+fn summary(batches: &[SlotBatch]) -> Vec<(u64, Vec<u64>)> {
+    batches.iter().map(|b| (b.slot, b.events.iter().map(|e| e.msg_seq).collect())).collect()
+}
+
+#[test]
+fn higher_slot_closes_lower_ones_in_order() {
+    let mut batcher = SlotBatcher::new(Duration::from_secs(1));
+    let now = Instant::now();
+
+    assert!(batcher.observe(100, now).is_empty());
+    batcher.push(100, event(1), now);
+    batcher.push(100, event(2), now);
+    assert_eq!(summary(&batcher.observe(101, now)), vec![(100, vec![1, 2])]);
+    batcher.push(101, event(3), now);
+    // A late message opens 99 below the open 101
+    assert!(batcher.observe(99, now).is_empty());
+    batcher.push(99, event(4), now);
+    assert_eq!(batcher.open_slots(), 2);
+
+    let closed = batcher.observe(102, now);
+    assert_eq!(summary(&closed), vec![(99, vec![4]), (101, vec![3])]);
+    assert_eq!(summary(&batcher.flush_all()), vec![(102, vec![])]);
+}
+
+#[test]
+fn late_slot_reopens_and_closes_again() {
+    let mut batcher = SlotBatcher::new(Duration::from_secs(1));
+    let now = Instant::now();
+    batcher.observe(100, now);
+    batcher.push(100, event(1), now);
+    assert_eq!(summary(&batcher.observe(101, now)), vec![(100, vec![1])]);
+
+    // A straggler from 100 doesn't close 101, but the next message from 101 closes it again
+    assert!(batcher.observe(100, now).is_empty());
+    batcher.push(100, event(2), now);
+    let closed = batcher.observe(101, now);
+    assert_eq!(summary(&closed), vec![(100, vec![2])]);
+    assert!(closed[0].late);
+}
+
+#[test]
+fn flags_only_slots_that_break_ascending_order() {
+    let mut batcher = SlotBatcher::new(Duration::from_secs(1));
+    let now = Instant::now();
+    batcher.observe(100, now);
+    batcher.observe(102, now);
+    // Below the open 102 but above the flushed 100, so still in order
+    batcher.observe(101, now);
+    batcher.observe(103, now);
+    batcher.observe(99, now);
+    let closed: Vec<(u64, bool)> = batcher.flush_all().iter().map(|b| (b.slot, b.late)).collect();
+    assert_eq!(closed, vec![(99, true), (103, false)]);
+}
+
+#[test]
+fn flushes_slots_open_past_the_timeout() {
+    let mut batcher = SlotBatcher::new(Duration::from_millis(400));
+    let start = Instant::now();
+    batcher.observe(101, start);
+    batcher.push(101, event(1), start);
+    // Late, so nothing closes 101 but the timeout
+    batcher.observe(100, start + Duration::from_millis(300));
+
+    assert!(batcher.flush_expired(start + Duration::from_millis(399)).is_empty());
+    // 100 hasn't timed out, but goes first so output never steps back a slot
+    let expired = batcher.flush_expired(start + Duration::from_millis(400));
+    assert_eq!(summary(&expired), vec![(100, vec![]), (101, vec![1])]);
+    assert!(expired.iter().all(|b| !b.late));
+    assert_eq!(batcher.open_slots(), 0);
+}
@@ -0,0 +1,29 @@
+use solana_sdk::hash::Hash;
+use test_shreds::slots::{SlotConflict, SlotHashes};
+
+#[test]
+fn flags_conflicting_terminal_hash() {
+    let mut slots = SlotHashes::new(16);
+    let (a, b) = (Hash::new_unique(), Hash::new_unique());
+
+    assert_eq!(slots.observe(100, a), None);
+    assert_eq!(slots.observe(101, b), None);
+    // Another copy of the same block
+    assert_eq!(slots.observe(100, a), None);
+    assert_eq!(slots.observe(100, b), Some(SlotConflict { slot: 100, first: a, conflicting: b }));
+    // Still checked against the first hash
+    assert!(slots.observe(100, Hash::new_unique()).is_some());
+    assert_eq!(slots.conflicts(), 2);
+}
+
+#[test]
+fn forgets_oldest_slots() {
+    let mut slots = SlotHashes::new(2);
+    let a = Hash::new_unique();
+    for slot in [10, 11, 12] {
+        assert_eq!(slots.observe(slot, a), None);
+    }
+    // Slot 10 was evicted and older than anything kept, so it can't be checked
+    assert_eq!(slots.observe(10, Hash::new_unique()), None);
+    assert!(slots.observe(12, Hash::new_unique()).is_some());
+}
@@ -0,0 +1,97 @@
+#![cfg(feature = "sqlite")]
+
+use std::{fs, path::PathBuf};
+
+use rusqlite::Connection;
+use solana_sdk::pubkey::Pubkey;
+use test_shreds::{
+    detector::{ComputeBudget, Detection},
+    sink::{sqlite::SqliteSink, DetectionEvent, Sink},
+};
+
+fn temp_db(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("test_shreds_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir.join("detections.db")
+}
+
+fn count(path: &PathBuf) -> i64 {
+    Connection::open(path).unwrap().query_row("SELECT COUNT(*) FROM detections", [], |r| r.get(0)).unwrap()
+}
+
+#[test]
+fn inserts_detections_in_batches() {
+    let path = temp_db("sqlite_batches");
+    let (mint, creator) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut sink = SqliteSink::open(&path, 2).unwrap();
+
+    let create = Detection::Create {
+        mint,
+        bonding_curve: Pubkey::new_unique(),
+        creator,
+        associated_bonding_curve: None,
+        metadata: None,
+        accounts: None,
+        compute_budget: Some(ComputeBudget { unit_limit: Some(250_000), unit_price: None }),
+        token_accounts: Vec::new(),
+        event: None,
+    };
+    sink.write(&DetectionEvent::new(create, 1)).unwrap();
+    sink.write(&DetectionEvent::new(Detection::Heartbeat { packets: 5, pending: 0 }, 1)).unwrap();
+    // Not committed until the batch fills or the queue drains
+    assert_eq!(count(&path), 0);
+
+    let buy = Detection::Buy { mint, buyer: Pubkey::new_unique(), token_amount: 1_000, max_sol_cost: u64::MAX, event: None };
+    sink.write(&DetectionEvent::new(buy, 2)).unwrap();
+    assert_eq!(count(&path), 2);
+    let migration = Detection::Migration { mint, pool: Pubkey::new_unique(), from_pumpfun: true };
+    sink.write(&DetectionEvent::new(migration, 3)).unwrap();
+    sink.flush().unwrap();
+    drop(sink);
+
+    let conn = Connection::open(&path).unwrap();
+    let (kind, limit, price): (String, Option<u32>, Option<i64>) = conn
+        .query_row(
+            "SELECT kind, compute_unit_limit, compute_unit_price FROM detections WHERE creator = ?1",
+            [creator.to_string()],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+        )
+        .unwrap();
+    assert_eq!((kind.as_str(), limit, price), ("create", Some(250_000), None));
+    let (amount, cap, json): (i64, Option<i64>, String) = conn
+        .query_row("SELECT token_amount, max_sol_cost, event_json FROM detections WHERE kind = 'buy'", [], |r| {
+            Ok((r.get(0)?, r.get(1)?, r.get(2)?))
+        })
+        .unwrap();
+    assert_eq!((amount, cap), (1_000, None));
+    assert!(json.contains(&u64::MAX.to_string()));
+    let kinds: Vec<String> = conn
+        .prepare("SELECT kind FROM detections WHERE mint = ?1 ORDER BY id")
+        .unwrap()
+        .query_map([mint.to_string()], |r| r.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(kinds, ["create", "buy", "migration"]);
+}
+
+#[test]
+fn migrates_once_and_refuses_newer_schemas() {
+    let path = temp_db("sqlite_schema");
+    let mut sink = SqliteSink::open(&path, 10).unwrap();
+    sink.write(&DetectionEvent::new(
+        Detection::Migration { mint: Pubkey::new_unique(), pool: Pubkey::new_unique(), from_pumpfun: false },
+        1,
+    ))
+    .unwrap();
+    sink.flush().unwrap();
+    drop(sink);
+
+    // Reopening keeps the existing rows
+    drop(SqliteSink::open(&path, 10).unwrap());
+    assert_eq!(count(&path), 1);
+
+    Connection::open(&path).unwrap().execute_batch("PRAGMA user_version = 99").unwrap();
+    assert!(SqliteSink::open(&path, 10).is_err());
+}
@@ -0,0 +1,94 @@
+use std::{
+    io,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use test_shreds::{
+    clock::MockClock,
+    metrics::Metrics,
+    source::{
+        supervisor::{RestartPolicy, SourceState, SupervisedSource},
+        MockSource, PacketSource,
+    },
+};
+
+/// A source delivering `count` one-byte datagrams counting up from `first`, then failing
+fn scripted(first: u8, count: u8) -> MockSource {
+    let from: SocketAddr = "127.0.0.1:9000".parse().unwrap();
+    let mut source = MockSource::new(MockClock::new());
+    for i in first..first + count {
+        source.push(Duration::ZERO, vec![i], from);
+    }
+    source
+}
+
+fn policy(backoff_ms: u64, limit: u32) -> RestartPolicy {
+    RestartPolicy { backoff: Duration::from_millis(backoff_ms), max_backoff: Duration::from_millis(backoff_ms * 4), limit }
+}
+
+#[tokio::test]
+async fn restarts_a_failed_source_and_keeps_delivering() {
+    let opens = Arc::new(AtomicU8::new(0));
+    let metrics = Arc::new(Metrics::default());
+    let open = {
+        let opens = opens.clone();
+        move || {
+            let first = opens.fetch_add(1, Ordering::SeqCst) * 2;
+            async move { Ok(scripted(first, 2)) }
+        }
+    };
+    let mut source = SupervisedSource::new("mock", open, policy(1, 0), metrics.clone());
+
+    let mut buf = [0u8; 16];
+    for i in 0..6 {
+        source.recv_from(&mut buf).await.unwrap();
+        assert_eq!(buf[0], i);
+    }
+    assert_eq!(opens.load(Ordering::SeqCst), 3);
+    let snapshot = metrics.snapshot();
+    assert_eq!((snapshot.source_state, snapshot.source_restarts), (SourceState::Connected, 2));
+}
+
+#[tokio::test]
+async fn gives_up_after_the_restart_limit() {
+    let metrics = Arc::new(Metrics::default());
+    let open = || async { Err::<MockSource, _>(io::Error::from(io::ErrorKind::ConnectionRefused)) };
+    let mut source = SupervisedSource::new("mock", open, policy(1, 2), metrics.clone()).with_source(scripted(0, 1));
+
+    let mut buf = [0u8; 16];
+    source.recv_from(&mut buf).await.unwrap();
+    // The source fails, then both restarts do
+    let err = source.recv_from(&mut buf).await.unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+    let snapshot = metrics.snapshot();
+    assert_eq!((snapshot.source_state, snapshot.source_restarts), (SourceState::Failed, 2));
+}
+
+#[tokio::test]
+async fn a_backoff_survives_being_interrupted() {
+    let metrics = Arc::new(Metrics::default());
+    let open = || async { Ok(scripted(7, 1)) };
+    let mut source = SupervisedSource::new("mock", open, policy(50, 0), metrics.clone()).with_source(scripted(0, 0));
+
+    // As in the recv loop, where a housekeeping tick drops the receive far more often than the backoff
+    let mut buf = [0u8; 16];
+    let mut interrupted = 0;
+    loop {
+        match tokio::time::timeout(Duration::from_millis(5), source.recv_from(&mut buf)).await {
+            Ok(received) => {
+                received.unwrap();
+                break;
+            }
+            Err(_) => interrupted += 1,
+        }
+        assert!(interrupted < 100, "backoff restarted on every interruption");
+        assert_eq!(metrics.snapshot().source_state, SourceState::Backoff);
+    }
+    assert_eq!(buf[0], 7);
+    assert!(interrupted > 0);
+}
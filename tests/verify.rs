@@ -0,0 +1,96 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{create_event, program_id, pumpfun_logs};
+use serde_json::{json, Value};
+use solana_entry::entry::Entry;
+use solana_sdk::{
+    hash::Hash,
+    instruction::{AccountMeta, Instruction},
+    message::Message,
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::{Transaction, VersionedTransaction},
+};
+use test_shreds::{
+    detector::{process_entries, DetectorConfig, CREATE_DISC},
+    verify::{verdict, Verdict, Verifier, VerifyConfig, VerifyStats},
+};
+
+/// A `getTransaction` result whose pumpfun instruction logged `events`
+fn transaction(events: &[Vec<u8>], err: Value) -> Value {
+    json!({"slot": 1, "meta": {"err": err, "logMessages": pumpfun_logs(events)}, "transaction": {}})
+}
+
+#[test]
+fn judges_creates_by_the_events_logged() {
+    let (mint, program) = (Pubkey::new_unique(), program_id());
+    let created = transaction(&[create_event(mint, Pubkey::new_unique(), true)], Value::Null);
+    assert_eq!(verdict(&created, &mint, &program), Verdict::Confirmed);
+    // Another mint's create, or none at all, in complete logs of a successful transaction
+    let other = transaction(&[create_event(Pubkey::new_unique(), Pubkey::new_unique(), true)], Value::Null);
+    assert_eq!(verdict(&other, &mint, &program), Verdict::FalsePositive);
+    assert_eq!(verdict(&transaction(&[], Value::Null), &mint, &program), Verdict::FalsePositive);
+
+    let failed = transaction(&[], json!({"InstructionError": [0, {"Custom": 6000}]}));
+    assert_eq!(verdict(&failed, &mint, &program), Verdict::Failed);
+    assert_eq!(verdict(&Value::Null, &mint, &program), Verdict::NotFound);
+    let mut truncated = transaction(&[], Value::Null);
+    truncated["meta"]["logMessages"].as_array_mut().unwrap().push(json!("Log truncated"));
+    assert_eq!(verdict(&truncated, &mint, &program), Verdict::Incomplete);
+    assert_eq!(verdict(&json!({"meta": {"err": null}}), &mint, &program), Verdict::Incomplete);
+}
+
+#[test]
+fn detections_carry_their_transaction_signature() {
+    let tx = |mint: Pubkey, signature: Signature| {
+        let mut accounts: Vec<AccountMeta> = (0..8).map(|_| AccountMeta::new(Pubkey::new_unique(), false)).collect();
+        accounts[0] = AccountMeta::new(mint, true);
+        let ix = Instruction::new_with_bytes(program_id(), &CREATE_DISC, accounts);
+        let mut tx = VersionedTransaction::from(Transaction::new_unsigned(Message::new(&[ix], Some(&Pubkey::new_unique()))));
+        tx.signatures = vec![signature];
+        tx
+    };
+    let signatures = [Signature::new_unique(), Signature::new_unique()];
+    let entries = vec![Entry {
+        num_hashes: 1,
+        hash: Hash::default(),
+        transactions: signatures.iter().map(|&signature| tx(Pubkey::new_unique(), signature)).collect(),
+    }];
+    let config = DetectorConfig::new(program_id(), false, None);
+    let processed = process_entries(&bincode::serialize(&entries).unwrap(), &config, 1).unwrap();
+    assert_eq!(processed.detections.len(), 2);
+    assert_eq!(processed.signatures, signatures);
+}
+
+#[tokio::test]
+async fn samples_one_create_in_n_and_counts_failed_lookups_as_unverified() {
+    // Nothing listens there, so every lookup fails
+    let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let mut verifier = Verifier::spawn(VerifyConfig {
+        rpc_url: format!("http://127.0.0.1:{}", port),
+        program_id: program_id(),
+        sample_every: 5,
+        delay: Duration::ZERO,
+        timeout: Duration::from_secs(5),
+        max_concurrency: 2,
+        queue_capacity: 16,
+    })
+    .unwrap();
+    for _ in 0..12 {
+        verifier.submit(Signature::new_unique(), Pubkey::new_unique());
+    }
+
+    let mut stats = VerifyStats::default();
+    for _ in 0..500 {
+        stats.accumulate(&verifier.take_stats());
+        if stats.unverified == 2 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    stats.accumulate(&verifier.shutdown().await);
+    assert_eq!(stats, VerifyStats { unverified: 2, ..Default::default() });
+    assert_eq!(stats.false_positive_rate(), None);
+}
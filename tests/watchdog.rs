@@ -0,0 +1,95 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use test_shreds::{
+    clock::{Clock, MockClock},
+    watchdog::{LoopProgress, Severity, StallAction, StallCheck, StallEvent, StallWatchdog, Watchdog, ERROR_AFTER},
+};
+
+#[test]
+fn alerts_each_threshold_and_escalates() {
+    let start = Instant::now();
+    let threshold = Duration::from_secs(10);
+    let mut watchdog = Watchdog::new(threshold, start);
+
+    assert_eq!(watchdog.poll(start + Duration::from_secs(9)), None);
+    let first = watchdog.poll(start + threshold).unwrap();
+    assert_eq!((first.severity, first.ever_received), (Severity::Warn, false));
+    // Not again until the next threshold
+    assert_eq!(watchdog.poll(start + Duration::from_secs(15)), None);
+    assert_eq!(watchdog.poll(start + threshold * 2).unwrap().severity, Severity::Warn);
+
+    let late = watchdog.poll(start + threshold * ERROR_AFTER).unwrap();
+    assert_eq!((late.severity, late.silent_for), (Severity::Error, threshold * ERROR_AFTER));
+}
+
+#[test]
+fn reports_recovery_only_after_an_alert() {
+    let start = Instant::now();
+    let threshold = Duration::from_secs(10);
+    let mut watchdog = Watchdog::new(threshold, start);
+
+    assert_eq!(watchdog.packet(start + Duration::from_secs(5)), None);
+    assert!(watchdog.poll(start + Duration::from_secs(16)).is_some_and(|a| a.ever_received));
+    assert_eq!(watchdog.packet(start + Duration::from_secs(20)), Some(Duration::from_secs(15)));
+    // The next silence starts over at a warning
+    let alert = watchdog.poll(start + Duration::from_secs(30)).unwrap();
+    assert_eq!((alert.severity, alert.silent_for), (Severity::Warn, threshold));
+}
+
+#[test]
+fn reports_a_stall_once_until_the_loop_turns_again() {
+    let clock = MockClock::new();
+    let progress = Arc::new(LoopProgress::with_clock(clock.clone()));
+    let mut check = StallCheck::new(progress.clone(), Duration::from_millis(100));
+
+    for _ in 0..60 {
+        clock.advance(Duration::from_millis(5));
+        progress.turn();
+        assert_eq!(check.check(clock.now()), None);
+    }
+
+    // Stuck for several timeouts, in the middle of a message
+    progress.scanning(42);
+    clock.advance(Duration::from_millis(99));
+    assert_eq!(check.check(clock.now()), None);
+    clock.advance(Duration::from_millis(1));
+    let stall = StallEvent::Stalled { idle_for: Duration::from_millis(100), scanning: Some(42) };
+    assert_eq!(check.check(clock.now()), Some(stall));
+    clock.advance(Duration::from_millis(400));
+    assert_eq!(check.check(clock.now()), None);
+
+    // Recovered, then stuck again between messages
+    progress.scanning(0);
+    progress.turn();
+    assert_eq!(check.check(clock.now()), Some(StallEvent::Recovered));
+    clock.advance(Duration::from_millis(250));
+    let stall = StallEvent::Stalled { idle_for: Duration::from_millis(250), scanning: None };
+    assert_eq!(check.check(clock.now()), Some(stall));
+}
+
+#[test]
+fn watchdog_thread_counts_stalls_by_the_progress_clock() {
+    let clock = MockClock::new();
+    let progress = Arc::new(LoopProgress::with_clock(clock.clone()));
+    let watchdog = StallWatchdog::spawn(progress.clone(), Duration::from_millis(100), StallAction::Log).unwrap();
+    assert_eq!(watchdog.stalls(), 0);
+
+    // The mock clock stands still until advanced, so only the wait for the thread's next check is real
+    clock.advance(Duration::from_millis(100));
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while watchdog.stalls() == 0 {
+        assert!(Instant::now() < deadline, "the stall was never counted");
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    assert_eq!(watchdog.stalls(), 1);
+}
+
+#[test]
+fn parses_stall_actions() {
+    assert_eq!(" Abort".parse::<StallAction>(), Ok(StallAction::Abort));
+    assert_eq!("log".parse::<StallAction>(), Ok(StallAction::Log));
+    assert!("restart".parse::<StallAction>().is_err());
+}
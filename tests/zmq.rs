@@ -0,0 +1,219 @@
+#![cfg(feature = "zmq")]
+
+use std::{
+    io::{Read, Write},
+    net::TcpStream,
+    process::{Command, Stdio},
+    time::{Duration, Instant},
+};
+
+use solana_sdk::pubkey::Pubkey;
+use test_shreds::{
+    codec,
+    detector::Detection,
+    sink::{tcp::OutputFormat, zmq::ZmqSink, DetectionEvent, Sink},
+};
+
+fn create(mint: Pubkey) -> DetectionEvent {
+    let detection = Detection::Create {
+        mint,
+        bonding_curve: Pubkey::new_unique(),
+        creator: Pubkey::new_unique(),
+        associated_bonding_curve: None,
+        metadata: None,
+        accounts: None,
+        compute_budget: None,
+        token_accounts: Vec::new(),
+        event: None,
+    };
+    DetectionEvent::new(detection, 1)
+}
+
+fn buy() -> DetectionEvent {
+    let detection = Detection::Buy {
+        mint: Pubkey::new_unique(),
+        buyer: Pubkey::new_unique(),
+        token_amount: 1,
+        max_sol_cost: 1,
+        event: None,
+    };
+    DetectionEvent::new(detection, 1)
+}
+
+fn frame(stream: &mut TcpStream, flags: u8, body: &[u8]) {
+    stream.write_all(&[flags, body.len() as u8]).unwrap();
+    stream.write_all(body).unwrap();
+}
+
+fn read_frame(stream: &mut TcpStream) -> (u8, Vec<u8>) {
+    let mut head = [0u8; 2];
+    stream.read_exact(&mut head).unwrap();
+    let len = match head[0] & 0x02 {
+        0 => head[1] as usize,
+        _ => {
+            let mut rest = [0u8; 7];
+            stream.read_exact(&mut rest).unwrap();
+            u64::from_be_bytes([head[1], rest[0], rest[1], rest[2], rest[3], rest[4], rest[5], rest[6]]) as usize
+        }
+    };
+    let mut body = vec![0u8; len];
+    stream.read_exact(&mut body).unwrap();
+    (head[0], body)
+}
+
+/// A SUB socket speaking ZMTP 3.`minor`, handshaken with the sink
+fn subscriber(sink: &ZmqSink, addr: &str, minor: u8) -> TcpStream {
+    let connected = sink.subscribers();
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    let mut greeting = [0u8; 64];
+    greeting[0] = 0xff;
+    greeting[9] = 0x7f;
+    greeting[10..12].copy_from_slice(&[3, minor]);
+    greeting[12..16].copy_from_slice(b"NULL");
+    stream.write_all(&greeting).unwrap();
+    stream.read_exact(&mut greeting).unwrap();
+    assert_eq!((greeting[10], &greeting[12..16]), (3, &b"NULL"[..]));
+
+    let mut ready = b"\x05READY\x0bSocket-Type".to_vec();
+    ready.extend_from_slice(&3u32.to_be_bytes());
+    ready.extend_from_slice(b"SUB");
+    frame(&mut stream, 0x04, &ready);
+    let (flags, body) = read_frame(&mut stream);
+    assert_eq!((flags, &body[..6]), (0x04, &b"\x05READY"[..]));
+    assert!(body.windows(3).any(|w| w == b"PUB"));
+    while sink.subscribers() == connected {
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    stream
+}
+
+/// Publish `event` until a message reaches `stream`, since subscriptions apply asynchronously, returning its frames
+fn publish_until_received(sink: &mut ZmqSink, stream: &mut TcpStream, event: &DetectionEvent) -> (Vec<u8>, Vec<u8>) {
+    stream.set_nonblocking(true).unwrap();
+    for _ in 0..500 {
+        sink.write(event).unwrap();
+        let mut peek = [0u8; 1];
+        if stream.peek(&mut peek).is_ok() {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    stream.set_nonblocking(false).unwrap();
+    let (flags, topic) = read_frame(stream);
+    assert_eq!(flags, 0x01, "the topic frame has more to follow");
+    let (flags, body) = read_frame(stream);
+    assert_eq!(flags & 0x01, 0);
+    (topic, body)
+}
+
+#[test]
+fn publishes_only_the_subscribed_kinds() {
+    let addr = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().to_string();
+    let mut sink = ZmqSink::bind(&addr, OutputFormat::Json).unwrap();
+    let mut stream = subscriber(&sink, &addr, 1);
+    frame(&mut stream, 0x04, b"\x09SUBSCRIBEcre");
+
+    let mint = Pubkey::new_unique();
+    let (topic, body) = publish_until_received(&mut sink, &mut stream, &create(mint));
+    assert_eq!(topic, b"create");
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!((json["kind"].as_str(), json["mint"].as_str()), (Some("create"), Some(mint.to_string().as_str())));
+
+    // Drain the extra copies published while the subscription was on its way
+    stream.set_read_timeout(Some(Duration::from_millis(200))).unwrap();
+    let mut rest = Vec::new();
+    let _ = stream.read_to_end(&mut rest);
+    stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+
+    // The buy matches no subscription, so the next message is the create after it
+    let next = Pubkey::new_unique();
+    sink.write(&buy()).unwrap();
+    sink.write(&create(next)).unwrap();
+    let (_, topic) = read_frame(&mut stream);
+    let (_, body) = read_frame(&mut stream);
+    assert_eq!(topic, b"create");
+    assert!(String::from_utf8(body).unwrap().contains(&next.to_string()));
+}
+
+#[test]
+fn accepts_zmtp_3_0_subscriptions_and_binary_bodies() {
+    let addr = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().to_string();
+    let mut sink = ZmqSink::bind(&addr, OutputFormat::Binary).unwrap();
+    let mut stream = subscriber(&sink, &addr, 0);
+    // An empty topic subscribes to everything
+    frame(&mut stream, 0x00, b"\x01");
+
+    let (topic, body) = publish_until_received(&mut sink, &mut stream, &buy());
+    assert_eq!(topic, b"buy");
+    assert!(matches!(codec::decode(&body).unwrap().detection, Detection::Buy { .. }));
+}
+
+#[test]
+fn rejects_peers_that_cannot_subscribe() {
+    let addr = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().to_string();
+    let sink = ZmqSink::bind(&addr, OutputFormat::Json).unwrap();
+    let mut stream = TcpStream::connect(&addr).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    let mut greeting = [0u8; 64];
+    greeting[0] = 0xff;
+    greeting[9] = 0x7f;
+    greeting[10..12].copy_from_slice(&[3, 1]);
+    greeting[12..16].copy_from_slice(b"NULL");
+    stream.write_all(&greeting).unwrap();
+    stream.read_exact(&mut greeting).unwrap();
+    let mut ready = b"\x05READY\x0bSocket-Type".to_vec();
+    ready.extend_from_slice(&4u32.to_be_bytes());
+    ready.extend_from_slice(b"PUSH");
+    frame(&mut stream, 0x04, &ready);
+
+    // The sink's READY, then the connection closes
+    read_frame(&mut stream);
+    let mut rest = Vec::new();
+    assert_eq!(stream.read_to_end(&mut rest).unwrap(), 0);
+    assert_eq!(sink.subscribers(), 0);
+}
+
+/// A pyzmq SUB socket: connects to `argv[1]`, subscribes to creates, and prints the first message's two frames
+const PYZMQ_SUBSCRIBER: &str = r#"
+import sys, zmq
+sub = zmq.Context().socket(zmq.SUB)
+sub.setsockopt(zmq.RCVTIMEO, 10000)
+sub.connect(sys.argv[1])
+sub.setsockopt(zmq.SUBSCRIBE, b"create")
+topic, event = sub.recv_multipart()
+sys.stdout.buffer.write(topic + b"\n" + event)
+"#;
+
+#[test]
+fn publishes_to_a_libzmq_subscriber() {
+    // Needs python3 with pyzmq, which bundles libzmq; skipped without it
+    let available = Command::new("python3").args(["-c", "import zmq"]).stderr(Stdio::null()).status();
+    if !available.is_ok_and(|status| status.success()) {
+        eprintln!("skipping: python3 with pyzmq isn't installed");
+        return;
+    }
+    let addr = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().to_string();
+    let mut sink = ZmqSink::bind(&addr, OutputFormat::Json).unwrap();
+    let mut child = Command::new("python3")
+        .args(["-c", PYZMQ_SUBSCRIBER, &format!("tcp://{}", addr)])
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    // Publish until libzmq has connected, subscribed and taken a message, skipping the buys it didn't subscribe to
+    let mint = Pubkey::new_unique();
+    let deadline = Instant::now() + Duration::from_secs(15);
+    while child.try_wait().unwrap().is_none() {
+        assert!(Instant::now() < deadline, "the libzmq subscriber never got a create");
+        sink.write(&buy()).unwrap();
+        sink.write(&create(mint)).unwrap();
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let (topic, body) = output.stdout.split_at(output.stdout.iter().position(|&b| b == b'\n').unwrap());
+    assert_eq!(topic, b"create");
+    let json: serde_json::Value = serde_json::from_slice(&body[1..]).unwrap();
+    assert_eq!(json["mint"].as_str(), Some(mint.to_string().as_str()));
+}